@@ -0,0 +1,184 @@
+use crate::benchmarks::compute_num_workgroups;
+use crate::curves::CurveParams;
+use crate::gpu::{create_bind_group, create_compute_pipeline, BufferPool, Dispatch, GpuContext};
+use crate::sec1::FinalStage;
+use crate::shader::render_main;
+use crate::Verdict;
+use std::fmt;
+use std::sync::Arc;
+
+/// The workgroup size of the reduction stage.
+const WORKGROUP_SIZE: usize = 256;
+
+/// Whether an all-valid check also reads back one bit per signature.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Bitmap {
+    /// Only whether every signature is valid, and the index of the first which is not.
+    #[default]
+    Off,
+    /// Also a verdict per signature, packed 32 to a word.
+    Packed,
+}
+
+impl Bitmap {
+    pub fn is_on(&self) -> bool {
+        *self == Bitmap::Packed
+    }
+}
+
+/// The reduction stage summarises verdicts, so a recovery pipeline can only end with it after
+/// `FinalStage::VerifyCompressed`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AllValidError {
+    pub final_stage: FinalStage,
+}
+
+impl AllValidError {
+    /// Checks that a recovery pipeline which ends with `final_stage` writes verdicts.
+    pub fn check(final_stage: FinalStage) -> Result<(), Self> {
+        match final_stage {
+            FinalStage::VerifyCompressed => Ok(()),
+            final_stage => Err(Self { final_stage }),
+        }
+    }
+}
+
+impl fmt::Display for AllValidError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "an all-valid check needs FinalStage::VerifyCompressed, found {:?}",
+            self.final_stage
+        )
+    }
+}
+
+impl std::error::Error for AllValidError {}
+
+/// The outcome of verifying a whole batch, e.g. the signatures of a block.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BatchVerdict {
+    /// The lowest index of an invalid signature, or `None` if every signature is valid.
+    pub first_failure: Option<usize>,
+    /// With `Bitmap::Packed`, bit `i % 32` of word `i / 32` is set if signature `i` is valid.
+    pub bitmap: Option<Vec<u32>>,
+}
+
+impl BatchVerdict {
+    pub fn is_all_valid(&self) -> bool {
+        self.first_failure.is_none()
+    }
+
+    /// The verdict of signature `index`, or `None` without a bitmap.
+    pub fn verdict(&self, index: usize) -> Option<Verdict> {
+        let bitmap = self.bitmap.as_ref()?;
+        Some(Verdict::from((bitmap[index / 32] >> (index % 32)) & 1 == 1))
+    }
+
+    /// The verdict of signature `index`, if the summary determines it: from the bitmap, or else
+    /// from `first_failure`, which says nothing about the signatures after it.
+    pub fn known_verdict(&self, index: usize) -> Option<Verdict> {
+        if self.bitmap.is_some() {
+            return self.verdict(index);
+        }
+        match self.first_failure {
+            Some(first_failure) if index > first_failure => None,
+            first_failure => Some(Verdict::from(first_failure != Some(index))),
+        }
+    }
+
+    /// Parses the summary which the reduction stage writes for `num_signatures` signatures.
+    pub fn from_bytes(summary: &[u8], num_signatures: usize, bitmap: Bitmap) -> Self {
+        let words: Vec<u32> = summary
+            .chunks_exact(std::mem::size_of::<u32>())
+            .map(|chunk| u32::from_le_bytes(chunk.try_into().unwrap()))
+            .collect();
+        // The shader writes the complement of the index, so that the zeroed word means none
+        let first_failure = (words[0] != 0).then(|| !words[0] as usize);
+        let bitmap = bitmap
+            .is_on()
+            .then(|| words[1..1 + num_signatures.div_ceil(32)].to_vec());
+        Self { first_failure, bitmap }
+    }
+}
+
+/// The reduction stage which follows the last stage of a verification pipeline, and its buffers.
+/// It reads the u32 verdict of each signature and writes a summary of a word, or a word and a
+/// bitmap, so that only a few bytes need to be read back instead of a verdict per signature.
+pub struct AllValidReduction {
+    pipelines: Arc<Vec<wgpu::ComputePipeline>>,
+    /// The complement of the lowest index of an invalid signature, then the bitmap if any.
+    pub summary_buf: wgpu::Buffer,
+    all_valid_params_buf: wgpu::Buffer,
+    params_buf: wgpu::Buffer,
+    bind_group: wgpu::BindGroup,
+    params: (u32, u32, u32),
+}
+
+impl AllValidReduction {
+    /// Compiles the reduction stage, or takes it from the context's cache, and acquires its buffers from the context's pool for the
+    /// verdicts of `num_signatures` signatures in `verdicts_buf`, padded to `next_pow_2`.
+    pub fn new<C: CurveParams>(
+        context: &GpuContext,
+        verdicts_buf: &wgpu::Buffer,
+        num_signatures: usize,
+        next_pow_2: usize,
+        bitmap: Bitmap,
+        log_limb_size: u32,
+    ) -> Self {
+        let device = &context.device;
+        let pool = &context.pool;
+
+        let name = format!("{}_all_valid", C::NAME);
+        let pipelines = context.cached_pipelines(&name, log_limb_size, &(), || {
+            let source = render_main::<C>("all_valid_main.wgsl", log_limb_size);
+            vec![create_compute_pipeline(device, &source, "all_valid")]
+        });
+
+        let num_words = 1 + if bitmap.is_on() { num_signatures.div_ceil(32) } else { 0 };
+        let summary_buf = pool.acquire_sb(device, (num_words * std::mem::size_of::<u32>()) as u64);
+
+        let all_valid_params = [num_signatures as u32, bitmap.is_on() as u32, 0, 0];
+        let all_valid_params_buf = pool.acquire_ub(device, std::mem::size_of_val(&all_valid_params) as u64);
+        context.queue.write_buffer(&all_valid_params_buf, 0, bytemuck::cast_slice(&all_valid_params));
+
+        let (num_x_workgroups, num_y_workgroups, num_z_workgroups) =
+            compute_num_workgroups(next_pow_2, WORKGROUP_SIZE);
+        let params = (num_x_workgroups as u32, num_y_workgroups as u32, num_z_workgroups as u32);
+        let params_buf = pool.acquire_ub(device, (3 * std::mem::size_of::<u32>()) as u64);
+        context.queue.write_buffer(&params_buf, 0, bytemuck::cast_slice(&[params.0, params.1, params.2]));
+
+        let bind_group = create_bind_group(
+            device,
+            &pipelines[0],
+            0,
+            &[verdicts_buf, &summary_buf, &all_valid_params_buf, &params_buf],
+        );
+
+        Self { pipelines, summary_buf, all_valid_params_buf, params_buf, bind_group, params }
+    }
+
+    /// The dispatch of the reduction stage, which must come after the last stage.
+    pub fn dispatch(&self) -> Dispatch<'_> {
+        Dispatch {
+            label: "reduce to a batch verdict",
+            compute_pipeline: &self.pipelines[0],
+            bind_group: &self.bind_group,
+            num_workgroups: self.params,
+            indirect_buffer: None,
+        }
+    }
+
+    /// Zeroes the summary, which may hold that of a previous batch.
+    pub fn clear(&self, command_encoder: &mut wgpu::CommandEncoder) {
+        command_encoder.clear_buffer(&self.summary_buf, 0, None);
+    }
+
+    /// Returns every buffer to the pool. The GPU must no longer be using them.
+    pub fn release(self, pool: &BufferPool) {
+        drop(self.bind_group);
+        pool.release(self.summary_buf);
+        pool.release(self.all_valid_params_buf);
+        pool.release(self.params_buf);
+    }
+}
@@ -0,0 +1,273 @@
+use crate::gpu::{
+    create_bind_group, create_command_encoder, create_compute_pipeline, create_empty_sb,
+    create_sb_with_data, execute_pipeline, GpuContext,
+};
+use crate::moduli;
+use crate::precompute::secp256k1_bases;
+use crate::secp256k1_ecdsa::ecrecover_with_timings;
+use crate::shader::render_bigint_ff_mont_tests;
+use fuel_crypto::{Message, PublicKey, SecretKey, Signature};
+use fuel_types::Bytes32;
+use multiprecision::mont::calc_rinv_and_n0;
+use multiprecision::utils::calc_num_limbs;
+use multiprecision::{bigint, mont};
+use num_bigint::BigUint;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::io;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+/// The limb sizes which `autotune` tries.
+pub const CANDIDATE_LOG_LIMB_SIZES: [u32; 5] = [11, 12, 13, 14, 15];
+
+/// The number of serial Montgomery multiplications which each candidate performs.
+const MONT_MUL_COST: u32 = 8192;
+
+/// The number of timed runs of each measurement, after an untimed warmup run which absorbs
+/// shader compilation and the first allocation of the buffers. The median is recorded.
+const NUM_RUNS: usize = 5;
+
+/// The number of signatures in the batch whose variable-base multiplication stage is timed.
+const NUM_SIGNATURES: usize = 1024;
+
+/// The index of the "variable-base mul u2 * R" stage of secp256k1 recovery, which is the most
+/// expensive one.
+const ECDSA_STAGE: usize = 2;
+
+/// Identifies an adapter and the driver it runs on, as reported by `wgpu::AdapterInfo`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AdapterKey {
+    pub vendor: u32,
+    pub device: u32,
+    pub driver: String,
+}
+
+impl From<&wgpu::AdapterInfo> for AdapterKey {
+    fn from(info: &wgpu::AdapterInfo) -> Self {
+        Self {
+            vendor: info.vendor,
+            device: info.device,
+            driver: info.driver.clone(),
+        }
+    }
+}
+
+/// The measurements of a single candidate limb size.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Candidate {
+    pub log_limb_size: u32,
+    /// Whether both the Montgomery multiplications and the signature recoveries were correct.
+    pub is_correct: bool,
+    pub mont_mul: Duration,
+    pub ecdsa_stage: Duration,
+}
+
+impl Candidate {
+    pub fn total(&self) -> Duration {
+        self.mont_mul + self.ecdsa_stage
+    }
+}
+
+/// The outcome of autotuning on one adapter.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Profile {
+    pub adapter: AdapterKey,
+    pub adapter_name: String,
+    /// The fastest correct candidate.
+    pub log_limb_size: u32,
+    pub candidates: Vec<Candidate>,
+}
+
+/// Benchmarks `mont_mul` and the variable-base multiplication stage of secp256k1 recovery for each
+/// of `CANDIDATE_LOG_LIMB_SIZES` on the context's adapter, and picks the candidate whose results
+/// are correct and whose combined time is the lowest. Candidates whose shaders fail validation or
+/// produce wrong results are recorded but never picked.
+pub async fn autotune(context: &GpuContext) -> Result<Profile, crate::ShaderFailureError> {
+    let (signatures, messages, expected_pks) = gen_test_data(NUM_SIGNATURES);
+
+    let mut candidates = Vec::with_capacity(CANDIDATE_LOG_LIMB_SIZES.len());
+    for log_limb_size in CANDIDATE_LOG_LIMB_SIZES {
+        // Capture validation errors instead of panicking, so that limb sizes which the adapter
+        // cannot compile are simply ruled out
+        context.device.push_error_scope(wgpu::ErrorFilter::Validation);
+
+        let (mont_mul, is_mont_mul_correct) = benchmark_mont_mul(context, log_limb_size).await;
+
+        let table_limbs = secp256k1_bases(log_limb_size);
+        let mut ecdsa_stage_runs = Vec::with_capacity(NUM_RUNS);
+        let mut is_ecdsa_correct = true;
+        for i in 0..NUM_RUNS + 1 {
+            match ecrecover_with_timings(
+                context,
+                &signatures,
+                &messages,
+                &table_limbs,
+                log_limb_size,
+            )
+            .await
+            {
+                Ok((recovered, timings)) => {
+                    is_ecdsa_correct &= recovered
+                        .into_iter()
+                        .zip(&expected_pks)
+                        .all(|(pk, expected)| pk == Some(*expected));
+                    if i > 0 {
+                        ecdsa_stage_runs.push(timings.stages[ECDSA_STAGE].1);
+                    }
+                }
+                Err(_) => {
+                    is_ecdsa_correct = false;
+                    break;
+                }
+            }
+        }
+        let ecdsa_stage = if is_ecdsa_correct { median(ecdsa_stage_runs) } else { Duration::ZERO };
+
+        let is_valid = context.device.pop_error_scope().await.is_none();
+
+        candidates.push(Candidate {
+            log_limb_size,
+            is_correct: is_valid && is_mont_mul_correct && is_ecdsa_correct,
+            mont_mul,
+            ecdsa_stage,
+        });
+    }
+
+    let log_limb_size = candidates
+        .iter()
+        .filter(|c| c.is_correct)
+        .min_by_key(|c| c.total())
+        .ok_or_else(crate::ShaderFailureError::default)?
+        .log_limb_size;
+
+    Ok(Profile {
+        adapter: AdapterKey::from(&context.adapter_info),
+        adapter_name: context.adapter_info.name.clone(),
+        log_limb_size,
+        candidates,
+    })
+}
+
+/// Returns the limb size from the profile of the context's adapter in the JSON file at `path`.
+/// If there is none, autotunes and stores the new profile in the file alongside the profiles of
+/// other adapters.
+pub async fn autotuned_log_limb_size(context: &GpuContext, path: &Path) -> io::Result<u32> {
+    let adapter = AdapterKey::from(&context.adapter_info);
+    if let Some(profile) = load_profile(path, &adapter)? {
+        return Ok(profile.log_limb_size);
+    }
+
+    let profile = autotune(context)
+        .await
+        .map_err(|_| io::Error::new(io::ErrorKind::Other, "no candidate limb size is correct on this adapter"))?;
+    save_profile(path, &profile)?;
+    Ok(profile.log_limb_size)
+}
+
+/// Reads the profile of `adapter` from the JSON file at `path`, if the file exists and has one.
+pub fn load_profile(path: &Path, adapter: &AdapterKey) -> io::Result<Option<Profile>> {
+    Ok(load_profiles(path)?.into_iter().find(|profile| &profile.adapter == adapter))
+}
+
+/// Writes `profile` to the JSON file at `path`, replacing any existing profile of the same
+/// adapter.
+pub fn save_profile(path: &Path, profile: &Profile) -> io::Result<()> {
+    let mut profiles = load_profiles(path)?;
+    profiles.retain(|p| p.adapter != profile.adapter);
+    profiles.push(profile.clone());
+
+    let json = serde_json::to_string_pretty(&profiles)?;
+    std::fs::write(path, json)
+}
+
+fn load_profiles(path: &Path) -> io::Result<Vec<Profile>> {
+    match std::fs::read_to_string(path) {
+        Ok(json) => Ok(serde_json::from_str(&json)?),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(vec![]),
+        Err(e) => Err(e),
+    }
+}
+
+/// Times `MONT_MUL_COST` serial Montgomery multiplications over the secp256k1 base field, as the
+/// median of `NUM_RUNS` runs after a warmup, and checks the result against the CPU.
+async fn benchmark_mont_mul(context: &GpuContext, log_limb_size: u32) -> (Duration, bool) {
+    let device = &context.device;
+    let p = moduli::secp256k1_fq_modulus_biguint();
+    let num_limbs = calc_num_limbs(log_limb_size, 256);
+    let r = mont::calc_mont_radix(num_limbs, log_limb_size);
+    let (rinv, _) = calc_rinv_and_n0(&p, &r, log_limb_size);
+
+    let a = BigUint::from_bytes_be(&Sha256::digest(b"a")) % &p;
+    let b = BigUint::from_bytes_be(&Sha256::digest(b"b")) % &p;
+    let ar = &a * &r % &p;
+    let br = &b * &r % &p;
+
+    let mut expected = ar.clone();
+    for _ in 1..MONT_MUL_COST {
+        expected = &ar * &expected * &rinv % &p;
+    }
+    expected = &expected * &br * &rinv % &p;
+
+    let ar_buf = create_sb_with_data(device, &bigint::from_biguint_le(&ar, num_limbs, log_limb_size));
+    let br_buf = create_sb_with_data(device, &bigint::from_biguint_le(&br, num_limbs, log_limb_size));
+    let result_buf = create_empty_sb(device, (num_limbs * std::mem::size_of::<u32>()) as u64);
+    let cost_buf = create_sb_with_data(device, &[MONT_MUL_COST]);
+
+    let source = render_bigint_ff_mont_tests(
+        "mont_mul_benchmarks.wgsl",
+        &p,
+        &BigUint::from(7u32),
+        log_limb_size,
+    );
+    let compute_pipeline = create_compute_pipeline(device, &source, "benchmark_mont_mul");
+    let bind_group = create_bind_group(
+        device,
+        &compute_pipeline,
+        0,
+        &[&ar_buf, &br_buf, &result_buf, &cost_buf],
+    );
+
+    let mut runs = Vec::with_capacity(NUM_RUNS);
+    let mut results = vec![];
+    for i in 0..NUM_RUNS + 1 {
+        let mut command_encoder = create_command_encoder(device);
+        let sw = Instant::now();
+        execute_pipeline(&mut command_encoder, &compute_pipeline, &bind_group, 1, 1, 1);
+        results = context.finish_encoder_and_read_bytes(command_encoder, &[&result_buf]).await;
+        if i > 0 {
+            runs.push(sw.elapsed());
+        }
+    }
+
+    let result_limbs: Vec<u32> = results[0]
+        .chunks_exact(4)
+        .map(|chunk| u32::from_le_bytes(chunk.try_into().unwrap()))
+        .collect();
+    let result = bigint::to_biguint_le(&result_limbs, num_limbs, log_limb_size);
+
+    (median(runs), result == expected)
+}
+
+fn median(mut runs: Vec<Duration>) -> Duration {
+    runs.sort();
+    runs[runs.len() / 2]
+}
+
+/// Signs distinct messages with deterministically derived keys.
+fn gen_test_data(num_signatures: usize) -> (Vec<Signature>, Vec<Message>, Vec<PublicKey>) {
+    let mut signatures = Vec::with_capacity(num_signatures);
+    let mut messages = Vec::with_capacity(num_signatures);
+    let mut expected_pks = Vec::with_capacity(num_signatures);
+
+    for i in 0..num_signatures {
+        let seed: [u8; 32] = Sha256::digest((i as u64).to_le_bytes()).into();
+        let secret = SecretKey::try_from(Bytes32::new(seed)).unwrap();
+        let message = Message::new((i as u64).to_be_bytes());
+
+        signatures.push(Signature::sign(&secret, &message));
+        messages.push(message);
+        expected_pks.push(secret.public_key());
+    }
+    (signatures, messages, expected_pks)
+}
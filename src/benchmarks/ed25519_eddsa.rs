@@ -1,5 +1,6 @@
 use crate::precompute::ed25519_bases;
-use crate::ed25519_eddsa::{ecverify, ecverify_single};
+use crate::ed25519_eddsa::{ecverify, ecverify_single, ecverify_with_timings};
+use crate::gpu::GpuContext;
 use crate::curve_algos::ed25519_eddsa::curve25519_ecverify;
 use ed25519_dalek::{Signature, Signer, SigningKey, VerifyingKey};
 use fuel_crypto::Message;
@@ -91,6 +92,22 @@ pub async fn ed25519_ecverify_benchmarks_single() {
     println!("GPU took {}ms to recover {} ed25519 EdDSA signatures in parallel (including data transfer cost).", gpu_ms, num_signatures);
 }
 
+#[serial_test::serial]
+#[tokio::test]
+pub async fn ed25519_ecverify_stage_timings() {
+    let log_limb_size = 13u32;
+    let table_limbs = ed25519_bases(log_limb_size);
+    let num_signatures = 2u32.pow(14u32) as usize;
+    let (signatures, messages, verifying_keys) = gen_test_data(num_signatures);
+
+    let context = GpuContext::new().await;
+    let (_all_is_valid, timings) = ecverify_with_timings(&context, &signatures, &messages, &verifying_keys, &table_limbs, log_limb_size)
+        .await
+        .expect("Shader failed");
+
+    println!("ed25519 signature verification stage timings ({} signatures): \n{}\n\n", num_signatures, timings);
+}
+
 pub async fn do_benchmark(
     check: bool,
     table_limbs: &Vec<u32>,
@@ -130,7 +147,7 @@ pub async fn do_benchmark(
 
     if check {
         for i in 0..num_signatures {
-            assert!(all_is_valid[i]);
+            assert!(all_is_valid[i].is_valid());
         }
     }
 
@@ -3,6 +3,8 @@ pub mod ed25519_eddsa;
 #[cfg(test)]
 pub mod mont;
 #[cfg(test)]
+pub mod packing;
+#[cfg(test)]
 pub mod secp256k1_ecdsa;
 #[cfg(test)]
 pub mod secp256r1_ecdsa;
@@ -3,7 +3,7 @@ use crate::gpu::{
     create_sb_with_data, execute_pipeline, finish_encoder_and_read_from_gpu, get_device_and_queue,
 };
 use crate::moduli;
-use crate::shader::render_bigint_ff_mont_tests;
+use crate::shader::{render_bigint_ff_mont_tests_with_reduction, Reduction};
 use crate::tests::get_secp256k1_b;
 use multiprecision::mont::calc_rinv_and_n0;
 use multiprecision::utils::calc_num_limbs;
@@ -79,6 +79,69 @@ pub async fn mont_mul_benchmarks() {
     }
 }
 
+/// Times `cost` serial multiplications with `Reduction::Montgomery` and then with
+/// `Reduction::PseudoMersenne`, for each limb size and each pseudo-Mersenne base field.
+#[serial_test::serial]
+#[tokio::test]
+pub async fn pseudo_mersenne_mul_benchmarks() {
+    let mut rng = gen_rng();
+
+    let cost = 8192;
+
+    let fields = [
+        ("secp256k1", moduli::secp256k1_fq_modulus_biguint()),
+        ("ed25519", moduli::ed25519_fq_modulus_biguint()),
+    ];
+
+    println!(
+        "Average times over {} runs of {} serial multiplications:",
+        NUM_RUNS_PER_BENCHMARK, cost
+    );
+
+    for (name, p) in &fields {
+        for log_limb_size in 11..16 {
+            let num_limbs = calc_num_limbs(log_limb_size, 256);
+            let a: BigUint = rng.sample::<BigUint, RandomBits>(RandomBits::new(256)) % p;
+            let b: BigUint = rng.sample::<BigUint, RandomBits>(RandomBits::new(256)) % p;
+
+            let mut averages = vec![];
+            for reduction in [Reduction::Montgomery, Reduction::PseudoMersenne] {
+                let r = reduction.radix(num_limbs, log_limb_size);
+                let ar = &a * &r % p;
+                let br = &b * &r % p;
+
+                let mut total = 0;
+                for i in 0..NUM_RUNS_PER_BENCHMARK + 1 {
+                    let elapsed = do_mont_benchmark_with_reduction(
+                        &ar,
+                        &br,
+                        p,
+                        &r,
+                        log_limb_size,
+                        num_limbs,
+                        "mont_mul_benchmarks.wgsl",
+                        "benchmark_mont_mul",
+                        cost,
+                        reduction,
+                    )
+                    .await;
+
+                    // The first run includes shader compilation
+                    if i > 0 {
+                        total += elapsed;
+                    }
+                }
+                averages.push(total as u32 / NUM_RUNS_PER_BENCHMARK as u32);
+            }
+
+            println!(
+                "{} base field, {}-bit limbs: Montgomery {}ms, pseudo-Mersenne {}ms",
+                name, log_limb_size, averages[0], averages[1]
+            );
+        }
+    }
+}
+
 pub async fn do_mont_benchmark(
     ar: &BigUint,
     br: &BigUint,
@@ -90,8 +153,38 @@ pub async fn do_mont_benchmark(
     entrypoint: &str,
     cost: u32,
 ) -> u32 {
-    let res = calc_rinv_and_n0(&p, &r, log_limb_size);
-    let rinv = res.0;
+    do_mont_benchmark_with_reduction(
+        ar,
+        br,
+        p,
+        r,
+        log_limb_size,
+        num_limbs,
+        filename,
+        entrypoint,
+        cost,
+        Reduction::Montgomery,
+    )
+    .await
+}
+
+/// Like `do_mont_benchmark`, with the shader rendered with `reduction`, whose radix must be `r`.
+pub async fn do_mont_benchmark_with_reduction(
+    ar: &BigUint,
+    br: &BigUint,
+    p: &BigUint,
+    r: &BigUint,
+    log_limb_size: u32,
+    num_limbs: usize,
+    filename: &str,
+    entrypoint: &str,
+    cost: u32,
+    reduction: Reduction,
+) -> u32 {
+    let rinv = match reduction {
+        Reduction::Montgomery => calc_rinv_and_n0(&p, &r, log_limb_size).0,
+        Reduction::PseudoMersenne => BigUint::from(1u32),
+    };
 
     let expected = expensive_computation(&ar, &br, &p, &rinv, cost);
     let ar_limbs = bigint::from_biguint_le(&ar, num_limbs, log_limb_size);
@@ -104,7 +197,13 @@ pub async fn do_mont_benchmark(
     let result_buf = create_empty_sb(&device, (num_limbs * 8 * std::mem::size_of::<u8>()) as u64);
     let cost_buf = create_sb_with_data(&device, &[cost]);
 
-    let source = render_bigint_ff_mont_tests(filename, &p, &get_secp256k1_b(), log_limb_size);
+    let source = render_bigint_ff_mont_tests_with_reduction(
+        filename,
+        &p,
+        &get_secp256k1_b(),
+        log_limb_size,
+        reduction,
+    );
     let compute_pipeline = create_compute_pipeline(&device, &source, entrypoint);
 
     let mut command_encoder = create_command_encoder(&device);
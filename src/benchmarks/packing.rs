@@ -0,0 +1,62 @@
+use crate::benchmarks::secp256k1_ecdsa::gen_test_data;
+use crate::gpu::{create_sb_with_data, create_sb_with_writer, get_device_and_queue};
+use crate::secp256k1_ecdsa::{pack_messages, pack_signatures};
+use fuel_crypto::{Message, Signature};
+use stopwatch::Stopwatch;
+
+const START: usize = 10;
+const END: usize = 17;
+
+/// Compares the host-side cost of uploading a batch of secp256k1 signatures and messages via
+/// intermediate `Vec`s (as the packers used to) against packing them straight into buffers which
+/// are mapped at creation.
+#[serial_test::serial]
+#[tokio::test]
+pub async fn secp256k1_packing_benchmarks() {
+    let (device, _queue) = get_device_and_queue().await;
+    let (signatures, messages, _) = gen_test_data(2u32.pow((END - 1) as u32) as usize);
+
+    let mut table = String::new();
+    table.push_str("| Num. signatures    | Via Vecs (ms)      | Mapped (ms)        |\n");
+    table.push_str("| ------------------ | ------------------ | ------------------ |\n");
+
+    for i in START..END {
+        let num_signatures = 2u32.pow(i as u32) as usize;
+        let signatures = &signatures[0..num_signatures];
+        let messages = &messages[0..num_signatures];
+
+        let sw = Stopwatch::start_new();
+        let (sig_buf, msg_buf) = upload_via_vecs(&device, signatures, messages);
+        let via_vecs_ms = sw.elapsed_ms();
+        drop((sig_buf, msg_buf));
+
+        let sw = Stopwatch::start_new();
+        let sig_buf = create_sb_with_writer(&device, (64 * num_signatures) as u64, |buf| {
+            pack_signatures(buf, signatures)
+        });
+        let msg_buf = create_sb_with_writer(&device, (32 * num_signatures) as u64, |buf| {
+            pack_messages(buf, messages)
+        });
+        let mapped_ms = sw.elapsed_ms();
+        drop((sig_buf, msg_buf));
+
+        table.push_str(format!("| {: <18} | {: <18} | {: <18} |\n", num_signatures, via_vecs_ms, mapped_ms).as_str());
+    }
+
+    println!("secp256k1 host-side input packing benchmarks: \n{}\n\n", table);
+}
+
+fn upload_via_vecs(
+    device: &wgpu::Device,
+    signatures: &[Signature],
+    messages: &[Message],
+) -> (wgpu::Buffer, wgpu::Buffer) {
+    let sig_bytes: Vec<u8> = signatures.iter().flat_map(|sig| sig.as_slice()).copied().collect();
+    let msg_bytes: Vec<u8> = messages.iter().flat_map(|msg| msg.as_slice()).copied().collect();
+    let sig_u32s: Vec<u32> = bytemuck::cast_slice(&sig_bytes).to_vec();
+    let msg_u32s: Vec<u32> = bytemuck::cast_slice(&msg_bytes).to_vec();
+    (
+        create_sb_with_data(device, &sig_u32s),
+        create_sb_with_data(device, &msg_u32s),
+    )
+}
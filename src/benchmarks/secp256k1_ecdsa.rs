@@ -1,4 +1,9 @@
-use crate::secp256k1_ecdsa::{ecrecover, ecrecover_single_shader};
+use crate::gpu::GpuContext;
+use crate::secp256k1_ecdsa::{
+    bytes_to_public_keys, ecrecover, ecrecover_single_shader, ecrecover_with_options,
+    ecrecover_with_timings, RecoveryOptions,
+};
+use crate::shader::Coordinates;
 use fuel_crypto::{Message, SecretKey, Signature, PublicKey};
 use num_bigint::{BigUint, RandomBits};
 use rand::Rng;
@@ -80,6 +85,71 @@ pub async fn secp256k1_ecrecover_benchmarks_single_shader() {
     do_benchmarks(check, &table_limbs, log_limb_size, num_signatures, &signatures, &messages, &expected_pks, true).await;
 }
 
+#[serial_test::serial]
+#[tokio::test]
+pub async fn secp256k1_ecrecover_stage_timings() {
+    let log_limb_size = 13;
+    let table_limbs = secp256k1_bases(log_limb_size);
+    let num_signatures = 2u32.pow(14u32) as usize;
+    let (signatures, messages, _expected_pks) = gen_test_data(num_signatures);
+
+    let context = GpuContext::new().await;
+    let (_recovered, timings) = ecrecover_with_timings(&context, &signatures, &messages, &table_limbs, log_limb_size)
+        .await
+        .expect("Shader failed");
+
+    println!("secp256k1 signature recovery stage timings ({} signatures): \n{}\n\n", num_signatures, timings);
+}
+
+/// Compares the projective and Jacobian coordinate systems of the multi-shader pipeline on this
+/// adapter. Each is warmed up first so that shader compilation is not timed.
+#[serial_test::serial]
+#[tokio::test]
+pub async fn secp256k1_ecrecover_coordinates_benchmarks() {
+    let log_limb_size = 13;
+    let table_limbs = secp256k1_bases(log_limb_size);
+    let (signatures, messages, expected_pks) = gen_test_data(2u32.pow(END as u32) as usize);
+    let context = GpuContext::new().await;
+    let coordinates = [Coordinates::Projective, Coordinates::Jacobian];
+
+    for c in coordinates {
+        let options = RecoveryOptions { coordinates: c, ..Default::default() };
+        let recovered = ecrecover_with_options(&context, &signatures[..256], &messages[..256], &[], &table_limbs, log_limb_size, &options)
+            .await
+            .expect("Shader failed");
+        let recovered = bytes_to_public_keys(&recovered);
+        for i in 0..256 {
+            assert_eq!(recovered[i], Some(expected_pks[i]));
+        }
+    }
+
+    let mut table = String::new();
+    table.push_str("| Num. signatures    | Projective (ms)    | Jacobian (ms)      |\n");
+    table.push_str("| ------------------ | ------------------ | ------------------ |\n");
+    let mut totals = [0u128; 2];
+    for i in START..END {
+        let num_signatures = 2u32.pow(i as u32) as usize;
+        let mut row = vec![];
+        for (j, c) in coordinates.into_iter().enumerate() {
+            let options = RecoveryOptions { coordinates: c, ..Default::default() };
+            let sw = Stopwatch::start_new();
+            ecrecover_with_options(&context, &signatures[..num_signatures], &messages[..num_signatures], &[], &table_limbs, log_limb_size, &options)
+                .await
+                .expect("Shader failed");
+            let ms = sw.elapsed_ms() as u128;
+            totals[j] += ms;
+            row.push(ms);
+        }
+        table.push_str(format!("| {: <18} | {: <18} | {: <18} |\n", num_signatures, row[0], row[1]).as_str());
+    }
+
+    let faster = if totals[0] <= totals[1] { coordinates[0] } else { coordinates[1] };
+    println!(
+        "secp256k1 signature recovery by coordinate system on {} ({:?}): \n{}\n{:?} coordinates are faster on this adapter.\n\n",
+        context.adapter_info.name, context.adapter_info.backend, table, faster
+    );
+}
+
 pub async fn do_benchmarks(
     check: bool,
     table_limbs: &Vec<u32>,
@@ -139,7 +209,7 @@ pub async fn do_benchmark(
 
     if check {
         for i in 0..num_signatures {
-            assert_eq!(recovered[i], expected_pks[i].as_slice());
+            assert_eq!(recovered[i], Some(expected_pks[i]));
         }
     }
 
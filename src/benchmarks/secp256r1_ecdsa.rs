@@ -1,9 +1,11 @@
-use crate::secp256r1_ecdsa::{ecrecover, ecrecover_single_shader};
+use crate::gpu::GpuContext;
+use crate::secp256r1_ecdsa::{ecrecover, ecrecover_single_shader, ecrecover_with_timings};
 use fuel_crypto::secp256r1::p256::{recover, sign_prehashed};
 use fuel_crypto::Message;
 use fuel_types::Bytes64;
 use num_bigint::{BigUint, RandomBits};
 use p256::ecdsa::SigningKey;
+use p256::PublicKey;
 use rand::Rng;
 use rand_chacha::rand_core::SeedableRng;
 use rand_chacha::ChaCha8Rng;
@@ -83,6 +85,22 @@ pub async fn secp256r1_ecrecover_benchmarks_single_shader() {
     do_benchmarks(check, &table_limbs, log_limb_size, num_signatures, &signatures, &messages, &expected_pks, true).await;
 }
 
+#[serial_test::serial]
+#[tokio::test]
+pub async fn secp256r1_ecrecover_stage_timings() {
+    let log_limb_size = 13;
+    let table_limbs = secp256r1_bases(log_limb_size);
+    let num_signatures = 2u32.pow(14u32) as usize;
+    let (signatures, messages, _expected_pks) = gen_test_data(num_signatures);
+
+    let context = GpuContext::new().await;
+    let (_recovered, timings) = ecrecover_with_timings(&context, &signatures, &messages, &table_limbs, log_limb_size)
+        .await
+        .expect("Shader failed");
+
+    println!("secp256r1 signature recovery stage timings ({} signatures): \n{}\n\n", num_signatures, timings);
+}
+
 pub async fn do_benchmarks(
     check: bool,
     table_limbs: &Vec<u32>,
@@ -90,7 +108,7 @@ pub async fn do_benchmarks(
     num_signatures: usize,
     signatures: &Vec<Bytes64>,
     messages: &Vec<Message>,
-    expected_pks: &Vec<Vec<u8>>,
+    expected_pks: &Vec<PublicKey>,
     invoke_single: bool,
 ) {
     let (cpu_ms, gpu_ms) = do_benchmark(check, table_limbs, log_limb_size, num_signatures, signatures, messages, expected_pks, invoke_single).await;
@@ -109,7 +127,7 @@ pub async fn do_benchmark(
     num_signatures: usize,
     signatures: &Vec<Bytes64>,
     messages: &Vec<Message>,
-    expected_pks: &Vec<Vec<u8>>,
+    expected_pks: &Vec<PublicKey>,
     invoke_single: bool,
 ) -> (u32, u32) {
     let signatures = signatures[0..num_signatures].to_vec();
@@ -140,7 +158,7 @@ pub async fn do_benchmark(
 
     if check {
         for i in 0..num_signatures {
-            assert_eq!(recovered[i], expected_pks[i].as_slice());
+            assert_eq!(recovered[i], Some(expected_pks[i]));
         }
     }
 
@@ -149,7 +167,7 @@ pub async fn do_benchmark(
 
 pub fn gen_test_data(
     num_signatures: usize,
-) -> (Vec<Bytes64>, Vec<Message>, Vec<Vec<u8>>) {
+) -> (Vec<Bytes64>, Vec<Message>, Vec<PublicKey>) {
     let scalar_p = crate::moduli::secp256r1_fr_modulus_biguint();
     let mut rng = ChaCha8Rng::seed_from_u64(2);
 
@@ -164,13 +182,12 @@ pub fn gen_test_data(
         let signing_key = SigningKey::random(&mut rng);
         let verifying_key = signing_key.verifying_key();
 
-        let pk_affine_bytes = &verifying_key.to_sec1_bytes()[1..65];
         let fuel_signature: Bytes64 =
             sign_prehashed(&signing_key, &message).expect("Couldn't sign");
 
         signatures.push(fuel_signature);
         messages.push(message);
-        expected_pks.push(pk_affine_bytes.to_vec());
+        expected_pks.push(PublicKey::from(verifying_key));
     }
     (signatures, messages, expected_pks)
 }
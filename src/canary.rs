@@ -0,0 +1,86 @@
+use crate::gpu::GpuContext;
+
+/// A stage of a multi-stage pipeline whose completion counter does not match the number of
+/// invocations which it was dispatched with.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StageFailure {
+    /// The index of the stage in its pipeline.
+    pub stage: usize,
+    /// What the stage computes.
+    pub label: &'static str,
+    /// The number of invocations which reached the end of the stage.
+    pub completed: u32,
+    /// The number of invocations of every dispatch of the stage.
+    pub expected: u32,
+}
+
+impl std::fmt::Display for StageFailure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "stage {} ({}) completed {} of {} invocations",
+            self.stage, self.label, self.completed, self.expected
+        )
+    }
+}
+
+/// Acquires the completion counters of a pipeline with `num_stages` stages from the context's
+/// pool, one u32 per stage. Every invocation of a stage adds one to the counter of its stage as
+/// it finishes, including those which have no signature to work on, so that a stage which
+/// silently fails to run, or runs only in part, is told apart from one which wrote zeroes.
+pub fn acquire_canaries(context: &GpuContext, num_stages: usize) -> wgpu::Buffer {
+    context
+        .pool
+        .acquire_sb(&context.device, (num_stages * std::mem::size_of::<u32>()) as u64)
+}
+
+/// The number of invocations of a dispatch of `num_workgroups` workgroups of `workgroup_size`,
+/// modulo 2^32 like the counters.
+pub fn num_invocations(num_workgroups: (u32, u32, u32), workgroup_size: usize) -> u32 {
+    num_workgroups
+        .0
+        .wrapping_mul(num_workgroups.1)
+        .wrapping_mul(num_workgroups.2)
+        .wrapping_mul(workgroup_size as u32)
+}
+
+/// Parses the number of workgroups which the compaction stage wrote for the indirect dispatches.
+pub fn parse_num_workgroups(indirect: &[u8]) -> (u32, u32, u32) {
+    let word = |i: usize| u32::from_le_bytes(indirect[i * 4..i * 4 + 4].try_into().unwrap());
+    (word(0), word(1), word(2))
+}
+
+/// Compares the counters in `canaries` with the number of invocations of each dispatch, given as
+/// (stage, number of invocations) in the order in which they ran, and returns the first stage to
+/// run whose counter differs from the sum over its dispatches. The sums wrap around like the
+/// `atomicAdd` of the counters.
+pub fn check(
+    labels: &[&'static str],
+    dispatches: impl IntoIterator<Item = (usize, u32)>,
+    canaries: &[u8],
+) -> Result<(), crate::ShaderFailureError> {
+    let mut expected = vec![0u32; labels.len()];
+    let mut order = vec![];
+    for (stage, num_invocations) in dispatches {
+        if !order.contains(&stage) {
+            order.push(stage);
+        }
+        expected[stage] = expected[stage].wrapping_add(num_invocations);
+    }
+
+    for stage in order {
+        let completed = u32::from_le_bytes(canaries[stage * 4..stage * 4 + 4].try_into().unwrap());
+        if completed != expected[stage] {
+            return Err(crate::ShaderFailureError {
+                stage: Some(StageFailure {
+                    stage,
+                    label: labels[stage],
+                    completed,
+                    expected: expected[stage],
+                }),
+                integrity: None,
+            });
+        }
+    }
+    Ok(())
+}
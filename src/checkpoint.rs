@@ -0,0 +1,50 @@
+use minijinja::Value;
+use std::collections::BTreeMap;
+
+/// The number of bits of the scalars of every scalar multiplication stage.
+pub const SCALAR_NUM_BITS: u32 = 256;
+
+/// Whether the scalar multiplication stages run all the bits of each scalar in one dispatch, or
+/// are split into sub-dispatches over ranges of bits. Long dispatches on large batches can trip
+/// the driver's watchdog (TDR), and a split stage shows its progress in `StageTimings`.
+///
+/// A split stage saves its accumulator and the number of bits it has processed to a checkpoint
+/// buffer, from which the next sub-dispatch resumes. It performs the same point operations in the
+/// same order as the single dispatch, so its results are bit-identical.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Checkpointing {
+    /// One dispatch per scalar multiplication stage.
+    #[default]
+    Off,
+    /// Each sub-dispatch processes this many bits of each scalar. The fixed-base multiplications
+    /// round it up to a whole number of windows.
+    BitsPerDispatch(u32),
+}
+
+impl Checkpointing {
+    pub fn is_on(&self) -> bool {
+        matches!(self, Checkpointing::BitsPerDispatch(_))
+    }
+
+    /// The number of dispatches of each scalar multiplication stage.
+    pub fn num_dispatches(&self) -> usize {
+        match self {
+            Checkpointing::Off => 1,
+            Checkpointing::BitsPerDispatch(bits) => {
+                assert!(*bits > 0, "a sub-dispatch must process at least one bit");
+                SCALAR_NUM_BITS.div_ceil(*bits) as usize
+            }
+        }
+    }
+
+    /// Inserts `checkpoint_bits`, which is 0 if checkpointing is off, and `checkpoint_windows`, the
+    /// number of windows of `log_table_size` bits per sub-dispatch of a fixed-base multiplication.
+    pub fn insert_into(&self, context: &mut BTreeMap<String, Value>, log_table_size: u32) {
+        let bits = match self {
+            Checkpointing::Off => 0,
+            Checkpointing::BitsPerDispatch(bits) => *bits,
+        };
+        context.insert("checkpoint_bits".to_owned(), Value::from(bits));
+        context.insert("checkpoint_windows".to_owned(), Value::from(bits.div_ceil(log_table_size)));
+    }
+}
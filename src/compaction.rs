@@ -0,0 +1,98 @@
+use crate::curves::CurveParams;
+use crate::gpu::{create_bind_group, create_compute_pipeline, BufferPool, GpuContext};
+use crate::shader::render_main_with_stage;
+use minijinja::Value;
+use std::collections::BTreeMap;
+
+/// Whether the stages after the decode stage run over every signature, or only over those which
+/// the decode stage could decode, e.g. whose R has a point on the curve. Batches with many
+/// malformed signatures then skip their scalar multiplications.
+///
+/// With compaction, the decode stage appends the index of each surviving signature to a list with
+/// an atomic counter, in no particular order. A one-invocation compaction stage then writes the
+/// number of workgroups for the count, and every later stage runs with `dispatch_workgroups_indirect`
+/// over the list, and reads and writes each signature's intermediate values at its original index.
+/// The workgroups are laid out in rows of at most 65535, the default
+/// `max_compute_workgroups_per_dimension`, so that batches of any size can be compacted.
+/// The outputs of the other signatures keep their zeroed values, which decode as invalid.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Compaction {
+    /// Every stage runs over the whole batch.
+    #[default]
+    Off,
+    /// The stages after the decode stage run over the survivors with indirect dispatches.
+    Indirect,
+}
+
+impl Compaction {
+    pub fn is_on(&self) -> bool {
+        *self == Compaction::Indirect
+    }
+
+    /// Inserts `compaction`, which selects the survivor bindings of each stage.
+    pub fn insert_into(&self, context: &mut BTreeMap<String, Value>) {
+        context.insert("compaction".to_owned(), Value::from(self.is_on()));
+    }
+
+    /// Compiles the compaction stage, whose index in its pipeline is `stage`, or returns `None` for
+    /// `Compaction::Off`.
+    pub fn create_pipeline<C: CurveParams>(
+        &self,
+        device: &wgpu::Device,
+        log_limb_size: u32,
+        stage: usize,
+    ) -> Option<wgpu::ComputePipeline> {
+        self.is_on().then(|| {
+            let source = render_main_with_stage::<C>("compaction_main.wgsl", log_limb_size, stage);
+            create_compute_pipeline(device, &source, "compact")
+        })
+    }
+
+    /// Acquires the survivor buffers for a batch padded to `next_pow_2` signatures from the
+    /// context's pool, or returns `None` for `Compaction::Off`.
+    pub fn acquire_survivors(&self, context: &GpuContext, next_pow_2: usize) -> Option<Survivors> {
+        self.is_on().then(|| Survivors::acquire(context, next_pow_2))
+    }
+}
+
+/// The buffers of `Compaction::Indirect`.
+pub struct Survivors {
+    /// The number of survivors, followed by their indices in the batch.
+    pub ids_buf: wgpu::Buffer,
+    /// The number of workgroups of the stages after the compaction stage, which it writes.
+    pub indirect_buf: wgpu::Buffer,
+}
+
+impl Survivors {
+    fn acquire(context: &GpuContext, next_pow_2: usize) -> Self {
+        let ids_size = (1 + next_pow_2) * std::mem::size_of::<u32>();
+        Self {
+            ids_buf: context.pool.acquire_sb(&context.device, ids_size as u64),
+            indirect_buf: context
+                .pool
+                .acquire_indirect(&context.device, (3 * std::mem::size_of::<u32>()) as u64),
+        }
+    }
+
+    /// Binds the survivor buffers and the completion counters of the pipeline to the compaction
+    /// stage.
+    pub fn create_bind_group(
+        &self,
+        device: &wgpu::Device,
+        pipeline: &wgpu::ComputePipeline,
+        canaries_buf: &wgpu::Buffer,
+    ) -> wgpu::BindGroup {
+        create_bind_group(device, pipeline, 0, &[&self.ids_buf, &self.indirect_buf, canaries_buf])
+    }
+
+    /// Zeroes the number of survivors, which may hold that of a previous batch.
+    pub fn clear(&self, command_encoder: &mut wgpu::CommandEncoder) {
+        command_encoder.clear_buffer(&self.ids_buf, 0, Some(std::mem::size_of::<u32>() as u64));
+    }
+
+    /// Returns both buffers to the pool. The GPU must no longer be using them.
+    pub fn release(self, pool: &BufferPool) {
+        pool.release(self.ids_buf);
+        pool.release(self.indirect_buf);
+    }
+}
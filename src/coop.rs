@@ -0,0 +1,154 @@
+use crate::benchmarks::compute_num_workgroups;
+use crate::curves::{CurveForm, CurveParams};
+use crate::gpu::{create_compute_pipeline, GpuContext};
+use crate::shader::{render_main_cooperative, Coordinates, Reduction};
+use std::fmt;
+
+/// The workgroup size of the cooperative kernels. The other stages use 256, but the workgroup
+/// memory of 256 / lanes groups would not fit in the default limit of 16 KiB.
+pub const COOP_WORKGROUP_SIZE: usize = 64;
+
+/// The largest limb size of the cooperative kernels, whose column sums of 2 ^ 13-bit limbs fit in a
+/// u32 for up to 64 limbs.
+pub const COOP_MAX_LOG_LIMB_SIZE: u32 = 13;
+
+/// How many invocations compute each signature's variable-base multiplication u2 * R, the stage
+/// which holds the most big integers in registers. Only this stage of the secp256k1 and secp256r1
+/// recovery pipelines has a cooperative kernel: the fixed-base multiplication u1 * G and every
+/// stage of ed25519 verification always run one invocation per signature.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Cooperation {
+    /// One invocation per signature, as in every other stage.
+    #[default]
+    PerInvocation,
+    /// A group of this many invocations per signature, which split the limbs of every field
+    /// element through workgroup memory (see `coop.wgsl`). Each invocation then needs a fraction
+    /// of the registers, so more of them fit on a compute unit. It must be a power of 2 from 2 to
+    /// `COOP_WORKGROUP_SIZE`, e.g. 4 or 8, and the limbs must be of at most
+    /// `COOP_MAX_LOG_LIMB_SIZE` bits.
+    Lanes(u32),
+}
+
+/// The reasons why a `Cooperation` cannot be used.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CooperationError {
+    /// The curve has no cooperative kernel.
+    Unsupported { curve: &'static str },
+    /// The number of lanes is not a power of 2 from 2 to `COOP_WORKGROUP_SIZE`.
+    InvalidLanes(u32),
+    /// The limbs are larger than `COOP_MAX_LOG_LIMB_SIZE` bits.
+    LimbsTooLarge { log_limb_size: u32 },
+    /// The cooperative kernels only implement Montgomery multiplication.
+    UnsupportedReduction(Reduction),
+}
+
+impl fmt::Display for CooperationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CooperationError::Unsupported { curve } => {
+                write!(f, "{} has no cooperative kernel", curve)
+            }
+            CooperationError::InvalidLanes(lanes) => write!(
+                f,
+                "expected a power of 2 from 2 to {} lanes, found {}",
+                COOP_WORKGROUP_SIZE, lanes
+            ),
+            CooperationError::LimbsTooLarge { log_limb_size } => write!(
+                f,
+                "the cooperative kernels need limbs of at most {} bits, found {}",
+                COOP_MAX_LOG_LIMB_SIZE, log_limb_size
+            ),
+            CooperationError::UnsupportedReduction(reduction) => {
+                write!(f, "the cooperative kernels do not support {:?}", reduction)
+            }
+        }
+    }
+}
+
+impl std::error::Error for CooperationError {}
+
+impl Cooperation {
+    /// Whether the curve `C` has a cooperative kernel. It implements the complete formulas for
+    /// short Weierstrass curves, so ed25519 has none.
+    pub fn supports<C: CurveParams>() -> bool {
+        matches!(C::form(), CurveForm::ShortWeierstrass { .. })
+    }
+
+    /// Returns an error unless the curve `C` can run this cooperation with `log_limb_size`.
+    /// `Cooperation::PerInvocation` always can.
+    pub fn check<C: CurveParams>(&self, log_limb_size: u32) -> Result<(), CooperationError> {
+        let lanes = match self {
+            Cooperation::PerInvocation => return Ok(()),
+            Cooperation::Lanes(lanes) => *lanes,
+        };
+        if !Self::supports::<C>() {
+            return Err(CooperationError::Unsupported { curve: C::NAME });
+        }
+        if !lanes.is_power_of_two() || lanes < 2 || lanes as usize > COOP_WORKGROUP_SIZE {
+            return Err(CooperationError::InvalidLanes(lanes));
+        }
+        if log_limb_size > COOP_MAX_LOG_LIMB_SIZE {
+            return Err(CooperationError::LimbsTooLarge { log_limb_size });
+        }
+        Ok(())
+    }
+
+    /// The number of workgroups of the variable-base multiplication stage for a batch padded to
+    /// `next_pow_2` signatures, where `params` is that of the other stages.
+    pub fn num_workgroups(&self, next_pow_2: usize, params: (u32, u32, u32)) -> (u32, u32, u32) {
+        match self {
+            Cooperation::PerInvocation => params,
+            Cooperation::Lanes(lanes) => {
+                let num_threads = next_pow_2 * *lanes as usize;
+                let (x, y, z) = compute_num_workgroups(num_threads, COOP_WORKGROUP_SIZE);
+                (x as u32, y as u32, z as u32)
+            }
+        }
+    }
+
+    /// Compiles the cooperative variable-base multiplication for the curve `C`, which has the same
+    /// bindings as stage 2 of the secp256k1 and secp256r1 recovery pipelines, or returns `None`
+    /// for `Cooperation::PerInvocation`. It panics unless `check` succeeds.
+    pub fn create_mul_pipeline<C: CurveParams>(
+        &self,
+        device: &wgpu::Device,
+        log_limb_size: u32,
+        coordinates: Coordinates,
+    ) -> Option<wgpu::ComputePipeline> {
+        match self {
+            Cooperation::PerInvocation => None,
+            Cooperation::Lanes(lanes) => {
+                let source = render_main_cooperative::<C>(
+                    "coop_scalar_mul_main.wgsl",
+                    log_limb_size,
+                    *lanes,
+                    coordinates,
+                );
+                Some(create_compute_pipeline(device, &source, "coop_scalar_mul"))
+            }
+        }
+    }
+
+    /// Returns the number of workgroups of the variable-base multiplication stage and, if it
+    /// differs from `params`, a parameter buffer from the context's pool which holds it.
+    pub fn acquire_mul_params(
+        &self,
+        context: &GpuContext,
+        next_pow_2: usize,
+        params: (u32, u32, u32),
+    ) -> ((u32, u32, u32), Option<wgpu::Buffer>) {
+        let num_workgroups = self.num_workgroups(next_pow_2, params);
+        if num_workgroups == params {
+            return (params, None);
+        }
+        let params_buf = context
+            .pool
+            .acquire_ub(&context.device, (3 * std::mem::size_of::<u32>()) as u64);
+        context.queue.write_buffer(
+            &params_buf,
+            0,
+            bytemuck::cast_slice(&[num_workgroups.0, num_workgroups.1, num_workgroups.2]),
+        );
+        (num_workgroups, Some(params_buf))
+    }
+}
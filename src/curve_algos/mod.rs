@@ -7,6 +7,7 @@ pub mod secp256k1_mul;
 pub mod secp256r1_curve;
 pub mod secp256r1_ecdsa;
 pub mod secp256r1_mul;
+pub mod secp384r1_curve;
 pub mod precompute;
 
 use num_bigint::BigUint;
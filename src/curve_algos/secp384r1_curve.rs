@@ -0,0 +1,37 @@
+use crate::curve_algos::coords::ProjectiveXYZ;
+use ark_ec::AffineRepr;
+use ark_ff::{Field, One, Zero};
+use ark_secp384r1::{Affine, Fq};
+
+pub fn is_projective_zero(pt: &ProjectiveXYZ<Fq>) -> bool {
+    pt.x == Fq::zero() && pt.y == Fq::one() && pt.z == Fq::zero()
+}
+
+pub fn projectivexyz_to_affine(point: &ProjectiveXYZ<Fq>) -> Affine {
+    let x = point.x;
+    let y = point.y;
+    let z = point.z;
+
+    if x == Fq::from(0u32) && y == Fq::from(1u32) && z == x {
+        return Affine::identity();
+    }
+
+    let zinv = z.inverse().unwrap();
+    Affine::new(x * zinv, y * zinv)
+}
+
+pub fn affine_to_projectivexyz(point: &Affine) -> ProjectiveXYZ<Fq> {
+    if point.is_zero() {
+        return ProjectiveXYZ::<Fq> {
+            x: Fq::zero(),
+            y: Fq::one(),
+            z: Fq::zero(),
+        };
+    }
+
+    ProjectiveXYZ {
+        x: point.x,
+        y: point.y,
+        z: Fq::one(),
+    }
+}
@@ -0,0 +1,188 @@
+use crate::tests::fq_to_biguint;
+use ark_ec::models::short_weierstrass::SWCurveConfig;
+use ark_ec::twisted_edwards::TECurveConfig;
+use ark_ec::AffineRepr;
+use ark_ff::PrimeField;
+use minijinja::Value;
+use num_bigint::BigUint;
+
+/// How the shaders compute square roots in the base field.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SqrtStrategy {
+    /// For p = 3 mod 4, sqrt(x) = x ^ ((p + 1) / 4).
+    Case3Mod4,
+    /// For p = 5 mod 8, Atkin's method, which needs x ^ ((p - 5) / 8) and sqrt(-1).
+    Case5Mod8,
+}
+
+/// The equation of a curve, with its coefficients as integers modulo the base field modulus.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CurveForm {
+    /// y^2 = x^3 + ax + b
+    ShortWeierstrass { a: BigUint, b: BigUint },
+    /// ax^2 + y^2 = 1 + dx^2y^2
+    TwistedEdwards { a: BigUint, d: BigUint },
+}
+
+/// The parameters of a curve which the shader renderer needs. `shader::render_curve` emits only
+/// the constants of the given curve, so adding a curve of a supported form needs an implementation
+/// of this trait and its WGSL, but no new render function.
+pub trait CurveParams {
+    /// The prefix of the curve's WGSL files, functions, and constants, e.g. `secp256k1`.
+    const NAME: &'static str;
+    /// The width of the shaders' big integers. It must be a multiple of 32.
+    const NUM_BITS: usize = 256;
+    const COFACTOR: u32 = 1;
+    const SQRT_STRATEGY: SqrtStrategy;
+
+    fn base_field_modulus() -> BigUint;
+    fn scalar_field_modulus() -> BigUint;
+    fn form() -> CurveForm;
+    /// The affine coordinates of the generator.
+    fn generator() -> (BigUint, BigUint);
+    /// The limbs of the table of multiples of the generator which the shaders read.
+    fn bases(log_limb_size: u32) -> Vec<u32>;
+
+    /// Constants which only the curve's own WGSL uses, as (name, value) pairs.
+    fn extra_constants() -> Vec<(String, Value)> {
+        vec![]
+    }
+}
+
+fn sw_params<C: SWCurveConfig>() -> (CurveForm, (BigUint, BigUint))
+where
+    C::BaseField: PrimeField,
+{
+    let g = C::GENERATOR;
+    (
+        CurveForm::ShortWeierstrass {
+            a: fq_to_biguint(C::COEFF_A),
+            b: fq_to_biguint(C::COEFF_B),
+        },
+        (fq_to_biguint(*g.x().unwrap()), fq_to_biguint(*g.y().unwrap())),
+    )
+}
+
+pub struct Secp256k1;
+
+impl CurveParams for Secp256k1 {
+    const NAME: &'static str = "secp256k1";
+    const SQRT_STRATEGY: SqrtStrategy = SqrtStrategy::Case3Mod4;
+
+    fn base_field_modulus() -> BigUint {
+        crate::moduli::secp256k1_fq_modulus_biguint()
+    }
+
+    fn scalar_field_modulus() -> BigUint {
+        crate::moduli::secp256k1_fr_modulus_biguint()
+    }
+
+    fn form() -> CurveForm {
+        sw_params::<ark_secp256k1::Config>().0
+    }
+
+    fn generator() -> (BigUint, BigUint) {
+        sw_params::<ark_secp256k1::Config>().1
+    }
+
+    fn bases(log_limb_size: u32) -> Vec<u32> {
+        crate::precompute::secp256k1_bases(log_limb_size)
+    }
+}
+
+pub struct Secp256r1;
+
+impl CurveParams for Secp256r1 {
+    const NAME: &'static str = "secp256r1";
+    const SQRT_STRATEGY: SqrtStrategy = SqrtStrategy::Case3Mod4;
+
+    fn base_field_modulus() -> BigUint {
+        crate::moduli::secp256r1_fq_modulus_biguint()
+    }
+
+    fn scalar_field_modulus() -> BigUint {
+        crate::moduli::secp256r1_fr_modulus_biguint()
+    }
+
+    fn form() -> CurveForm {
+        sw_params::<ark_secp256r1::Config>().0
+    }
+
+    fn generator() -> (BigUint, BigUint) {
+        sw_params::<ark_secp256r1::Config>().1
+    }
+
+    fn bases(log_limb_size: u32) -> Vec<u32> {
+        crate::precompute::secp256r1_bases(log_limb_size)
+    }
+}
+
+pub struct Secp384r1;
+
+impl CurveParams for Secp384r1 {
+    const NAME: &'static str = "secp384r1";
+    const NUM_BITS: usize = 384;
+    const SQRT_STRATEGY: SqrtStrategy = SqrtStrategy::Case3Mod4;
+
+    fn base_field_modulus() -> BigUint {
+        crate::moduli::secp384r1_fq_modulus_biguint()
+    }
+
+    fn scalar_field_modulus() -> BigUint {
+        crate::moduli::secp384r1_fr_modulus_biguint()
+    }
+
+    fn form() -> CurveForm {
+        sw_params::<ark_secp384r1::Config>().0
+    }
+
+    fn generator() -> (BigUint, BigUint) {
+        sw_params::<ark_secp384r1::Config>().1
+    }
+
+    fn bases(log_limb_size: u32) -> Vec<u32> {
+        crate::precompute::secp384r1_bases(log_limb_size)
+    }
+}
+
+pub struct Ed25519;
+
+impl CurveParams for Ed25519 {
+    const NAME: &'static str = "ed25519";
+    const COFACTOR: u32 = 8;
+    const SQRT_STRATEGY: SqrtStrategy = SqrtStrategy::Case5Mod8;
+
+    fn base_field_modulus() -> BigUint {
+        crate::moduli::ed25519_fq_modulus_biguint()
+    }
+
+    fn scalar_field_modulus() -> BigUint {
+        crate::moduli::ed25519_fr_modulus_biguint()
+    }
+
+    fn form() -> CurveForm {
+        CurveForm::TwistedEdwards {
+            a: fq_to_biguint(ark_ed25519::EdwardsConfig::COEFF_A),
+            d: fq_to_biguint(ark_ed25519::EdwardsConfig::COEFF_D),
+        }
+    }
+
+    fn generator() -> (BigUint, BigUint) {
+        let g = ark_ed25519::EdwardsAffine::generator();
+        (fq_to_biguint(g.x), fq_to_biguint(g.y))
+    }
+
+    fn bases(log_limb_size: u32) -> Vec<u32> {
+        crate::precompute::ed25519_bases(log_limb_size)
+    }
+
+    /// The constants of the reduction of 512-bit hashes modulo the scalar field modulus.
+    fn extra_constants() -> Vec<(String, Value)> {
+        let (fr_reduce_r_limbs_array, scalar_p_limbs_array) =
+            crate::shader::gen_ed25519_reduce_fr_constants(&Self::scalar_field_modulus());
+        vec![
+            ("fr_reduce_r_limbs_array".to_owned(), Value::from(fr_reduce_r_limbs_array)),
+            ("scalar_p_limbs_array".to_owned(), Value::from(scalar_p_limbs_array)),
+        ]
+    }
+}
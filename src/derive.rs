@@ -0,0 +1,94 @@
+use crate::benchmarks::compute_num_workgroups;
+use crate::canary::{self, acquire_canaries};
+use crate::curves::CurveParams;
+use crate::gpu::{
+    create_bind_group, create_command_encoder, create_compute_pipeline, execute_pipeline,
+    write_sb_with, GpuContext,
+};
+use crate::shader::render_main;
+
+/// What the single stage of the derivation computes.
+const STAGE_LABELS: [&str; 1] = ["fixed-base mul of the secret keys"];
+
+/// The workgroup size of the stage.
+const WORKGROUP_SIZE: usize = 256;
+
+/// Maps secret keys to public keys with the fixed-base multiplication of the curve `C`, in a
+/// single dispatch of `src/wgsl/main/{C::NAME}_derive_main.wgsl`. `write_secret_keys` fills the
+/// zero-initialised upload memory with `num_keys` 32-byte secret keys, and every public key in the
+/// output takes `public_key_len` bytes.
+pub async fn do_derive<C: CurveParams>(
+    context: &GpuContext,
+    num_keys: usize,
+    write_secret_keys: impl FnOnce(&mut [u8]),
+    public_key_len: usize,
+    table_limbs: &[u32],
+    log_limb_size: u32,
+) -> Result<Vec<u8>, crate::ShaderFailureError> {
+    // A quarantined device is not trusted with any call
+    if let Some(failure) = context.quarantined() {
+        return Err(failure.into());
+    }
+    if num_keys == 0 {
+        return Ok(vec![]);
+    }
+
+    assert!(num_keys <= 256 * 256 * 256 * 64);
+    let next_pow_2 = num_keys.next_power_of_two();
+    let (num_x_workgroups, num_y_workgroups, num_z_workgroups) =
+        compute_num_workgroups(next_pow_2, WORKGROUP_SIZE);
+    let params = [num_x_workgroups as u32, num_y_workgroups as u32, num_z_workgroups as u32];
+
+    let device = &context.device;
+    let pool = &context.pool;
+
+    let name = format!("{}_derive_public_keys", C::NAME);
+    let pipelines = context.cached_pipelines(&name, log_limb_size, &(), || {
+        let source = render_main::<C>(&format!("{}_derive_main.wgsl", C::NAME), log_limb_size);
+        vec![create_compute_pipeline(device, &source, &name)]
+    });
+    let compute_pipeline = &pipelines[0];
+
+    let table_buf = context.table_buffer(table_limbs);
+    let sk_buf = pool.acquire_sb(device, (32 * next_pow_2) as u64);
+    write_sb_with(&context.queue, &sk_buf, write_secret_keys);
+    let result_buf = pool.acquire_sb(device, (public_key_len * next_pow_2) as u64);
+    let canaries_buf = acquire_canaries(context, STAGE_LABELS.len());
+    let params_buf = pool.acquire_ub(device, std::mem::size_of_val(&params) as u64);
+    context.queue.write_buffer(&params_buf, 0, bytemuck::cast_slice(&params));
+
+    let bind_group = create_bind_group(
+        device,
+        compute_pipeline,
+        0,
+        &[table_buf.as_ref(), &sk_buf, &result_buf, &canaries_buf, &params_buf],
+    );
+
+    let mut command_encoder = create_command_encoder(device);
+    command_encoder.clear_buffer(&result_buf, 0, None);
+    command_encoder.clear_buffer(&canaries_buf, 0, None);
+    execute_pipeline(
+        &mut command_encoder,
+        compute_pipeline,
+        &bind_group,
+        params[0],
+        params[1],
+        params[2],
+    );
+
+    let mut results = context
+        .finish_encoder_and_read_bytes(command_encoder, &[&result_buf, &canaries_buf])
+        .await;
+
+    drop(bind_group);
+    for buffer in [sk_buf, result_buf, canaries_buf, params_buf] {
+        pool.release(buffer);
+    }
+
+    let num_invocations = canary::num_invocations((params[0], params[1], params[2]), WORKGROUP_SIZE);
+    canary::check(&STAGE_LABELS, [(0, num_invocations)], &results[1])?;
+
+    let mut public_keys = results.swap_remove(0);
+    public_keys.truncate(num_keys * public_key_len);
+    Ok(public_keys)
+}
@@ -1,25 +1,36 @@
 use crate::benchmarks::compute_num_workgroups;
+use crate::canary::{self, acquire_canaries};
+use crate::all_valid::{AllValidReduction, BatchVerdict, Bitmap};
+use crate::checkpoint::Checkpointing;
+use crate::compaction::{Compaction, Survivors};
+use crate::integrity::{check_call, NUM_KNOWN_ANSWERS};
 use crate::gpu::{
-    create_bind_group, create_command_encoder, create_compute_pipeline, create_empty_sb,
-    create_sb_with_data, create_ub_with_data, execute_pipeline,
-    finish_encoder_and_read_bytes_from_gpu, get_device_and_queue,
+    create_bind_group, create_bind_group_with_optional, create_command_encoder,
+    create_compute_pipeline, create_empty_sb, create_sb_with_data, create_sb_with_writer,
+    create_ub_with_data, execute_pipeline, finish_encoder_and_read_bytes_from_gpu,
+    get_device_and_queue, write_sb_with, BufferPool, Dispatch, GpuContext,
 };
-use crate::shader::render_ed25519_eddsa;
-use ed25519_dalek::{Signature, VerifyingKey};
+use crate::curves::{CurveParams, Ed25519};
+use crate::precompute::BaseTable;
+use crate::derive::do_derive;
+use crate::shader::{render_main, render_main_with_compaction, Coordinates, Reduction};
+use crate::streaming::Scheme;
+use crate::timings::{run_timed, StageTimings};
+use crate::Verdict;
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
 use fuel_crypto::Message;
 use multiprecision::utils::calc_num_limbs;
+use sha2::{Digest, Sha256};
+use std::time::Instant;
 
+/// Returns the batch size padded to the next power of 2, the number of limbs per big integer, and
+/// the number of workgroups to dispatch for `num_signatures` signatures.
 pub fn init(
-    signatures: &Vec<Signature>,
-    messages: &Vec<Message>,
-    verifying_keys: &Vec<VerifyingKey>,
+    num_signatures: usize,
     log_limb_size: u32,
-) ->
-    (usize, usize, usize, Vec<u32>, Vec<u32>, Vec<u32>, (u32, u32, u32))
-{
+) -> (usize, usize, (u32, u32, u32)) {
     let num_limbs = calc_num_limbs(log_limb_size, 256);
-    let num_signatures = signatures.len();
-    assert_eq!(num_signatures, messages.len());
+
     assert!(num_signatures <= 256 * 256 * 256 * 64);
 
     // Compute the next power of 2
@@ -27,242 +38,825 @@ pub fn init(
 
     assert!(num_signatures <= next_pow_2);
 
-    // Set up data for the input buffers
-
-    let mut all_sig_bytes = Vec::with_capacity(num_signatures * 64 * 8);
-    let mut all_pk_bytes = Vec::with_capacity(num_signatures * 32 * 8);
-    let mut all_msg_bytes: Vec<u8> = Vec::with_capacity(num_signatures * 32 * 8);
-
-    for i in 0..num_signatures {
-        let sig_bytes_be = signatures[i].to_bytes();
-        let pk_bytes_be = verifying_keys[i].to_bytes();
-        let msg_bytes = messages[i].as_slice();
-
-        all_sig_bytes.extend(sig_bytes_be);
-        all_pk_bytes.extend(pk_bytes_be);
-        all_msg_bytes.extend(msg_bytes);
-    }
-
-    if num_signatures < next_pow_2 {
-        all_sig_bytes.resize(next_pow_2 * 64, 0);
-        all_msg_bytes.resize(next_pow_2 * 32, 0);
-    }
-
     let workgroup_size = 256;
     let (num_x_workgroups, num_y_workgroups, num_z_workgroups) =
         compute_num_workgroups(next_pow_2, workgroup_size);
 
-    let all_sig_u32s: Vec<u32> = bytemuck::cast_slice(&all_sig_bytes).to_vec();
-    let all_msg_u32s: Vec<u32> = bytemuck::cast_slice(&all_msg_bytes).to_vec();
-    let all_pk_u32s: Vec<u32> = bytemuck::cast_slice(&all_pk_bytes).to_vec();
-
     let params = (
         num_x_workgroups as u32,
         num_y_workgroups as u32,
         num_z_workgroups as u32,
     );
-    (num_signatures, next_pow_2, num_limbs, all_sig_u32s, all_msg_u32s, all_pk_u32s, params)
+    (next_pow_2, num_limbs, params)
+}
+
+/// Writes 64-byte signatures into `dst`, which is usually the mapped range of an upload buffer.
+pub fn pack_signatures(dst: &mut [u8], signatures: &[Signature]) {
+    for (chunk, sig) in dst.chunks_exact_mut(64).zip(signatures) {
+        chunk.copy_from_slice(&sig.to_bytes());
+    }
+}
+
+/// Writes 32-byte messages into `dst`, which is usually the mapped range of an upload buffer.
+pub fn pack_messages(dst: &mut [u8], messages: &[Message]) {
+    for (chunk, msg) in dst.chunks_exact_mut(32).zip(messages) {
+        chunk.copy_from_slice(msg.as_slice());
+    }
+}
+
+/// Writes 32-byte compressed verifying keys into `dst`, which is usually the mapped range of an
+/// upload buffer.
+pub fn pack_verifying_keys(dst: &mut [u8], verifying_keys: &[VerifyingKey]) {
+    for (chunk, vk) in dst.chunks_exact_mut(32).zip(verifying_keys) {
+        chunk.copy_from_slice(vk.as_bytes());
+    }
 }
 
 pub async fn ecverify(
-    signatures: &Vec<Signature>,
-    messages: &Vec<Message>,
-    verifying_keys: &Vec<VerifyingKey>,
-    table_limbs: &Vec<u32>,
+    signatures: &[Signature],
+    messages: &[Message],
+    verifying_keys: &[VerifyingKey],
+    table_limbs: &[u32],
+    log_limb_size: u32,
+) -> Result<Vec<Verdict>, crate::ShaderFailureError> {
+    let context = GpuContext::new().await;
+    ecverify_with_context(&context, signatures, messages, verifying_keys, table_limbs, log_limb_size).await
+}
+
+/// Like `ecverify`, but runs on an existing context so that buffers are reused across calls.
+pub async fn ecverify_with_context(
+    context: &GpuContext,
+    signatures: &[Signature],
+    messages: &[Message],
+    verifying_keys: &[VerifyingKey],
+    table_limbs: &[u32],
+    log_limb_size: u32,
+) -> Result<Vec<Verdict>, crate::ShaderFailureError> {
+    assert_eq!(signatures.len(), messages.len());
+    assert_eq!(signatures.len(), verifying_keys.len());
+    let is_valid = do_ecverify(
+        context,
+        signatures.len(),
+        |buf| pack_signatures(buf, signatures),
+        |buf| pack_messages(buf, messages),
+        |buf| pack_verifying_keys(buf, verifying_keys),
+        |index| reference(&signatures[index], &messages[index], &verifying_keys[index]),
+        table_limbs,
+        log_limb_size,
+        &VerificationOptions::default(),
+        None,
+    )
+    .await?;
+    Ok(bytes_to_is_valid(&is_valid).into_iter().map(Verdict::from).collect())
+}
+
+/// Like `ecverify_with_context`, but takes a `BaseTable` and refuses it unless it was built for
+/// ed25519 and `log_limb_size`.
+pub async fn ecverify_with_table(
+    context: &GpuContext,
+    signatures: &[Signature],
+    messages: &[Message],
+    verifying_keys: &[VerifyingKey],
+    table: &BaseTable,
+    log_limb_size: u32,
+) -> Result<Vec<Verdict>, crate::PipelineError> {
+    table.check::<Ed25519>(log_limb_size)?;
+    let verdicts = ecverify_with_context(context, signatures, messages, verifying_keys, table.limbs(), log_limb_size).await?;
+    Ok(verdicts)
+}
+
+/// The options of the multi-shader verification pipeline, which combine freely. The defaults
+/// write a verdict per signature with a single dispatch per stage.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct VerificationOptions {
+    /// How every stage reduces products of base field elements. The table must come from
+    /// `precompute::ed25519_bases_with_reduction` with the same reduction.
+    pub reduction: Reduction,
+    /// How many sub-dispatches the scalar multiplications of stages 2 and 3 take.
+    pub checkpointing: Checkpointing,
+    /// Whether the stages after stage 0 run only over the signatures whose verifying key decodes
+    /// to a point. The others are invalid.
+    pub compaction: Compaction,
+    /// A reduction stage which summarises the verdicts, optionally with a bit per signature.
+    pub all_valid: Option<Bitmap>,
+}
+
+/// Like `ecverify_with_context`, but with any combination of `options`, and returns the raw
+/// output of the pipeline: a u32 per signature, which `bytes_to_is_valid` parses, or with
+/// `options.all_valid` the summary which `BatchVerdict::from_bytes` parses.
+pub async fn ecverify_with_options(
+    context: &GpuContext,
+    signatures: &[Signature],
+    messages: &[Message],
+    verifying_keys: &[VerifyingKey],
+    table_limbs: &[u32],
+    log_limb_size: u32,
+    options: &VerificationOptions,
+) -> Result<Vec<u8>, crate::PipelineError> {
+    assert_eq!(signatures.len(), messages.len());
+    assert_eq!(signatures.len(), verifying_keys.len());
+    options.reduction.check(&Ed25519::base_field_modulus(), log_limb_size)?;
+    let result = do_ecverify(
+        context,
+        signatures.len(),
+        |buf| pack_signatures(buf, signatures),
+        |buf| pack_messages(buf, messages),
+        |buf| pack_verifying_keys(buf, verifying_keys),
+        |index| reference(&signatures[index], &messages[index], &verifying_keys[index]),
+        table_limbs,
+        log_limb_size,
+        options,
+        None,
+    )
+    .await?;
+    Ok(result)
+}
+
+/// Like `ecverify_with_context`, but also reports how long the upload, each stage, and the
+/// readback took.
+pub async fn ecverify_with_timings(
+    context: &GpuContext,
+    signatures: &[Signature],
+    messages: &[Message],
+    verifying_keys: &[VerifyingKey],
+    table_limbs: &[u32],
+    log_limb_size: u32,
+) -> Result<(Vec<Verdict>, StageTimings), crate::ShaderFailureError> {
+    assert_eq!(signatures.len(), messages.len());
+    assert_eq!(signatures.len(), verifying_keys.len());
+    let mut timings = StageTimings::default();
+    let is_valid = do_ecverify(
+        context,
+        signatures.len(),
+        |buf| pack_signatures(buf, signatures),
+        |buf| pack_messages(buf, messages),
+        |buf| pack_verifying_keys(buf, verifying_keys),
+        |index| reference(&signatures[index], &messages[index], &verifying_keys[index]),
+        table_limbs,
+        log_limb_size,
+        &VerificationOptions::default(),
+        Some(&mut timings),
+    )
+    .await?;
+    Ok((bytes_to_is_valid(&is_valid).into_iter().map(Verdict::from).collect(), timings))
+}
+
+/// Derives the compressed verifying keys of signing keys in a single dispatch. The shader hashes
+/// each 32-byte secret key with SHA-512, clamps the first half of the hash into a scalar, and
+/// multiplies the base point by it with the fixed-base multiplication used in verification.
+pub async fn derive_public_keys(
+    signing_keys: &[SigningKey],
+    table_limbs: &[u32],
+    log_limb_size: u32,
+) -> Result<Vec<VerifyingKey>, crate::ShaderFailureError> {
+    let context = GpuContext::new().await;
+    derive_public_keys_with_context(&context, signing_keys, table_limbs, log_limb_size).await
+}
+
+/// Like `derive_public_keys`, but runs on an existing context so that buffers are reused across
+/// calls.
+pub async fn derive_public_keys_with_context(
+    context: &GpuContext,
+    signing_keys: &[SigningKey],
+    table_limbs: &[u32],
+    log_limb_size: u32,
+) -> Result<Vec<VerifyingKey>, crate::ShaderFailureError> {
+    let derived = do_derive::<Ed25519>(
+        context,
+        signing_keys.len(),
+        |buf| {
+            for (chunk, sk) in buf.chunks_exact_mut(32).zip(signing_keys) {
+                chunk.copy_from_slice(&sk.to_bytes());
+            }
+        },
+        32,
+        table_limbs,
+        log_limb_size,
+    )
+    .await?;
+    // Every signing key has a verifying key, so an invalid encoding means that the shader failed
+    derived
+        .chunks_exact(32)
+        .map(|vk_bytes| VerifyingKey::from_bytes(vk_bytes.try_into().unwrap()).ok())
+        .collect::<Option<Vec<_>>>()
+        .ok_or_else(crate::ShaderFailureError::default)
+}
+
+/// Verifies signatures given as flat buffers of 64-byte signatures, 32-byte messages, and 32-byte
+/// compressed verifying keys.
+pub async fn ecverify_bytes(
+    signatures: &[u8],
+    messages: &[u8],
+    verifying_keys: &[u8],
+    table_limbs: &[u32],
     log_limb_size: u32,
 ) -> Result<Vec<bool>, crate::ShaderFailureError> {
-    let (num_signatures, next_pow_2, num_limbs, all_sig_u32s, all_msg_u32s, all_pk_u32s, params_t) = init(&signatures, &messages, &verifying_keys, log_limb_size);
-    let (num_x_workgroups, num_y_workgroups, num_z_workgroups) = params_t;
-    let params = &[
-        num_x_workgroups as u32,
-        num_y_workgroups as u32,
-        num_z_workgroups as u32,
-    ];
+    let context = GpuContext::new().await;
+    ecverify_bytes_with_context(&context, signatures, messages, verifying_keys, table_limbs, log_limb_size).await
+}
 
+/// Like `ecverify_bytes`, but runs on an existing context so that buffers are reused across calls.
+pub async fn ecverify_bytes_with_context(
+    context: &GpuContext,
+    signatures: &[u8],
+    messages: &[u8],
+    verifying_keys: &[u8],
+    table_limbs: &[u32],
+    log_limb_size: u32,
+) -> Result<Vec<bool>, crate::ShaderFailureError> {
+    assert_eq!(signatures.len() % 64, 0);
+    assert_eq!(messages.len() % 32, 0);
+    assert_eq!(verifying_keys.len() % 32, 0);
+    assert_eq!(signatures.len() / 64, messages.len() / 32);
+    assert_eq!(signatures.len() / 64, verifying_keys.len() / 32);
+
+    let is_valid = do_ecverify(
+        context,
+        signatures.len() / 64,
+        |buf| buf[..signatures.len()].copy_from_slice(signatures),
+        |buf| buf[..messages.len()].copy_from_slice(messages),
+        |buf| buf[..verifying_keys.len()].copy_from_slice(verifying_keys),
+        |index| {
+            let message = &messages[32 * index..][..32];
+            reference_bytes(&signatures[64 * index..][..64], message, &verifying_keys[32 * index..][..32])
+        },
+        table_limbs,
+        log_limb_size,
+        &VerificationOptions::default(),
+        None,
+    )
+    .await?;
+    Ok(bytes_to_is_valid(&is_valid))
+}
+
+/// Like `ecverify_with_context`, but only reads back whether every signature is valid, the index
+/// of the first which is not, and with `Bitmap::Packed` a bit per signature. A reduction stage on
+/// the GPU computes them from the verdicts.
+pub async fn ecverify_all_valid(
+    context: &GpuContext,
+    signatures: &[Signature],
+    messages: &[Message],
+    verifying_keys: &[VerifyingKey],
+    table_limbs: &[u32],
+    log_limb_size: u32,
+    bitmap: Bitmap,
+) -> Result<BatchVerdict, crate::ShaderFailureError> {
+    assert_eq!(signatures.len(), messages.len());
+    assert_eq!(signatures.len(), verifying_keys.len());
+    if signatures.is_empty() {
+        return Ok(BatchVerdict { first_failure: None, bitmap: bitmap.is_on().then(Vec::new) });
+    }
+    let summary = do_ecverify(
+        context,
+        signatures.len(),
+        |buf| pack_signatures(buf, signatures),
+        |buf| pack_messages(buf, messages),
+        |buf| pack_verifying_keys(buf, verifying_keys),
+        |index| reference(&signatures[index], &messages[index], &verifying_keys[index]),
+        table_limbs,
+        log_limb_size,
+        &VerificationOptions { all_valid: Some(bitmap), ..Default::default() },
+        None,
+    )
+    .await?;
+    Ok(BatchVerdict::from_bytes(&summary, signatures.len(), bitmap))
+}
+
+/// Parses the output of the last stage, a u32 per signature which is 1 if it is valid.
+pub fn bytes_to_is_valid(is_valid: &[u8]) -> Vec<bool> {
+    is_valid
+        .chunks_exact(std::mem::size_of::<u32>())
+        .map(|chunk| chunk[0] == 1)
+        .collect()
+}
+
+/// Runs the multi-shader verification pipeline with `options`. Every buffer comes from the
+/// context's pool, and the `write_*` closures fill the zero-initialised upload memory in place so
+/// that the inputs are copied only once on the host. `reference` verifies a signature on the CPU,
+/// for the sample which the context's `Integrity` checks. With `options.all_valid`, the summary of
+/// the reduction stage is returned instead of the verdicts. If `timings` is given, each stage is
+/// timed.
+#[allow(clippy::too_many_arguments)]
+async fn do_ecverify(
+    context: &GpuContext,
+    num_signatures: usize,
+    write_signatures: impl FnOnce(&mut [u8]),
+    write_messages: impl FnOnce(&mut [u8]),
+    write_verifying_keys: impl FnOnce(&mut [u8]),
+    reference: impl Fn(usize) -> Verdict,
+    table_limbs: &[u32],
+    log_limb_size: u32,
+    options: &VerificationOptions,
+    mut timings: Option<&mut StageTimings>,
+) -> Result<Vec<u8>, crate::ShaderFailureError> {
+    // A quarantined device is not trusted with any batch
+    if let Some(failure) = context.quarantined() {
+        return Err(failure.into());
+    }
     if num_signatures == 0 {
         return Ok(vec![]);
     }
 
-    let (device, queue) = get_device_and_queue().await;
-    let mut command_encoder = create_command_encoder(&device);
+    let (next_pow_2, _num_limbs, _params) = init(num_signatures, log_limb_size);
+
+    let device = &context.device;
+    let pipelines = context.cached_pipelines("ed25519_ecverify", log_limb_size, options, || {
+        create_pipelines(device, log_limb_size, options)
+    });
+
+    let sw = Instant::now();
+    let table_buf = context.table_buffer(table_limbs);
+    let sig_buf = context.pool.acquire_sb(device, (64 * next_pow_2) as u64);
+    write_sb_with(&context.queue, &sig_buf, write_signatures);
+    let pk_buf = context.pool.acquire_sb(device, (32 * next_pow_2) as u64);
+    write_sb_with(&context.queue, &pk_buf, write_verifying_keys);
+    let msg_buf = context.pool.acquire_sb(device, (32 * next_pow_2) as u64);
+    write_sb_with(&context.queue, &msg_buf, write_messages);
+    let upload = sw.elapsed();
+
+    let buffers = Buffers::new(
+        context,
+        &pipelines,
+        &table_buf,
+        sig_buf,
+        pk_buf,
+        msg_buf,
+        next_pow_2,
+        log_limb_size,
+        options,
+    );
 
-    // Stage 0
-    let sig_buf = create_sb_with_data(&device, &all_sig_u32s);
-    let pk_buf = create_sb_with_data(&device, &all_pk_u32s);
-    let msg_buf = create_sb_with_data(&device, &all_msg_u32s);
-    let s_buf = create_empty_sb(&device, (next_pow_2 * num_limbs * std::mem::size_of::<u32>()) as u64);
-    let ayr_buf = create_empty_sb(&device, (next_pow_2 * num_limbs * std::mem::size_of::<u32>()) as u64);
-    let preimage_buf = create_empty_sb(&device, (next_pow_2 * 24 * std::mem::size_of::<u32>()) as u64);
-    let compressed_sign_bit_buf = create_empty_sb(&device, (next_pow_2 * std::mem::size_of::<u32>()) as u64);
-    let params_buf = create_ub_with_data(&device, params);
+    let reduction = options.all_valid.map(|bitmap| {
+        AllValidReduction::new::<Ed25519>(
+            context,
+            &buffers.is_valid_buf,
+            num_signatures,
+            next_pow_2,
+            bitmap,
+            log_limb_size,
+        )
+    });
+
+    let mut command_encoder = create_command_encoder(device);
+    clear_outputs(&mut command_encoder, &buffers);
+    let mut dispatches = dispatches(&pipelines, &buffers);
+    if let Some(reduction) = &reduction {
+        reduction.clear(&mut command_encoder);
+        dispatches.push(reduction.dispatch());
+    }
 
-    let source = render_ed25519_eddsa("ed25519_eddsa_main_0.wgsl", log_limb_size);
-    let compute_pipeline = create_compute_pipeline(&device, &source, "ed25519_verify_main_0");
+    let command_encoder = match timings.as_mut() {
+        Some(timings) => {
+            let (source, stages) = run_timed(context, command_encoder, &dispatches).await;
+            timings.source = source;
+            timings.upload = upload;
+            timings.stages = stages;
+            create_command_encoder(device)
+        }
+        None => {
+            for dispatch in &dispatches {
+                dispatch.execute(&mut command_encoder);
+            }
+            command_encoder
+        }
+    };
+
+    // With a reduction stage, only its summary is read back
+    // With compaction, the number of workgroups of the indirect dispatches is needed to check the
+    // completion counters
+    let output_buf = reduction.as_ref().map_or(&buffers.is_valid_buf, |reduction| &reduction.summary_buf);
+    let mut readback_bufs = vec![output_buf, &buffers.canaries_buf];
+    readback_bufs.extend(buffers.survivors.as_ref().map(|survivors| &survivors.indirect_buf));
+    let sw = Instant::now();
+    let mut results = context.finish_encoder_and_read_bytes(command_encoder, &readback_bufs).await;
+    if let Some(timings) = timings {
+        timings.readback = sw.elapsed();
+    }
 
-    let bind_group = create_bind_group(
-        &device,
-        &compute_pipeline,
-        0,
-        &[&sig_buf, &pk_buf, &msg_buf, &s_buf, &ayr_buf, &preimage_buf, &compressed_sign_bit_buf, &params_buf],
-    );
+    let checked = buffers.check_canaries(&results[1], results.get(2).map(Vec::as_slice));
+    drop(dispatches);
+    buffers.release(&context.pool);
+    if let Some(reduction) = reduction {
+        reduction.release(&context.pool);
+    }
+    checked?;
 
-    execute_pipeline(
-        &mut command_encoder,
-        &compute_pipeline,
-        &bind_group,
-        num_x_workgroups as u32,
-        num_y_workgroups as u32,
-        num_z_workgroups as u32,
-    );
+    let mut all_is_valid = results.swap_remove(0);
+    if options.all_valid.is_none() {
+        all_is_valid.truncate(num_signatures * std::mem::size_of::<u32>());
+    }
 
-    // Stage 1
-    let source = render_ed25519_eddsa("ed25519_eddsa_main_1.wgsl", log_limb_size);
-    let compute_pipeline = create_compute_pipeline(&device, &source, "ed25519_verify_main_1");
+    let batch_verdict = options
+        .all_valid
+        .map(|bitmap| BatchVerdict::from_bytes(&all_is_valid, num_signatures, bitmap));
+    check_call(
+        context,
+        num_signatures,
+        |index| match &batch_verdict {
+            Some(batch_verdict) => batch_verdict.known_verdict(index),
+            None => Some(Verdict::from(all_is_valid[index * std::mem::size_of::<u32>()] == 1)),
+        },
+        reference,
+    )?;
+    Ok(all_is_valid)
+}
 
-    let k_buf = create_empty_sb(&device, (next_pow_2 * num_limbs * std::mem::size_of::<u32>()) as u64);
+/// Verifies a signature on the CPU.
+fn reference(signature: &Signature, message: &Message, verifying_key: &VerifyingKey) -> Verdict {
+    Verdict::from(verifying_key.verify(message.as_slice(), signature).is_ok())
+}
 
-    let bind_group = create_bind_group(
-        &device,
-        &compute_pipeline,
-        0,
-        &[&preimage_buf, &k_buf, &params_buf],
-    );
+/// Like `reference`, but for a 64-byte signature, a 32-byte message, and a 32-byte compressed
+/// verifying key, which is invalid if it does not decode to a point.
+fn reference_bytes(signature: &[u8], message: &[u8], verifying_key: &[u8]) -> Verdict {
+    let signature = Signature::from_bytes(signature.try_into().unwrap());
+    match VerifyingKey::from_bytes(verifying_key.try_into().unwrap()) {
+        Ok(verifying_key) => Verdict::from(verifying_key.verify(message, &signature).is_ok()),
+        Err(_) => Verdict::Invalid,
+    }
+}
 
-    execute_pipeline(
-        &mut command_encoder,
-        &compute_pipeline,
-        &bind_group,
-        num_x_workgroups as u32,
-        num_y_workgroups as u32,
-        num_z_workgroups as u32,
-    );
+/// Compiles the stages of the multi-shader verification pipeline with `options`, in order. The
+/// compaction stage, if any, comes last. Its buffers must come from `Buffers::new` with the same
+/// options.
+pub fn create_pipelines(
+    device: &wgpu::Device,
+    log_limb_size: u32,
+    options: &VerificationOptions,
+) -> Vec<wgpu::ComputePipeline> {
+    let VerificationOptions { reduction, checkpointing, compaction, .. } = *options;
+    let mut pipelines: Vec<wgpu::ComputePipeline> = (0..6)
+        .map(|i| {
+            let source = render_main_with_compaction::<Ed25519>(
+                &format!("ed25519_eddsa_main_{}.wgsl", i),
+                log_limb_size,
+                Coordinates::default(),
+                checkpointing,
+                compaction,
+                reduction,
+            );
+            create_compute_pipeline(device, &source, &format!("ed25519_verify_main_{}", i))
+        })
+        .collect();
+    pipelines.extend(compaction.create_pipeline::<Ed25519>(device, log_limb_size, COMPACTION_STAGE));
+    pipelines
+}
 
-    execute_pipeline(
-        &mut command_encoder,
-        &compute_pipeline,
-        &bind_group,
-        num_x_workgroups as u32,
-        num_y_workgroups as u32,
-        num_z_workgroups as u32,
-    );
+/// The buffers and bind groups of the multi-shader verification pipeline for a batch of up to
+/// `next_pow_2` signatures. They can be reused for any batch which pads to the same size.
+pub struct Buffers {
+    pub next_pow_2: usize,
+    pub sig_buf: wgpu::Buffer,
+    pub pk_buf: wgpu::Buffer,
+    pub msg_buf: wgpu::Buffer,
+    pub is_valid_buf: wgpu::Buffer,
+    /// The completion counter of each stage.
+    pub canaries_buf: wgpu::Buffer,
+    params: (u32, u32, u32),
+    /// The stage of each dispatch, in order. Stage 1 is dispatched twice, stages 2 and 3 repeat
+    /// with `Checkpointing`, and the compaction stage follows stage 0 with `Compaction::Indirect`.
+    stage_order: Vec<usize>,
+    /// The checkpoints of the split stages, which must be zeroed before each batch.
+    checkpoint_bufs: Vec<wgpu::Buffer>,
+    /// The survivors of stage 0, with `Compaction::Indirect`.
+    survivors: Option<Survivors>,
+    intermediate_bufs: Vec<wgpu::Buffer>,
+    bind_groups: Vec<wgpu::BindGroup>,
+}
 
-    // Stage 2
-    let source = render_ed25519_eddsa("ed25519_eddsa_main_2.wgsl", log_limb_size);
-    let compute_pipeline = create_compute_pipeline(&device, &source, "ed25519_verify_main_2");
+impl Buffers {
+    /// Acquires the parameter, intermediate, and output buffers from the context's pool, for
+    /// pipelines from `create_pipelines` with the same `options`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        context: &GpuContext,
+        pipelines: &[wgpu::ComputePipeline],
+        table_buf: &wgpu::Buffer,
+        sig_buf: wgpu::Buffer,
+        pk_buf: wgpu::Buffer,
+        msg_buf: wgpu::Buffer,
+        next_pow_2: usize,
+        log_limb_size: u32,
+        options: &VerificationOptions,
+    ) -> Self {
+        let VerificationOptions { checkpointing, compaction, .. } = *options;
+        let device = &context.device;
+        let pool = &context.pool;
+        let (_, num_limbs, params) = init(next_pow_2, log_limb_size);
+
+        let params_buf = pool.acquire_ub(device, (3 * std::mem::size_of::<u32>()) as u64);
+        context.queue.write_buffer(&params_buf, 0, bytemuck::cast_slice(&[params.0, params.1, params.2]));
+
+        let s_buf = pool.acquire_sb(device, (next_pow_2 * num_limbs * std::mem::size_of::<u32>()) as u64);
+        let ayr_buf = pool.acquire_sb(device, (next_pow_2 * num_limbs * std::mem::size_of::<u32>()) as u64);
+        let preimage_buf = pool.acquire_sb(device, (next_pow_2 * 24 * std::mem::size_of::<u32>()) as u64);
+        let k_buf = pool.acquire_sb(device, (next_pow_2 * num_limbs * std::mem::size_of::<u32>()) as u64);
+        let gs_buf = pool.acquire_sb(device, (next_pow_2 * num_limbs * 4 * std::mem::size_of::<u32>()) as u64);
+        let neg_ak_buf = pool.acquire_sb(device, (next_pow_2 * num_limbs * 4 * std::mem::size_of::<u32>()) as u64);
+        let pt_buf = pool.acquire_sb(device, (next_pow_2 * num_limbs * 2 * std::mem::size_of::<u32>()) as u64);
+        let canaries_buf = acquire_canaries(context, STAGE_LABELS.len());
+        let is_valid_buf = pool.acquire_sb(device, (next_pow_2 * std::mem::size_of::<u32>()) as u64);
+
+        // Each checkpoint holds two points, an infinity flag, and the bit position
+        let checkpoint_size = (num_limbs * 8 + 2) * next_pow_2 * std::mem::size_of::<u32>();
+        let gs_checkpoint_buf = checkpointing.is_on().then(|| pool.acquire_sb(device, checkpoint_size as u64));
+        let neg_ak_checkpoint_buf = checkpointing.is_on().then(|| pool.acquire_sb(device, checkpoint_size as u64));
+        let num_dispatches = checkpointing.num_dispatches();
+
+        let survivors = compaction.acquire_survivors(context, next_pow_2);
+        let survivors_buf = survivors.as_ref().map(|survivors| &survivors.ids_buf);
+        let compaction_stage = if survivors.is_some() { vec![COMPACTION_STAGE] } else { vec![] };
+        let stage_order = [vec![0], compaction_stage, vec![1, 1], vec![2; num_dispatches], vec![3; num_dispatches], vec![4, 5]].concat();
+
+        let mut bind_groups = vec![
+            // Stage 0
+            create_bind_group_with_optional(device, &pipelines[0], &[&sig_buf, &pk_buf, &msg_buf, &s_buf, &ayr_buf, &preimage_buf, &canaries_buf], &[survivors_buf], &params_buf),
+            // Stage 1
+            create_bind_group_with_optional(device, &pipelines[1], &[&preimage_buf, &k_buf, &canaries_buf], &[survivors_buf], &params_buf),
+            // Stage 2
+            create_bind_group_with_optional(device, &pipelines[2], &[table_buf, &s_buf, &gs_buf, &canaries_buf], &[gs_checkpoint_buf.as_ref(), survivors_buf], &params_buf),
+            // Stage 3
+            create_bind_group_with_optional(device, &pipelines[3], &[&s_buf, &ayr_buf, &k_buf, &pk_buf, &neg_ak_buf, &canaries_buf], &[neg_ak_checkpoint_buf.as_ref(), survivors_buf], &params_buf),
+            // Stage 4
+            create_bind_group_with_optional(device, &pipelines[4], &[&gs_buf, &neg_ak_buf, &pt_buf, &canaries_buf], &[survivors_buf], &params_buf),
+            // Stage 5
+            create_bind_group_with_optional(device, &pipelines[5], &[&pt_buf, &is_valid_buf, &sig_buf, &canaries_buf], &[survivors_buf], &params_buf),
+        ];
+        if let Some(survivors) = &survivors {
+            bind_groups.push(survivors.create_bind_group(device, &pipelines[COMPACTION_STAGE], &canaries_buf));
+        }
+
+        Self {
+            next_pow_2,
+            sig_buf,
+            pk_buf,
+            msg_buf,
+            is_valid_buf,
+            canaries_buf,
+            params,
+            stage_order,
+            checkpoint_bufs: gs_checkpoint_buf.into_iter().chain(neg_ak_checkpoint_buf).collect(),
+            survivors,
+            intermediate_bufs: vec![params_buf, s_buf, ayr_buf, preimage_buf, k_buf, gs_buf, neg_ak_buf, pt_buf],
+            bind_groups,
+        }
+    }
 
-    let table_buf = create_sb_with_data(&device, table_limbs);
-    let gs_buf = create_empty_sb(&device, (next_pow_2 * num_limbs * 4 * std::mem::size_of::<u32>()) as u64);
+    /// Returns every buffer to the pool. The GPU must no longer be using them.
+    pub fn release(self, pool: &BufferPool) {
+        drop(self.bind_groups);
+        for buffer in [self.sig_buf, self.pk_buf, self.msg_buf, self.is_valid_buf, self.canaries_buf] {
+            pool.release(buffer);
+        }
+        for buffer in self.checkpoint_bufs.into_iter().chain(self.intermediate_bufs) {
+            pool.release(buffer);
+        }
+        if let Some(survivors) = self.survivors {
+            survivors.release(pool);
+        }
+    }
 
-    let bind_group = create_bind_group(
-        &device,
-        &compute_pipeline,
-        0,
-        &[&table_buf, &s_buf, &ayr_buf, &k_buf, &compressed_sign_bit_buf, &gs_buf, &params_buf],
-    );
+    /// Whether `stage` runs with an indirect dispatch over the survivors of stage 0.
+    fn is_compacted(&self, stage: usize) -> bool {
+        self.survivors.is_some() && stage != 0 && stage != COMPACTION_STAGE
+    }
 
-    execute_pipeline(
-        &mut command_encoder,
-        &compute_pipeline,
-        &bind_group,
-        num_x_workgroups as u32,
-        num_y_workgroups as u32,
-        num_z_workgroups as u32,
-    );
+    /// Checks the completion counters which the stages left in `canaries` against the number of
+    /// invocations of their dispatches. With compaction, `indirect` must hold the number of
+    /// workgroups which the compaction stage wrote for the indirect dispatches.
+    pub fn check_canaries(&self, canaries: &[u8], indirect: Option<&[u8]>) -> Result<(), crate::ShaderFailureError> {
+        let indirect_num_workgroups = indirect.map(canary::parse_num_workgroups);
+        let dispatches = self.stage_order.iter().map(|&stage| {
+            let num_invocations = match (stage, indirect_num_workgroups) {
+                (COMPACTION_STAGE, _) => 1,
+                (_, Some(num_workgroups)) if self.is_compacted(stage) => {
+                    canary::num_invocations(num_workgroups, WORKGROUP_SIZE)
+                }
+                _ => canary::num_invocations(self.params, WORKGROUP_SIZE),
+            };
+            (stage, num_invocations)
+        });
+        canary::check(&STAGE_LABELS, dispatches, canaries)
+    }
+}
 
-    // Stage 3
-    let source = render_ed25519_eddsa("ed25519_eddsa_main_3.wgsl", log_limb_size);
-    let compute_pipeline = create_compute_pipeline(&device, &source, "ed25519_verify_main_3");
+/// What each stage of the multi-shader verification pipeline computes, in order.
+const STAGE_LABELS: [&str; 7] = [
+    "decode inputs",
+    "hash to scalar k",
+    "fixed-base mul s * G",
+    "variable-base mul k * -A",
+    "point addition and affine conversion",
+    "compress and compare with R",
+    "compact the survivors of stage 0",
+];
+
+/// The index of the compaction stage, which runs after stage 0 with `Compaction::Indirect`.
+const COMPACTION_STAGE: usize = 6;
+
+/// The workgroup size of every stage other than the compaction stage.
+const WORKGROUP_SIZE: usize = 256;
+
+/// Returns the dispatches of the multi-shader verification pipeline, in order.
+pub fn dispatches<'a>(pipelines: &'a [wgpu::ComputePipeline], buffers: &'a Buffers) -> Vec<Dispatch<'a>> {
+    buffers
+        .stage_order
+        .iter()
+        .map(|&stage| Dispatch {
+            label: STAGE_LABELS[stage],
+            compute_pipeline: &pipelines[stage],
+            bind_group: &buffers.bind_groups[stage],
+            num_workgroups: if stage == COMPACTION_STAGE { (1, 1, 1) } else { buffers.params },
+            // Every stage after stage 0 runs over its survivors
+            indirect_buffer: buffers
+                .survivors
+                .as_ref()
+                .filter(|_| buffers.is_compacted(stage))
+                .map(|survivors| &survivors.indirect_buf),
+        })
+        .collect()
+}
 
-    let neg_ak_buf = create_empty_sb(&device, (next_pow_2 * num_limbs * 4 * std::mem::size_of::<u32>()) as u64);
+/// Clears the output, completion counter, checkpoint, and survivor buffers, which may be reused
+/// and hold the results of a previous batch.
+pub fn clear_outputs(command_encoder: &mut wgpu::CommandEncoder, buffers: &Buffers) {
+    command_encoder.clear_buffer(&buffers.is_valid_buf, 0, None);
+    command_encoder.clear_buffer(&buffers.canaries_buf, 0, None);
+    for checkpoint_buf in &buffers.checkpoint_bufs {
+        command_encoder.clear_buffer(checkpoint_buf, 0, None);
+    }
+    if let Some(survivors) = &buffers.survivors {
+        survivors.clear(command_encoder);
+    }
+}
 
-    let bind_group = create_bind_group(
-        &device,
-        &compute_pipeline,
-        0,
-        &[&s_buf, &ayr_buf, &k_buf, &compressed_sign_bit_buf, &neg_ak_buf, &params_buf],
-    );
+/// Records every stage of the multi-shader verification pipeline into `command_encoder`.
+pub fn encode_stages(
+    command_encoder: &mut wgpu::CommandEncoder,
+    pipelines: &[wgpu::ComputePipeline],
+    buffers: &Buffers,
+) {
+    clear_outputs(command_encoder, buffers);
+    for dispatch in dispatches(pipelines, buffers) {
+        dispatch.execute(command_encoder);
+    }
+}
 
-    execute_pipeline(
-        &mut command_encoder,
-        &compute_pipeline,
-        &bind_group,
-        num_x_workgroups as u32,
-        num_y_workgroups as u32,
-        num_z_workgroups as u32,
-    );
+/// ed25519 EdDSA signature verification for use with the streaming `Verifier`.
+pub struct Verification {
+    pipelines: Vec<wgpu::ComputePipeline>,
+    table_buf: wgpu::Buffer,
+    log_limb_size: u32,
+}
 
-    // Stage 4
-    let source = render_ed25519_eddsa("ed25519_eddsa_main_4.wgsl", log_limb_size);
-    let compute_pipeline = create_compute_pipeline(&device, &source, "ed25519_verify_main_4");
+impl Verification {
+    pub fn new(device: &wgpu::Device, table_limbs: &[u32], log_limb_size: u32) -> Self {
+        Self {
+            pipelines: create_pipelines(device, log_limb_size, &VerificationOptions::default()),
+            table_buf: create_sb_with_data(device, table_limbs),
+            log_limb_size,
+        }
+    }
+}
 
-    let pt_buf = create_empty_sb(&device, (next_pow_2 * num_limbs * 2 * std::mem::size_of::<u32>()) as u64);
+impl Scheme for Verification {
+    type Batch = (Vec<Signature>, Vec<Message>, Vec<VerifyingKey>);
+    type Output = Vec<Verdict>;
+    type Buffers = Buffers;
+    type Item = Verdict;
 
-    let bind_group = create_bind_group(
-        &device,
-        &compute_pipeline,
-        0,
-        &[&gs_buf, &neg_ak_buf, &pt_buf, &params_buf],
-    );
+    fn num_signatures(batch: &Self::Batch) -> usize {
+        assert_eq!(batch.0.len(), batch.1.len());
+        assert_eq!(batch.0.len(), batch.2.len());
+        batch.0.len()
+    }
 
-    execute_pipeline(
-        &mut command_encoder,
-        &compute_pipeline,
-        &bind_group,
-        num_x_workgroups as u32,
-        num_y_workgroups as u32,
-        num_z_workgroups as u32,
-    );
+    fn create_buffers(&self, context: &GpuContext, next_pow_2: usize) -> Buffers {
+        let sig_buf = context.pool.acquire_sb(&context.device, (64 * next_pow_2) as u64);
+        let pk_buf = context.pool.acquire_sb(&context.device, (32 * next_pow_2) as u64);
+        let msg_buf = context.pool.acquire_sb(&context.device, (32 * next_pow_2) as u64);
+        Buffers::new(
+            context,
+            &self.pipelines,
+            &self.table_buf,
+            sig_buf,
+            pk_buf,
+            msg_buf,
+            next_pow_2,
+            self.log_limb_size,
+            &VerificationOptions::default(),
+        )
+    }
 
-    // Stage 5
-    let source = render_ed25519_eddsa("ed25519_eddsa_main_5.wgsl", log_limb_size);
-    let compute_pipeline = create_compute_pipeline(&device, &source, "ed25519_verify_main_5");
+    fn release_buffers(buffers: Buffers, pool: &BufferPool) {
+        buffers.release(pool);
+    }
 
-    let success_buf = create_empty_sb(&device, std::mem::size_of::<u32>() as u64);
-    let is_valid_buf = create_empty_sb(&device, (next_pow_2 * std::mem::size_of::<u32>()) as u64);
+    fn write_batch(queue: &wgpu::Queue, buffers: &Buffers, batch: &Self::Batch) {
+        let (signatures, messages, verifying_keys) = batch;
+        write_sb_with(queue, &buffers.sig_buf, |buf| pack_signatures(buf, signatures));
+        write_sb_with(queue, &buffers.pk_buf, |buf| pack_verifying_keys(buf, verifying_keys));
+        write_sb_with(queue, &buffers.msg_buf, |buf| pack_messages(buf, messages));
+    }
 
-    let bind_group = create_bind_group(
-        &device,
-        &compute_pipeline,
-        0,
-        &[&pt_buf, &is_valid_buf, &sig_buf, &success_buf, &params_buf],
-    );
+    fn encode(&self, command_encoder: &mut wgpu::CommandEncoder, buffers: &Buffers) {
+        encode_stages(command_encoder, &self.pipelines, buffers);
+    }
 
-    execute_pipeline(
-        &mut command_encoder,
-        &compute_pipeline,
-        &bind_group,
-        num_x_workgroups as u32,
-        num_y_workgroups as u32,
-        num_z_workgroups as u32,
-    );
+    fn output_buffers(buffers: &Buffers) -> [&wgpu::Buffer; 2] {
+        [&buffers.is_valid_buf, &buffers.canaries_buf]
+    }
 
-    let results = finish_encoder_and_read_bytes_from_gpu(
-        &device,
-        &queue,
-        Box::new(command_encoder),
-        &[is_valid_buf, success_buf],
-    )
-    .await;
+    fn check_canaries(buffers: &Buffers, canaries: &[u8]) -> Result<(), crate::ShaderFailureError> {
+        buffers.check_canaries(canaries, None)
+    }
 
-    if results[1][0] != 1 {
-        return Err(crate::ShaderFailureError);
+    fn decode(num_signatures: usize, results: &[u8]) -> Self::Output {
+        (0..num_signatures)
+            .map(|i| Verdict::from(results[i * 4] == 1))
+            .collect()
     }
 
-    let mut all_is_valid: Vec<bool> = Vec::with_capacity(num_signatures);
-    for i in 0..num_signatures {
-        all_is_valid.push(results[0][i * 4] == 1);
+    fn item(output: &Self::Output, index: usize) -> Self::Item {
+        output[index]
     }
 
-    Ok(all_is_valid)
+    fn reference(batch: &Self::Batch, index: usize) -> Self::Item {
+        let (signatures, messages, verifying_keys) = batch;
+        Verdict::from(verifying_keys[index].verify(messages[index].as_slice(), &signatures[index]).is_ok())
+    }
+
+    fn known_answers() -> Self::Batch {
+        let mut signatures = Vec::with_capacity(NUM_KNOWN_ANSWERS);
+        let mut messages = Vec::with_capacity(NUM_KNOWN_ANSWERS);
+        let mut verifying_keys = Vec::with_capacity(NUM_KNOWN_ANSWERS);
+        for i in 0..NUM_KNOWN_ANSWERS {
+            let seed: [u8; 32] = Sha256::digest((i as u64).to_le_bytes()).into();
+            let signing_key = SigningKey::from_bytes(&seed);
+            let message = Message::new((i as u64).to_be_bytes());
+            signatures.push(signing_key.sign(message.as_slice()));
+            messages.push(message);
+            verifying_keys.push(signing_key.verifying_key());
+        }
+        // The first two signatures are invalid
+        messages.swap(0, 1);
+        (signatures, messages, verifying_keys)
+    }
 }
 
 pub async fn ecverify_single(
-    signatures: &Vec<Signature>,
-    messages: &Vec<Message>,
-    verifying_keys: &Vec<VerifyingKey>,
+    signatures: &[Signature],
+    messages: &[Message],
+    verifying_keys: &[VerifyingKey],
+    log_limb_size: u32,
+) -> Result<Vec<Verdict>, crate::ShaderFailureError> {
+    assert_eq!(signatures.len(), messages.len());
+    assert_eq!(signatures.len(), verifying_keys.len());
+    let is_valid = do_ecverify_single(
+        signatures.len(),
+        |buf| pack_signatures(buf, signatures),
+        |buf| pack_messages(buf, messages),
+        |buf| pack_verifying_keys(buf, verifying_keys),
+        log_limb_size,
+    )
+    .await?;
+    Ok(is_valid.into_iter().map(Verdict::from).collect())
+}
+
+pub async fn ecverify_single_bytes(
+    signatures: &[u8],
+    messages: &[u8],
+    verifying_keys: &[u8],
     log_limb_size: u32,
 ) -> Result<Vec<bool>, crate::ShaderFailureError> {
-    let (num_signatures, next_pow_2, _num_limbs, all_sig_u32s, all_msg_u32s, all_pk_u32s, params_t) = init(&signatures, &messages, &verifying_keys, log_limb_size);
+    assert_eq!(signatures.len() % 64, 0);
+    assert_eq!(messages.len() % 32, 0);
+    assert_eq!(verifying_keys.len() % 32, 0);
+    assert_eq!(signatures.len() / 64, messages.len() / 32);
+    assert_eq!(signatures.len() / 64, verifying_keys.len() / 32);
+
+    do_ecverify_single(
+        signatures.len() / 64,
+        |buf| buf[..signatures.len()].copy_from_slice(signatures),
+        |buf| buf[..messages.len()].copy_from_slice(messages),
+        |buf| buf[..verifying_keys.len()].copy_from_slice(verifying_keys),
+        log_limb_size,
+    )
+    .await
+}
+
+async fn do_ecverify_single(
+    num_signatures: usize,
+    write_signatures: impl FnOnce(&mut [u8]),
+    write_messages: impl FnOnce(&mut [u8]),
+    write_verifying_keys: impl FnOnce(&mut [u8]),
+    log_limb_size: u32,
+) -> Result<Vec<bool>, crate::ShaderFailureError> {
+    if num_signatures == 0 {
+        return Ok(vec![]);
+    }
+
+    let (next_pow_2, _num_limbs, params_t) = init(num_signatures, log_limb_size);
     let (num_x_workgroups, num_y_workgroups, num_z_workgroups) = params_t;
     let params = &[
         num_x_workgroups as u32,
@@ -270,21 +864,17 @@ pub async fn ecverify_single(
         num_z_workgroups as u32,
     ];
 
-    if num_signatures == 0 {
-        return Ok(vec![]);
-    }
-
     let (device, queue) = get_device_and_queue().await;
     let mut command_encoder = create_command_encoder(&device);
 
-    let sig_buf = create_sb_with_data(&device, &all_sig_u32s);
-    let pk_buf = create_sb_with_data(&device, &all_pk_u32s);
-    let msg_buf = create_sb_with_data(&device, &all_msg_u32s);
+    let sig_buf = create_sb_with_writer(&device, (64 * next_pow_2) as u64, write_signatures);
+    let pk_buf = create_sb_with_writer(&device, (32 * next_pow_2) as u64, write_verifying_keys);
+    let msg_buf = create_sb_with_writer(&device, (32 * next_pow_2) as u64, write_messages);
     let is_valid_buf = create_empty_sb(&device, (next_pow_2 * std::mem::size_of::<u32>()) as u64);
     let success_buf = create_empty_sb(&device, std::mem::size_of::<u32>() as u64);
     let params_buf = create_ub_with_data(&device, params);
 
-    let source = render_ed25519_eddsa("ed25519_eddsa_main.wgsl", log_limb_size);
+    let source = render_main::<Ed25519>("ed25519_eddsa_main.wgsl", log_limb_size);
     let compute_pipeline = create_compute_pipeline(&device, &source, "ed25519_verify_main");
 
     let bind_group = create_bind_group(
@@ -312,7 +902,7 @@ pub async fn ecverify_single(
     .await;
 
     if results[1][0] != 1 {
-        return Err(crate::ShaderFailureError);
+        return Err(crate::ShaderFailureError::default());
     }
 
     let mut all_is_valid: Vec<bool> = Vec::with_capacity(num_signatures);
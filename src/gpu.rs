@@ -1,23 +1,42 @@
+use crate::integrity::{Integrity, IntegrityFailure};
+use crate::precompute::{ed25519_bases, secp256k1_bases, secp256r1_bases};
+use crate::streaming::check_known_answers;
+use crate::{ed25519_eddsa, secp256k1_ecdsa, secp256r1_ecdsa};
 use std::borrow::Cow;
 use std::boxed::Box;
+use std::collections::HashMap;
+use std::fmt::Debug;
+use sha2::{Digest, Sha256};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
 use wgpu::util::DeviceExt;
 
-pub async fn get_device_and_queue() -> (wgpu::Device, wgpu::Queue) {
+pub async fn get_adapter() -> wgpu::Adapter {
     let instance = wgpu::Instance::default();
-    let adapter = instance
+    instance
         .request_adapter(&wgpu::RequestAdapterOptions {
             power_preference: wgpu::PowerPreference::HighPerformance,
             force_fallback_adapter: false,
             compatible_surface: None,
         })
         .await
-        .unwrap();
+        .unwrap()
+}
 
+pub async fn get_device_and_queue() -> (wgpu::Device, wgpu::Queue) {
+    let adapter = get_adapter().await;
+    get_device_and_queue_with_features(&adapter, wgpu::Features::empty()).await
+}
+
+pub async fn get_device_and_queue_with_features(
+    adapter: &wgpu::Adapter,
+    required_features: wgpu::Features,
+) -> (wgpu::Device, wgpu::Queue) {
     let (device, queue) = adapter
         .request_device(
             &wgpu::DeviceDescriptor {
                 label: None,
-                required_features: wgpu::Features::empty(),
+                required_features,
                 required_limits: wgpu::Limits::default(),
             },
             None,
@@ -34,6 +53,318 @@ pub async fn get_device_and_queue() -> (wgpu::Device, wgpu::Queue) {
     (device, queue)
 }
 
+/// A device and queue which live across calls, along with a pool of the buffers which the calls
+/// have released.
+pub struct GpuContext {
+    pub device: wgpu::Device,
+    pub queue: wgpu::Queue,
+    pub pool: BufferPool,
+    /// Identifies the adapter, e.g. to key autotuning profiles.
+    pub adapter_info: wgpu::AdapterInfo,
+    /// How the results of the calls on this context are checked against the CPU.
+    integrity: Integrity,
+    /// The number of calls and streamed batches numbered by `next_batch`, which identifies one in
+    /// a diagnostic.
+    num_batches: AtomicUsize,
+    /// Set once an integrity check has caught a wrong result, after which no call uses the device.
+    quarantine: Mutex<Option<IntegrityFailure>>,
+    /// The pipelines compiled by earlier calls, keyed by `PipelineKey`.
+    pipelines: Mutex<HashMap<PipelineKey, Arc<Vec<wgpu::ComputePipeline>>>>,
+    /// The base tables uploaded by earlier calls, keyed by the SHA-256 digest of their limbs.
+    tables: Mutex<HashMap<[u8; 32], Arc<wgpu::Buffer>>>,
+}
+
+/// Identifies a set of compiled pipelines: its name, the limb size, and the `Debug` rendering of
+/// the options which it was compiled with.
+type PipelineKey = (String, u32, String);
+
+impl GpuContext {
+    /// Enables `wgpu::Features::TIMESTAMP_QUERY` if the adapter supports it, so that stages can
+    /// be timed on the GPU.
+    pub async fn new() -> Self {
+        let adapter = get_adapter().await;
+        let features = adapter.features() & wgpu::Features::TIMESTAMP_QUERY;
+        let (device, queue) = get_device_and_queue_with_features(&adapter, features).await;
+        Self {
+            device,
+            queue,
+            pool: BufferPool::default(),
+            adapter_info: adapter.get_info(),
+            integrity: Integrity::Off,
+            num_batches: AtomicUsize::new(0),
+            quarantine: Mutex::new(None),
+            pipelines: Mutex::default(),
+            tables: Mutex::default(),
+        }
+    }
+
+    /// Like `new`, but checks the results of the GPU with `integrity`. The known-answer vectors of
+    /// the secp256k1, secp256r1, and ed25519 streaming schemes run with the tables for
+    /// `log_limb_size` before the context is returned, and every later call and streamed batch
+    /// recomputes a sample of its signatures on the CPU. If a result differs, the context is
+    /// quarantined: every later call fails, and `quarantined` describes the first wrong result.
+    pub async fn new_with_integrity(integrity: Integrity, log_limb_size: u32) -> Self {
+        let mut context = Self::new().await;
+        context.integrity = integrity;
+        if integrity.is_on() {
+            let device = &context.device;
+            let secp256k1 = secp256k1_ecdsa::Recovery::new(device, &secp256k1_bases(log_limb_size), log_limb_size);
+            check_known_answers(&context, &secp256k1).await;
+            let secp256r1 = secp256r1_ecdsa::Recovery::new(device, &secp256r1_bases(log_limb_size), log_limb_size);
+            check_known_answers(&context, &secp256r1).await;
+            let ed25519 = ed25519_eddsa::Verification::new(device, &ed25519_bases(log_limb_size), log_limb_size);
+            check_known_answers(&context, &ed25519).await;
+        }
+        context
+    }
+
+    pub fn integrity(&self) -> Integrity {
+        self.integrity
+    }
+
+    /// The position of the next call or streamed batch to be sampled, for `Check::Sample`.
+    pub fn next_batch(&self) -> usize {
+        self.num_batches.fetch_add(1, Ordering::Relaxed)
+    }
+
+    /// Returns the pipelines named `name` for `log_limb_size` and `options`, and only calls
+    /// `create` to compile them on the first call with that key. `options` must determine
+    /// everything which `create` renders into the shaders other than the limb size.
+    pub fn cached_pipelines<O: Debug>(
+        &self,
+        name: &str,
+        log_limb_size: u32,
+        options: &O,
+        create: impl FnOnce() -> Vec<wgpu::ComputePipeline>,
+    ) -> Arc<Vec<wgpu::ComputePipeline>> {
+        let key = (name.to_owned(), log_limb_size, format!("{:?}", options));
+        if let Some(pipelines) = self.pipelines.lock().unwrap().get(&key) {
+            return pipelines.clone();
+        }
+        // Compile without holding the lock, so that other calls are not blocked meanwhile
+        let pipelines = Arc::new(create());
+        self.pipelines.lock().unwrap().entry(key).or_insert(pipelines).clone()
+    }
+
+    /// Returns a storage buffer which holds `table_limbs`, and only uploads them on the first call
+    /// with the same limbs. Tables are told apart by a collision-resistant digest of their limbs,
+    /// so no copy of them is kept on the host. The buffer is kept for the lifetime of the context,
+    /// so it must not be written to.
+    pub fn table_buffer(&self, table_limbs: &[u32]) -> Arc<wgpu::Buffer> {
+        let digest: [u8; 32] = Sha256::digest(bytemuck::cast_slice::<u32, u8>(table_limbs)).into();
+        self.tables
+            .lock()
+            .unwrap()
+            .entry(digest)
+            .or_insert_with(|| Arc::new(create_sb_with_data(&self.device, table_limbs)))
+            .clone()
+    }
+
+    pub fn supports_timestamp_queries(&self) -> bool {
+        self.device.features().contains(wgpu::Features::TIMESTAMP_QUERY)
+    }
+
+    /// Stops every later call on this context from using the device, and keeps `failure` as the
+    /// diagnostic, unless an earlier failure already did.
+    pub fn quarantine(&self, failure: IntegrityFailure) {
+        self.quarantine.lock().unwrap().get_or_insert(failure);
+    }
+
+    /// The failure for which the context was quarantined, if any.
+    pub fn quarantined(&self) -> Option<IntegrityFailure> {
+        self.quarantine.lock().unwrap().clone()
+    }
+
+    /// The number of sets of pipelines which `cached_pipelines` has compiled.
+    pub fn num_cached_pipelines(&self) -> usize {
+        self.pipelines.lock().unwrap().len()
+    }
+
+    /// The number of tables which `table_buffer` has uploaded.
+    pub fn num_cached_tables(&self) -> usize {
+        self.tables.lock().unwrap().len()
+    }
+
+    /// The adapter's name and backend, for diagnostics.
+    pub fn adapter_name(&self) -> String {
+        format!("{} ({:?})", self.adapter_info.name, self.adapter_info.backend)
+    }
+
+    /// Like `finish_encoder_and_read_bytes_from_gpu`, but the staging buffers come from (and are
+    /// returned to) the pool, and the device is left alive.
+    pub async fn finish_encoder_and_read_bytes(
+        &self,
+        mut command_encoder: wgpu::CommandEncoder,
+        buffers: &[&wgpu::Buffer],
+    ) -> Vec<Vec<u8>> {
+        let mut results = Vec::<Vec<u8>>::with_capacity(buffers.len());
+        let mut staging_buffers = Vec::<wgpu::Buffer>::with_capacity(buffers.len());
+
+        for buffer in buffers {
+            let size = buffer.size();
+            let staging_buffer = self.pool.acquire_staging(&self.device, size);
+            command_encoder.copy_buffer_to_buffer(buffer, 0, &staging_buffer, 0, size);
+            staging_buffers.push(staging_buffer);
+        }
+
+        self.queue.submit(Some(command_encoder.finish()));
+
+        for (buffer, staging_buffer) in buffers.iter().zip(staging_buffers) {
+            let buffer_slice = staging_buffer.slice(..);
+            let (sender, receiver) = futures_intrusive::channel::shared::oneshot_channel();
+            buffer_slice.map_async(wgpu::MapMode::Read, move |v| sender.send(v).unwrap());
+            self.device.poll(wgpu::Maintain::Wait);
+
+            if let Some(Ok(())) = receiver.receive().await {
+                let data = buffer_slice.get_mapped_range();
+                // Pooled staging buffers may be larger than the buffer which was copied
+                results.push(data[..buffer.size() as usize].to_vec());
+                drop(data);
+                staging_buffer.unmap();
+            } else {
+                panic!("failed to run compute on gpu!")
+            }
+            self.pool.release(staging_buffer);
+        }
+
+        results
+    }
+}
+
+/// The default of `BufferPool::max_free_bytes`.
+pub const DEFAULT_MAX_FREE_BYTES: u64 = 1 << 30;
+
+/// A pool of buffers which are bucketed by usage and by size, rounded up to the next power of 2,
+/// so that repeated batches of similar sizes can reuse buffers instead of allocating new ones.
+/// Buffers which are acquired from the pool may hold stale data. At most `max_free_bytes` are
+/// kept free, and a buffer which is released beyond that is dropped instead.
+pub struct BufferPool {
+    free: Mutex<FreeBuffers>,
+    max_free_bytes: u64,
+    hits: AtomicUsize,
+    misses: AtomicUsize,
+}
+
+#[derive(Default)]
+struct FreeBuffers {
+    buckets: HashMap<(wgpu::BufferUsages, u64), Vec<wgpu::Buffer>>,
+    /// The total size of the buffers in `buckets`.
+    num_bytes: u64,
+}
+
+impl Default for BufferPool {
+    fn default() -> Self {
+        Self::with_max_free_bytes(DEFAULT_MAX_FREE_BYTES)
+    }
+}
+
+impl BufferPool {
+    /// A pool which keeps at most `max_free_bytes` of free buffers.
+    pub fn with_max_free_bytes(max_free_bytes: u64) -> Self {
+        Self {
+            free: Mutex::default(),
+            max_free_bytes,
+            hits: AtomicUsize::new(0),
+            misses: AtomicUsize::new(0),
+        }
+    }
+
+    /// Returns a free buffer of at least `size` bytes with exactly the given usage, and only
+    /// allocates one if the bucket is empty.
+    pub fn acquire(&self, device: &wgpu::Device, size: u64, usage: wgpu::BufferUsages) -> wgpu::Buffer {
+        let bucket_size = size.max(4).next_power_of_two();
+        let reused = {
+            let mut free = self.free.lock().unwrap();
+            let buffer = free.buckets.get_mut(&(usage, bucket_size)).and_then(|buffers| buffers.pop());
+            if let Some(buffer) = &buffer {
+                free.num_bytes -= buffer.size();
+            }
+            buffer
+        };
+
+        match reused {
+            Some(buffer) => {
+                self.hits.fetch_add(1, Ordering::Relaxed);
+                buffer
+            }
+            None => {
+                self.misses.fetch_add(1, Ordering::Relaxed);
+                device.create_buffer(&wgpu::BufferDescriptor {
+                    label: None,
+                    size: bucket_size,
+                    mapped_at_creation: false,
+                    usage,
+                })
+            }
+        }
+    }
+
+    pub fn acquire_sb(&self, device: &wgpu::Device, size: u64) -> wgpu::Buffer {
+        self.acquire(
+            device,
+            size,
+            wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::COPY_SRC,
+        )
+    }
+
+    /// Like `acquire_sb`, but the buffer can also hold the arguments of an indirect dispatch, which
+    /// are read back to check the completion counters of the stages which it dispatches.
+    pub fn acquire_indirect(&self, device: &wgpu::Device, size: u64) -> wgpu::Buffer {
+        self.acquire(
+            device,
+            size,
+            wgpu::BufferUsages::STORAGE
+                | wgpu::BufferUsages::INDIRECT
+                | wgpu::BufferUsages::COPY_DST
+                | wgpu::BufferUsages::COPY_SRC,
+        )
+    }
+
+    pub fn acquire_ub(&self, device: &wgpu::Device, size: u64) -> wgpu::Buffer {
+        self.acquire(device, size, wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST)
+    }
+
+    pub fn acquire_staging(&self, device: &wgpu::Device, size: u64) -> wgpu::Buffer {
+        self.acquire(device, size, wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST)
+    }
+
+    /// Returns a buffer to the pool, or drops it if the pool would then hold more than
+    /// `max_free_bytes`. The GPU must no longer be using it.
+    pub fn release(&self, buffer: wgpu::Buffer) {
+        let mut free = self.free.lock().unwrap();
+        if free.num_bytes + buffer.size() > self.max_free_bytes {
+            return;
+        }
+        free.num_bytes += buffer.size();
+        free.buckets.entry((buffer.usage(), buffer.size())).or_default().push(buffer);
+    }
+
+    /// Drops every free buffer, e.g. after an unusually large batch whose buffers would otherwise
+    /// stay allocated until a batch of the same size reuses them.
+    pub fn trim(&self) {
+        *self.free.lock().unwrap() = FreeBuffers::default();
+    }
+
+    /// The total size of the free buffers.
+    pub fn free_bytes(&self) -> u64 {
+        self.free.lock().unwrap().num_bytes
+    }
+
+    pub fn max_free_bytes(&self) -> u64 {
+        self.max_free_bytes
+    }
+
+    /// The number of acquisitions which reused a pooled buffer.
+    pub fn hits(&self) -> usize {
+        self.hits.load(Ordering::Relaxed)
+    }
+
+    /// The number of acquisitions which had to allocate a new buffer.
+    pub fn misses(&self) -> usize {
+        self.misses.load(Ordering::Relaxed)
+    }
+}
+
 pub fn create_command_encoder(device: &wgpu::Device) -> wgpu::CommandEncoder {
     device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None })
 }
@@ -48,6 +379,49 @@ pub fn create_sb_with_data(device: &wgpu::Device, data: &[u32]) -> wgpu::Buffer
     })
 }
 
+/// Creates a storage buffer of `size` bytes which is mapped at creation and lets `write` fill the
+/// mapped range in place, which avoids staging the data in an intermediate host-side `Vec`. The
+/// mapped range is zero-initialised, so bytes which `write` does not touch remain zero.
+pub fn create_sb_with_writer(
+    device: &wgpu::Device,
+    size: u64,
+    write: impl FnOnce(&mut [u8]),
+) -> wgpu::Buffer {
+    let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+        label: None,
+        size,
+        mapped_at_creation: true,
+        usage: wgpu::BufferUsages::STORAGE
+            | wgpu::BufferUsages::COPY_DST
+            | wgpu::BufferUsages::COPY_SRC,
+    });
+    {
+        let mut data = buffer.slice(..).get_mapped_range_mut();
+        write(&mut data);
+    }
+    buffer.unmap();
+    buffer
+}
+
+/// Overwrites the whole of an existing buffer via `queue.write_buffer_with`, which lets `write` fill
+/// the queue's staging memory in place. The staging memory is zeroed first, so bytes which `write`
+/// does not touch become zero. The write takes effect at the next `queue.submit`.
+pub fn write_sb_with(queue: &wgpu::Queue, buffer: &wgpu::Buffer, write: impl FnOnce(&mut [u8])) {
+    let size = wgpu::BufferSize::new(buffer.size()).unwrap();
+    let mut data = queue.write_buffer_with(buffer, 0, size).unwrap();
+    data.fill(0);
+    write(&mut data);
+}
+
+pub fn create_staging_buffer(device: &wgpu::Device, size: u64) -> wgpu::Buffer {
+    device.create_buffer(&wgpu::BufferDescriptor {
+        label: None,
+        size,
+        usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    })
+}
+
 pub fn create_ub_with_data(device: &wgpu::Device, data: &[u32]) -> wgpu::Buffer {
     device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
         label: None,
@@ -92,10 +466,32 @@ pub fn execute_pipeline(
     num_x_workgroups: u32,
     num_y_workgroups: u32,
     num_z_workgroups: u32,
+) {
+    execute_pipeline_with_timestamps(
+        command_encoder,
+        compute_pipeline,
+        bind_group,
+        num_x_workgroups,
+        num_y_workgroups,
+        num_z_workgroups,
+        None,
+    );
+}
+
+/// Like `execute_pipeline`, but the pass can write timestamps at its beginning and end. This
+/// requires `wgpu::Features::TIMESTAMP_QUERY`.
+pub fn execute_pipeline_with_timestamps(
+    command_encoder: &mut wgpu::CommandEncoder,
+    compute_pipeline: &wgpu::ComputePipeline,
+    bind_group: &wgpu::BindGroup,
+    num_x_workgroups: u32,
+    num_y_workgroups: u32,
+    num_z_workgroups: u32,
+    timestamp_writes: Option<wgpu::ComputePassTimestampWrites>,
 ) {
     let mut cpass = command_encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
         label: None,
-        timestamp_writes: None,
+        timestamp_writes,
     });
     cpass.set_pipeline(compute_pipeline);
     cpass.set_bind_group(0, &bind_group, &[]);
@@ -103,6 +499,69 @@ pub fn execute_pipeline(
     cpass.dispatch_workgroups(num_x_workgroups, num_y_workgroups, num_z_workgroups);
 }
 
+/// Like `execute_pipeline_with_timestamps`, but the number of workgroups is read from the first
+/// three u32s of `indirect_buffer` when the dispatch runs, so an earlier stage can set it.
+pub fn execute_pipeline_indirect_with_timestamps(
+    command_encoder: &mut wgpu::CommandEncoder,
+    compute_pipeline: &wgpu::ComputePipeline,
+    bind_group: &wgpu::BindGroup,
+    indirect_buffer: &wgpu::Buffer,
+    timestamp_writes: Option<wgpu::ComputePassTimestampWrites>,
+) {
+    let mut cpass = command_encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+        label: None,
+        timestamp_writes,
+    });
+    cpass.set_pipeline(compute_pipeline);
+    cpass.set_bind_group(0, &bind_group, &[]);
+    cpass.dispatch_workgroups_indirect(indirect_buffer, 0);
+}
+
+/// One dispatch of a multi-stage pipeline.
+pub struct Dispatch<'a> {
+    /// What the stage computes, for profiling reports.
+    pub label: &'static str,
+    pub compute_pipeline: &'a wgpu::ComputePipeline,
+    pub bind_group: &'a wgpu::BindGroup,
+    pub num_workgroups: (u32, u32, u32),
+    /// If set, the number of workgroups is read from this buffer instead of `num_workgroups`.
+    pub indirect_buffer: Option<&'a wgpu::Buffer>,
+}
+
+impl Dispatch<'_> {
+    pub fn execute(&self, command_encoder: &mut wgpu::CommandEncoder) {
+        self.execute_with_timestamps(command_encoder, None);
+    }
+
+    pub fn execute_with_timestamps(
+        &self,
+        command_encoder: &mut wgpu::CommandEncoder,
+        timestamp_writes: Option<wgpu::ComputePassTimestampWrites>,
+    ) {
+        if let Some(indirect_buffer) = self.indirect_buffer {
+            execute_pipeline_indirect_with_timestamps(
+                command_encoder,
+                self.compute_pipeline,
+                self.bind_group,
+                indirect_buffer,
+                timestamp_writes,
+            );
+            return;
+        }
+
+        let (num_x_workgroups, num_y_workgroups, num_z_workgroups) = self.num_workgroups;
+        execute_pipeline_with_timestamps(
+            command_encoder,
+            self.compute_pipeline,
+            self.bind_group,
+            num_x_workgroups,
+            num_y_workgroups,
+            num_z_workgroups,
+            timestamp_writes,
+        );
+    }
+}
+
 pub fn create_bind_group(
     device: &wgpu::Device,
     compute_pipeline: &wgpu::ComputePipeline,
@@ -126,6 +585,24 @@ pub fn create_bind_group(
     })
 }
 
+/// Like `create_bind_group`, but binds `buffers`, then those of `optional_buffers` which are
+/// present, then `params_buf`. The stages which take optional buffers declare them in that order.
+pub fn create_bind_group_with_optional(
+    device: &wgpu::Device,
+    compute_pipeline: &wgpu::ComputePipeline,
+    buffers: &[&wgpu::Buffer],
+    optional_buffers: &[Option<&wgpu::Buffer>],
+    params_buf: &wgpu::Buffer,
+) -> wgpu::BindGroup {
+    let buffers: Vec<&wgpu::Buffer> = buffers
+        .iter()
+        .copied()
+        .chain(optional_buffers.iter().flatten().copied())
+        .chain([params_buf])
+        .collect();
+    create_bind_group(device, compute_pipeline, 0, &buffers)
+}
+
 pub async fn finish_encoder_and_read_bytes_from_gpu(
     device: &wgpu::Device,
     queue: &wgpu::Queue,
@@ -137,12 +614,7 @@ pub async fn finish_encoder_and_read_bytes_from_gpu(
 
     for buffer in buffers {
         let size = buffer.size();
-        let staging_buffer = device.create_buffer(&wgpu::BufferDescriptor {
-            label: None,
-            size,
-            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
-            mapped_at_creation: false,
-        });
+        let staging_buffer = create_staging_buffer(device, size);
         (*command_encoder).copy_buffer_to_buffer(&buffer, 0, &staging_buffer, 0, size);
         staging_buffers.push(staging_buffer);
     }
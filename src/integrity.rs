@@ -0,0 +1,135 @@
+use crate::gpu::GpuContext;
+use std::collections::hash_map::RandomState;
+use std::collections::BTreeSet;
+use std::fmt::Debug;
+use std::hash::{BuildHasher, Hasher};
+
+/// Whether a `GpuContext` checks the results of the GPU against the CPU implementations of each
+/// scheme, so that a device which silently computes wrong results, e.g. because of a driver bug
+/// which the completion counters do not catch, is not trusted.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Integrity {
+    /// The results of the GPU are returned as they are.
+    #[default]
+    Off,
+    /// The known-answer vectors of each scheme run when the context is created, and this many
+    /// randomly chosen signatures of every call and of every streamed batch are recomputed on the
+    /// CPU. On a mismatch, the context is quarantined.
+    SamplesPerBatch(usize),
+}
+
+impl Integrity {
+    pub fn is_on(&self) -> bool {
+        *self != Integrity::Off
+    }
+
+    /// The number of signatures of each batch to recompute on the CPU.
+    pub fn num_samples(&self) -> usize {
+        match self {
+            Integrity::Off => 0,
+            Integrity::SamplesPerBatch(num_samples) => *num_samples,
+        }
+    }
+}
+
+/// Which check found a wrong result.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Check {
+    /// The known-answer vectors, when the context was created.
+    KnownAnswers,
+    /// The sample of a call or of a streamed batch, by its position among those on the context.
+    Sample { batch: usize },
+}
+
+/// Why a context was quarantined: the first result of the GPU which differed from that of the
+/// CPU.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IntegrityFailure {
+    /// The name and backend of the adapter.
+    pub adapter: String,
+    pub check: Check,
+    /// The index of the signature in its call or batch.
+    pub index: usize,
+    /// The result of the GPU, formatted with `Debug`.
+    pub gpu: String,
+    /// The result of the CPU, formatted with `Debug`.
+    pub cpu: String,
+}
+
+impl std::fmt::Display for IntegrityFailure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} computed {} for signature {} of {:?}, but the CPU computed {}",
+            self.adapter, self.gpu, self.index, self.check, self.cpu
+        )
+    }
+}
+
+/// Picks `num_samples` distinct indices below `num_signatures`, or every index if there are not
+/// enough, in ascending order. The indices differ between calls, so that a fault cannot hide in
+/// the signatures which are never sampled.
+pub fn sample_indices(num_signatures: usize, num_samples: usize) -> Vec<usize> {
+    if num_samples >= num_signatures {
+        return (0..num_signatures).collect();
+    }
+
+    // Each RandomState is seeded randomly, which suffices without a dependency on rand
+    let mut hasher = RandomState::new().build_hasher();
+    let mut indices = BTreeSet::new();
+    while indices.len() < num_samples {
+        hasher.write_usize(indices.len());
+        indices.insert(hasher.finish() as usize % num_signatures);
+    }
+    indices.into_iter().collect()
+}
+
+/// The number of signatures in the known-answer batch of each scheme.
+pub const NUM_KNOWN_ANSWERS: usize = 8;
+
+/// Compares the result of the GPU and that of the CPU of each `(index, gpu, cpu)` sample, in
+/// order, and quarantines `context` at the first which differ.
+pub fn check_samples<T: Debug + PartialEq>(
+    context: &GpuContext,
+    check: Check,
+    samples: impl IntoIterator<Item = (usize, T, T)>,
+) {
+    for (index, gpu, cpu) in samples {
+        if gpu != cpu {
+            context.quarantine(IntegrityFailure {
+                adapter: context.adapter_name(),
+                check,
+                index,
+                gpu: format!("{:?}", gpu),
+                cpu: format!("{:?}", cpu),
+            });
+            return;
+        }
+    }
+}
+
+/// With `Integrity` on `context`, recomputes a sample of the `num_signatures` signatures of a call
+/// with `cpu`, and compares each with its result on the GPU. `gpu` returns `None` for a signature
+/// whose result the output does not determine, e.g. one after the first failure of an all-valid
+/// summary, which is then skipped. On a mismatch, the context is quarantined and the call fails
+/// with the failure.
+pub fn check_call<T: Debug + PartialEq>(
+    context: &GpuContext,
+    num_signatures: usize,
+    gpu: impl Fn(usize) -> Option<T>,
+    cpu: impl Fn(usize) -> T,
+) -> Result<(), crate::ShaderFailureError> {
+    let integrity = context.integrity();
+    if !integrity.is_on() {
+        return Ok(());
+    }
+    let check = Check::Sample { batch: context.next_batch() };
+    let samples = sample_indices(num_signatures, integrity.num_samples())
+        .into_iter()
+        .filter_map(|index| Some((index, gpu(index)?, cpu(index))));
+    check_samples(context, check, samples);
+    match context.quarantined() {
+        Some(failure) => Err(failure.into()),
+        None => Ok(()),
+    }
+}
@@ -1,14 +1,135 @@
+pub mod all_valid;
+pub mod autotune;
 pub mod benchmarks;
+pub mod canary;
+pub mod checkpoint;
+pub mod compaction;
+pub mod coop;
 pub mod curve_algos;
+pub mod curves;
+pub mod derive;
 pub mod ed25519_eddsa;
 pub mod gpu;
+pub mod integrity;
 pub mod moduli;
+pub mod msm;
+pub mod ops;
+pub mod sec1;
 pub mod secp256k1_ecdsa;
 pub mod secp256r1_ecdsa;
+pub mod secp384r1_ecdsa;
 pub mod shader;
+pub mod signature_encoding;
 pub mod precompute;
+pub mod streaming;
+pub mod timings;
+pub mod webauthn;
 pub mod tests;
 
-/// This error is raised if the shader silently fails to execute.
-#[derive(Debug, Clone)]
-pub struct ShaderFailureError;
+/// This error is raised if the shader silently fails to execute, or if the device is not
+/// trusted with its results.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ShaderFailureError {
+    /// For a multi-stage pipeline, the first stage whose completion counter fell short, if any.
+    pub stage: Option<canary::StageFailure>,
+    /// The failure for which the context is quarantined, if the call was refused or its sample
+    /// differed from the CPU results.
+    pub integrity: Option<integrity::IntegrityFailure>,
+}
+
+impl std::fmt::Display for ShaderFailureError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (&self.stage, &self.integrity) {
+            (Some(stage), _) => write!(f, "the shader failed to execute: {}", stage),
+            (None, Some(failure)) => write!(f, "the device is quarantined: {}", failure),
+            (None, None) => write!(f, "the shader failed to execute"),
+        }
+    }
+}
+
+impl From<integrity::IntegrityFailure> for ShaderFailureError {
+    fn from(failure: integrity::IntegrityFailure) -> Self {
+        ShaderFailureError { stage: None, integrity: Some(failure) }
+    }
+}
+
+/// The reasons why a pipeline with options cannot recover or verify a batch.
+#[derive(Debug)]
+pub enum PipelineError {
+    Shader(ShaderFailureError),
+    /// The options ask for a cooperative kernel which the curve or limb size cannot run.
+    Cooperation(coop::CooperationError),
+    /// The base table was built for other parameters than the shader which is being run.
+    Table(precompute::BaseTableError),
+    /// The options ask for a reduction which the base field or limb size cannot use.
+    Reduction(shader::ReductionError),
+    /// The options ask for an all-valid check after a final stage which writes no verdicts.
+    AllValid(all_valid::AllValidError),
+}
+
+impl std::fmt::Display for PipelineError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PipelineError::Shader(e) => write!(f, "{}", e),
+            PipelineError::Cooperation(e) => write!(f, "{}", e),
+            PipelineError::Table(e) => write!(f, "{}", e),
+            PipelineError::Reduction(e) => write!(f, "{}", e),
+            PipelineError::AllValid(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for PipelineError {}
+
+impl From<ShaderFailureError> for PipelineError {
+    fn from(e: ShaderFailureError) -> Self {
+        PipelineError::Shader(e)
+    }
+}
+
+impl From<coop::CooperationError> for PipelineError {
+    fn from(e: coop::CooperationError) -> Self {
+        PipelineError::Cooperation(e)
+    }
+}
+
+impl From<precompute::BaseTableError> for PipelineError {
+    fn from(e: precompute::BaseTableError) -> Self {
+        PipelineError::Table(e)
+    }
+}
+
+impl From<shader::ReductionError> for PipelineError {
+    fn from(e: shader::ReductionError) -> Self {
+        PipelineError::Reduction(e)
+    }
+}
+
+impl From<all_valid::AllValidError> for PipelineError {
+    fn from(e: all_valid::AllValidError) -> Self {
+        PipelineError::AllValid(e)
+    }
+}
+
+/// The outcome of verifying a single signature.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verdict {
+    Valid,
+    Invalid,
+}
+
+impl Verdict {
+    pub fn is_valid(&self) -> bool {
+        *self == Verdict::Valid
+    }
+}
+
+impl From<bool> for Verdict {
+    fn from(is_valid: bool) -> Self {
+        if is_valid {
+            Verdict::Valid
+        } else {
+            Verdict::Invalid
+        }
+    }
+}
@@ -48,3 +48,19 @@ pub fn ed25519_fr_modulus_biguint() -> BigUint {
 pub fn ed25519_fr_modulus() -> ark_ed25519::Fr {
     ark_ed25519::Fr::from_be_bytes_mod_order(&ark_ed25519::Fr::MODULUS.to_bytes_be())
 }
+
+pub fn secp384r1_fq_modulus_biguint() -> BigUint {
+    BigUint::from_bytes_be(&ark_secp384r1::Fq::MODULUS.to_bytes_be())
+}
+
+pub fn secp384r1_fq_modulus() -> ark_secp384r1::Fq {
+    ark_secp384r1::Fq::from_be_bytes_mod_order(&ark_secp384r1::Fq::MODULUS.to_bytes_be())
+}
+
+pub fn secp384r1_fr_modulus_biguint() -> BigUint {
+    BigUint::from_bytes_be(&ark_secp384r1::Fr::MODULUS.to_bytes_be())
+}
+
+pub fn secp384r1_fr_modulus() -> ark_secp384r1::Fr {
+    ark_secp384r1::Fr::from_be_bytes_mod_order(&ark_secp384r1::Fr::MODULUS.to_bytes_be())
+}
@@ -0,0 +1,271 @@
+use crate::benchmarks::compute_num_workgroups;
+use crate::canary::{self, acquire_canaries};
+use crate::curve_algos::coords::ETEProjective;
+use crate::curve_algos::{ed25519_curve, secp256k1_curve, secp256r1_curve};
+use crate::curves::{CurveForm, CurveParams, Ed25519, Secp256k1, Secp256r1};
+use crate::gpu::{
+    create_bind_group, create_command_encoder, create_compute_pipeline, write_sb_with, Dispatch,
+    GpuContext,
+};
+use crate::ops::{mont_limbs_to_field_elements, sw_from_mont_limbs};
+use crate::shader::{render_main, Reduction};
+use crate::tests::{eteprojective_to_mont_limbs, projectivexyz_to_mont_limbs};
+use ark_ff::{BigInteger, PrimeField};
+use multiprecision::utils::calc_num_limbs;
+use num_bigint::BigUint;
+
+/// The largest window size which `window_size` picks. Every bucket is accumulated by its own
+/// thread, which scans the digits of all the points, so larger windows trade more threads for
+/// more scanning.
+pub const MAX_WINDOW_SIZE: u32 = 8;
+
+/// The window size in bits for `num_points` points, as chosen by arkworks' `VariableBaseMSM`, but
+/// capped at `MAX_WINDOW_SIZE`.
+pub fn window_size(num_points: usize) -> u32 {
+    if num_points < 32 {
+        3
+    } else {
+        // ln(n) ~= log2(n) * 69 / 100
+        let ln = (usize::BITS - num_points.leading_zeros()) * 69 / 100;
+        (ln + 2).min(MAX_WINDOW_SIZE)
+    }
+}
+
+/// The workgroup size of every stage.
+const WORKGROUP_SIZE: usize = 256;
+
+/// What each stage of the MSM pipeline computes, in order.
+const STAGE_LABELS: [&str; 4] = [
+    "scalar decomposition into window digits",
+    "bucket accumulation",
+    "bucket reduction per window",
+    "window combination",
+];
+
+/// Computes sum(scalars[i] * points[i]) over the curve `C` with Pippenger's bucket method.
+/// `point_limbs` holds the points in the Montgomery limb layout of the shaders, i.e. that of
+/// `projectivexyz_to_mont_limbs` for short Weierstrass curves and of `eteprojective_to_mont_limbs`
+/// for twisted Edwards curves. The scalars must be smaller than 2 ^ `C::NUM_BITS`. Returns the
+/// sum in the same layout.
+pub async fn msm_limbs<C: CurveParams>(
+    context: &GpuContext,
+    point_limbs: &[u32],
+    scalars: &[BigUint],
+    log_limb_size: u32,
+) -> Result<Vec<u32>, crate::ShaderFailureError> {
+    // A quarantined device is not trusted with any call
+    if let Some(failure) = context.quarantined() {
+        return Err(failure.into());
+    }
+    let num_points = scalars.len();
+    let num_limbs = calc_num_limbs(log_limb_size, C::NUM_BITS);
+    let num_coords = match C::form() {
+        CurveForm::ShortWeierstrass { .. } => 3,
+        CurveForm::TwistedEdwards { .. } => 4,
+    };
+    let point_size = num_coords * num_limbs * std::mem::size_of::<u32>();
+    assert_eq!(point_limbs.len(), num_points * num_coords * num_limbs);
+    assert!(num_points > 0);
+
+    let num_words = C::NUM_BITS / 32;
+    let window_size = window_size(num_points);
+    let num_windows = (C::NUM_BITS as u32).div_ceil(window_size);
+    let num_buckets = (num_windows as usize) << window_size;
+
+    let mut scalar_words = vec![0u32; num_points * num_words];
+    for (words, scalar) in scalar_words.chunks_exact_mut(num_words).zip(scalars) {
+        let digits = scalar.to_u32_digits();
+        assert!(digits.len() <= num_words);
+        words[..digits.len()].copy_from_slice(&digits);
+    }
+
+    let device = &context.device;
+    let pool = &context.pool;
+
+    let name = format!("{}_msm", C::NAME);
+    let pipelines = context.cached_pipelines(&name, log_limb_size, &(), || {
+        (0..4)
+            .map(|i| {
+                let source = render_main::<C>(&format!("msm_main_{}.wgsl", i), log_limb_size);
+                create_compute_pipeline(device, &source, &format!("msm_main_{}", i))
+            })
+            .collect()
+    });
+
+    let points_buf = pool.acquire_sb(device, std::mem::size_of_val(point_limbs) as u64);
+    write_sb_with(&context.queue, &points_buf, |buf| {
+        buf[..std::mem::size_of_val(point_limbs)].copy_from_slice(bytemuck::cast_slice(point_limbs))
+    });
+    let scalars_size = std::mem::size_of_val(scalar_words.as_slice());
+    let scalars_buf = pool.acquire_sb(device, scalars_size as u64);
+    write_sb_with(&context.queue, &scalars_buf, |buf| {
+        buf[..scalars_size].copy_from_slice(bytemuck::cast_slice(&scalar_words))
+    });
+    let digits_buf = pool.acquire_sb(device, (num_points * num_windows as usize * 4) as u64);
+    let buckets_buf = pool.acquire_sb(device, (num_buckets * point_size) as u64);
+    let window_sums_buf = pool.acquire_sb(device, (num_windows as usize * point_size) as u64);
+    let result_buf = pool.acquire_sb(device, point_size as u64);
+    let canaries_buf = acquire_canaries(context, STAGE_LABELS.len());
+
+    let msm_params = [num_points as u32, window_size, num_windows, 0];
+    let msm_params_buf = pool.acquire_ub(device, std::mem::size_of_val(&msm_params) as u64);
+    context.queue.write_buffer(&msm_params_buf, 0, bytemuck::cast_slice(&msm_params));
+
+    // Every stage has a different number of threads
+    let num_threads = [num_points, num_buckets, num_windows as usize, 1];
+    let mut params_bufs = vec![];
+    let mut num_workgroups = vec![];
+    for n in num_threads {
+        let (x, y, z) = compute_num_workgroups(n.next_power_of_two(), WORKGROUP_SIZE);
+        let params = [x as u32, y as u32, z as u32];
+        let params_buf = pool.acquire_ub(device, std::mem::size_of_val(&params) as u64);
+        context.queue.write_buffer(&params_buf, 0, bytemuck::cast_slice(&params));
+        params_bufs.push(params_buf);
+        num_workgroups.push((params[0], params[1], params[2]));
+    }
+
+    let bind_groups = [
+        create_bind_group(
+            device,
+            &pipelines[0],
+            0,
+            &[&scalars_buf, &digits_buf, &canaries_buf, &msm_params_buf, &params_bufs[0]],
+        ),
+        create_bind_group(
+            device,
+            &pipelines[1],
+            0,
+            &[&points_buf, &digits_buf, &buckets_buf, &canaries_buf, &msm_params_buf, &params_bufs[1]],
+        ),
+        create_bind_group(
+            device,
+            &pipelines[2],
+            0,
+            &[&buckets_buf, &window_sums_buf, &canaries_buf, &msm_params_buf, &params_bufs[2]],
+        ),
+        create_bind_group(
+            device,
+            &pipelines[3],
+            0,
+            &[&window_sums_buf, &result_buf, &canaries_buf, &msm_params_buf, &params_bufs[3]],
+        ),
+    ];
+
+    let mut command_encoder = create_command_encoder(device);
+    command_encoder.clear_buffer(&canaries_buf, 0, None);
+    for i in 0..4 {
+        Dispatch {
+            label: STAGE_LABELS[i],
+            compute_pipeline: &pipelines[i],
+            bind_group: &bind_groups[i],
+            num_workgroups: num_workgroups[i],
+            indirect_buffer: None,
+        }
+        .execute(&mut command_encoder);
+    }
+
+    let mut results = context
+        .finish_encoder_and_read_bytes(command_encoder, &[&result_buf, &canaries_buf])
+        .await;
+
+    drop(bind_groups);
+    for buffer in [
+        points_buf,
+        scalars_buf,
+        digits_buf,
+        buckets_buf,
+        window_sums_buf,
+        result_buf,
+        canaries_buf,
+        msm_params_buf,
+    ]
+    .into_iter()
+    .chain(params_bufs)
+    {
+        pool.release(buffer);
+    }
+
+    let dispatches = num_workgroups
+        .iter()
+        .enumerate()
+        .map(|(stage, &workgroups)| (stage, canary::num_invocations(workgroups, WORKGROUP_SIZE)));
+    canary::check(&STAGE_LABELS, dispatches, &results[1])?;
+
+    let result_bytes = results.swap_remove(0);
+    Ok(bytemuck::cast_slice(&result_bytes[..point_size]).to_vec())
+}
+
+/// MSM over secp256k1, with points and scalars as arkworks types.
+pub async fn secp256k1_msm(
+    context: &GpuContext,
+    points: &[ark_secp256k1::Affine],
+    scalars: &[ark_secp256k1::Fr],
+    log_limb_size: u32,
+) -> Result<ark_secp256k1::Projective, crate::ShaderFailureError> {
+    let p = Secp256k1::base_field_modulus();
+    let point_limbs: Vec<u32> = points
+        .iter()
+        .flat_map(|pt| {
+            let pt = secp256k1_curve::affine_to_projectivexyz(pt);
+            projectivexyz_to_mont_limbs(&pt, &p, log_limb_size, Reduction::Montgomery)
+        })
+        .collect();
+    let scalars = fr_to_biguints(scalars);
+    let result = msm_limbs::<Secp256k1>(context, &point_limbs, &scalars, log_limb_size).await?;
+    Ok(sw_from_mont_limbs::<ark_secp256k1::Config>(&result, log_limb_size))
+}
+
+/// MSM over secp256r1, with points and scalars as arkworks types.
+pub async fn secp256r1_msm(
+    context: &GpuContext,
+    points: &[ark_secp256r1::Affine],
+    scalars: &[ark_secp256r1::Fr],
+    log_limb_size: u32,
+) -> Result<ark_secp256r1::Projective, crate::ShaderFailureError> {
+    let p = Secp256r1::base_field_modulus();
+    let point_limbs: Vec<u32> = points
+        .iter()
+        .flat_map(|pt| {
+            let pt = secp256r1_curve::affine_to_projectivexyz(pt);
+            projectivexyz_to_mont_limbs(&pt, &p, log_limb_size, Reduction::Montgomery)
+        })
+        .collect();
+    let scalars = fr_to_biguints(scalars);
+    let result = msm_limbs::<Secp256r1>(context, &point_limbs, &scalars, log_limb_size).await?;
+    Ok(sw_from_mont_limbs::<ark_secp256r1::Config>(&result, log_limb_size))
+}
+
+/// MSM over ed25519, with points and scalars as arkworks types.
+pub async fn ed25519_msm(
+    context: &GpuContext,
+    points: &[ark_ed25519::EdwardsAffine],
+    scalars: &[ark_ed25519::Fr],
+    log_limb_size: u32,
+) -> Result<ark_ed25519::EdwardsProjective, crate::ShaderFailureError> {
+    let p = Ed25519::base_field_modulus();
+    let point_limbs: Vec<u32> = points
+        .iter()
+        .flat_map(|pt| {
+            let pt = ed25519_curve::affine_to_projective(pt);
+            eteprojective_to_mont_limbs(&pt, &p, log_limb_size, Reduction::Montgomery)
+        })
+        .collect();
+    let scalars = fr_to_biguints(scalars);
+    let result = msm_limbs::<Ed25519>(context, &point_limbs, &scalars, log_limb_size).await?;
+
+    let coords = mont_limbs_to_field_elements::<ark_ed25519::Fq>(&result, log_limb_size);
+    let pt = ETEProjective {
+        x: coords[0],
+        y: coords[1],
+        t: coords[2],
+        z: coords[3],
+    };
+    Ok(ed25519_curve::projective_to_affine(&pt).into())
+}
+
+fn fr_to_biguints<F: PrimeField>(scalars: &[F]) -> Vec<BigUint> {
+    scalars
+        .iter()
+        .map(|s| BigUint::from_bytes_le(&s.into_bigint().to_bytes_le()))
+        .collect()
+}
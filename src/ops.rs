@@ -0,0 +1,397 @@
+use crate::benchmarks::compute_num_workgroups;
+use crate::canary::{self, acquire_canaries};
+use crate::curve_algos::coords::ProjectiveXYZ;
+use crate::curve_algos::ed25519_curve;
+use crate::curves::{CurveForm, CurveParams, Ed25519, Secp256k1, Secp256r1};
+use crate::gpu::{
+    create_bind_group, create_command_encoder, create_compute_pipeline, execute_pipeline,
+    write_sb_with, GpuContext,
+};
+use crate::shader::{render_main, Reduction};
+use crate::tests::{eteprojective_to_mont_limbs, fq_to_biguint, projectivexyz_to_mont_limbs};
+use ark_ec::short_weierstrass::{Affine, Projective, SWCurveConfig};
+use ark_ec::{AffineRepr, CurveGroup};
+use ark_ff::{BigInteger, Field, One, PrimeField, Zero};
+use multiprecision::utils::calc_num_limbs;
+use multiprecision::{bigint, mont};
+use num_bigint::BigUint;
+use std::marker::PhantomData;
+
+/// A curve whose arkworks types the batched operations accept and return.
+pub trait ArkCurve: CurveParams {
+    type Fq: PrimeField;
+    type Fr: PrimeField;
+    type Group: CurveGroup<BaseField = Self::Fq, ScalarField = Self::Fr>;
+
+    /// The limbs of `pt` in the Montgomery layout of the curve's `MsmPoint`.
+    fn point_to_mont_limbs(pt: &Self::Group, log_limb_size: u32) -> Vec<u32>;
+    fn point_from_mont_limbs(limbs: &[u32], log_limb_size: u32) -> Self::Group;
+}
+
+impl ArkCurve for Secp256k1 {
+    type Fq = ark_secp256k1::Fq;
+    type Fr = ark_secp256k1::Fr;
+    type Group = ark_secp256k1::Projective;
+
+    fn point_to_mont_limbs(pt: &Self::Group, log_limb_size: u32) -> Vec<u32> {
+        sw_to_mont_limbs(pt, log_limb_size)
+    }
+
+    fn point_from_mont_limbs(limbs: &[u32], log_limb_size: u32) -> Self::Group {
+        sw_from_mont_limbs(limbs, log_limb_size)
+    }
+}
+
+impl ArkCurve for Secp256r1 {
+    type Fq = ark_secp256r1::Fq;
+    type Fr = ark_secp256r1::Fr;
+    type Group = ark_secp256r1::Projective;
+
+    fn point_to_mont_limbs(pt: &Self::Group, log_limb_size: u32) -> Vec<u32> {
+        sw_to_mont_limbs(pt, log_limb_size)
+    }
+
+    fn point_from_mont_limbs(limbs: &[u32], log_limb_size: u32) -> Self::Group {
+        sw_from_mont_limbs(limbs, log_limb_size)
+    }
+}
+
+impl ArkCurve for Ed25519 {
+    type Fq = ark_ed25519::Fq;
+    type Fr = ark_ed25519::Fr;
+    type Group = ark_ed25519::EdwardsProjective;
+
+    fn point_to_mont_limbs(pt: &Self::Group, log_limb_size: u32) -> Vec<u32> {
+        let pt = ed25519_curve::affine_to_projective(&pt.into_affine());
+        eteprojective_to_mont_limbs(&pt, &Self::base_field_modulus(), log_limb_size, Reduction::Montgomery)
+    }
+
+    fn point_from_mont_limbs(limbs: &[u32], log_limb_size: u32) -> Self::Group {
+        let coords = mont_limbs_to_field_elements::<Self::Fq>(limbs, log_limb_size);
+        // Only x / z and y / z are needed, so t is dropped
+        let zinv = coords[3].inverse().unwrap();
+        ark_ed25519::EdwardsAffine::new_unchecked(coords[0] * zinv, coords[1] * zinv).into()
+    }
+}
+
+/// Batched arithmetic in the base field of the curve `C`. Each call is one dispatch with a thread
+/// per element.
+pub struct FieldOps<C>(PhantomData<C>);
+
+impl<C: ArkCurve> FieldOps<C> {
+    /// Returns a[i] * b[i], computed with `mont_mul`.
+    pub async fn mul_batch(
+        context: &GpuContext,
+        a: &[C::Fq],
+        b: &[C::Fq],
+        log_limb_size: u32,
+    ) -> Result<Vec<C::Fq>, crate::ShaderFailureError> {
+        assert_eq!(a.len(), b.len());
+        let num_limbs = calc_num_limbs(log_limb_size, C::NUM_BITS);
+        let a_limbs = field_elements_to_mont_limbs(a, log_limb_size);
+        let b_limbs = field_elements_to_mont_limbs(b, log_limb_size);
+        let result = dispatch::<C>(
+            context,
+            "ops_mul",
+            a.len(),
+            &[(&a_limbs, num_limbs), (&b_limbs, num_limbs)],
+            num_limbs,
+            log_limb_size,
+        )
+        .await?;
+        Ok(mont_limbs_to_field_elements(&result, log_limb_size))
+    }
+
+    /// Returns a[i] ^ -1, computed with `ff_inverse`, or `None` where a[i] is zero.
+    pub async fn inverse_batch(
+        context: &GpuContext,
+        a: &[C::Fq],
+        log_limb_size: u32,
+    ) -> Result<Vec<Option<C::Fq>>, crate::ShaderFailureError> {
+        let num_limbs = calc_num_limbs(log_limb_size, C::NUM_BITS);
+        let a_limbs: Vec<u32> = a
+            .iter()
+            .flat_map(|x| bigint::from_biguint_le(&fq_to_biguint(*x), num_limbs, log_limb_size))
+            .collect();
+        let result = dispatch::<C>(
+            context,
+            "ops_inverse",
+            a.len(),
+            &[(&a_limbs, num_limbs)],
+            num_limbs,
+            log_limb_size,
+        )
+        .await?;
+        Ok(result
+            .chunks_exact(num_limbs)
+            .map(|chunk| {
+                let x = bigint::to_biguint_le(chunk, num_limbs, log_limb_size);
+                Some(C::Fq::from_le_bytes_mod_order(&x.to_bytes_le())).filter(|x| !x.is_zero())
+            })
+            .collect())
+    }
+
+    /// Returns a[i] ^ exponent, computed with `modpow`. The exponent must be smaller than
+    /// 2 ^ `C::NUM_BITS`.
+    pub async fn pow_batch(
+        context: &GpuContext,
+        a: &[C::Fq],
+        exponent: &BigUint,
+        log_limb_size: u32,
+    ) -> Result<Vec<C::Fq>, crate::ShaderFailureError> {
+        assert!(exponent.bits() <= C::NUM_BITS as u64);
+        let num_limbs = calc_num_limbs(log_limb_size, C::NUM_BITS);
+        let a_limbs = field_elements_to_mont_limbs(a, log_limb_size);
+        let exponent_limbs = bigint::from_biguint_le(exponent, num_limbs, log_limb_size);
+        let result = dispatch::<C>(
+            context,
+            "ops_pow",
+            a.len(),
+            &[(&a_limbs, num_limbs), (&exponent_limbs, 0)],
+            num_limbs,
+            log_limb_size,
+        )
+        .await?;
+        Ok(mont_limbs_to_field_elements(&result, log_limb_size))
+    }
+}
+
+/// Batched group operations on the curve `C`, with the addition and doubling formulae of
+/// `src/wgsl/{C::NAME}_msm.wgsl`. Each call is one dispatch with a thread per point.
+pub struct GroupOps<C>(PhantomData<C>);
+
+impl<C: ArkCurve> GroupOps<C> {
+    /// Returns a[i] + b[i].
+    pub async fn point_add_batch(
+        context: &GpuContext,
+        a: &[C::Group],
+        b: &[C::Group],
+        log_limb_size: u32,
+    ) -> Result<Vec<C::Group>, crate::ShaderFailureError> {
+        assert_eq!(a.len(), b.len());
+        let point_words = Self::point_words(log_limb_size);
+        let a_limbs: Vec<u32> =
+            a.iter().flat_map(|pt| C::point_to_mont_limbs(pt, log_limb_size)).collect();
+        let b_limbs: Vec<u32> =
+            b.iter().flat_map(|pt| C::point_to_mont_limbs(pt, log_limb_size)).collect();
+        let result = dispatch::<C>(
+            context,
+            "ops_point_add",
+            a.len(),
+            &[(&a_limbs, point_words), (&b_limbs, point_words)],
+            point_words,
+            log_limb_size,
+        )
+        .await?;
+        Ok(Self::points_from_mont_limbs(&result, log_limb_size))
+    }
+
+    /// Returns scalars[i] * points[i], by double-and-add over all `C::NUM_BITS` bits.
+    pub async fn scalar_mul_batch(
+        context: &GpuContext,
+        points: &[C::Group],
+        scalars: &[C::Fr],
+        log_limb_size: u32,
+    ) -> Result<Vec<C::Group>, crate::ShaderFailureError> {
+        assert_eq!(points.len(), scalars.len());
+        let point_words = Self::point_words(log_limb_size);
+        let num_words = C::NUM_BITS / 32;
+        let point_limbs: Vec<u32> =
+            points.iter().flat_map(|pt| C::point_to_mont_limbs(pt, log_limb_size)).collect();
+        let scalar_words: Vec<u32> = scalars
+            .iter()
+            .flat_map(|s| {
+                let s = BigUint::from_bytes_le(&s.into_bigint().to_bytes_le());
+                let mut words = s.to_u32_digits();
+                words.resize(num_words, 0);
+                words
+            })
+            .collect();
+        let result = dispatch::<C>(
+            context,
+            "ops_scalar_mul",
+            points.len(),
+            &[(&point_limbs, point_words), (&scalar_words, num_words)],
+            point_words,
+            log_limb_size,
+        )
+        .await?;
+        Ok(Self::points_from_mont_limbs(&result, log_limb_size))
+    }
+
+    fn point_words(log_limb_size: u32) -> usize {
+        let num_coords = match C::form() {
+            CurveForm::ShortWeierstrass { .. } => 3,
+            CurveForm::TwistedEdwards { .. } => 4,
+        };
+        num_coords * calc_num_limbs(log_limb_size, C::NUM_BITS)
+    }
+
+    fn points_from_mont_limbs(limbs: &[u32], log_limb_size: u32) -> Vec<C::Group> {
+        limbs
+            .chunks_exact(Self::point_words(log_limb_size))
+            .map(|chunk| C::point_from_mont_limbs(chunk, log_limb_size))
+            .collect()
+    }
+}
+
+/// What the single stage of `dispatch` computes, whichever its entry point.
+const STAGE_LABELS: [&str; 1] = ["batched operation"];
+
+/// The workgroup size of `dispatch`.
+const WORKGROUP_SIZE: usize = 256;
+
+/// Runs the entry point `entry_point` of `src/wgsl/main/{entry_point}_main.wgsl` with a thread
+/// per item. Each input is a slice of u32s and the number of u32s per item, or 0 if all the threads
+/// share it. The input buffers are zero-padded to a power of 2 of items, so that the padding
+/// threads, whose results are discarded, read zeroes.
+async fn dispatch<C: CurveParams>(
+    context: &GpuContext,
+    entry_point: &str,
+    num_items: usize,
+    inputs: &[(&[u32], usize)],
+    output_words: usize,
+    log_limb_size: u32,
+) -> Result<Vec<u32>, crate::ShaderFailureError> {
+    // A quarantined device is not trusted with any call
+    if let Some(failure) = context.quarantined() {
+        return Err(failure.into());
+    }
+    if num_items == 0 {
+        return Ok(vec![]);
+    }
+
+    assert!(num_items <= 256 * 256 * 256 * 64);
+    let next_pow_2 = num_items.next_power_of_two();
+    let (num_x_workgroups, num_y_workgroups, num_z_workgroups) =
+        compute_num_workgroups(next_pow_2, WORKGROUP_SIZE);
+    let params = [num_x_workgroups as u32, num_y_workgroups as u32, num_z_workgroups as u32];
+
+    let device = &context.device;
+    let pool = &context.pool;
+
+    let source = render_main::<C>(&format!("{}_main.wgsl", entry_point), log_limb_size);
+    let compute_pipeline = create_compute_pipeline(device, &source, entry_point);
+
+    let mut buffers: Vec<wgpu::Buffer> = inputs
+        .iter()
+        .map(|(data, item_words)| {
+            let num_words = if *item_words == 0 { data.len() } else { item_words * next_pow_2 };
+            let buf = pool.acquire_sb(device, (num_words * 4) as u64);
+            write_sb_with(&context.queue, &buf, |bytes| {
+                bytes[..data.len() * 4].copy_from_slice(bytemuck::cast_slice(data))
+            });
+            buf
+        })
+        .collect();
+    buffers.push(pool.acquire_sb(device, (output_words * next_pow_2 * 4) as u64));
+    buffers.push(acquire_canaries(context, STAGE_LABELS.len()));
+    let params_buf = pool.acquire_ub(device, std::mem::size_of_val(&params) as u64);
+    context.queue.write_buffer(&params_buf, 0, bytemuck::cast_slice(&params));
+
+    let result_buf = &buffers[inputs.len()];
+    let canaries_buf = &buffers[inputs.len() + 1];
+    let bind_group_buffers: Vec<&wgpu::Buffer> =
+        buffers.iter().chain(std::iter::once(&params_buf)).collect();
+    let bind_group = create_bind_group(device, &compute_pipeline, 0, &bind_group_buffers);
+
+    let mut command_encoder = create_command_encoder(device);
+    command_encoder.clear_buffer(canaries_buf, 0, None);
+    execute_pipeline(
+        &mut command_encoder,
+        &compute_pipeline,
+        &bind_group,
+        params[0],
+        params[1],
+        params[2],
+    );
+
+    let mut results = context
+        .finish_encoder_and_read_bytes(command_encoder, &[result_buf, canaries_buf])
+        .await;
+
+    drop(bind_group);
+    drop(bind_group_buffers);
+    for buffer in buffers.into_iter().chain(std::iter::once(params_buf)) {
+        pool.release(buffer);
+    }
+
+    let num_invocations = canary::num_invocations((params[0], params[1], params[2]), WORKGROUP_SIZE);
+    canary::check(&STAGE_LABELS, [(0, num_invocations)], &results[1])?;
+
+    let result_bytes = results.swap_remove(0);
+    Ok(bytemuck::cast_slice(&result_bytes[..num_items * output_words * 4]).to_vec())
+}
+
+fn field_elements_to_mont_limbs<F: PrimeField>(vals: &[F], log_limb_size: u32) -> Vec<u32> {
+    let p = BigUint::from_bytes_le(&F::MODULUS.to_bytes_le());
+    let num_limbs = calc_num_limbs(log_limb_size, 256);
+    let r = mont::calc_mont_radix(num_limbs, log_limb_size);
+    vals.iter()
+        .flat_map(|x| {
+            let xr = fq_to_biguint(*x) * &r % &p;
+            bigint::from_biguint_le(&xr, num_limbs, log_limb_size)
+        })
+        .collect()
+}
+
+/// Converts consecutive big integers in Montgomery form to field elements.
+pub(crate) fn mont_limbs_to_field_elements<F: PrimeField>(
+    limbs: &[u32],
+    log_limb_size: u32,
+) -> Vec<F> {
+    let p = BigUint::from_bytes_le(&F::MODULUS.to_bytes_le());
+    let num_limbs = calc_num_limbs(log_limb_size, 256);
+    let r = mont::calc_mont_radix(num_limbs, log_limb_size);
+    let (rinv, _) = mont::calc_rinv_and_n0(&p, &r, log_limb_size);
+    limbs
+        .chunks_exact(num_limbs)
+        .map(|chunk| {
+            let val = bigint::to_biguint_le(chunk, num_limbs, log_limb_size) * &rinv % &p;
+            F::from_le_bytes_mod_order(&val.to_bytes_le())
+        })
+        .collect()
+}
+
+/// Converts a point to projective (X, Y, Z) coordinates, with the point at infinity as (0, 1, 0).
+fn sw_to_mont_limbs<P: SWCurveConfig>(pt: &Projective<P>, log_limb_size: u32) -> Vec<u32>
+where
+    P::BaseField: PrimeField,
+{
+    let p = BigUint::from_bytes_le(&P::BaseField::MODULUS.to_bytes_le());
+    let pt = match pt.into_affine().xy() {
+        Some((x, y)) => ProjectiveXYZ {
+            x: *x,
+            y: *y,
+            z: P::BaseField::one(),
+        },
+        None => ProjectiveXYZ {
+            x: P::BaseField::zero(),
+            y: P::BaseField::one(),
+            z: P::BaseField::zero(),
+        },
+    };
+    projectivexyz_to_mont_limbs(&pt, &p, log_limb_size, Reduction::Montgomery)
+}
+
+/// Converts a point in projective (X, Y, Z) coordinates to arkworks' Jacobian representation.
+/// Any point with Z = 0 is the point at infinity.
+pub(crate) fn sw_from_mont_limbs<P: SWCurveConfig>(
+    limbs: &[u32],
+    log_limb_size: u32,
+) -> Projective<P>
+where
+    P::BaseField: PrimeField,
+{
+    let coords = mont_limbs_to_field_elements::<P::BaseField>(limbs, log_limb_size);
+    let pt = ProjectiveXYZ {
+        x: coords[0],
+        y: coords[1],
+        z: coords[2],
+    };
+    if pt.z.is_zero() {
+        return Projective::<P>::zero();
+    }
+    let zinv = pt.z.inverse().unwrap();
+    Affine::<P>::new_unchecked(pt.x * zinv, pt.y * zinv).into()
+}
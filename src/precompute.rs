@@ -3,20 +3,31 @@ use crate::curve_algos::coords::ProjectiveXYZ;
 use crate::curve_algos::ed25519_curve::affine_to_projective;
 use num_bigint::BigUint;
 use crate::tests::{projectivexy_to_mont_limbs, eteprojective_to_xyt_mont_limbs};
-use crate::curve_algos::{secp256k1_curve, secp256r1_curve};
+use crate::curve_algos::{secp256k1_curve, secp256r1_curve, secp384r1_curve};
 use ark_ed25519::{EdwardsAffine, EdwardsProjective, Fq};
 use ark_ec::AffineRepr;
 use ark_ec::CurveGroup;
 use ark_ff::{BigInteger, PrimeField};
+use crate::curves::CurveParams;
+use crate::shader::Reduction;
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::io;
+use std::path::Path;
 
 pub const WINDOW_SIZE: u32 = 4;
 
+/// Precomputes the table of multiples of the generator of `P`, with the coordinates converted with
+/// the radix of `reduction`, which must be that of the shaders which read it.
 pub fn generate_table<P: CurveGroup, Q: PrimeField>(
     log_limb_size: u32,
     affine_to_projectivexyz: fn (point: &P::Affine) -> ProjectiveXYZ<Q>,
+    reduction: Reduction,
 ) -> Vec<u32> {
     let g = P::Affine::generator();
     let p = BigUint::from_bytes_be(&Q::MODULUS.to_bytes_be());
+    // The width of the shaders' big integers, e.g. 256 bits for secp256k1 and 384 for secp384r1
+    let num_bits = (Q::MODULUS_BIT_SIZE as usize).next_multiple_of(32);
 
     let table = precompute_table::<P>(g.into(), WINDOW_SIZE);
 
@@ -27,18 +38,30 @@ pub fn generate_table<P: CurveGroup, Q: PrimeField>(
     let mut table_limbs = vec![];
     for t in &table {
         let pt_xyz = affine_to_projectivexyz(t);
-        table_limbs.extend(projectivexy_to_mont_limbs(&pt_xyz, &p, log_limb_size));
+        table_limbs.extend(projectivexy_to_mont_limbs(&pt_xyz, &p, log_limb_size, num_bits, reduction));
     }
 
     table_limbs
 }
 
+/// The table of the secp256k1 pipeline. It holds the x and y of affine points, i.e. with Z = 1,
+/// which are the same in projective and Jacobian coordinates, so every `shader::Coordinates`
+/// reads it.
 pub fn secp256k1_bases(
     log_limb_size: u32
+) -> Vec<u32> {
+    secp256k1_bases_with_reduction(log_limb_size, Reduction::Montgomery)
+}
+
+/// Like `secp256k1_bases`, for a pipeline whose `mont_mul` reduces with `reduction`.
+pub fn secp256k1_bases_with_reduction(
+    log_limb_size: u32,
+    reduction: Reduction,
 ) -> Vec<u32> {
     generate_table::<ark_secp256k1::Projective, ark_secp256k1::Fq>(
         log_limb_size,
         secp256k1_curve::affine_to_projectivexyz,
+        reduction,
     )
 }
 
@@ -48,11 +71,30 @@ pub fn secp256r1_bases(
     generate_table::<ark_secp256r1::Projective, ark_secp256r1::Fq>(
         log_limb_size,
         secp256r1_curve::affine_to_projectivexyz,
+        Reduction::Montgomery,
+    )
+}
+
+pub fn secp384r1_bases(
+    log_limb_size: u32
+) -> Vec<u32> {
+    generate_table::<ark_secp384r1::Projective, ark_secp384r1::Fq>(
+        log_limb_size,
+        secp384r1_curve::affine_to_projectivexyz,
+        Reduction::Montgomery,
     )
 }
 
 pub fn ed25519_bases(
     log_limb_size: u32
+) -> Vec<u32> {
+    ed25519_bases_with_reduction(log_limb_size, Reduction::Montgomery)
+}
+
+/// Like `ed25519_bases`, for a pipeline whose `mont_mul` reduces with `reduction`.
+pub fn ed25519_bases_with_reduction(
+    log_limb_size: u32,
+    reduction: Reduction,
 ) -> Vec<u32> {
     let g = EdwardsAffine::generator();
     let p = BigUint::from_bytes_be(&Fq::MODULUS.to_bytes_be());
@@ -62,8 +104,241 @@ pub fn ed25519_bases(
     let mut table_limbs = vec![];
     for t in &table {
         let pt_xytz = affine_to_projective(t);
-        table_limbs.extend(eteprojective_to_xyt_mont_limbs(&pt_xytz, &p, log_limb_size));
+        table_limbs.extend(eteprojective_to_xyt_mont_limbs(&pt_xytz, &p, log_limb_size, reduction));
     }
 
     table_limbs
 }
+
+/// The first bytes of a file written by `BaseTable::save`.
+const BASE_TABLE_MAGIC: &[u8; 4] = b"WSBT";
+
+/// The version of the on-disk format of `BaseTable`. Bump it whenever the layout of the file or of
+/// the table limbs changes, so that stale files are refused instead of misread.
+pub const BASE_TABLE_FORMAT_VERSION: u32 = 1;
+
+/// A precomputed table of multiples of a curve's generator, together with the parameters which its
+/// layout depends on. The coordinates are in Montgomery form, for pipelines with the default
+/// `shader::Reduction`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BaseTable {
+    pub curve: String,
+    pub log_limb_size: u32,
+    pub window_size: u32,
+    /// The SHA-256 digest of the parameters and the limbs.
+    pub checksum: [u8; 32],
+    limbs: Vec<u32>,
+}
+
+/// The reasons why a `BaseTable` cannot be loaded.
+#[derive(Debug)]
+pub enum BaseTableError {
+    Io(io::Error),
+    /// The file is not a base table, or was written in another version of the format.
+    InvalidFormat,
+    /// The limbs or the parameters in the file do not match its checksum.
+    ChecksumMismatch,
+    /// The table was built for other parameters than the shader which is being run.
+    ParamsMismatch {
+        expected: (String, u32, u32),
+        found: (String, u32, u32),
+    },
+}
+
+impl fmt::Display for BaseTableError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BaseTableError::Io(e) => write!(f, "{}", e),
+            BaseTableError::InvalidFormat => write!(
+                f,
+                "not a base table of format version {}",
+                BASE_TABLE_FORMAT_VERSION
+            ),
+            BaseTableError::ChecksumMismatch => write!(f, "the base table is corrupt"),
+            BaseTableError::ParamsMismatch { expected, found } => write!(
+                f,
+                "expected a base table for (curve, log_limb_size, window_size) = {:?}, found {:?}",
+                expected, found
+            ),
+        }
+    }
+}
+
+impl std::error::Error for BaseTableError {}
+
+impl From<io::Error> for BaseTableError {
+    fn from(e: io::Error) -> Self {
+        BaseTableError::Io(e)
+    }
+}
+
+impl BaseTable {
+    /// Precomputes the table of the curve `C` for `log_limb_size` and `WINDOW_SIZE`.
+    pub fn generate<C: CurveParams>(log_limb_size: u32) -> Self {
+        Self::new(C::NAME, log_limb_size, WINDOW_SIZE, C::bases(log_limb_size))
+    }
+
+    fn new(curve: &str, log_limb_size: u32, window_size: u32, limbs: Vec<u32>) -> Self {
+        let checksum = Self::compute_checksum(curve, log_limb_size, window_size, &limbs);
+        Self {
+            curve: curve.to_owned(),
+            log_limb_size,
+            window_size,
+            checksum,
+            limbs,
+        }
+    }
+
+    fn compute_checksum(
+        curve: &str,
+        log_limb_size: u32,
+        window_size: u32,
+        limbs: &[u32],
+    ) -> [u8; 32] {
+        let mut hasher = Sha256::new();
+        hasher.update(curve.as_bytes());
+        hasher.update(log_limb_size.to_le_bytes());
+        hasher.update(window_size.to_le_bytes());
+        for limb in limbs {
+            hasher.update(limb.to_le_bytes());
+        }
+        hasher.finalize().into()
+    }
+
+    /// The limbs to pass as `table_limbs` to the recovery and verification functions.
+    pub fn limbs(&self) -> &[u32] {
+        &self.limbs
+    }
+
+    /// Returns an error unless the table was built for the curve `C`, `log_limb_size`, and
+    /// `WINDOW_SIZE`.
+    pub fn check<C: CurveParams>(&self, log_limb_size: u32) -> Result<(), BaseTableError> {
+        let expected = (C::NAME.to_owned(), log_limb_size, WINDOW_SIZE);
+        let found = (self.curve.clone(), self.log_limb_size, self.window_size);
+        if expected != found {
+            return Err(BaseTableError::ParamsMismatch { expected, found });
+        }
+        Ok(())
+    }
+
+    /// Writes the table to `path`, with every limb packed into `log_limb_size` bits.
+    ///
+    /// The layout is: magic, version, curve name length (1 byte), curve name, log_limb_size,
+    /// window_size, number of limbs, packed limbs, checksum. Integers are little-endian u32s.
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let mut bytes = vec![];
+        bytes.extend_from_slice(BASE_TABLE_MAGIC);
+        bytes.extend_from_slice(&BASE_TABLE_FORMAT_VERSION.to_le_bytes());
+        bytes.push(self.curve.len() as u8);
+        bytes.extend_from_slice(self.curve.as_bytes());
+        bytes.extend_from_slice(&self.log_limb_size.to_le_bytes());
+        bytes.extend_from_slice(&self.window_size.to_le_bytes());
+        bytes.extend_from_slice(&(self.limbs.len() as u32).to_le_bytes());
+        bytes.extend(pack_limbs(&self.limbs, self.log_limb_size));
+        bytes.extend_from_slice(&self.checksum);
+        std::fs::write(path, bytes)
+    }
+
+    /// Reads a table written by `save`, and verifies its checksum.
+    pub fn load(path: &Path) -> Result<Self, BaseTableError> {
+        let bytes = std::fs::read(path)?;
+        let mut reader = ByteReader { bytes: &bytes, pos: 0 };
+
+        if reader.take(4)? != BASE_TABLE_MAGIC || reader.read_u32()? != BASE_TABLE_FORMAT_VERSION {
+            return Err(BaseTableError::InvalidFormat);
+        }
+        let curve_len = reader.take(1)?[0] as usize;
+        let curve = std::str::from_utf8(reader.take(curve_len)?)
+            .map_err(|_| BaseTableError::InvalidFormat)?
+            .to_owned();
+        let log_limb_size = reader.read_u32()?;
+        let window_size = reader.read_u32()?;
+        let num_limbs = reader.read_u32()? as usize;
+        if log_limb_size == 0 || log_limb_size > 32 {
+            return Err(BaseTableError::InvalidFormat);
+        }
+
+        let packed_len = (num_limbs * log_limb_size as usize).div_ceil(8);
+        let limbs = unpack_limbs(reader.take(packed_len)?, num_limbs, log_limb_size);
+        let checksum: [u8; 32] = reader.take(32)?.try_into().unwrap();
+        if reader.pos != bytes.len() {
+            return Err(BaseTableError::InvalidFormat);
+        }
+
+        let table = Self::new(&curve, log_limb_size, window_size, limbs);
+        if table.checksum != checksum {
+            return Err(BaseTableError::ChecksumMismatch);
+        }
+        Ok(table)
+    }
+
+    /// Reads a table written by `save`, and refuses it unless it was built for the curve `C`,
+    /// `log_limb_size`, and `WINDOW_SIZE`.
+    pub fn load_for<C: CurveParams>(
+        path: &Path,
+        log_limb_size: u32,
+    ) -> Result<Self, BaseTableError> {
+        let table = Self::load(path)?;
+        table.check::<C>(log_limb_size)?;
+        Ok(table)
+    }
+
+    /// Like `load_for`, but if there is no file at `path`, precomputes the table and saves it
+    /// there. Files for other parameters are still refused rather than overwritten.
+    pub fn load_or_generate<C: CurveParams>(
+        path: &Path,
+        log_limb_size: u32,
+    ) -> Result<Self, BaseTableError> {
+        match Self::load_for::<C>(path, log_limb_size) {
+            Err(BaseTableError::Io(e)) if e.kind() == io::ErrorKind::NotFound => {
+                let table = Self::generate::<C>(log_limb_size);
+                table.save(path)?;
+                Ok(table)
+            }
+            result => result,
+        }
+    }
+}
+
+struct ByteReader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> ByteReader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], BaseTableError> {
+        let end = self.pos.checked_add(len).ok_or(BaseTableError::InvalidFormat)?;
+        let slice = self.bytes.get(self.pos..end).ok_or(BaseTableError::InvalidFormat)?;
+        self.pos = end;
+        Ok(slice)
+    }
+
+    fn read_u32(&mut self) -> Result<u32, BaseTableError> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+}
+
+/// Concatenates the low `log_limb_size` bits of each limb, least significant bit first.
+fn pack_limbs(limbs: &[u32], log_limb_size: u32) -> Vec<u8> {
+    let mut packed = vec![0u8; (limbs.len() * log_limb_size as usize).div_ceil(8)];
+    for (i, limb) in limbs.iter().enumerate() {
+        for j in 0..log_limb_size as usize {
+            if (limb >> j) & 1 == 1 {
+                let bit = i * log_limb_size as usize + j;
+                packed[bit / 8] |= 1 << (bit % 8);
+            }
+        }
+    }
+    packed
+}
+
+fn unpack_limbs(packed: &[u8], num_limbs: usize, log_limb_size: u32) -> Vec<u32> {
+    (0..num_limbs)
+        .map(|i| {
+            (0..log_limb_size as usize).fold(0u32, |limb, j| {
+                let bit = i * log_limb_size as usize + j;
+                limb | ((((packed[bit / 8] >> (bit % 8)) & 1) as u32) << j)
+            })
+        })
+        .collect()
+}
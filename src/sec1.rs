@@ -0,0 +1,123 @@
+use crate::Verdict;
+
+/// The length of a SEC1 compressed public key: a 0x02 or 0x03 prefix for the parity of y, followed
+/// by the 32 big-endian bytes of x.
+pub const COMPRESSED_PUBLIC_KEY_LEN: usize = 33;
+
+/// The stride of compressed public keys in the shaders' buffers, which pad each key with zeroes to
+/// a whole number of words.
+pub const PADDED_COMPRESSED_PUBLIC_KEY_LEN: usize = 36;
+
+pub type CompressedPublicKey = [u8; COMPRESSED_PUBLIC_KEY_LEN];
+
+/// What the last stage of the secp256k1 and secp256r1 recovery pipelines writes for each
+/// signature.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FinalStage {
+    /// The 64-byte x || y public key.
+    #[default]
+    Uncompressed,
+    /// The SEC1 compressed public key, padded to `PADDED_COMPRESSED_PUBLIC_KEY_LEN` bytes.
+    Compressed,
+    /// Whether the recovered public key equals a given SEC1 compressed public key, as a u32.
+    VerifyCompressed,
+}
+
+impl FinalStage {
+    /// The number of bytes which the stage writes per signature.
+    pub fn output_len(&self) -> usize {
+        match self {
+            FinalStage::Uncompressed => 64,
+            FinalStage::Compressed => PADDED_COMPRESSED_PUBLIC_KEY_LEN,
+            FinalStage::VerifyCompressed => std::mem::size_of::<u32>(),
+        }
+    }
+
+    /// The template in `src/wgsl/main` and the entry point of the stage for the curve `curve_name`.
+    pub fn shader(&self, curve_name: &str) -> (String, String) {
+        match self {
+            FinalStage::Uncompressed => (
+                format!("{}_ecdsa_main_4.wgsl", curve_name),
+                format!("{}_recover_4", curve_name),
+            ),
+            FinalStage::Compressed => (
+                format!("{}_ecdsa_main_4.wgsl", curve_name),
+                format!("{}_recover_4_compressed", curve_name),
+            ),
+            FinalStage::VerifyCompressed => (
+                format!("{}_ecdsa_verify_main_4.wgsl", curve_name),
+                format!("{}_verify_4", curve_name),
+            ),
+        }
+    }
+}
+
+/// The result of one signature in the output of a `FinalStage`, which an integrity check compares
+/// with that of the CPU.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StageItem {
+    Uncompressed(Option<[u8; 64]>),
+    Compressed(Option<CompressedPublicKey>),
+    Verdict(Verdict),
+}
+
+impl FinalStage {
+    /// The result of signature `index` in the output of the stage. `parse` maps 64 bytes of x || y
+    /// to the public key, or to `None` if they are not a valid point.
+    pub fn item(&self, output: &[u8], index: usize, parse: impl Fn(&[u8]) -> Option<[u8; 64]>) -> StageItem {
+        let chunk = &output[index * self.output_len()..(index + 1) * self.output_len()];
+        match self {
+            FinalStage::Uncompressed => StageItem::Uncompressed(parse(chunk)),
+            FinalStage::Compressed => StageItem::Compressed(bytes_to_compressed_public_keys(chunk)[0]),
+            FinalStage::VerifyCompressed => StageItem::Verdict(bytes_to_verdicts(chunk)[0]),
+        }
+    }
+
+    /// The result which the stage writes for a signature whose recovered x || y public key is
+    /// `recovered`, and for `FinalStage::VerifyCompressed` whose given key is `public_key`.
+    pub fn reference_item(&self, recovered: Option<[u8; 64]>, public_key: Option<&CompressedPublicKey>) -> StageItem {
+        let compressed = recovered.map(|xy| compress(&xy));
+        match self {
+            FinalStage::Uncompressed => StageItem::Uncompressed(recovered),
+            FinalStage::Compressed => StageItem::Compressed(compressed),
+            FinalStage::VerifyCompressed => {
+                StageItem::Verdict(Verdict::from(compressed.is_some() && compressed.as_ref() == public_key))
+            }
+        }
+    }
+}
+
+/// Compresses a 64-byte x || y public key.
+pub fn compress(xy: &[u8; 64]) -> CompressedPublicKey {
+    let mut pk = [0u8; COMPRESSED_PUBLIC_KEY_LEN];
+    pk[0] = 0x02 | (xy[63] & 1);
+    pk[1..].copy_from_slice(&xy[..32]);
+    pk
+}
+
+/// Writes compressed public keys into `dst` with the padded stride of the shaders.
+pub fn pack_compressed_public_keys(dst: &mut [u8], public_keys: &[CompressedPublicKey]) {
+    for (chunk, pk) in dst.chunks_exact_mut(PADDED_COMPRESSED_PUBLIC_KEY_LEN).zip(public_keys) {
+        chunk[..COMPRESSED_PUBLIC_KEY_LEN].copy_from_slice(pk);
+    }
+}
+
+/// Parses the output of `FinalStage::Compressed`. Invalid points, which the shader writes with a
+/// zero prefix, map to `None`.
+pub fn bytes_to_compressed_public_keys(recovered: &[u8]) -> Vec<Option<CompressedPublicKey>> {
+    recovered
+        .chunks_exact(PADDED_COMPRESSED_PUBLIC_KEY_LEN)
+        .map(|chunk| {
+            let pk: CompressedPublicKey = chunk[..COMPRESSED_PUBLIC_KEY_LEN].try_into().unwrap();
+            (pk[0] != 0).then_some(pk)
+        })
+        .collect()
+}
+
+/// Parses the output of `FinalStage::VerifyCompressed`.
+pub fn bytes_to_verdicts(results: &[u8]) -> Vec<Verdict> {
+    results
+        .chunks_exact(std::mem::size_of::<u32>())
+        .map(|chunk| Verdict::from(chunk[0] == 1))
+        .collect()
+}
@@ -1,24 +1,42 @@
 use crate::benchmarks::compute_num_workgroups;
+use crate::canary::{self, acquire_canaries};
+use crate::all_valid::{AllValidError, AllValidReduction, BatchVerdict, Bitmap};
+use crate::checkpoint::Checkpointing;
+use crate::compaction::{Compaction, Survivors};
+use crate::coop::{Cooperation, CooperationError, COOP_WORKGROUP_SIZE};
+use crate::integrity::{check_call, NUM_KNOWN_ANSWERS};
 use crate::gpu::{
-    create_bind_group, create_command_encoder, create_compute_pipeline, create_empty_sb,
-    create_sb_with_data, create_ub_with_data, execute_pipeline,
-    finish_encoder_and_read_bytes_from_gpu, get_device_and_queue,
+    create_bind_group, create_bind_group_with_optional, create_command_encoder,
+    create_compute_pipeline, create_empty_sb, create_sb_with_data, create_sb_with_writer,
+    create_ub_with_data, execute_pipeline, finish_encoder_and_read_bytes_from_gpu,
+    get_device_and_queue, write_sb_with, BufferPool, Dispatch, GpuContext,
 };
-use crate::shader::render_secp256k1_ecdsa;
-use fuel_crypto::{Message, Signature};
+use crate::curves::{CurveParams, Secp256k1};
+use crate::precompute::BaseTable;
+use crate::derive::do_derive;
+use crate::sec1::{
+    bytes_to_compressed_public_keys, bytes_to_verdicts, pack_compressed_public_keys,
+    CompressedPublicKey, FinalStage, StageItem, PADDED_COMPRESSED_PUBLIC_KEY_LEN,
+};
+use crate::shader::{render_main, render_main_with_compaction, Coordinates, Reduction};
+use crate::signature_encoding::SignatureEncoding;
+use crate::streaming::Scheme;
+use crate::timings::{run_timed, StageTimings};
+use crate::Verdict;
+use fuel_crypto::{Message, PublicKey, SecretKey, Signature};
+use fuel_types::Bytes32;
 use multiprecision::utils::calc_num_limbs;
+use sha2::{Digest, Sha256};
+use std::time::Instant;
 
+/// Returns the batch size padded to the next power of 2, the number of limbs per big integer, and
+/// the number of workgroups to dispatch for `num_signatures` signatures.
 pub fn init(
-    signatures: &Vec<Signature>,
-    messages: &Vec<Message>,
+    num_signatures: usize,
     log_limb_size: u32,
-) ->
-    (usize, usize, usize, Vec<u32>, Vec<u32>, (u32, u32, u32))
-{
+) -> (usize, usize, (u32, u32, u32)) {
     let num_limbs = calc_num_limbs(log_limb_size, 256);
 
-    let num_signatures = signatures.len();
-    assert_eq!(num_signatures, messages.len());
     assert!(num_signatures <= 256 * 256 * 256 * 64);
 
     // Compute the next power of 2
@@ -26,213 +44,1046 @@ pub fn init(
 
     assert!(num_signatures <= next_pow_2);
 
-    let mut all_sig_bytes = Vec::<u8>::with_capacity(next_pow_2 * 64);
-    let mut all_msg_bytes = Vec::<u8>::with_capacity(next_pow_2 * 32);
-    for sig in signatures {
-        let sig_bytes = sig.as_slice();
-        all_sig_bytes.extend(sig_bytes);
-    }
-
-    for msg in messages {
-        let msg_bytes = msg.as_slice();
-        all_msg_bytes.extend(msg_bytes);
-    }
-
-    if num_signatures < next_pow_2 {
-        all_sig_bytes.resize(next_pow_2 * 64, 0);
-        all_msg_bytes.resize(next_pow_2 * 32, 0);
-    }
-
     let workgroup_size = 256;
     let (num_x_workgroups, num_y_workgroups, num_z_workgroups) =
         compute_num_workgroups(next_pow_2, workgroup_size);
 
-    let all_sig_u32s: Vec<u32> = bytemuck::cast_slice(&all_sig_bytes).to_vec();
-    let all_msg_u32s: Vec<u32> = bytemuck::cast_slice(&all_msg_bytes).to_vec();
-
     let params = (
         num_x_workgroups as u32,
         num_y_workgroups as u32,
         num_z_workgroups as u32,
     );
-    (num_signatures, next_pow_2, num_limbs, all_sig_u32s, all_msg_u32s, params)
+    (next_pow_2, num_limbs, params)
+}
+
+/// Writes 64-byte signatures into `dst`, which is usually the mapped range of an upload buffer.
+pub fn pack_signatures(dst: &mut [u8], signatures: &[Signature]) {
+    for (chunk, sig) in dst.chunks_exact_mut(64).zip(signatures) {
+        chunk.copy_from_slice(sig.as_slice());
+    }
+}
+
+/// Writes 32-byte messages into `dst`, which is usually the mapped range of an upload buffer.
+pub fn pack_messages(dst: &mut [u8], messages: &[Message]) {
+    for (chunk, msg) in dst.chunks_exact_mut(32).zip(messages) {
+        chunk.copy_from_slice(msg.as_slice());
+    }
+}
+
+/// Parses the 64-byte x || y output of the shaders into public keys. Invalid points (e.g. those
+/// recovered from malformed signatures) map to `None`, as does the point at infinity, which the
+/// last stage writes as 64 zero bytes when u1 * G == -u2 * R.
+pub fn bytes_to_public_keys(recovered: &[u8]) -> Vec<Option<PublicKey>> {
+    recovered
+        .chunks_exact(64)
+        .map(|pk_bytes| {
+            if pk_bytes.iter().all(|b| *b == 0) {
+                return None;
+            }
+            PublicKey::try_from(pk_bytes).ok()
+        })
+        .collect()
 }
 
 pub async fn ecrecover(
-    signatures: &Vec<Signature>,
-    messages: &Vec<Message>,
-    table_limbs: &Vec<u32>,
+    signatures: &[Signature],
+    messages: &[Message],
+    table_limbs: &[u32],
     log_limb_size: u32,
-) -> Result<Vec<Vec<u8>>, crate::ShaderFailureError> {
-    let (num_signatures, next_pow_2, num_limbs, all_sig_u32s, all_msg_u32s, params_t) = init(&signatures, &messages, log_limb_size);
-    let (num_x_workgroups, num_y_workgroups, num_z_workgroups) = params_t;
-    let params = &[num_x_workgroups, num_y_workgroups, num_z_workgroups];
+) -> Result<Vec<Option<PublicKey>>, crate::ShaderFailureError> {
+    let context = GpuContext::new().await;
+    ecrecover_with_context(&context, signatures, messages, table_limbs, log_limb_size).await
+}
+
+/// Like `ecrecover`, but runs on an existing context so that buffers are reused across calls.
+pub async fn ecrecover_with_context(
+    context: &GpuContext,
+    signatures: &[Signature],
+    messages: &[Message],
+    table_limbs: &[u32],
+    log_limb_size: u32,
+) -> Result<Vec<Option<PublicKey>>, crate::ShaderFailureError> {
+    assert_eq!(signatures.len(), messages.len());
+    let recovered = do_ecrecover(
+        context,
+        signatures.len(),
+        |buf| pack_signatures(buf, signatures),
+        |buf| pack_messages(buf, messages),
+        |index| reference(&signatures[index], &messages[index]),
+        &[],
+        table_limbs,
+        log_limb_size,
+        &RecoveryOptions::default(),
+        None,
+    )
+    .await?;
+    Ok(bytes_to_public_keys(&recovered))
+}
+
+/// Like `ecrecover_with_context`, but takes a `BaseTable` and refuses it unless it was built for
+/// secp256k1 and `log_limb_size`.
+pub async fn ecrecover_with_table(
+    context: &GpuContext,
+    signatures: &[Signature],
+    messages: &[Message],
+    table: &BaseTable,
+    log_limb_size: u32,
+) -> Result<Vec<Option<PublicKey>>, crate::PipelineError> {
+    table.check::<Secp256k1>(log_limb_size)?;
+    let recovered = ecrecover_with_context(context, signatures, messages, table.limbs(), log_limb_size).await?;
+    Ok(recovered)
+}
+
+/// The options of the multi-shader recovery pipeline, which combine freely except where noted.
+/// The defaults recover 64-byte public keys with a single dispatch per stage.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RecoveryOptions {
+    /// What the last stage writes for each signature.
+    pub final_stage: FinalStage,
+    /// The coordinate system of the points in every stage. The same table serves either.
+    pub coordinates: Coordinates,
+    /// How every stage reduces products of base field elements. The table must come from
+    /// `precompute::secp256k1_bases_with_reduction` with the same reduction. The cooperative
+    /// kernel only supports `Reduction::Montgomery`.
+    pub reduction: Reduction,
+    /// The number of invocations per signature in the variable-base multiplication u2 * R (see
+    /// `coop.rs`).
+    pub cooperation: Cooperation,
+    /// How many sub-dispatches the scalar multiplications of stages 1 and 2 take. The cooperative
+    /// kernel always runs in one dispatch.
+    pub checkpointing: Checkpointing,
+    /// Whether the stages after stage 0 run only over the signatures whose R is on the curve. The
+    /// cooperative kernel runs over every signature.
+    pub compaction: Compaction,
+    /// A reduction stage which summarises the verdicts of `FinalStage::VerifyCompressed`, which it
+    /// needs, optionally with a bit per signature.
+    pub all_valid: Option<Bitmap>,
+}
+
+/// Like `ecrecover_with_context`, but with any combination of `options`, and returns the raw
+/// output of the pipeline: `options.final_stage.output_len()` bytes per signature, which
+/// `bytes_to_public_keys`, `sec1::bytes_to_compressed_public_keys`, or `sec1::bytes_to_verdicts`
+/// parse, or with `options.all_valid` the summary which `BatchVerdict::from_bytes` parses.
+/// `public_keys` is only read for `FinalStage::VerifyCompressed`. Options which the curve cannot
+/// run, e.g. `Cooperation::Lanes` with limbs of more than 13 bits, are an error.
+pub async fn ecrecover_with_options(
+    context: &GpuContext,
+    signatures: &[Signature],
+    messages: &[Message],
+    public_keys: &[CompressedPublicKey],
+    table_limbs: &[u32],
+    log_limb_size: u32,
+    options: &RecoveryOptions,
+) -> Result<Vec<u8>, crate::PipelineError> {
+    assert_eq!(signatures.len(), messages.len());
+    if options.final_stage == FinalStage::VerifyCompressed {
+        assert_eq!(signatures.len(), public_keys.len());
+    }
+    options.reduction.check(&Secp256k1::base_field_modulus(), log_limb_size)?;
+    if options.all_valid.is_some() {
+        AllValidError::check(options.final_stage)?;
+    }
+    options.cooperation.check::<Secp256k1>(log_limb_size)?;
+    if options.cooperation != Cooperation::PerInvocation && options.reduction != Reduction::Montgomery {
+        return Err(CooperationError::UnsupportedReduction(options.reduction).into());
+    }
+    let result = do_ecrecover(
+        context,
+        signatures.len(),
+        |buf| pack_signatures(buf, signatures),
+        |buf| pack_messages(buf, messages),
+        |index| reference(&signatures[index], &messages[index]),
+        public_keys,
+        table_limbs,
+        log_limb_size,
+        options,
+        None,
+    )
+    .await?;
+    Ok(result)
+}
+
+/// Like `ecrecover_with_context`, but also reports how long the upload, each stage, and the
+/// readback took.
+pub async fn ecrecover_with_timings(
+    context: &GpuContext,
+    signatures: &[Signature],
+    messages: &[Message],
+    table_limbs: &[u32],
+    log_limb_size: u32,
+) -> Result<(Vec<Option<PublicKey>>, StageTimings), crate::ShaderFailureError> {
+    assert_eq!(signatures.len(), messages.len());
+    let mut timings = StageTimings::default();
+    let recovered = do_ecrecover(
+        context,
+        signatures.len(),
+        |buf| pack_signatures(buf, signatures),
+        |buf| pack_messages(buf, messages),
+        |index| reference(&signatures[index], &messages[index]),
+        &[],
+        table_limbs,
+        log_limb_size,
+        &RecoveryOptions::default(),
+        Some(&mut timings),
+    )
+    .await?;
+    Ok((bytes_to_public_keys(&recovered), timings))
+}
+
+/// Like `ecrecover`, but takes signatures in any `SignatureEncoding`, e.g. Ethereum's 65-byte
+/// r || s || v. Signatures which cannot be decoded map to `None`.
+pub async fn ecrecover_encoded<S: AsRef<[u8]>>(
+    encoding: SignatureEncoding,
+    signatures: &[S],
+    messages: &[Message],
+    table_limbs: &[u32],
+    log_limb_size: u32,
+) -> Result<Vec<Option<PublicKey>>, crate::ShaderFailureError> {
+    let context = GpuContext::new().await;
+    ecrecover_encoded_with_context(
+        &context,
+        encoding,
+        signatures,
+        messages,
+        table_limbs,
+        log_limb_size,
+    )
+    .await
+}
+
+/// Like `ecrecover_encoded`, but runs on an existing context so that buffers are reused across
+/// calls.
+pub async fn ecrecover_encoded_with_context<S: AsRef<[u8]>>(
+    context: &GpuContext,
+    encoding: SignatureEncoding,
+    signatures: &[S],
+    messages: &[Message],
+    table_limbs: &[u32],
+    log_limb_size: u32,
+) -> Result<Vec<Option<PublicKey>>, crate::ShaderFailureError> {
+    assert_eq!(signatures.len(), messages.len());
+    let n = crate::moduli::secp256k1_fr_modulus_biguint();
+    let compact: Vec<Option<[u8; 64]>> =
+        signatures.iter().map(|sig| encoding.to_compact(sig.as_ref(), &n)).collect();
+    let recovered = do_ecrecover(
+        context,
+        signatures.len(),
+        |buf| {
+            for (chunk, sig) in buf.chunks_exact_mut(64).zip(&compact) {
+                if let Some(sig) = sig {
+                    chunk.copy_from_slice(sig);
+                }
+            }
+        },
+        |buf| pack_messages(buf, messages),
+        |index| compact[index].and_then(|sig| reference(&Signature::from_bytes(sig), &messages[index])),
+        &[],
+        table_limbs,
+        log_limb_size,
+        &RecoveryOptions::default(),
+        None,
+    )
+    .await?;
+    // Undecodable signatures are uploaded as zeroes, so their output is meaningless
+    Ok(bytes_to_public_keys(&recovered)
+        .into_iter()
+        .zip(&compact)
+        .map(|(pk, sig)| pk.filter(|_| sig.is_some()))
+        .collect())
+}
+
+/// Like `ecrecover`, but returns SEC1 compressed public keys, which the last stage computes.
+pub async fn ecrecover_compressed(
+    signatures: &[Signature],
+    messages: &[Message],
+    table_limbs: &[u32],
+    log_limb_size: u32,
+) -> Result<Vec<Option<CompressedPublicKey>>, crate::ShaderFailureError> {
+    let context = GpuContext::new().await;
+    ecrecover_compressed_with_context(&context, signatures, messages, table_limbs, log_limb_size)
+        .await
+}
+
+/// Like `ecrecover_compressed`, but runs on an existing context so that buffers are reused across
+/// calls.
+pub async fn ecrecover_compressed_with_context(
+    context: &GpuContext,
+    signatures: &[Signature],
+    messages: &[Message],
+    table_limbs: &[u32],
+    log_limb_size: u32,
+) -> Result<Vec<Option<CompressedPublicKey>>, crate::ShaderFailureError> {
+    assert_eq!(signatures.len(), messages.len());
+    let recovered = do_ecrecover(
+        context,
+        signatures.len(),
+        |buf| pack_signatures(buf, signatures),
+        |buf| pack_messages(buf, messages),
+        |index| reference(&signatures[index], &messages[index]),
+        &[],
+        table_limbs,
+        log_limb_size,
+        &RecoveryOptions {
+            final_stage: FinalStage::Compressed,
+            ..Default::default()
+        },
+        None,
+    )
+    .await?;
+    Ok(bytes_to_compressed_public_keys(&recovered))
+}
+
+/// Verifies each signature against a SEC1 compressed public key. The last stage decompresses the
+/// key with `secp256k1_recover_affine_ys` and compares it with the recovered key, so only a
+/// verdict per signature is read back.
+pub async fn ecverify_compressed(
+    signatures: &[Signature],
+    messages: &[Message],
+    public_keys: &[CompressedPublicKey],
+    table_limbs: &[u32],
+    log_limb_size: u32,
+) -> Result<Vec<Verdict>, crate::ShaderFailureError> {
+    let context = GpuContext::new().await;
+    ecverify_compressed_with_context(
+        &context,
+        signatures,
+        messages,
+        public_keys,
+        table_limbs,
+        log_limb_size,
+    )
+    .await
+}
 
+/// Like `ecverify_compressed`, but runs on an existing context so that buffers are reused across
+/// calls.
+pub async fn ecverify_compressed_with_context(
+    context: &GpuContext,
+    signatures: &[Signature],
+    messages: &[Message],
+    public_keys: &[CompressedPublicKey],
+    table_limbs: &[u32],
+    log_limb_size: u32,
+) -> Result<Vec<Verdict>, crate::ShaderFailureError> {
+    assert_eq!(signatures.len(), messages.len());
+    assert_eq!(signatures.len(), public_keys.len());
+    let results = do_ecrecover(
+        context,
+        signatures.len(),
+        |buf| pack_signatures(buf, signatures),
+        |buf| pack_messages(buf, messages),
+        |index| reference(&signatures[index], &messages[index]),
+        public_keys,
+        table_limbs,
+        log_limb_size,
+        &RecoveryOptions {
+            final_stage: FinalStage::VerifyCompressed,
+            ..Default::default()
+        },
+        None,
+    )
+    .await?;
+    Ok(bytes_to_verdicts(&results))
+}
+
+/// Like `ecverify_compressed_with_context`, but only reads back whether every signature is valid,
+/// the index of the first which is not, and with `Bitmap::Packed` a bit per signature. A reduction
+/// stage on the GPU computes them from the verdicts.
+pub async fn ecverify_compressed_all_valid(
+    context: &GpuContext,
+    signatures: &[Signature],
+    messages: &[Message],
+    public_keys: &[CompressedPublicKey],
+    table_limbs: &[u32],
+    log_limb_size: u32,
+    bitmap: Bitmap,
+) -> Result<BatchVerdict, crate::ShaderFailureError> {
+    assert_eq!(signatures.len(), messages.len());
+    assert_eq!(signatures.len(), public_keys.len());
+    if signatures.is_empty() {
+        return Ok(BatchVerdict { first_failure: None, bitmap: bitmap.is_on().then(Vec::new) });
+    }
+    let summary = do_ecrecover(
+        context,
+        signatures.len(),
+        |buf| pack_signatures(buf, signatures),
+        |buf| pack_messages(buf, messages),
+        |index| reference(&signatures[index], &messages[index]),
+        public_keys,
+        table_limbs,
+        log_limb_size,
+        &RecoveryOptions {
+            final_stage: FinalStage::VerifyCompressed,
+            all_valid: Some(bitmap),
+            ..Default::default()
+        },
+        None,
+    )
+    .await?;
+    Ok(BatchVerdict::from_bytes(&summary, signatures.len(), bitmap))
+}
+
+/// Derives the public keys of secret keys with the fixed-base multiplication used in recovery, in
+/// a single dispatch.
+pub async fn derive_public_keys(
+    secret_keys: &[SecretKey],
+    table_limbs: &[u32],
+    log_limb_size: u32,
+) -> Result<Vec<PublicKey>, crate::ShaderFailureError> {
+    let context = GpuContext::new().await;
+    derive_public_keys_with_context(&context, secret_keys, table_limbs, log_limb_size).await
+}
+
+/// Like `derive_public_keys`, but runs on an existing context so that buffers are reused across
+/// calls.
+pub async fn derive_public_keys_with_context(
+    context: &GpuContext,
+    secret_keys: &[SecretKey],
+    table_limbs: &[u32],
+    log_limb_size: u32,
+) -> Result<Vec<PublicKey>, crate::ShaderFailureError> {
+    let derived = do_derive::<Secp256k1>(
+        context,
+        secret_keys.len(),
+        |buf| {
+            for (chunk, sk) in buf.chunks_exact_mut(32).zip(secret_keys) {
+                chunk.copy_from_slice(sk.as_slice());
+            }
+        },
+        64,
+        table_limbs,
+        log_limb_size,
+    )
+    .await?;
+    // Every secret key has a public key, so an invalid point means that the shader failed
+    bytes_to_public_keys(&derived)
+        .into_iter()
+        .collect::<Option<Vec<_>>>()
+        .ok_or_else(crate::ShaderFailureError::default)
+}
+
+/// Recovers the public keys of signatures given as a flat buffer of 64-byte signatures and a flat
+/// buffer of 32-byte messages. The output is a flat buffer of 64-byte x || y public keys.
+pub async fn ecrecover_bytes(
+    signatures: &[u8],
+    messages: &[u8],
+    table_limbs: &[u32],
+    log_limb_size: u32,
+) -> Result<Vec<u8>, crate::ShaderFailureError> {
+    let context = GpuContext::new().await;
+    ecrecover_bytes_with_context(&context, signatures, messages, table_limbs, log_limb_size).await
+}
+
+/// Like `ecrecover_bytes`, but runs on an existing context so that buffers are reused across
+/// calls.
+pub async fn ecrecover_bytes_with_context(
+    context: &GpuContext,
+    signatures: &[u8],
+    messages: &[u8],
+    table_limbs: &[u32],
+    log_limb_size: u32,
+) -> Result<Vec<u8>, crate::ShaderFailureError> {
+    assert_eq!(signatures.len() % 64, 0);
+    assert_eq!(messages.len() % 32, 0);
+    assert_eq!(signatures.len() / 64, messages.len() / 32);
+
+    do_ecrecover(
+        context,
+        signatures.len() / 64,
+        |buf| buf[..signatures.len()].copy_from_slice(signatures),
+        |buf| buf[..messages.len()].copy_from_slice(messages),
+        |index| reference_bytes(&signatures[64 * index..][..64], &messages[32 * index..][..32]),
+        &[],
+        table_limbs,
+        log_limb_size,
+        &RecoveryOptions::default(),
+        None,
+    )
+    .await
+}
+
+/// Runs the multi-shader recovery pipeline with `options`. Every buffer comes from the context's
+/// pool, and `write_signatures` and `write_messages` fill the zero-initialised upload memory in
+/// place so that the inputs are copied only once on the host. `reference` recovers the public key
+/// of a signature on the CPU, for the sample which the context's `Integrity` checks.
+/// `public_keys` is only read for `FinalStage::VerifyCompressed`. With `options.all_valid`, the
+/// summary of the reduction stage is returned instead of the verdicts. If `timings` is given, each
+/// stage is timed.
+#[allow(clippy::too_many_arguments)]
+async fn do_ecrecover(
+    context: &GpuContext,
+    num_signatures: usize,
+    write_signatures: impl FnOnce(&mut [u8]),
+    write_messages: impl FnOnce(&mut [u8]),
+    reference: impl Fn(usize) -> Option<[u8; 64]>,
+    public_keys: &[CompressedPublicKey],
+    table_limbs: &[u32],
+    log_limb_size: u32,
+    options: &RecoveryOptions,
+    mut timings: Option<&mut StageTimings>,
+) -> Result<Vec<u8>, crate::ShaderFailureError> {
+    // A quarantined device is not trusted with any batch
+    if let Some(failure) = context.quarantined() {
+        return Err(failure.into());
+    }
     if num_signatures == 0 {
         return Ok(vec![]);
     }
 
-    let (device, queue) = get_device_and_queue().await;
-    let mut command_encoder = create_command_encoder(&device);
+    let (next_pow_2, _num_limbs, _params) = init(num_signatures, log_limb_size);
+    let final_stage = options.final_stage;
 
-    // Stage 0
-    let source = render_secp256k1_ecdsa("secp256k1_ecdsa_main_0.wgsl", log_limb_size);
-    let compute_pipeline = create_compute_pipeline(&device, &source, "secp256k1_recover_0");
+    let device = &context.device;
+    let pipelines = context.cached_pipelines("secp256k1_ecrecover", log_limb_size, options, || {
+        create_pipelines(device, log_limb_size, options)
+    });
 
-    let sig_buf = create_sb_with_data(&device, &all_sig_u32s);
-    let msg_buf = create_sb_with_data(&device, &all_msg_u32s);
-    let u1_buf = create_empty_sb(&device, (num_limbs * next_pow_2 * std::mem::size_of::<u32>()) as u64);
-    let u2_buf = create_empty_sb(&device, (num_limbs * next_pow_2 * std::mem::size_of::<u32>()) as u64);
-    let recovered_r_buf = create_empty_sb(&device, (num_limbs * 3 * next_pow_2 * std::mem::size_of::<u32>()) as u64);
-    let params_buf = create_ub_with_data(&device, params);
+    let sw = Instant::now();
+    let table_buf = context.table_buffer(table_limbs);
+    let sig_buf = context.pool.acquire_sb(device, (64 * next_pow_2) as u64);
+    write_sb_with(&context.queue, &sig_buf, write_signatures);
+    let msg_buf = context.pool.acquire_sb(device, (32 * next_pow_2) as u64);
+    write_sb_with(&context.queue, &msg_buf, write_messages);
+    let pk_buf = (final_stage == FinalStage::VerifyCompressed).then(|| {
+        let pk_buf = context
+            .pool
+            .acquire_sb(device, (PADDED_COMPRESSED_PUBLIC_KEY_LEN * next_pow_2) as u64);
+        write_sb_with(&context.queue, &pk_buf, |buf| pack_compressed_public_keys(buf, public_keys));
+        pk_buf
+    });
+    let upload = sw.elapsed();
 
-    let bind_group = create_bind_group(
-        &device,
-        &compute_pipeline,
-        0,
-        &[&sig_buf, &msg_buf, &u1_buf, &u2_buf, &recovered_r_buf, &params_buf],
+    let buffers = Buffers::new(
+        context,
+        &pipelines,
+        &table_buf,
+        sig_buf,
+        msg_buf,
+        pk_buf,
+        next_pow_2,
+        log_limb_size,
+        options,
     );
 
-    execute_pipeline(
-        &mut command_encoder,
-        &compute_pipeline,
-        &bind_group,
-        num_x_workgroups as u32,
-        num_y_workgroups as u32,
-        num_z_workgroups as u32,
-    );
+    let reduction = options.all_valid.map(|bitmap| {
+        // ecrecover_with_options rejects the other final stages
+        assert_eq!(final_stage, FinalStage::VerifyCompressed);
+        AllValidReduction::new::<Secp256k1>(
+            context,
+            &buffers.result_buf,
+            num_signatures,
+            next_pow_2,
+            bitmap,
+            log_limb_size,
+        )
+    });
 
-    // Stage 1
-    let source = render_secp256k1_ecdsa("secp256k1_ecdsa_main_1.wgsl", log_limb_size);
-    let compute_pipeline = create_compute_pipeline(&device, &source, "secp256k1_recover_1");
+    let mut command_encoder = create_command_encoder(device);
+    clear_outputs(&mut command_encoder, &buffers);
+    let mut dispatches = dispatches(&pipelines, &buffers);
+    if let Some(reduction) = &reduction {
+        reduction.clear(&mut command_encoder);
+        dispatches.push(reduction.dispatch());
+    }
 
-    let table_buf = create_sb_with_data(&device, table_limbs);
-    let u1g_buf = create_empty_sb(&device, (num_limbs * 3 * next_pow_2 * std::mem::size_of::<u32>()) as u64);
+    let command_encoder = match timings.as_mut() {
+        Some(timings) => {
+            let (source, stages) = run_timed(context, command_encoder, &dispatches).await;
+            timings.source = source;
+            timings.upload = upload;
+            timings.stages = stages;
+            create_command_encoder(device)
+        }
+        None => {
+            for dispatch in &dispatches {
+                dispatch.execute(&mut command_encoder);
+            }
+            command_encoder
+        }
+    };
 
-    let bind_group = create_bind_group(
-        &device,
-        &compute_pipeline,
-        0,
-        &[&table_buf, &u1_buf, &u1g_buf, &params_buf],
-    );
+    // With a reduction stage, only its summary is read back
+    // With compaction, the number of workgroups of the indirect dispatches is needed to check the
+    // completion counters
+    let output_buf = reduction.as_ref().map_or(&buffers.result_buf, |reduction| &reduction.summary_buf);
+    let mut readback_bufs = vec![output_buf, &buffers.canaries_buf];
+    readback_bufs.extend(buffers.survivors.as_ref().map(|survivors| &survivors.indirect_buf));
+    let sw = Instant::now();
+    let mut results = context.finish_encoder_and_read_bytes(command_encoder, &readback_bufs).await;
+    if let Some(timings) = timings {
+        timings.readback = sw.elapsed();
+    }
 
-    execute_pipeline(
-        &mut command_encoder,
-        &compute_pipeline,
-        &bind_group,
-        num_x_workgroups as u32,
-        num_y_workgroups as u32,
-        num_z_workgroups as u32,
-    );
+    let checked = buffers.check_canaries(&results[1], results.get(2).map(Vec::as_slice));
+    drop(dispatches);
+    buffers.release(&context.pool);
+    if let Some(reduction) = reduction {
+        reduction.release(&context.pool);
+    }
+    checked?;
+
+    let mut all_recovered = results.swap_remove(0);
+    if options.all_valid.is_none() {
+        all_recovered.truncate(num_signatures * final_stage.output_len());
+    }
 
-    // Stage 2
-    let source = render_secp256k1_ecdsa("secp256k1_ecdsa_main_2.wgsl", log_limb_size);
-    let compute_pipeline = create_compute_pipeline(&device, &source, "secp256k1_recover_2");
+    let batch_verdict = options
+        .all_valid
+        .map(|bitmap| BatchVerdict::from_bytes(&all_recovered, num_signatures, bitmap));
+    let parse = |xy: &[u8]| bytes_to_public_keys(xy)[0].map(|_| xy.try_into().unwrap());
+    check_call(
+        context,
+        num_signatures,
+        |index| match &batch_verdict {
+            Some(batch_verdict) => batch_verdict.known_verdict(index).map(StageItem::Verdict),
+            None => Some(final_stage.item(&all_recovered, index, parse)),
+        },
+        |index| final_stage.reference_item(reference(index), public_keys.get(index)),
+    )?;
+    Ok(all_recovered)
+}
 
-    let u2r_buf = create_empty_sb(&device, (num_limbs * 3 * next_pow_2 * std::mem::size_of::<u32>()) as u64);
+/// Recovers the x || y public key of a signature on the CPU.
+fn reference(signature: &Signature, message: &Message) -> Option<[u8; 64]> {
+    signature.recover(message).ok().map(|pk| pk.as_slice().try_into().unwrap())
+}
 
-    let bind_group = create_bind_group(
-        &device,
-        &compute_pipeline,
-        0,
-        &[&u2_buf, &recovered_r_buf, &u2r_buf, &params_buf],
-    );
+/// Like `reference`, but for a 64-byte signature and a 32-byte message.
+fn reference_bytes(signature: &[u8], message: &[u8]) -> Option<[u8; 64]> {
+    let signature = Signature::from_bytes(signature.try_into().unwrap());
+    reference(&signature, &Message::from_bytes(message.try_into().unwrap()))
+}
 
-    execute_pipeline(
-        &mut command_encoder,
-        &compute_pipeline,
-        &bind_group,
-        num_x_workgroups as u32,
-        num_y_workgroups as u32,
-        num_z_workgroups as u32,
-    );
+/// Compiles the stages of the multi-shader recovery pipeline with `options`, in order. With
+/// `Cooperation::Lanes`, stage 2 is the cooperative kernel, and the compaction stage, if any,
+/// comes last. Its buffers must come from `Buffers::new` with the same options. It panics unless
+/// `options.cooperation.check` succeeds.
+pub fn create_pipelines(
+    device: &wgpu::Device,
+    log_limb_size: u32,
+    options: &RecoveryOptions,
+) -> Vec<wgpu::ComputePipeline> {
+    let RecoveryOptions {
+        final_stage,
+        coordinates,
+        reduction,
+        cooperation,
+        checkpointing,
+        compaction,
+        ..
+    } = *options;
+    let render = |template_file: &str| {
+        render_main_with_compaction::<Secp256k1>(
+            template_file,
+            log_limb_size,
+            coordinates,
+            checkpointing,
+            compaction,
+            reduction,
+        )
+    };
+    let mut pipelines: Vec<wgpu::ComputePipeline> = (0..4)
+        .map(|i| {
+            let source = render(&format!("secp256k1_ecdsa_main_{}.wgsl", i));
+            create_compute_pipeline(device, &source, &format!("secp256k1_recover_{}", i))
+        })
+        .collect();
+    if let Some(pipeline) =
+        cooperation.create_mul_pipeline::<Secp256k1>(device, log_limb_size, coordinates)
+    {
+        pipelines[2] = pipeline;
+    }
+    let (template_file, entry_point) = final_stage.shader("secp256k1");
+    let source = render(&template_file);
+    pipelines.push(create_compute_pipeline(device, &source, &entry_point));
+    pipelines.extend(compaction.create_pipeline::<Secp256k1>(device, log_limb_size, COMPACTION_STAGE));
+    pipelines
+}
 
-    // Stage 3
-    let source = render_secp256k1_ecdsa("secp256k1_ecdsa_main_3.wgsl", log_limb_size);
-    let compute_pipeline = create_compute_pipeline(&device, &source, "secp256k1_recover_3");
+/// The buffers and bind groups of the multi-shader recovery pipeline for a batch of up to
+/// `next_pow_2` signatures. They can be reused for any batch which pads to the same size.
+pub struct Buffers {
+    pub next_pow_2: usize,
+    pub sig_buf: wgpu::Buffer,
+    pub msg_buf: wgpu::Buffer,
+    /// The compressed public keys, for `FinalStage::VerifyCompressed` only.
+    pub pk_buf: Option<wgpu::Buffer>,
+    pub result_buf: wgpu::Buffer,
+    /// The completion counter of each stage.
+    pub canaries_buf: wgpu::Buffer,
+    params: (u32, u32, u32),
+    /// The number of workgroups of stage 2, and their size, which differ from those of the other
+    /// stages with `Cooperation::Lanes`.
+    mul_num_workgroups: (u32, u32, u32),
+    mul_workgroup_size: usize,
+    /// The stage of each dispatch, in order. Stages 1 and 2 repeat with `Checkpointing`.
+    stage_order: Vec<usize>,
+    /// The checkpoints of the split stages, which must be zeroed before each batch.
+    checkpoint_bufs: Vec<wgpu::Buffer>,
+    /// The survivors of stage 0, with `Compaction::Indirect`.
+    survivors: Option<Survivors>,
+    /// The stages which run over the survivors with indirect dispatches.
+    compacted_stages: Vec<usize>,
+    intermediate_bufs: Vec<wgpu::Buffer>,
+    bind_groups: Vec<wgpu::BindGroup>,
+}
 
-    let sum_buf = create_empty_sb(&device, (num_limbs * 3 * next_pow_2 * std::mem::size_of::<u32>()) as u64);
+impl Buffers {
+    /// Acquires the parameter, intermediate, and output buffers from the context's pool, for
+    /// pipelines from `create_pipelines` with the same `options`. `pk_buf` must hold the compressed
+    /// public keys if `options.final_stage` is `FinalStage::VerifyCompressed`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        context: &GpuContext,
+        pipelines: &[wgpu::ComputePipeline],
+        table_buf: &wgpu::Buffer,
+        sig_buf: wgpu::Buffer,
+        msg_buf: wgpu::Buffer,
+        pk_buf: Option<wgpu::Buffer>,
+        next_pow_2: usize,
+        log_limb_size: u32,
+        options: &RecoveryOptions,
+    ) -> Self {
+        let RecoveryOptions { final_stage, cooperation, checkpointing, compaction, .. } = *options;
+        let device = &context.device;
+        let pool = &context.pool;
+        let (_, num_limbs, params) = init(next_pow_2, log_limb_size);
 
-    let bind_group = create_bind_group(
-        &device,
-        &compute_pipeline,
-        0,
-        &[&u1g_buf, &u2r_buf, &sum_buf, &params_buf],
-    );
+        let params_buf = pool.acquire_ub(device, (3 * std::mem::size_of::<u32>()) as u64);
+        context.queue.write_buffer(&params_buf, 0, bytemuck::cast_slice(&[params.0, params.1, params.2]));
 
-    execute_pipeline(
-        &mut command_encoder,
-        &compute_pipeline,
-        &bind_group,
-        num_x_workgroups as u32,
-        num_y_workgroups as u32,
-        num_z_workgroups as u32,
-    );
+        let u1_buf = pool.acquire_sb(device, (num_limbs * next_pow_2 * std::mem::size_of::<u32>()) as u64);
+        let u2_buf = pool.acquire_sb(device, (num_limbs * next_pow_2 * std::mem::size_of::<u32>()) as u64);
+        let recovered_r_buf = pool.acquire_sb(device, (num_limbs * 3 * next_pow_2 * std::mem::size_of::<u32>()) as u64);
+        let u1g_buf = pool.acquire_sb(device, (num_limbs * 3 * next_pow_2 * std::mem::size_of::<u32>()) as u64);
+        let u2r_buf = pool.acquire_sb(device, (num_limbs * 3 * next_pow_2 * std::mem::size_of::<u32>()) as u64);
+        let sum_buf = pool.acquire_sb(device, (num_limbs * 3 * next_pow_2 * std::mem::size_of::<u32>()) as u64);
+        let result_buf = pool.acquire_sb(device, (final_stage.output_len() * next_pow_2) as u64);
+        let canaries_buf = acquire_canaries(context, STAGE_LABELS.len());
+        let (mul_num_workgroups, mul_params_buf) =
+            cooperation.acquire_mul_params(context, next_pow_2, params);
 
-    // Stage 4
-    let source = render_secp256k1_ecdsa("secp256k1_ecdsa_main_4.wgsl", log_limb_size);
-    let compute_pipeline = create_compute_pipeline(&device, &source, "secp256k1_recover_4");
+        // Each checkpoint holds two points, an infinity flag, and the bit position
+        let checkpoint_size = (num_limbs * 6 + 2) * next_pow_2 * std::mem::size_of::<u32>();
+        // The cooperative kernel runs in one dispatch over every signature
+        let is_mul_per_invocation = cooperation == Cooperation::PerInvocation;
+        let u1g_checkpoint_buf = checkpointing.is_on().then(|| pool.acquire_sb(device, checkpoint_size as u64));
+        let u2r_checkpoint_buf = (checkpointing.is_on() && is_mul_per_invocation).then(|| pool.acquire_sb(device, checkpoint_size as u64));
+        let num_dispatches = checkpointing.num_dispatches();
+        let num_mul_dispatches = if is_mul_per_invocation { num_dispatches } else { 1 };
 
-    let result_buf = create_empty_sb(&device, (64 * next_pow_2 * std::mem::size_of::<u32>()) as u64);
-    let success_buf = create_empty_sb(&device, std::mem::size_of::<u32>() as u64);
+        let survivors = compaction.acquire_survivors(context, next_pow_2);
+        let survivors_buf = survivors.as_ref().map(|survivors| &survivors.ids_buf);
+        let (compaction_stage, compacted_stages) = match survivors {
+            Some(_) if is_mul_per_invocation => (vec![COMPACTION_STAGE], vec![1, 2, 3, 4]),
+            Some(_) => (vec![COMPACTION_STAGE], vec![1, 3, 4]),
+            None => (vec![], vec![]),
+        };
+        let stage_order = [vec![0], compaction_stage, vec![1; num_dispatches], vec![2; num_mul_dispatches], vec![3, 4]].concat();
 
-    let bind_group = create_bind_group(
-        &device,
-        &compute_pipeline,
-        0,
-        &[&sum_buf, &result_buf, &success_buf, &params_buf],
-    );
+        let mut bind_groups = vec![
+            // Stage 0
+            create_bind_group_with_optional(device, &pipelines[0], &[&sig_buf, &msg_buf, &u1_buf, &u2_buf, &recovered_r_buf, &canaries_buf], &[survivors_buf], &params_buf),
+            // Stage 1
+            create_bind_group_with_optional(device, &pipelines[1], &[table_buf, &u1_buf, &u1g_buf, &canaries_buf], &[u1g_checkpoint_buf.as_ref(), survivors_buf], &params_buf),
+            // Stage 2
+            if is_mul_per_invocation {
+                create_bind_group_with_optional(device, &pipelines[2], &[&u2_buf, &recovered_r_buf, &u2r_buf, &canaries_buf], &[u2r_checkpoint_buf.as_ref(), survivors_buf], &params_buf)
+            } else {
+                create_bind_group(device, &pipelines[2], 0, &[&u2_buf, &recovered_r_buf, &u2r_buf, &canaries_buf, mul_params_buf.as_ref().unwrap_or(&params_buf)])
+            },
+            // Stage 3
+            create_bind_group_with_optional(device, &pipelines[3], &[&u1g_buf, &u2r_buf, &sum_buf, &canaries_buf], &[survivors_buf], &params_buf),
+            // Stage 4
+            match &pk_buf {
+                Some(pk_buf) => create_bind_group_with_optional(device, &pipelines[4], &[&sum_buf, pk_buf, &result_buf, &canaries_buf], &[survivors_buf], &params_buf),
+                None => create_bind_group_with_optional(device, &pipelines[4], &[&sum_buf, &result_buf, &canaries_buf], &[survivors_buf], &params_buf),
+            },
+        ];
+        if let Some(survivors) = &survivors {
+            bind_groups.push(survivors.create_bind_group(device, &pipelines[COMPACTION_STAGE], &canaries_buf));
+        }
 
-    execute_pipeline(
-        &mut command_encoder,
-        &compute_pipeline,
-        &bind_group,
-        num_x_workgroups as u32,
-        num_y_workgroups as u32,
-        num_z_workgroups as u32,
-    );
+        Self {
+            next_pow_2,
+            sig_buf,
+            msg_buf,
+            pk_buf,
+            result_buf,
+            canaries_buf,
+            params,
+            mul_num_workgroups,
+            mul_workgroup_size: if is_mul_per_invocation { WORKGROUP_SIZE } else { COOP_WORKGROUP_SIZE },
+            stage_order,
+            checkpoint_bufs: u1g_checkpoint_buf.into_iter().chain(u2r_checkpoint_buf).collect(),
+            survivors,
+            compacted_stages,
+            intermediate_bufs: [params_buf, u1_buf, u2_buf, recovered_r_buf, u1g_buf, u2r_buf, sum_buf]
+                .into_iter()
+                .chain(mul_params_buf)
+                .collect(),
+            bind_groups,
+        }
+    }
 
-    let results = finish_encoder_and_read_bytes_from_gpu(
-        &device,
-        &queue,
-        Box::new(command_encoder),
-        &[result_buf, success_buf],
-    )
-    .await;
+    /// Returns every buffer to the pool. The GPU must no longer be using them.
+    pub fn release(self, pool: &BufferPool) {
+        drop(self.bind_groups);
+        for buffer in [self.sig_buf, self.msg_buf, self.result_buf, self.canaries_buf] {
+            pool.release(buffer);
+        }
+        if let Some(pk_buf) = self.pk_buf {
+            pool.release(pk_buf);
+        }
+        for buffer in self.checkpoint_bufs.into_iter().chain(self.intermediate_bufs) {
+            pool.release(buffer);
+        }
+        if let Some(survivors) = self.survivors {
+            survivors.release(pool);
+        }
+    }
 
-    if results[1][0] != 1 {
-        return Err(crate::ShaderFailureError);
+    /// The number of workgroups of each dispatch of `stage`, other than an indirect one.
+    fn num_workgroups(&self, stage: usize) -> (u32, u32, u32) {
+        match stage {
+            2 => self.mul_num_workgroups,
+            COMPACTION_STAGE => (1, 1, 1),
+            _ => self.params,
+        }
     }
 
-    let mut all_recovered: Vec<Vec<u8>> = Vec::with_capacity(num_signatures * 64);
-    for i in 0..num_signatures {
-        let result_bytes = &results[0][i * 64..i * 64 + 64];
-        all_recovered.push(result_bytes.to_vec());
+    /// Checks the completion counters which the stages left in `canaries` against the number of
+    /// invocations of their dispatches. With compaction, `indirect` must hold the number of
+    /// workgroups which the compaction stage wrote for the indirect dispatches.
+    pub fn check_canaries(&self, canaries: &[u8], indirect: Option<&[u8]>) -> Result<(), crate::ShaderFailureError> {
+        let indirect_num_workgroups = indirect.map(canary::parse_num_workgroups);
+        let dispatches = self.stage_order.iter().map(|&stage| {
+            let num_workgroups = match indirect_num_workgroups {
+                Some(num_workgroups) if self.compacted_stages.contains(&stage) => num_workgroups,
+                _ => self.num_workgroups(stage),
+            };
+            let workgroup_size = match stage {
+                2 => self.mul_workgroup_size,
+                COMPACTION_STAGE => 1,
+                _ => WORKGROUP_SIZE,
+            };
+            (stage, canary::num_invocations(num_workgroups, workgroup_size))
+        });
+        canary::check(&STAGE_LABELS, dispatches, canaries)
+    }
+}
+
+/// What each stage of the multi-shader recovery pipeline computes, in order.
+const STAGE_LABELS: [&str; 6] = [
+    "recover R; compute u1 and u2",
+    "fixed-base mul u1 * G",
+    "variable-base mul u2 * R",
+    "point addition",
+    "affine conversion",
+    "compact the survivors of stage 0",
+];
+
+/// The index of the compaction stage, which runs after stage 0 with `Compaction::Indirect`.
+const COMPACTION_STAGE: usize = 5;
+
+/// The workgroup size of every stage other than the compaction stage and the cooperative kernel.
+const WORKGROUP_SIZE: usize = 256;
+
+/// Returns the dispatches of the multi-shader recovery pipeline, in order.
+pub fn dispatches<'a>(pipelines: &'a [wgpu::ComputePipeline], buffers: &'a Buffers) -> Vec<Dispatch<'a>> {
+    buffers
+        .stage_order
+        .iter()
+        .map(|&stage| Dispatch {
+            label: STAGE_LABELS[stage],
+            compute_pipeline: &pipelines[stage],
+            bind_group: &buffers.bind_groups[stage],
+            num_workgroups: buffers.num_workgroups(stage),
+            indirect_buffer: buffers
+                .survivors
+                .as_ref()
+                .filter(|_| buffers.compacted_stages.contains(&stage))
+                .map(|survivors| &survivors.indirect_buf),
+        })
+        .collect()
+}
+
+/// Clears the output, completion counter, checkpoint, and survivor buffers, which may be reused
+/// and hold the results of a previous batch.
+pub fn clear_outputs(command_encoder: &mut wgpu::CommandEncoder, buffers: &Buffers) {
+    command_encoder.clear_buffer(&buffers.result_buf, 0, None);
+    command_encoder.clear_buffer(&buffers.canaries_buf, 0, None);
+    for checkpoint_buf in &buffers.checkpoint_bufs {
+        command_encoder.clear_buffer(checkpoint_buf, 0, None);
+    }
+    if let Some(survivors) = &buffers.survivors {
+        survivors.clear(command_encoder);
+    }
+}
+
+/// Records every stage of the multi-shader recovery pipeline into `command_encoder`.
+pub fn encode_stages(
+    command_encoder: &mut wgpu::CommandEncoder,
+    pipelines: &[wgpu::ComputePipeline],
+    buffers: &Buffers,
+) {
+    clear_outputs(command_encoder, buffers);
+    for dispatch in dispatches(pipelines, buffers) {
+        dispatch.execute(command_encoder);
+    }
+}
+
+/// secp256k1 ECDSA public key recovery for use with the streaming `Verifier`.
+pub struct Recovery {
+    pipelines: Vec<wgpu::ComputePipeline>,
+    table_buf: wgpu::Buffer,
+    log_limb_size: u32,
+}
+
+impl Recovery {
+    pub fn new(device: &wgpu::Device, table_limbs: &[u32], log_limb_size: u32) -> Self {
+        Self {
+            pipelines: create_pipelines(device, log_limb_size, &RecoveryOptions::default()),
+            table_buf: create_sb_with_data(device, table_limbs),
+            log_limb_size,
+        }
+    }
+}
+
+impl Scheme for Recovery {
+    type Batch = (Vec<Signature>, Vec<Message>);
+    type Output = Vec<Option<PublicKey>>;
+    type Buffers = Buffers;
+    type Item = Option<PublicKey>;
+
+    fn num_signatures(batch: &Self::Batch) -> usize {
+        assert_eq!(batch.0.len(), batch.1.len());
+        batch.0.len()
+    }
+
+    fn create_buffers(&self, context: &GpuContext, next_pow_2: usize) -> Buffers {
+        let sig_buf = context.pool.acquire_sb(&context.device, (64 * next_pow_2) as u64);
+        let msg_buf = context.pool.acquire_sb(&context.device, (32 * next_pow_2) as u64);
+        Buffers::new(
+            context,
+            &self.pipelines,
+            &self.table_buf,
+            sig_buf,
+            msg_buf,
+            None,
+            next_pow_2,
+            self.log_limb_size,
+            &RecoveryOptions::default(),
+        )
+    }
+
+    fn release_buffers(buffers: Buffers, pool: &BufferPool) {
+        buffers.release(pool);
+    }
+
+    fn write_batch(queue: &wgpu::Queue, buffers: &Buffers, batch: &Self::Batch) {
+        let (signatures, messages) = batch;
+        write_sb_with(queue, &buffers.sig_buf, |buf| pack_signatures(buf, signatures));
+        write_sb_with(queue, &buffers.msg_buf, |buf| pack_messages(buf, messages));
+    }
+
+    fn encode(&self, command_encoder: &mut wgpu::CommandEncoder, buffers: &Buffers) {
+        encode_stages(command_encoder, &self.pipelines, buffers);
+    }
+
+    fn output_buffers(buffers: &Buffers) -> [&wgpu::Buffer; 2] {
+        [&buffers.result_buf, &buffers.canaries_buf]
+    }
+
+    fn check_canaries(buffers: &Buffers, canaries: &[u8]) -> Result<(), crate::ShaderFailureError> {
+        buffers.check_canaries(canaries, None)
+    }
+
+    fn decode(num_signatures: usize, results: &[u8]) -> Self::Output {
+        bytes_to_public_keys(&results[..num_signatures * 64])
+    }
+
+    fn item(output: &Self::Output, index: usize) -> Self::Item {
+        output[index]
+    }
+
+    fn reference(batch: &Self::Batch, index: usize) -> Self::Item {
+        let (signatures, messages) = batch;
+        signatures[index].recover(&messages[index]).ok()
+    }
+
+    fn known_answers() -> Self::Batch {
+        let mut signatures = Vec::with_capacity(NUM_KNOWN_ANSWERS);
+        let mut messages = Vec::with_capacity(NUM_KNOWN_ANSWERS);
+        for i in 0..NUM_KNOWN_ANSWERS {
+            let seed: [u8; 32] = Sha256::digest((i as u64).to_le_bytes()).into();
+            let secret = SecretKey::try_from(Bytes32::new(seed)).unwrap();
+            let message = Message::new((i as u64).to_be_bytes());
+            signatures.push(Signature::sign(&secret, &message));
+            messages.push(message);
+        }
+        // The first two signatures recover other keys than those which signed them
+        messages.swap(0, 1);
+        (signatures, messages)
     }
-    Ok(all_recovered)
 }
 
 pub async fn ecrecover_single_shader(
-    signatures: &Vec<Signature>,
-    messages: &Vec<Message>,
+    signatures: &[Signature],
+    messages: &[Message],
     log_limb_size: u32,
-) -> Result<Vec<Vec<u8>>, crate::ShaderFailureError> {
-    let (num_signatures, next_pow_2, _num_limbs, all_sig_u32s, all_msg_u32s, params_t) = init(&signatures, &messages, log_limb_size);
-    let (num_x_workgroups, num_y_workgroups, num_z_workgroups) = params_t;
-    let params = &[num_x_workgroups, num_y_workgroups, num_z_workgroups];
+) -> Result<Vec<Option<PublicKey>>, crate::ShaderFailureError> {
+    assert_eq!(signatures.len(), messages.len());
+    let recovered = do_ecrecover_single_shader(
+        signatures.len(),
+        |buf| pack_signatures(buf, signatures),
+        |buf| pack_messages(buf, messages),
+        log_limb_size,
+    )
+    .await?;
+    Ok(bytes_to_public_keys(&recovered))
+}
+
+pub async fn ecrecover_single_shader_bytes(
+    signatures: &[u8],
+    messages: &[u8],
+    log_limb_size: u32,
+) -> Result<Vec<u8>, crate::ShaderFailureError> {
+    assert_eq!(signatures.len() % 64, 0);
+    assert_eq!(messages.len() % 32, 0);
+    assert_eq!(signatures.len() / 64, messages.len() / 32);
+
+    do_ecrecover_single_shader(
+        signatures.len() / 64,
+        |buf| buf[..signatures.len()].copy_from_slice(signatures),
+        |buf| buf[..messages.len()].copy_from_slice(messages),
+        log_limb_size,
+    )
+    .await
+}
 
+async fn do_ecrecover_single_shader(
+    num_signatures: usize,
+    write_signatures: impl FnOnce(&mut [u8]),
+    write_messages: impl FnOnce(&mut [u8]),
+    log_limb_size: u32,
+) -> Result<Vec<u8>, crate::ShaderFailureError> {
     if num_signatures == 0 {
         return Ok(vec![]);
     }
 
+    let (next_pow_2, _num_limbs, params_t) = init(num_signatures, log_limb_size);
+    let (num_x_workgroups, num_y_workgroups, num_z_workgroups) = params_t;
+    let params = &[num_x_workgroups, num_y_workgroups, num_z_workgroups];
+
     let (device, queue) = get_device_and_queue().await;
     let mut command_encoder = create_command_encoder(&device);
 
-    let source = render_secp256k1_ecdsa("secp256k1_ecdsa_main.wgsl", log_limb_size);
+    let source = render_main::<Secp256k1>("secp256k1_ecdsa_main.wgsl", log_limb_size);
     let compute_pipeline = create_compute_pipeline(&device, &source, "secp256k1_recover_main");
 
-    let sig_buf = create_sb_with_data(&device, &all_sig_u32s);
-    let msg_buf = create_sb_with_data(&device, &all_msg_u32s);
+    let sig_buf = create_sb_with_writer(&device, (64 * next_pow_2) as u64, write_signatures);
+    let msg_buf = create_sb_with_writer(&device, (32 * next_pow_2) as u64, write_messages);
     let result_buf = create_empty_sb(&device, (64 * next_pow_2 * std::mem::size_of::<u32>()) as u64);
     let success_buf = create_empty_sb(&device, std::mem::size_of::<u32>() as u64);
     let params_buf = create_ub_with_data(&device, params);
@@ -253,7 +1104,7 @@ pub async fn ecrecover_single_shader(
         num_z_workgroups as u32,
     );
 
-    let results = finish_encoder_and_read_bytes_from_gpu(
+    let mut results = finish_encoder_and_read_bytes_from_gpu(
         &device,
         &queue,
         Box::new(command_encoder),
@@ -262,13 +1113,10 @@ pub async fn ecrecover_single_shader(
     .await;
 
     if results[1][0] != 1 {
-        return Err(crate::ShaderFailureError);
+        return Err(crate::ShaderFailureError::default());
     }
 
-    let mut all_recovered: Vec<Vec<u8>> = Vec::with_capacity(num_signatures * 64);
-    for i in 0..num_signatures {
-        let result_bytes = &results[0][i * 64..i * 64 + 64];
-        all_recovered.push(result_bytes.to_vec());
-    }
+    let mut all_recovered = results.swap_remove(0);
+    all_recovered.truncate(num_signatures * 64);
     Ok(all_recovered)
 }
@@ -0,0 +1,587 @@
+use crate::benchmarks::compute_num_workgroups;
+use crate::canary::{self, acquire_canaries};
+use crate::integrity::{check_call, NUM_KNOWN_ANSWERS};
+use crate::gpu::{
+    create_bind_group, create_command_encoder, create_compute_pipeline, create_sb_with_data,
+    write_sb_with, BufferPool, Dispatch, GpuContext,
+};
+use crate::curves::Secp384r1;
+use crate::precompute::BaseTable;
+use crate::shader::render_main;
+use crate::streaming::Scheme;
+use crate::timings::{run_timed, StageTimings};
+use crate::Verdict;
+use multiprecision::utils::calc_num_limbs;
+use p384::ecdsa::{SigningKey, VerifyingKey};
+use p384::PublicKey;
+use sha2::{Digest, Sha384};
+use std::time::Instant;
+
+/// A 96-byte r || s signature in which the top bit of s holds the parity of the y-coordinate of
+/// R, as in Fuel's compact secp256k1 and secp256r1 signatures.
+pub type Signature = [u8; 96];
+
+/// A 48-byte message prehash, e.g. a SHA-384 digest.
+pub type Message = [u8; 48];
+
+/// The width of the shaders' big integers.
+const NUM_BITS: usize = 384;
+
+/// Returns the batch size padded to the next power of 2, the number of limbs per big integer, and
+/// the number of workgroups to dispatch for `num_signatures` signatures.
+pub fn init(
+    num_signatures: usize,
+    log_limb_size: u32,
+) -> (usize, usize, (u32, u32, u32)) {
+    let num_limbs = calc_num_limbs(log_limb_size, NUM_BITS);
+
+    assert!(num_signatures <= 256 * 256 * 256 * 64);
+
+    // Compute the next power of 2
+    let next_pow_2 = (2u32.pow((num_signatures as f32).log2().ceil() as u32)) as usize;
+
+    assert!(num_signatures <= next_pow_2);
+
+    let workgroup_size = 256;
+    let (num_x_workgroups, num_y_workgroups, num_z_workgroups) =
+        compute_num_workgroups(next_pow_2, workgroup_size);
+
+    let params = (
+        num_x_workgroups as u32,
+        num_y_workgroups as u32,
+        num_z_workgroups as u32,
+    );
+    (next_pow_2, num_limbs, params)
+}
+
+/// Signs a prehashed message and encodes the signature in the compact form which the shaders
+/// expect. The signature is normalised to low-s, which leaves the top bit of s free.
+pub fn sign_prehashed(signing_key: &SigningKey, prehash: &Message) -> Signature {
+    let (sig, recovery_id) = signing_key
+        .sign_prehash_recoverable(prehash)
+        .expect("Couldn't sign");
+
+    // Negating s also negates R, which flips the parity of its y-coordinate
+    let (sig, is_y_odd) = match sig.normalize_s() {
+        Some(normalized) => (normalized, !recovery_id.is_y_odd()),
+        None => (sig, recovery_id.is_y_odd()),
+    };
+
+    let mut signature = [0u8; 96];
+    signature.copy_from_slice(&sig.to_bytes());
+    if is_y_odd {
+        signature[48] |= 0x80;
+    }
+    signature
+}
+
+/// Recovers the public key of a compact signature on the CPU, using `p384`.
+pub fn recover(signature: &Signature, prehash: &Message) -> Option<PublicKey> {
+    let mut sig_bytes = *signature;
+    let is_y_odd = (sig_bytes[48] & 0x80) != 0;
+    sig_bytes[48] &= 0x7f;
+
+    let sig = p384::ecdsa::Signature::from_slice(&sig_bytes).ok()?;
+    let recovery_id = u8::from(is_y_odd).try_into().ok()?;
+    let verifying_key = VerifyingKey::recover_from_prehash(prehash, &sig, recovery_id).ok()?;
+    Some(PublicKey::from(&verifying_key))
+}
+
+/// Writes 96-byte signatures into `dst`, which is usually the mapped range of an upload buffer.
+pub fn pack_signatures(dst: &mut [u8], signatures: &[Signature]) {
+    for (chunk, sig) in dst.chunks_exact_mut(96).zip(signatures) {
+        chunk.copy_from_slice(sig);
+    }
+}
+
+/// Writes 48-byte messages into `dst`, which is usually the mapped range of an upload buffer.
+pub fn pack_messages(dst: &mut [u8], messages: &[Message]) {
+    for (chunk, msg) in dst.chunks_exact_mut(48).zip(messages) {
+        chunk.copy_from_slice(msg);
+    }
+}
+
+/// Parses the 96-byte x || y output of the shaders into public keys. Invalid points (e.g. those
+/// recovered from malformed signatures) map to `None`.
+pub fn bytes_to_public_keys(recovered: &[u8]) -> Vec<Option<PublicKey>> {
+    recovered
+        .chunks_exact(96)
+        .map(|pk_bytes| {
+            // Prepend the SEC1 tag for uncompressed points
+            let mut sec1_bytes = [0u8; 97];
+            sec1_bytes[0] = 0x04;
+            sec1_bytes[1..].copy_from_slice(pk_bytes);
+            PublicKey::from_sec1_bytes(&sec1_bytes).ok()
+        })
+        .collect()
+}
+
+pub async fn ecrecover(
+    signatures: &[Signature],
+    messages: &[Message],
+    table_limbs: &[u32],
+    log_limb_size: u32,
+) -> Result<Vec<Option<PublicKey>>, crate::ShaderFailureError> {
+    let context = GpuContext::new().await;
+    ecrecover_with_context(&context, signatures, messages, table_limbs, log_limb_size).await
+}
+
+/// Like `ecrecover`, but runs on an existing context so that buffers are reused across calls.
+pub async fn ecrecover_with_context(
+    context: &GpuContext,
+    signatures: &[Signature],
+    messages: &[Message],
+    table_limbs: &[u32],
+    log_limb_size: u32,
+) -> Result<Vec<Option<PublicKey>>, crate::ShaderFailureError> {
+    assert_eq!(signatures.len(), messages.len());
+    let recovered = do_ecrecover(
+        context,
+        signatures.len(),
+        |buf| pack_signatures(buf, signatures),
+        |buf| pack_messages(buf, messages),
+        |index| recover(&signatures[index], &messages[index]),
+        table_limbs,
+        log_limb_size,
+        None,
+    )
+    .await?;
+    Ok(bytes_to_public_keys(&recovered))
+}
+
+/// Like `ecrecover_with_context`, but takes a `BaseTable` and refuses it unless it was built for
+/// secp384r1 and `log_limb_size`.
+pub async fn ecrecover_with_table(
+    context: &GpuContext,
+    signatures: &[Signature],
+    messages: &[Message],
+    table: &BaseTable,
+    log_limb_size: u32,
+) -> Result<Vec<Option<PublicKey>>, crate::PipelineError> {
+    table.check::<Secp384r1>(log_limb_size)?;
+    let recovered = ecrecover_with_context(context, signatures, messages, table.limbs(), log_limb_size).await?;
+    Ok(recovered)
+}
+
+/// Like `ecrecover_with_context`, but also reports how long the upload, each stage, and the
+/// readback took.
+pub async fn ecrecover_with_timings(
+    context: &GpuContext,
+    signatures: &[Signature],
+    messages: &[Message],
+    table_limbs: &[u32],
+    log_limb_size: u32,
+) -> Result<(Vec<Option<PublicKey>>, StageTimings), crate::ShaderFailureError> {
+    assert_eq!(signatures.len(), messages.len());
+    let mut timings = StageTimings::default();
+    let recovered = do_ecrecover(
+        context,
+        signatures.len(),
+        |buf| pack_signatures(buf, signatures),
+        |buf| pack_messages(buf, messages),
+        |index| recover(&signatures[index], &messages[index]),
+        table_limbs,
+        log_limb_size,
+        Some(&mut timings),
+    )
+    .await?;
+    Ok((bytes_to_public_keys(&recovered), timings))
+}
+
+/// Verifies each signature against the given public key by recovering the signer's key on the
+/// GPU and comparing the two.
+pub async fn ecverify(
+    signatures: &[Signature],
+    messages: &[Message],
+    public_keys: &[PublicKey],
+    table_limbs: &[u32],
+    log_limb_size: u32,
+) -> Result<Vec<Verdict>, crate::ShaderFailureError> {
+    let context = GpuContext::new().await;
+    ecverify_with_context(&context, signatures, messages, public_keys, table_limbs, log_limb_size).await
+}
+
+/// Like `ecverify`, but runs on an existing context so that buffers are reused across calls.
+pub async fn ecverify_with_context(
+    context: &GpuContext,
+    signatures: &[Signature],
+    messages: &[Message],
+    public_keys: &[PublicKey],
+    table_limbs: &[u32],
+    log_limb_size: u32,
+) -> Result<Vec<Verdict>, crate::ShaderFailureError> {
+    assert_eq!(signatures.len(), public_keys.len());
+    let recovered = ecrecover_with_context(context, signatures, messages, table_limbs, log_limb_size).await?;
+    Ok(recovered
+        .iter()
+        .zip(public_keys)
+        .map(|(pk, expected)| Verdict::from(pk.as_ref() == Some(expected)))
+        .collect())
+}
+
+/// Like `ecverify_with_context`, but takes a `BaseTable` and refuses it unless it was built for
+/// secp384r1 and `log_limb_size`.
+pub async fn ecverify_with_table(
+    context: &GpuContext,
+    signatures: &[Signature],
+    messages: &[Message],
+    public_keys: &[PublicKey],
+    table: &BaseTable,
+    log_limb_size: u32,
+) -> Result<Vec<Verdict>, crate::PipelineError> {
+    table.check::<Secp384r1>(log_limb_size)?;
+    let verdicts = ecverify_with_context(context, signatures, messages, public_keys, table.limbs(), log_limb_size).await?;
+    Ok(verdicts)
+}
+
+/// Recovers the public keys of signatures given as a flat buffer of 96-byte signatures and a flat
+/// buffer of 48-byte messages. The output is a flat buffer of 96-byte x || y public keys.
+pub async fn ecrecover_bytes(
+    signatures: &[u8],
+    messages: &[u8],
+    table_limbs: &[u32],
+    log_limb_size: u32,
+) -> Result<Vec<u8>, crate::ShaderFailureError> {
+    let context = GpuContext::new().await;
+    ecrecover_bytes_with_context(&context, signatures, messages, table_limbs, log_limb_size).await
+}
+
+/// Like `ecrecover_bytes`, but runs on an existing context so that buffers are reused across
+/// calls.
+pub async fn ecrecover_bytes_with_context(
+    context: &GpuContext,
+    signatures: &[u8],
+    messages: &[u8],
+    table_limbs: &[u32],
+    log_limb_size: u32,
+) -> Result<Vec<u8>, crate::ShaderFailureError> {
+    assert_eq!(signatures.len() % 96, 0);
+    assert_eq!(messages.len() % 48, 0);
+    assert_eq!(signatures.len() / 96, messages.len() / 48);
+
+    do_ecrecover(
+        context,
+        signatures.len() / 96,
+        |buf| buf[..signatures.len()].copy_from_slice(signatures),
+        |buf| buf[..messages.len()].copy_from_slice(messages),
+        |index| {
+            let signature = signatures[96 * index..][..96].try_into().unwrap();
+            recover(signature, messages[48 * index..][..48].try_into().unwrap())
+        },
+        table_limbs,
+        log_limb_size,
+        None,
+    )
+    .await
+}
+
+/// Runs the multi-shader recovery pipeline. Every buffer comes from the context's pool, and
+/// `write_signatures` and `write_messages` fill the zero-initialised upload memory in place so
+/// that the inputs are copied only once on the host. `reference` recovers the public key of a
+/// signature on the CPU, for the sample which the context's `Integrity` checks. If `timings` is
+/// given, each stage is timed.
+#[allow(clippy::too_many_arguments)]
+async fn do_ecrecover(
+    context: &GpuContext,
+    num_signatures: usize,
+    write_signatures: impl FnOnce(&mut [u8]),
+    write_messages: impl FnOnce(&mut [u8]),
+    reference: impl Fn(usize) -> Option<PublicKey>,
+    table_limbs: &[u32],
+    log_limb_size: u32,
+    mut timings: Option<&mut StageTimings>,
+) -> Result<Vec<u8>, crate::ShaderFailureError> {
+    // A quarantined device is not trusted with any batch
+    if let Some(failure) = context.quarantined() {
+        return Err(failure.into());
+    }
+    if num_signatures == 0 {
+        return Ok(vec![]);
+    }
+
+    let (next_pow_2, _num_limbs, _params) = init(num_signatures, log_limb_size);
+
+    let device = &context.device;
+    let pipelines =
+        context.cached_pipelines("secp384r1_ecrecover", log_limb_size, &(), || create_pipelines(device, log_limb_size));
+
+    let sw = Instant::now();
+    let table_buf = context.table_buffer(table_limbs);
+    let sig_buf = context.pool.acquire_sb(device, (96 * next_pow_2) as u64);
+    write_sb_with(&context.queue, &sig_buf, write_signatures);
+    let msg_buf = context.pool.acquire_sb(device, (48 * next_pow_2) as u64);
+    write_sb_with(&context.queue, &msg_buf, write_messages);
+    let upload = sw.elapsed();
+
+    let buffers = Buffers::new(context, &pipelines, &table_buf, sig_buf, msg_buf, next_pow_2, log_limb_size);
+
+    let mut command_encoder = create_command_encoder(device);
+    clear_outputs(&mut command_encoder, &buffers);
+    let dispatches = dispatches(&pipelines, &buffers);
+
+    let command_encoder = match timings.as_mut() {
+        Some(timings) => {
+            let (source, stages) = run_timed(context, command_encoder, &dispatches).await;
+            timings.source = source;
+            timings.upload = upload;
+            timings.stages = stages;
+            create_command_encoder(device)
+        }
+        None => {
+            for dispatch in &dispatches {
+                dispatch.execute(&mut command_encoder);
+            }
+            command_encoder
+        }
+    };
+
+    let sw = Instant::now();
+    let mut results = context
+        .finish_encoder_and_read_bytes(command_encoder, &[&buffers.result_buf, &buffers.canaries_buf])
+        .await;
+    if let Some(timings) = timings {
+        timings.readback = sw.elapsed();
+    }
+
+    let checked = buffers.check_canaries(&results[1]);
+    drop(dispatches);
+    buffers.release(&context.pool);
+    checked?;
+
+    let mut all_recovered = results.swap_remove(0);
+    all_recovered.truncate(num_signatures * 96);
+    check_call(
+        context,
+        num_signatures,
+        |index| Some(bytes_to_public_keys(&all_recovered[96 * index..][..96])[0]),
+        reference,
+    )?;
+    Ok(all_recovered)
+}
+
+/// Compiles the stages of the multi-shader recovery pipeline, in order.
+pub fn create_pipelines(device: &wgpu::Device, log_limb_size: u32) -> Vec<wgpu::ComputePipeline> {
+    (0..5)
+        .map(|i| {
+            let source = render_main::<Secp384r1>(&format!("secp384r1_ecdsa_main_{}.wgsl", i), log_limb_size);
+            create_compute_pipeline(device, &source, &format!("secp384r1_recover_{}", i))
+        })
+        .collect()
+}
+
+/// The buffers and bind groups of the multi-shader recovery pipeline for a batch of up to
+/// `next_pow_2` signatures. They can be reused for any batch which pads to the same size.
+pub struct Buffers {
+    pub next_pow_2: usize,
+    pub sig_buf: wgpu::Buffer,
+    pub msg_buf: wgpu::Buffer,
+    pub result_buf: wgpu::Buffer,
+    /// The completion counter of each stage.
+    pub canaries_buf: wgpu::Buffer,
+    params: (u32, u32, u32),
+    intermediate_bufs: Vec<wgpu::Buffer>,
+    bind_groups: Vec<wgpu::BindGroup>,
+}
+
+impl Buffers {
+    /// Acquires the parameter, intermediate, and output buffers from the context's pool.
+    pub fn new(
+        context: &GpuContext,
+        pipelines: &[wgpu::ComputePipeline],
+        table_buf: &wgpu::Buffer,
+        sig_buf: wgpu::Buffer,
+        msg_buf: wgpu::Buffer,
+        next_pow_2: usize,
+        log_limb_size: u32,
+    ) -> Self {
+        let device = &context.device;
+        let pool = &context.pool;
+        let (_, num_limbs, params) = init(next_pow_2, log_limb_size);
+
+        let params_buf = pool.acquire_ub(device, (3 * std::mem::size_of::<u32>()) as u64);
+        context.queue.write_buffer(&params_buf, 0, bytemuck::cast_slice(&[params.0, params.1, params.2]));
+
+        let u1_buf = pool.acquire_sb(device, (num_limbs * next_pow_2 * std::mem::size_of::<u32>()) as u64);
+        let u2_buf = pool.acquire_sb(device, (num_limbs * next_pow_2 * std::mem::size_of::<u32>()) as u64);
+        let recovered_r_buf = pool.acquire_sb(device, (num_limbs * 3 * next_pow_2 * std::mem::size_of::<u32>()) as u64);
+        let u1g_buf = pool.acquire_sb(device, (num_limbs * 3 * next_pow_2 * std::mem::size_of::<u32>()) as u64);
+        let u2r_buf = pool.acquire_sb(device, (num_limbs * 3 * next_pow_2 * std::mem::size_of::<u32>()) as u64);
+        let sum_buf = pool.acquire_sb(device, (num_limbs * 3 * next_pow_2 * std::mem::size_of::<u32>()) as u64);
+        let result_buf = pool.acquire_sb(device, (96 * next_pow_2) as u64);
+        let canaries_buf = acquire_canaries(context, STAGE_LABELS.len());
+
+        let bind_groups = vec![
+            // Stage 0
+            create_bind_group(device, &pipelines[0], 0, &[&sig_buf, &msg_buf, &u1_buf, &u2_buf, &recovered_r_buf, &canaries_buf, &params_buf]),
+            // Stage 1
+            create_bind_group(device, &pipelines[1], 0, &[table_buf, &u1_buf, &u1g_buf, &canaries_buf, &params_buf]),
+            // Stage 2
+            create_bind_group(device, &pipelines[2], 0, &[&u2_buf, &recovered_r_buf, &u2r_buf, &canaries_buf, &params_buf]),
+            // Stage 3
+            create_bind_group(device, &pipelines[3], 0, &[&u1g_buf, &u2r_buf, &sum_buf, &canaries_buf, &params_buf]),
+            // Stage 4
+            create_bind_group(device, &pipelines[4], 0, &[&sum_buf, &result_buf, &canaries_buf, &params_buf]),
+        ];
+
+        Self {
+            next_pow_2,
+            sig_buf,
+            msg_buf,
+            result_buf,
+            canaries_buf,
+            params,
+            intermediate_bufs: vec![params_buf, u1_buf, u2_buf, recovered_r_buf, u1g_buf, u2r_buf, sum_buf],
+            bind_groups,
+        }
+    }
+
+    /// Returns every buffer to the pool. The GPU must no longer be using them.
+    pub fn release(self, pool: &BufferPool) {
+        drop(self.bind_groups);
+        for buffer in [self.sig_buf, self.msg_buf, self.result_buf, self.canaries_buf] {
+            pool.release(buffer);
+        }
+        for buffer in self.intermediate_bufs {
+            pool.release(buffer);
+        }
+    }
+
+    /// Checks the completion counters which the stages left in `canaries` against the number of
+    /// invocations of their dispatches.
+    pub fn check_canaries(&self, canaries: &[u8]) -> Result<(), crate::ShaderFailureError> {
+        let num_invocations = canary::num_invocations(self.params, WORKGROUP_SIZE);
+        canary::check(&STAGE_LABELS, (0..STAGE_LABELS.len()).map(|stage| (stage, num_invocations)), canaries)
+    }
+}
+
+/// What each stage of the multi-shader recovery pipeline computes, in order.
+const STAGE_LABELS: [&str; 5] = [
+    "recover R; compute u1 and u2",
+    "fixed-base mul u1 * G",
+    "variable-base mul u2 * R",
+    "point addition",
+    "affine conversion",
+];
+
+/// The workgroup size of every stage.
+const WORKGROUP_SIZE: usize = 256;
+
+/// Returns the dispatches of the multi-shader recovery pipeline, in order.
+pub fn dispatches<'a>(pipelines: &'a [wgpu::ComputePipeline], buffers: &'a Buffers) -> Vec<Dispatch<'a>> {
+    pipelines
+        .iter()
+        .zip(&buffers.bind_groups)
+        .zip(STAGE_LABELS)
+        .map(|((compute_pipeline, bind_group), label)| Dispatch {
+            label,
+            compute_pipeline,
+            bind_group,
+            num_workgroups: buffers.params,
+            indirect_buffer: None,
+        })
+        .collect()
+}
+
+/// Clears the output and completion counter buffers, which may be reused and hold the results of
+/// a previous batch.
+pub fn clear_outputs(command_encoder: &mut wgpu::CommandEncoder, buffers: &Buffers) {
+    command_encoder.clear_buffer(&buffers.result_buf, 0, None);
+    command_encoder.clear_buffer(&buffers.canaries_buf, 0, None);
+}
+
+/// Records every stage of the multi-shader recovery pipeline into `command_encoder`.
+pub fn encode_stages(
+    command_encoder: &mut wgpu::CommandEncoder,
+    pipelines: &[wgpu::ComputePipeline],
+    buffers: &Buffers,
+) {
+    clear_outputs(command_encoder, buffers);
+    for dispatch in dispatches(pipelines, buffers) {
+        dispatch.execute(command_encoder);
+    }
+}
+
+/// secp384r1 ECDSA public key recovery for use with the streaming `Verifier`.
+pub struct Recovery {
+    pipelines: Vec<wgpu::ComputePipeline>,
+    table_buf: wgpu::Buffer,
+    log_limb_size: u32,
+}
+
+impl Recovery {
+    pub fn new(device: &wgpu::Device, table_limbs: &[u32], log_limb_size: u32) -> Self {
+        Self {
+            pipelines: create_pipelines(device, log_limb_size),
+            table_buf: create_sb_with_data(device, table_limbs),
+            log_limb_size,
+        }
+    }
+}
+
+impl Scheme for Recovery {
+    type Batch = (Vec<Signature>, Vec<Message>);
+    type Output = Vec<Option<PublicKey>>;
+    type Buffers = Buffers;
+    type Item = Option<PublicKey>;
+
+    fn num_signatures(batch: &Self::Batch) -> usize {
+        assert_eq!(batch.0.len(), batch.1.len());
+        batch.0.len()
+    }
+
+    fn create_buffers(&self, context: &GpuContext, next_pow_2: usize) -> Buffers {
+        let sig_buf = context.pool.acquire_sb(&context.device, (96 * next_pow_2) as u64);
+        let msg_buf = context.pool.acquire_sb(&context.device, (48 * next_pow_2) as u64);
+        Buffers::new(context, &self.pipelines, &self.table_buf, sig_buf, msg_buf, next_pow_2, self.log_limb_size)
+    }
+
+    fn release_buffers(buffers: Buffers, pool: &BufferPool) {
+        buffers.release(pool);
+    }
+
+    fn write_batch(queue: &wgpu::Queue, buffers: &Buffers, batch: &Self::Batch) {
+        let (signatures, messages) = batch;
+        write_sb_with(queue, &buffers.sig_buf, |buf| pack_signatures(buf, signatures));
+        write_sb_with(queue, &buffers.msg_buf, |buf| pack_messages(buf, messages));
+    }
+
+    fn encode(&self, command_encoder: &mut wgpu::CommandEncoder, buffers: &Buffers) {
+        encode_stages(command_encoder, &self.pipelines, buffers);
+    }
+
+    fn output_buffers(buffers: &Buffers) -> [&wgpu::Buffer; 2] {
+        [&buffers.result_buf, &buffers.canaries_buf]
+    }
+
+    fn check_canaries(buffers: &Buffers, canaries: &[u8]) -> Result<(), crate::ShaderFailureError> {
+        buffers.check_canaries(canaries)
+    }
+
+    fn decode(num_signatures: usize, results: &[u8]) -> Self::Output {
+        bytes_to_public_keys(&results[..num_signatures * 96])
+    }
+
+    fn item(output: &Self::Output, index: usize) -> Self::Item {
+        output[index]
+    }
+
+    fn reference(batch: &Self::Batch, index: usize) -> Self::Item {
+        let (signatures, messages) = batch;
+        recover(&signatures[index], &messages[index])
+    }
+
+    fn known_answers() -> Self::Batch {
+        let mut signatures = Vec::with_capacity(NUM_KNOWN_ANSWERS);
+        let mut messages = Vec::with_capacity(NUM_KNOWN_ANSWERS);
+        for i in 0..NUM_KNOWN_ANSWERS {
+            let signing_key = SigningKey::from_slice(&Sha384::digest((i as u64).to_le_bytes())).unwrap();
+            let mut message: Message = [0u8; 48];
+            message.copy_from_slice(&Sha384::digest((i as u64).to_be_bytes()));
+            signatures.push(sign_prehashed(&signing_key, &message));
+            messages.push(message);
+        }
+        // The first two signatures recover other keys than those which signed them
+        messages.swap(0, 1);
+        (signatures, messages)
+    }
+}
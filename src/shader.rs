@@ -1,13 +1,14 @@
+use crate::checkpoint::Checkpointing;
+use crate::compaction::Compaction;
+use crate::coop::{COOP_MAX_LOG_LIMB_SIZE, COOP_WORKGROUP_SIZE};
+use crate::curves::{CurveForm, CurveParams, SqrtStrategy};
 use crate::precompute::WINDOW_SIZE;
-use crate::tests::{get_ed25519_d2, get_secp256k1_b, get_secp256r1_b};
-use ark_ec::twisted_edwards::TECurveConfig;
-use ark_ec::AffineRepr;
-use ark_ed25519::EdwardsAffine;
-use ark_ff::{BigInteger, Field, PrimeField};
-use minijinja::{context, Environment, Template};
+use crate::tests::get_secp256k1_b;
+use minijinja::{context, Environment, Template, Value};
 use multiprecision::utils::calc_num_limbs;
 use multiprecision::{bigint, ff, mont, utils::calc_bitwidth};
 use num_bigint::BigUint;
+use std::collections::BTreeMap;
 use std::path::PathBuf;
 
 fn read_from_file(path: &str, file: &str) -> String {
@@ -41,6 +42,116 @@ pub fn gen_constant_bigint(
     result
 }
 
+/// How `mont_mul` in `mont.wgsl` reduces products of base field elements. This is chosen when a
+/// shader is rendered, and the host must convert field elements with the matching `radix`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Reduction {
+    /// Montgomery multiplication, with field elements in Montgomery form. Works for every odd
+    /// modulus.
+    #[default]
+    Montgomery,
+    /// Schoolbook multiplication followed by folding the high bits of the product into the low bits
+    /// (see `pseudo_mersenne.wgsl`), with field elements in canonical form. Only for moduli of the
+    /// form 2^k - c with a small c, i.e. the ed25519 base field (2^255 - 19) and the secp256k1 base
+    /// field (2^256 - 2^32 - 977), but not P-256's.
+    PseudoMersenne,
+}
+
+impl Reduction {
+    /// The largest c, in bits, for which `pm_fold` cannot overflow a limb with any limb size.
+    const MAX_PSEUDO_MERSENNE_C_BITS: u64 = 40;
+
+    /// Returns (k, c) such that p = 2^k - c, if c is small enough for `Reduction::PseudoMersenne`.
+    pub fn pseudo_mersenne_form(p: &BigUint) -> Option<(u64, BigUint)> {
+        let k = p.bits();
+        let c = (BigUint::from(1u32) << k) - p;
+        (c.bits() <= Self::MAX_PSEUDO_MERSENNE_C_BITS).then_some((k, c))
+    }
+
+    pub fn supports(&self, p: &BigUint) -> bool {
+        match self {
+            Reduction::Montgomery => true,
+            Reduction::PseudoMersenne => Self::pseudo_mersenne_form(p).is_some(),
+        }
+    }
+
+    /// Checks that shaders can be rendered with this reduction for the modulus `p` and limbs of
+    /// `log_limb_size` bits.
+    pub fn check(&self, p: &BigUint, log_limb_size: u32) -> Result<(), ReductionError> {
+        if !self.supports(p) {
+            return Err(ReductionError::UnsupportedModulus(*self));
+        }
+        if let Reduction::PseudoMersenne = self {
+            let (_, c) = Self::pseudo_mersenne_form(p).unwrap();
+            let pm_c_num_limbs = (c.bits() as usize).div_ceil(log_limb_size as usize);
+            // pm_fold adds pm_c_num_limbs products of two limbs to a limb before carrying
+            let max_limb_sum = (pm_c_num_limbs as u64) << (2 * log_limb_size);
+            if max_limb_sum + (1u64 << log_limb_size) >= 1u64 << 32 {
+                return Err(ReductionError::LimbsTooLarge { log_limb_size });
+            }
+        }
+        Ok(())
+    }
+
+    /// The R such that the shaders keep a base field element x as x * R mod p.
+    pub fn radix(&self, num_limbs: usize, log_limb_size: u32) -> BigUint {
+        match self {
+            Reduction::Montgomery => mont::calc_mont_radix(num_limbs, log_limb_size),
+            Reduction::PseudoMersenne => BigUint::from(1u32),
+        }
+    }
+}
+
+/// The reasons why shaders cannot be rendered with a `Reduction`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReductionError {
+    /// The modulus is not of the form which the reduction needs.
+    UnsupportedModulus(Reduction),
+    /// `pm_fold` could overflow a limb of this size.
+    LimbsTooLarge { log_limb_size: u32 },
+}
+
+impl std::fmt::Display for ReductionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ReductionError::UnsupportedModulus(reduction) => {
+                write!(f, "{:?} does not support the base field modulus", reduction)
+            }
+            ReductionError::LimbsTooLarge { log_limb_size } => write!(
+                f,
+                "the pseudo-Mersenne reduction can overflow limbs of {} bits",
+                log_limb_size
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ReductionError {}
+
+/// The coordinate system of the points in the secp256k1 recovery pipeline. This is chosen when
+/// the shaders are rendered, and every stage of a pipeline must use the same one.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Coordinates {
+    /// Homogeneous projective (X : Y : Z) for (X / Z, Y / Z), with the complete addition formulas
+    /// of `projective_add_2015_rcb`.
+    #[default]
+    Projective,
+    /// Jacobian (X : Y : Z) for (X / Z^2, Y / Z^3), with `jacobian_add_2007_bl_unsafe` and
+    /// `jacobian_dbl_2009_l`, which need fewer multiplications but branch on the point at infinity
+    /// and on equal points.
+    Jacobian,
+}
+
+impl Coordinates {
+    /// The value of `coordinates` in the templates.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Coordinates::Projective => "projective",
+            Coordinates::Jacobian => "jacobian",
+        }
+    }
+}
+
 pub fn do_render(
     p: &BigUint,
     scalar_p: &BigUint,
@@ -48,109 +159,422 @@ pub fn do_render(
     log_limb_size: u32,
     template: &Template,
 ) -> String {
-    let num_limbs = calc_num_limbs(log_limb_size, 256);
+    do_render_with_num_bits(p, scalar_p, b, log_limb_size, 256, template)
+}
+
+/// Like `do_render`, but the big integers in the shader are wide enough for `num_bits`-bit
+/// moduli instead of 256-bit ones. `num_bits` must be a multiple of 32.
+pub fn do_render_with_num_bits(
+    p: &BigUint,
+    scalar_p: &BigUint,
+    b: &BigUint,
+    log_limb_size: u32,
+    num_bits: usize,
+    template: &Template,
+) -> String {
+    do_render_with_reduction(
+        p,
+        scalar_p,
+        b,
+        log_limb_size,
+        num_bits,
+        Reduction::Montgomery,
+        template,
+    )
+}
+
+/// Like `do_render_with_num_bits`, but `mont_mul` reduces with `reduction`, and the constants in
+/// Montgomery form use its radix.
+pub fn do_render_with_reduction(
+    p: &BigUint,
+    scalar_p: &BigUint,
+    b: &BigUint,
+    log_limb_size: u32,
+    num_bits: usize,
+    reduction: Reduction,
+    template: &Template,
+) -> String {
+    let mut context = field_context(p, scalar_p, log_limb_size, num_bits, reduction);
+    insert_short_weierstrass_constants(&mut context, p, b, log_limb_size, num_bits, reduction);
+    insert_sqrt_case3mod4_constants(&mut context, p, log_limb_size, num_bits);
+    template.render(context).unwrap()
+}
+
+/// The constants of the base and scalar fields which every big-integer shader uses.
+fn field_context(
+    p: &BigUint,
+    scalar_p: &BigUint,
+    log_limb_size: u32,
+    num_bits: usize,
+    reduction: Reduction,
+) -> BTreeMap<String, Value> {
+    if let Err(e) = reduction.check(p, log_limb_size) {
+        panic!("{} (modulus {})", e, p);
+    }
+    assert_eq!(num_bits % 32, 0);
+    let num_limbs = calc_num_limbs(log_limb_size, num_bits);
     let two_pow_word_size = 2u32.pow(log_limb_size);
     let mask = two_pow_word_size - 1u32;
     let nsafe = mont::calc_nsafe(log_limb_size);
     let r = mont::calc_mont_radix(num_limbs, log_limb_size);
     let res = mont::calc_rinv_and_n0(&p, &r, log_limb_size);
-    let rinv = res.0;
     let n0 = res.1;
+    let (r, rinv) = match reduction {
+        Reduction::Montgomery => (r, res.0),
+        Reduction::PseudoMersenne => (BigUint::from(1u32), BigUint::from(1u32)),
+    };
 
     let p_bitlength = calc_bitwidth(&p);
     let slack = num_limbs * log_limb_size as usize - p_bitlength;
 
-    let r_bigint = gen_constant_bigint("r", &(&r % p), num_limbs, log_limb_size);
-    let rinv_bigint = gen_constant_bigint("rinv", &(&rinv % p), num_limbs, log_limb_size);
-    let p_bigint = gen_constant_bigint("p", p, num_limbs, log_limb_size);
-    let scalar_p_bigint = gen_constant_bigint("scalar_p", scalar_p, num_limbs, log_limb_size);
-
-    let br = b * &r % p;
-    let br_bigint = gen_constant_bigint("br", &br, num_limbs, log_limb_size);
-
-    let br3 = (BigUint::from(3u32) * b * &r) % p;
-    let br3_bigint = gen_constant_bigint("br3", &br3, num_limbs, log_limb_size);
-
-    let mu_fp_bigint = gen_constant_bigint("mu_fp", &ff::gen_mu(&p), num_limbs, log_limb_size);
-    let mu_fr_bigint =
-        gen_constant_bigint("mu_fr", &ff::gen_mu(&scalar_p), num_limbs, log_limb_size);
-
-    let secp256k1_generator_x =
-        BigUint::from_bytes_be(&ark_secp256k1::G_GENERATOR_X.into_bigint().to_bytes_be());
-    let secp256k1_generator_y =
-        BigUint::from_bytes_be(&ark_secp256k1::G_GENERATOR_Y.into_bigint().to_bytes_be());
-    let secp256k1_generator_xr = secp256k1_generator_x * &r % p;
-    let secp256k1_generator_yr = secp256k1_generator_y * &r % p;
-    let secp256k1_generator_xr_bigint = gen_constant_bigint(
-        "secp256k1_generator_xr",
-        &secp256k1_generator_xr,
-        num_limbs,
-        log_limb_size,
-    );
-    let secp256k1_generator_yr_bigint = gen_constant_bigint(
-        "secp256k1_generator_yr",
-        &secp256k1_generator_yr,
-        num_limbs,
-        log_limb_size,
-    );
+    let log_table_size = WINDOW_SIZE;
+    let table_size = 2u32.pow(log_table_size);
 
-    let secp256r1_generator_x =
-        BigUint::from_bytes_be(&ark_secp256r1::G_GENERATOR_X.into_bigint().to_bytes_be());
-    let secp256r1_generator_y =
-        BigUint::from_bytes_be(&ark_secp256r1::G_GENERATOR_Y.into_bigint().to_bytes_be());
-    let secp256r1_generator_xr = secp256r1_generator_x * &r % p;
-    let secp256r1_generator_yr = secp256r1_generator_y * &r % p;
-    let secp256r1_generator_xr_bigint = gen_constant_bigint(
-        "secp256r1_generator_xr",
-        &secp256r1_generator_xr,
-        num_limbs,
-        log_limb_size,
-    );
-    let secp256r1_generator_yr_bigint = gen_constant_bigint(
-        "secp256r1_generator_yr",
-        &secp256r1_generator_yr,
-        num_limbs,
-        log_limb_size,
+    let mut context = BTreeMap::new();
+    let mut insert = |name: &str, value: Value| {
+        context.insert(name.to_owned(), value);
+    };
+    insert("table_size", Value::from(table_size));
+    insert("log_table_size", Value::from(log_table_size));
+    insert("num_bits", Value::from(num_bits));
+    insert("num_bytes", Value::from(num_bits / 8));
+    insert("num_words", Value::from(num_bits / 32));
+    insert("num_limbs", Value::from(num_limbs));
+    insert("log_limb_size", Value::from(log_limb_size));
+    insert("two_pow_word_size", Value::from(two_pow_word_size));
+    insert("mask", Value::from(mask));
+    insert("nsafe", Value::from(nsafe));
+    insert("n0", Value::from(n0));
+    insert("slack", Value::from(slack));
+
+    insert_bigint(&mut context, "r", &(&r % p), log_limb_size, num_bits);
+    insert_bigint(&mut context, "rinv", &(&rinv % p), log_limb_size, num_bits);
+    insert_bigint(&mut context, "p", p, log_limb_size, num_bits);
+    insert_bigint(&mut context, "scalar_p", scalar_p, log_limb_size, num_bits);
+    insert_bigint(&mut context, "mu_fp", &ff::gen_mu(&p), log_limb_size, num_bits);
+    insert_bigint(&mut context, "mu_fr", &ff::gen_mu(&scalar_p), log_limb_size, num_bits);
+
+    match reduction {
+        Reduction::Montgomery => {
+            context.insert("reduction".to_owned(), Value::from("montgomery"));
+        }
+        Reduction::PseudoMersenne => {
+            let (k, c) = Reduction::pseudo_mersenne_form(p).unwrap();
+            let pm_c_num_limbs = (c.bits() as usize).div_ceil(log_limb_size as usize);
+            context.insert("reduction".to_owned(), Value::from("pseudo_mersenne"));
+            context.insert("pm_k".to_owned(), Value::from(k));
+            context.insert("pm_k_limb".to_owned(), Value::from(k as u32 / log_limb_size));
+            context.insert("pm_k_shift".to_owned(), Value::from(k as u32 % log_limb_size));
+            context.insert("pm_c_num_limbs".to_owned(), Value::from(pm_c_num_limbs));
+            insert_bigint(&mut context, "pm_c", &c, log_limb_size, num_bits);
+        }
+    }
+    context
+}
+
+/// Inserts the declaration of the constant `var_name` as `{var_name}_bigint`.
+fn insert_bigint(
+    context: &mut BTreeMap<String, Value>,
+    var_name: &str,
+    val: &BigUint,
+    log_limb_size: u32,
+    num_bits: usize,
+) {
+    let num_limbs = calc_num_limbs(log_limb_size, num_bits);
+    context.insert(
+        format!("{}_bigint", var_name),
+        Value::from(gen_constant_bigint(var_name, val, num_limbs, log_limb_size)),
     );
+}
+
+/// Inserts `val` in Montgomery form, with the radix of `reduction`.
+fn insert_mont_bigint(
+    context: &mut BTreeMap<String, Value>,
+    var_name: &str,
+    val: &BigUint,
+    p: &BigUint,
+    log_limb_size: u32,
+    num_bits: usize,
+    reduction: Reduction,
+) {
+    let num_limbs = calc_num_limbs(log_limb_size, num_bits);
+    let r = reduction.radix(num_limbs, log_limb_size);
+    insert_bigint(context, var_name, &(val * r % p), log_limb_size, num_bits);
+}
 
+fn insert_short_weierstrass_constants(
+    context: &mut BTreeMap<String, Value>,
+    p: &BigUint,
+    b: &BigUint,
+    log_limb_size: u32,
+    num_bits: usize,
+    reduction: Reduction,
+) {
+    let b3 = BigUint::from(3u32) * b;
+    insert_mont_bigint(context, "br", b, p, log_limb_size, num_bits, reduction);
+    insert_mont_bigint(context, "br3", &b3, p, log_limb_size, num_bits, reduction);
+}
+
+fn insert_sqrt_case3mod4_constants(
+    context: &mut BTreeMap<String, Value>,
+    p: &BigUint,
+    log_limb_size: u32,
+    num_bits: usize,
+) {
     let sqrt_case3mod4_exponent = (p + BigUint::from(1u32)) / BigUint::from(4u32);
-    let sqrt_case3mod4_exponent_bigint = gen_constant_bigint(
+    insert_bigint(
+        context,
         "sqrt_case3mod4_exponent",
         &sqrt_case3mod4_exponent,
-        num_limbs,
         log_limb_size,
+        num_bits,
     );
+}
 
-    let log_table_size = WINDOW_SIZE;
-    let table_size = 2u32.pow(log_table_size);
+/// The constants of the curve `C`, and none of any other curve.
+pub fn curve_context<C: CurveParams>(log_limb_size: u32) -> BTreeMap<String, Value> {
+    curve_context_with_reduction::<C>(log_limb_size, Reduction::Montgomery)
+}
 
-    let context = context! {
-        table_size => table_size,
-        log_table_size => log_table_size,
-        num_limbs => num_limbs,
-        log_limb_size => log_limb_size,
-        two_pow_word_size => two_pow_word_size,
-        mask => mask,
-        nsafe => nsafe,
-        n0 => n0,
-        slack => slack,
-        r_bigint => r_bigint,
-        rinv_bigint => rinv_bigint,
-        p_bigint => p_bigint,
-        scalar_p_bigint => scalar_p_bigint,
-        br_bigint => br_bigint,
-        br3_bigint => br3_bigint,
-        mu_fp_bigint => mu_fp_bigint,
-        mu_fr_bigint => mu_fr_bigint,
-        secp256k1_generator_xr_bigint => secp256k1_generator_xr_bigint,
-        secp256k1_generator_yr_bigint => secp256k1_generator_yr_bigint,
-        secp256r1_generator_xr_bigint => secp256r1_generator_xr_bigint,
-        secp256r1_generator_yr_bigint => secp256r1_generator_yr_bigint,
-        sqrt_case3mod4_exponent_bigint => sqrt_case3mod4_exponent_bigint,
+/// Like `curve_context`, with the constants in Montgomery form using the radix of `reduction`.
+pub fn curve_context_with_reduction<C: CurveParams>(
+    log_limb_size: u32,
+    reduction: Reduction,
+) -> BTreeMap<String, Value> {
+    let p = C::base_field_modulus();
+    let num_bits = C::NUM_BITS;
+    let scalar_p = C::scalar_field_modulus();
+    let mut context = field_context(&p, &scalar_p, log_limb_size, num_bits, reduction);
+    let insert_mont = |context: &mut BTreeMap<String, Value>, name: &str, val: &BigUint| {
+        insert_mont_bigint(context, name, val, &p, log_limb_size, num_bits, reduction);
     };
+    // Lets curve-agnostic templates include the curve's own files, e.g. `{NAME}_msm.wgsl`
+    context.insert("curve_name".to_owned(), Value::from(C::NAME));
+    context.insert("coordinates".to_owned(), Value::from(Coordinates::default().name()));
+    Checkpointing::default().insert_into(&mut context, WINDOW_SIZE);
+    Compaction::default().insert_into(&mut context);
+
+    let (x, y) = C::generator();
+    let generator_name = |coord: &str| format!("{}_generator_{}r", C::NAME, coord);
+    insert_mont(&mut context, &generator_name("x"), &x);
+    insert_mont(&mut context, &generator_name("y"), &y);
+
+    match C::form() {
+        CurveForm::ShortWeierstrass { b, .. } => {
+            insert_short_weierstrass_constants(
+                &mut context,
+                &p,
+                &b,
+                log_limb_size,
+                num_bits,
+                reduction,
+            );
+        }
+        CurveForm::TwistedEdwards { d, .. } => {
+            let d2 = BigUint::from(2u32) * &d % &p;
+            insert_mont(&mut context, "d2r", &d2);
+            insert_mont(&mut context, "edwards_dr", &d);
+            // The extended coordinates of the generator also need t = xy
+            let t = &x * &y % &p;
+            insert_mont(&mut context, &generator_name("t"), &t);
+        }
+    }
+
+    match C::SQRT_STRATEGY {
+        SqrtStrategy::Case3Mod4 => {
+            insert_sqrt_case3mod4_constants(&mut context, &p, log_limb_size, num_bits);
+        }
+        SqrtStrategy::Case5Mod8 => {
+            let p58_exponent = (&p - BigUint::from(5u32)) / BigUint::from(8u32);
+            insert_bigint(&mut context, "p58_exponent", &p58_exponent, log_limb_size, num_bits);
+
+            // As p = 5 mod 8, 2 is a non-residue and 2 ^ ((p - 1) / 4) is a square root of -1
+            let exponent = (&p - BigUint::from(1u32)) / BigUint::from(4u32);
+            let sqrt_m1 = BigUint::from(2u32).modpow(&exponent, &p);
+            insert_mont(&mut context, "sqrt_m1", &sqrt_m1);
+        }
+    }
+
+    context.extend(C::extra_constants());
+    context
+}
+
+/// Renders `template_file` in `template_path` with the constants of the curve `C`. Every WGSL
+/// file directly under `src/wgsl` is available to `{% include %}`.
+pub fn render_curve<C: CurveParams>(
+    template_path: &str,
+    template_file: &str,
+    log_limb_size: u32,
+) -> String {
+    render_curve_with_reduction::<C>(
+        template_path,
+        template_file,
+        log_limb_size,
+        Reduction::Montgomery,
+    )
+}
+
+/// Like `render_curve`, but `mont_mul` reduces with `reduction`.
+pub fn render_curve_with_reduction<C: CurveParams>(
+    template_path: &str,
+    template_file: &str,
+    log_limb_size: u32,
+    reduction: Reduction,
+) -> String {
+    let context = curve_context_with_reduction::<C>(log_limb_size, reduction);
+    render_with_context(template_path, template_file, context)
+}
+
+/// Renders `template_file` in `template_path` with `context`, with every WGSL file directly under
+/// `src/wgsl` available to `{% include %}`.
+fn render_with_context(
+    template_path: &str,
+    template_file: &str,
+    context: BTreeMap<String, Value>,
+) -> String {
+    let library_path: &str = "src/wgsl/";
+
+    let mut env = Environment::new();
+
+    let library_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join(library_path);
+    for entry in std::fs::read_dir(library_dir).unwrap() {
+        let path = entry.unwrap().path();
+        if path.is_file() && path.extension().is_some_and(|ext| ext == "wgsl") {
+            let file_name = path.file_name().unwrap().to_str().unwrap();
+            add_source_to_env(library_path, file_name, &mut env);
+        }
+    }
+    add_source_to_env(template_path, template_file, &mut env);
+
+    let template = env.get_template(template_file).unwrap();
     template.render(context).unwrap()
 }
 
+/// Renders an entry point in `src/wgsl/main` for the curve `C`.
+pub fn render_main<C: CurveParams>(template_file: &str, log_limb_size: u32) -> String {
+    render_curve::<C>("src/wgsl/main", template_file, log_limb_size)
+}
+
+/// Like `render_main`, but `mont_mul` reduces with `reduction`.
+pub fn render_main_with_reduction<C: CurveParams>(
+    template_file: &str,
+    log_limb_size: u32,
+    reduction: Reduction,
+) -> String {
+    render_curve_with_reduction::<C>("src/wgsl/main", template_file, log_limb_size, reduction)
+}
+
+/// Like `render_main`, but the points of the secp256k1 recovery pipeline are in `coordinates`.
+pub fn render_main_with_coordinates<C: CurveParams>(
+    template_file: &str,
+    log_limb_size: u32,
+    coordinates: Coordinates,
+) -> String {
+    render_main_with_checkpointing::<C>(
+        template_file,
+        log_limb_size,
+        coordinates,
+        Checkpointing::default(),
+    )
+}
+
+/// Like `render_main_with_coordinates`, but the scalar multiplication stages of the ECDSA and
+/// EdDSA pipelines are split into sub-dispatches with `checkpointing`.
+pub fn render_main_with_checkpointing<C: CurveParams>(
+    template_file: &str,
+    log_limb_size: u32,
+    coordinates: Coordinates,
+    checkpointing: Checkpointing,
+) -> String {
+    render_main_with_compaction::<C>(
+        template_file,
+        log_limb_size,
+        coordinates,
+        checkpointing,
+        Compaction::default(),
+        Reduction::Montgomery,
+    )
+}
+
+/// Like `render_main_with_checkpointing`, but the stages after the decode stage of the ECDSA and
+/// EdDSA pipelines run over its survivors with `compaction`, and `mont_mul` reduces with
+/// `reduction`.
+pub fn render_main_with_compaction<C: CurveParams>(
+    template_file: &str,
+    log_limb_size: u32,
+    coordinates: Coordinates,
+    checkpointing: Checkpointing,
+    compaction: Compaction,
+    reduction: Reduction,
+) -> String {
+    let mut context = curve_context_with_reduction::<C>(log_limb_size, reduction);
+    context.insert("coordinates".to_owned(), Value::from(coordinates.name()));
+    checkpointing.insert_into(&mut context, WINDOW_SIZE);
+    compaction.insert_into(&mut context);
+    render_with_context("src/wgsl/main", template_file, context)
+}
+
+/// Like `render_main`, but `stage` is the index of the stage in its pipeline, e.g. of a stage which
+/// several pipelines share, which adds to the completion counter of that index.
+pub fn render_main_with_stage<C: CurveParams>(
+    template_file: &str,
+    log_limb_size: u32,
+    stage: usize,
+) -> String {
+    let mut context = curve_context::<C>(log_limb_size);
+    context.insert("stage".to_owned(), Value::from(stage));
+    render_with_context("src/wgsl/main", template_file, context)
+}
+
+/// Renders a cooperative kernel in `src/wgsl/main` for the curve `C`, with `lanes` invocations per
+/// point (see `coop.wgsl`). The kernel multiplies points in projective coordinates, and converts
+/// its output to `coordinates`.
+pub fn render_main_cooperative<C: CurveParams>(
+    template_file: &str,
+    log_limb_size: u32,
+    lanes: u32,
+    coordinates: Coordinates,
+) -> String {
+    // `Cooperation::check` rejects these at the API
+    assert!(log_limb_size <= COOP_MAX_LOG_LIMB_SIZE, "the cooperative kernels need limbs of at most 13 bits");
+    assert!(lanes.is_power_of_two() && lanes >= 2 && lanes as usize <= COOP_WORKGROUP_SIZE);
+    let a = match C::form() {
+        CurveForm::ShortWeierstrass { a, .. } => a,
+        CurveForm::TwistedEdwards { .. } => panic!("{} has no cooperative kernels", C::NAME),
+    };
+
+    let p = C::base_field_modulus();
+    let num_bits = C::NUM_BITS;
+    let num_limbs = calc_num_limbs(log_limb_size, num_bits);
+    let r = mont::calc_mont_radix(num_limbs, log_limb_size);
+    // The multiplicative group of the integers modulo R = 2^k has order 2^(k - 1)
+    let p_inv = p.modpow(&(&r / 2u32 - 1u32), &r);
+    let np = (&r - p_inv) % &r;
+
+    let mut context = curve_context::<C>(log_limb_size);
+    context.insert("coordinates".to_owned(), Value::from(coordinates.name()));
+    context.insert("coop_lanes".to_owned(), Value::from(lanes));
+    context.insert("coop_slice_len".to_owned(), Value::from(num_limbs.div_ceil(lanes as usize)));
+    context.insert("coop_groups".to_owned(), Value::from(COOP_WORKGROUP_SIZE / lanes as usize));
+    context.insert("coop_workgroup_size".to_owned(), Value::from(COOP_WORKGROUP_SIZE));
+    insert_bigint(&mut context, "coop_np", &np, log_limb_size, num_bits);
+    insert_mont_bigint(
+        &mut context,
+        "ar",
+        &a,
+        &p,
+        log_limb_size,
+        num_bits,
+        Reduction::Montgomery,
+    );
+    render_with_context("src/wgsl/main", template_file, context)
+}
+
+/// Renders a test shader in `src/wgsl/tests` for the curve `C`.
+pub fn render_test<C: CurveParams>(template_file: &str, log_limb_size: u32) -> String {
+    render_curve::<C>("src/wgsl/tests", template_file, log_limb_size)
+}
+
 pub fn render_buffer_test(template_file: &str) -> String {
     let tests_path: &str = "src/wgsl/tests";
 
@@ -220,290 +644,83 @@ pub fn render_bigint_ff_mont_tests(
     b: &BigUint,
     log_limb_size: u32,
 ) -> String {
-    let template_path: &str = "src/wgsl/";
-    let tests_path: &str = "src/wgsl/tests";
-    let mut env = Environment::new();
-
-    add_source_to_env(template_path, "bigint.wgsl", &mut env);
-    add_source_to_env(template_path, "ff.wgsl", &mut env);
-    add_source_to_env(template_path, "mont.wgsl", &mut env);
-    add_source_to_env(template_path, "constants.wgsl", &mut env);
-    add_source_to_env(tests_path, template_file, &mut env);
-
-    let template = env.get_template(template_file).unwrap();
-    do_render(p, p, b, log_limb_size, &template)
+    render_bigint_ff_mont_tests_with_num_bits(template_file, p, b, log_limb_size, 256)
 }
 
-pub fn render_mont_sqrt_case3mod4_test(
+/// Like `render_bigint_ff_mont_tests`, but `mont_mul` reduces with `reduction`.
+pub fn render_bigint_ff_mont_tests_with_reduction(
     template_file: &str,
     p: &BigUint,
+    b: &BigUint,
     log_limb_size: u32,
+    reduction: Reduction,
 ) -> String {
-    let template_path: &str = "src/wgsl/";
-    let tests_path: &str = "src/wgsl/tests";
-
-    let mut env = Environment::new();
-
-    let b = get_secp256k1_b();
-
-    add_source_to_env(template_path, "bigint.wgsl", &mut env);
-    add_source_to_env(template_path, "ff.wgsl", &mut env);
-    add_source_to_env(template_path, "mont.wgsl", &mut env);
-    add_source_to_env(template_path, "secp256k1_curve.wgsl", &mut env);
-    add_source_to_env(template_path, "secp_constants.wgsl", &mut env);
-    add_source_to_env(template_path, "constants.wgsl", &mut env);
-    add_source_to_env(tests_path, template_file, &mut env);
+    do_render_bigint_ff_mont_tests(template_file, p, b, log_limb_size, 256, reduction)
+}
 
-    let template = env.get_template(template_file).unwrap();
-    do_render(&p, &p, &b, log_limb_size, &template)
+/// Like `render_bigint_ff_mont_tests`, for `num_bits`-bit moduli.
+pub fn render_bigint_ff_mont_tests_with_num_bits(
+    template_file: &str,
+    p: &BigUint,
+    b: &BigUint,
+    log_limb_size: u32,
+    num_bits: usize,
+) -> String {
+    do_render_bigint_ff_mont_tests(
+        template_file,
+        p,
+        b,
+        log_limb_size,
+        num_bits,
+        Reduction::Montgomery,
+    )
 }
 
-pub fn render_secp256k1_curve_tests(template_file: &str, log_limb_size: u32) -> String {
+fn do_render_bigint_ff_mont_tests(
+    template_file: &str,
+    p: &BigUint,
+    b: &BigUint,
+    log_limb_size: u32,
+    num_bits: usize,
+    reduction: Reduction,
+) -> String {
     let template_path: &str = "src/wgsl/";
     let tests_path: &str = "src/wgsl/tests";
-
     let mut env = Environment::new();
 
-    let p = crate::moduli::secp256k1_fq_modulus_biguint();
-    let scalar_p = crate::moduli::secp256k1_fr_modulus_biguint();
-
-    let b = get_secp256k1_b();
-
     add_source_to_env(template_path, "bigint.wgsl", &mut env);
     add_source_to_env(template_path, "ff.wgsl", &mut env);
     add_source_to_env(template_path, "mont.wgsl", &mut env);
-    add_source_to_env(template_path, "secp256k1_curve.wgsl", &mut env);
-    add_source_to_env(template_path, "secp_constants.wgsl", &mut env);
-    add_source_to_env(template_path, "secp_curve_utils.wgsl", &mut env);
+    add_source_to_env(template_path, "pseudo_mersenne.wgsl", &mut env);
     add_source_to_env(template_path, "constants.wgsl", &mut env);
     add_source_to_env(tests_path, template_file, &mut env);
 
     let template = env.get_template(template_file).unwrap();
-    do_render(&p, &scalar_p, &b, log_limb_size, &template)
+    do_render_with_reduction(p, p, b, log_limb_size, num_bits, reduction, &template)
 }
 
-pub fn render_secp256k1_ecdsa(template_file: &str, log_limb_size: u32) -> String {
+pub fn render_mont_sqrt_case3mod4_test(
+    template_file: &str,
+    p: &BigUint,
+    log_limb_size: u32,
+) -> String {
     let template_path: &str = "src/wgsl/";
-    let main_path: &str = "src/wgsl/main";
+    let tests_path: &str = "src/wgsl/tests";
 
     let mut env = Environment::new();
 
     let b = get_secp256k1_b();
-    let p = crate::moduli::secp256k1_fq_modulus_biguint();
-    let scalar_p = crate::moduli::secp256k1_fr_modulus_biguint();
 
     add_source_to_env(template_path, "bigint.wgsl", &mut env);
     add_source_to_env(template_path, "ff.wgsl", &mut env);
     add_source_to_env(template_path, "mont.wgsl", &mut env);
     add_source_to_env(template_path, "secp256k1_curve.wgsl", &mut env);
-    add_source_to_env(template_path, "signature.wgsl", &mut env);
-    add_source_to_env(template_path, "secp256k1_ecdsa.wgsl", &mut env);
     add_source_to_env(template_path, "secp_constants.wgsl", &mut env);
-    add_source_to_env(template_path, "secp_curve_utils.wgsl", &mut env);
-    add_source_to_env(template_path, "constants.wgsl", &mut env);
-    add_source_to_env(template_path, "secp256k1_curve_generators.wgsl", &mut env);
-    add_source_to_env(template_path, "bytes_be_to_limbs_le.wgsl", &mut env);
-    add_source_to_env(template_path, "limbs_le_to_u32s_be.wgsl", &mut env);
-    add_source_to_env(main_path, template_file, &mut env);
-
-    let template = env.get_template(template_file).unwrap();
-    do_render(&p, &scalar_p, &b, log_limb_size, &template)
-}
-
-pub fn render_secp256r1_curve_tests(template_file: &str, log_limb_size: u32) -> String {
-    let template_path: &str = "src/wgsl/";
-    let tests_path: &str = "src/wgsl/tests";
-
-    let mut env = Environment::new();
-
-    let p = crate::moduli::secp256r1_fq_modulus_biguint();
-    let scalar_p = crate::moduli::secp256r1_fr_modulus_biguint();
-    let b = get_secp256r1_b();
-
-    add_source_to_env(template_path, "bigint.wgsl", &mut env);
-    add_source_to_env(template_path, "ff.wgsl", &mut env);
-    add_source_to_env(template_path, "mont.wgsl", &mut env);
-    add_source_to_env(template_path, "secp256r1_curve.wgsl", &mut env);
-    add_source_to_env(template_path, "secp256r1_curve_generators.wgsl", &mut env);
-    add_source_to_env(template_path, "secp_constants.wgsl", &mut env);
-    add_source_to_env(template_path, "secp_curve_utils.wgsl", &mut env);
-    add_source_to_env(template_path, "constants.wgsl", &mut env);
-    add_source_to_env(tests_path, template_file, &mut env);
-
-    let template = env.get_template(template_file).unwrap();
-    do_render(&p, &scalar_p, &b, log_limb_size, &template)
-}
-
-pub fn render_secp256r1_ecdsa(template_file: &str, log_limb_size: u32) -> String {
-    let template_path: &str = "src/wgsl/";
-    let main_path: &str = "src/wgsl/main";
-
-    let mut env = Environment::new();
-
-    let p = crate::moduli::secp256r1_fq_modulus_biguint();
-    let scalar_p = crate::moduli::secp256r1_fr_modulus_biguint();
-    let b = get_secp256r1_b();
-
-    add_source_to_env(template_path, "bigint.wgsl", &mut env);
-    add_source_to_env(template_path, "ff.wgsl", &mut env);
-    add_source_to_env(template_path, "mont.wgsl", &mut env);
-    add_source_to_env(template_path, "secp256r1_curve.wgsl", &mut env);
-    add_source_to_env(template_path, "signature.wgsl", &mut env);
-    add_source_to_env(template_path, "secp256r1_ecdsa.wgsl", &mut env);
-    add_source_to_env(template_path, "secp_constants.wgsl", &mut env);
-    add_source_to_env(template_path, "secp_curve_utils.wgsl", &mut env);
-    add_source_to_env(template_path, "constants.wgsl", &mut env);
-    add_source_to_env(template_path, "secp256r1_curve_generators.wgsl", &mut env);
-    add_source_to_env(template_path, "bytes_be_to_limbs_le.wgsl", &mut env);
-    add_source_to_env(template_path, "limbs_le_to_u32s_be.wgsl", &mut env);
-    add_source_to_env(main_path, template_file, &mut env);
-
-    let template = env.get_template(template_file).unwrap();
-    do_render(&p, &scalar_p, &b, log_limb_size, &template)
-}
-
-pub fn render_ed25519_curve_tests(template_file: &str, log_limb_size: u32) -> String {
-    let template_path: &str = "src/wgsl/";
-    let tests_path: &str = "src/wgsl/tests";
-
-    let mut env = Environment::new();
-
-    let p = crate::moduli::ed25519_fq_modulus_biguint();
-    let scalar_p = crate::moduli::ed25519_fr_modulus_biguint();
-    let d2 = get_ed25519_d2();
-
-    add_source_to_env(template_path, "bigint.wgsl", &mut env);
-    add_source_to_env(template_path, "ff.wgsl", &mut env);
-    add_source_to_env(template_path, "mont.wgsl", &mut env);
-    add_source_to_env(template_path, "ed25519_curve.wgsl", &mut env);
-    add_source_to_env(template_path, "ed25519_utils.wgsl", &mut env);
     add_source_to_env(template_path, "constants.wgsl", &mut env);
-    add_source_to_env(template_path, "ed25519_constants.wgsl", &mut env);
-    add_source_to_env(template_path, "limbs_le_to_u32s_be.wgsl", &mut env);
     add_source_to_env(tests_path, template_file, &mut env);
 
     let template = env.get_template(template_file).unwrap();
-    do_render_ed25519(&p, &scalar_p, &d2, log_limb_size, &template)
-}
-
-pub fn do_render_ed25519(
-    p: &BigUint,
-    scalar_p: &BigUint,
-    d2: &BigUint,
-    log_limb_size: u32,
-    template: &Template,
-) -> String {
-    let num_limbs = calc_num_limbs(log_limb_size, 256);
-    let two_pow_word_size = 2u32.pow(log_limb_size);
-    let mask = two_pow_word_size - 1u32;
-    let nsafe = mont::calc_nsafe(log_limb_size);
-    let r = mont::calc_mont_radix(num_limbs, log_limb_size);
-    let res = mont::calc_rinv_and_n0(&p, &r, log_limb_size);
-    let rinv = res.0;
-    let n0 = res.1;
-
-    let p_bitlength = calc_bitwidth(&p);
-    let slack = num_limbs * log_limb_size as usize - p_bitlength;
-
-    let r_bigint = gen_constant_bigint("r", &(&r % p), num_limbs, log_limb_size);
-    let rinv_bigint = gen_constant_bigint("rinv", &(&rinv % p), num_limbs, log_limb_size);
-    let p_bigint = gen_constant_bigint("p", p, num_limbs, log_limb_size);
-    let scalar_p_bigint = gen_constant_bigint("scalar_p", scalar_p, num_limbs, log_limb_size);
-
-    let d2r = d2 * &r % p;
-    let d2r_bigint = gen_constant_bigint("d2r", &d2r, num_limbs, log_limb_size);
-
-    let mu_fp_bigint = gen_constant_bigint("mu_fp", &ff::gen_mu(&p), num_limbs, log_limb_size);
-    let mu_fr_bigint =
-        gen_constant_bigint("mu_fr", &ff::gen_mu(&scalar_p), num_limbs, log_limb_size);
-
-    let p58_exponent = BigUint::parse_bytes(
-        b"ffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffd",
-        16,
-    )
-    .unwrap();
-    assert_eq!(
-        p58_exponent,
-        (p - BigUint::from(5u32)) / BigUint::from(8u32)
-    );
-    let p58_exponent_bigint =
-        gen_constant_bigint("p58_exponent", &p58_exponent, num_limbs, log_limb_size);
-
-    let sqrt_m1 = ark_ed25519::Fq::from(-1i32).sqrt().unwrap();
-    let sqrt_m1_bigint: BigUint = sqrt_m1.into_bigint().into();
-    let sqrt_m1r_bigint = gen_constant_bigint(
-        "sqrt_m1r",
-        &(sqrt_m1_bigint * &r % p),
-        num_limbs,
-        log_limb_size,
-    );
-
-    let edwards_dr: BigUint = ark_ed25519::EdwardsConfig::COEFF_D.into_bigint().into();
-    let edwards_dr_bigint = gen_constant_bigint(
-        "edwards_dr",
-        &(edwards_dr * &r % p),
-        num_limbs,
-        log_limb_size,
-    );
-
-    let generator = EdwardsAffine::generator();
-    let ed25519_generator_x = BigUint::from_bytes_be(&generator.x.into_bigint().to_bytes_be());
-    let ed25519_generator_y = BigUint::from_bytes_be(&generator.y.into_bigint().to_bytes_be());
-    let ed25519_generator_xr = &ed25519_generator_x * &r % p;
-    let ed25519_generator_yr = &ed25519_generator_y * &r % p;
-    let ed25519_generator_tr = (&ed25519_generator_x * &ed25519_generator_y) * &r % p;
-    let ed25519_generator_xr_bigint = gen_constant_bigint(
-        "ed25519_generator_xr",
-        &ed25519_generator_xr,
-        num_limbs,
-        log_limb_size,
-    );
-    let ed25519_generator_yr_bigint = gen_constant_bigint(
-        "ed25519_generator_yr",
-        &ed25519_generator_yr,
-        num_limbs,
-        log_limb_size,
-    );
-    let ed25519_generator_tr_bigint = gen_constant_bigint(
-        "ed25519_generator_tr",
-        &ed25519_generator_tr,
-        num_limbs,
-        log_limb_size,
-    );
-
-    let (fr_reduce_r_limbs_array, scalar_p_limbs_array) = gen_ed25519_reduce_fr_constants(scalar_p);
-
-    let log_table_size = WINDOW_SIZE;
-    let table_size = 2u32.pow(log_table_size);
-
-    let context = context! {
-        table_size => table_size,
-        log_table_size => log_table_size,
-        num_limbs => num_limbs,
-        log_limb_size => log_limb_size,
-        two_pow_word_size => two_pow_word_size,
-        mask => mask,
-        nsafe => nsafe,
-        n0 => n0,
-        slack => slack,
-        r_bigint => r_bigint,
-        rinv_bigint => rinv_bigint,
-        p_bigint => p_bigint,
-        scalar_p_bigint => scalar_p_bigint,
-        d2r_bigint => d2r_bigint,
-        mu_fp_bigint => mu_fp_bigint,
-        mu_fr_bigint => mu_fr_bigint,
-        p58_exponent_bigint => p58_exponent_bigint,
-        sqrt_m1r_bigint => sqrt_m1r_bigint,
-        edwards_dr_bigint => edwards_dr_bigint,
-        ed25519_generator_xr_bigint => ed25519_generator_xr_bigint,
-        ed25519_generator_yr_bigint => ed25519_generator_yr_bigint,
-        ed25519_generator_tr_bigint => ed25519_generator_tr_bigint,
-        scalar_p_limbs_array => scalar_p_limbs_array,
-        fr_reduce_r_limbs_array => fr_reduce_r_limbs_array,
-    };
-
-    template.render(context).unwrap()
+    do_render(&p, &p, &b, log_limb_size, &template)
 }
 
 pub fn render_ed25519_reduce_fr_tests(template_file: &str) -> String {
@@ -566,60 +783,6 @@ pub fn do_render_ed25519_reduce_fr_tests(scalar_p: &BigUint, template: &Template
     template.render(context).unwrap()
 }
 
-pub fn render_ed25519_utils_tests(template_file: &str, log_limb_size: u32) -> String {
-    let template_path: &str = "src/wgsl/";
-    let tests_path: &str = "src/wgsl/tests";
-
-    let mut env = Environment::new();
-
-    let p = crate::moduli::ed25519_fq_modulus_biguint();
-    let scalar_p = crate::moduli::ed25519_fr_modulus_biguint();
-    let d2 = get_ed25519_d2();
-
-    add_source_to_env(template_path, "bigint.wgsl", &mut env);
-
-    add_source_to_env(template_path, "ff.wgsl", &mut env);
-    add_source_to_env(template_path, "mont.wgsl", &mut env);
-    add_source_to_env(template_path, "ed25519_curve.wgsl", &mut env);
-    add_source_to_env(template_path, "constants.wgsl", &mut env);
-    add_source_to_env(template_path, "ed25519_constants.wgsl", &mut env);
-    add_source_to_env(template_path, "ed25519_utils.wgsl", &mut env);
-    add_source_to_env(template_path, "bytes_be_to_limbs_le.wgsl", &mut env);
-    add_source_to_env(template_path, "limbs_le_to_u32s_be.wgsl", &mut env);
-    add_source_to_env(tests_path, template_file, &mut env);
-
-    let template = env.get_template(template_file).unwrap();
-    do_render_ed25519(&p, &scalar_p, &d2, log_limb_size, &template)
-}
-
-pub fn render_ed25519_eddsa(template_file: &str, log_limb_size: u32) -> String {
-    let template_path: &str = "src/wgsl/";
-    let main_path: &str = "src/wgsl/main";
-
-    let mut env = Environment::new();
-
-    let p = crate::moduli::ed25519_fq_modulus_biguint();
-    let scalar_p = crate::moduli::ed25519_fr_modulus_biguint();
-    let d2 = get_ed25519_d2();
-
-    add_source_to_env(template_path, "bigint.wgsl", &mut env);
-    add_source_to_env(template_path, "ff.wgsl", &mut env);
-    add_source_to_env(template_path, "mont.wgsl", &mut env);
-    add_source_to_env(template_path, "ed25519_curve.wgsl", &mut env);
-    add_source_to_env(template_path, "constants.wgsl", &mut env);
-    add_source_to_env(template_path, "ed25519_constants.wgsl", &mut env);
-    add_source_to_env(template_path, "ed25519_utils.wgsl", &mut env);
-    add_source_to_env(template_path, "ed25519_eddsa.wgsl", &mut env);
-    add_source_to_env(template_path, "bytes_be_to_limbs_le.wgsl", &mut env);
-    add_source_to_env(template_path, "limbs_le_to_u32s_be.wgsl", &mut env);
-    add_source_to_env(template_path, "sha512.wgsl", &mut env);
-    add_source_to_env(template_path, "ed25519_reduce_fr.wgsl", &mut env);
-    add_source_to_env(main_path, template_file, &mut env);
-
-    let template = env.get_template(template_file).unwrap();
-    do_render_ed25519(&p, &scalar_p, &d2, log_limb_size, &template)
-}
-
 pub fn render_sha512_96_tests(template_file: &str) -> String {
     let template_path: &str = "src/wgsl/";
     let tests_path: &str = "src/wgsl/tests";
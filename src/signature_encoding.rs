@@ -0,0 +1,114 @@
+use num_bigint::BigUint;
+
+/// How an ECDSA signature with a recovery id is encoded. Every encoding is converted to Fuel's
+/// compact form, which is what `signature.wgsl::decode_signature` reads, before it is uploaded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignatureEncoding {
+    /// Fuel's 64-byte r || s, with the parity of the y-coordinate of R in the top bit of s.
+    FuelCompact,
+    /// Ethereum's r || s || v, with 32-byte r and s and a big-endian v of up to 8 bytes. v may be
+    /// the recovery id itself, 27 or 28, or 35 + 2 * chain_id plus the recovery id as in EIP-155.
+    Rsv,
+    /// A DER-encoded `SEQUENCE { r INTEGER, s INTEGER }` followed by a one-byte recovery id.
+    DerWithRecoveryId,
+}
+
+impl SignatureEncoding {
+    /// Converts `signature` to Fuel's compact form for a curve whose scalar field modulus is `n`.
+    /// Returns `None` if the signature is malformed, if r or s is not in [1, n), or if the
+    /// recovery id says that the x-coordinate of R is r + n, which the compact form cannot express.
+    pub fn to_compact(&self, signature: &[u8], n: &BigUint) -> Option<[u8; 64]> {
+        match self {
+            SignatureEncoding::FuelCompact => signature.try_into().ok(),
+            SignatureEncoding::Rsv => {
+                if signature.len() < 65 || signature.len() > 72 {
+                    return None;
+                }
+                let v = signature[64..].iter().fold(0u64, |v, b| (v << 8) | *b as u64);
+                let recovery_id = match v {
+                    0..=3 => v,
+                    27..=30 => v - 27,
+                    // EIP-155 cannot express the recovery ids 2 and 3
+                    35..=u64::MAX => (v - 35) % 2,
+                    _ => return None,
+                };
+                let r = BigUint::from_bytes_be(&signature[..32]);
+                let s = BigUint::from_bytes_be(&signature[32..64]);
+                compact_from_parts(&r, &s, recovery_id, n)
+            }
+            SignatureEncoding::DerWithRecoveryId => {
+                let (recovery_id, der) = signature.split_last()?;
+                let (r, s) = parse_der(der)?;
+                compact_from_parts(&r, &s, *recovery_id as u64, n)
+            }
+        }
+    }
+}
+
+/// Encodes (r, s) and the parity of the y-coordinate of R in Fuel's compact form. A high s is
+/// replaced with n - s, which is a valid signature for -R, so the parity is flipped to match and
+/// the top bit of s is free.
+pub fn compact_from_parts(
+    r: &BigUint,
+    s: &BigUint,
+    recovery_id: u64,
+    n: &BigUint,
+) -> Option<[u8; 64]> {
+    if recovery_id > 1 || r.bits() == 0 || s.bits() == 0 || r >= n || s >= n {
+        return None;
+    }
+
+    let mut is_y_odd = recovery_id == 1;
+    let mut s = s.clone();
+    if s > (n >> 1) {
+        s = n - &s;
+        is_y_odd = !is_y_odd;
+    }
+
+    let mut compact = [0u8; 64];
+    let r_bytes = r.to_bytes_be();
+    let s_bytes = s.to_bytes_be();
+    compact[32 - r_bytes.len()..32].copy_from_slice(&r_bytes);
+    compact[64 - s_bytes.len()..].copy_from_slice(&s_bytes);
+    if is_y_odd {
+        compact[32] |= 0x80;
+    }
+    Some(compact)
+}
+
+/// Parses a DER-encoded `SEQUENCE { r INTEGER, s INTEGER }` with 256-bit r and s. Only the
+/// short form of lengths, which every such signature uses, is accepted.
+pub fn parse_der(der: &[u8]) -> Option<(BigUint, BigUint)> {
+    let (&tag, rest) = der.split_first()?;
+    let (&len, rest) = rest.split_first()?;
+    if tag != 0x30 || len as usize != rest.len() {
+        return None;
+    }
+    let (r, rest) = parse_der_integer(rest)?;
+    let (s, rest) = parse_der_integer(rest)?;
+    if !rest.is_empty() {
+        return None;
+    }
+    Some((r, s))
+}
+
+/// Parses a DER-encoded non-negative INTEGER of up to 256 bits and returns it with the remaining
+/// input.
+fn parse_der_integer(der: &[u8]) -> Option<(BigUint, &[u8])> {
+    let (&tag, rest) = der.split_first()?;
+    let (&len, rest) = rest.split_first()?;
+    let len = len as usize;
+    if tag != 0x02 || len == 0 || len > 33 || rest.len() < len {
+        return None;
+    }
+    let (value, rest) = rest.split_at(len);
+
+    // Negative integers are invalid, and a leading zero is only allowed before a set top bit
+    if value[0] & 0x80 != 0 || (len > 1 && value[0] == 0 && value[1] & 0x80 == 0) {
+        return None;
+    }
+    if len == 33 && value[0] != 0 {
+        return None;
+    }
+    Some((BigUint::from_bytes_be(value), rest))
+}
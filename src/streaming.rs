@@ -0,0 +1,285 @@
+use crate::gpu::{create_command_encoder, BufferPool, GpuContext};
+use crate::integrity::{check_samples, sample_indices, Check};
+use futures::{Stream, StreamExt};
+use futures_intrusive::channel::shared::OneshotReceiver;
+use std::collections::VecDeque;
+
+/// The number of buffer sets which the `Verifier` rotates through. With three sets, the upload of
+/// batch N+1, the computation of batch N, and the readback of batch N-1 can overlap.
+pub const NUM_BUFFER_SETS: usize = 3;
+
+/// A signature scheme whose multi-shader pipeline can be driven by the streaming `Verifier`.
+pub trait Scheme {
+    /// The inputs of one batch, e.g. signatures and messages.
+    type Batch;
+    /// The result of one batch, e.g. recovered public keys or verdicts.
+    type Output;
+    /// The GPU buffers and bind groups which one batch in flight needs.
+    type Buffers;
+    /// The result of one signature, which `Integrity` compares with that of the CPU.
+    type Item: std::fmt::Debug + PartialEq;
+
+    fn num_signatures(batch: &Self::Batch) -> usize;
+
+    /// Acquires the buffers for batches of up to `next_pow_2` signatures from the context's pool.
+    fn create_buffers(&self, context: &GpuContext, next_pow_2: usize) -> Self::Buffers;
+
+    /// Returns the buffers to the pool.
+    fn release_buffers(buffers: Self::Buffers, pool: &BufferPool);
+
+    /// Queues the upload of a batch into existing buffers.
+    fn write_batch(queue: &wgpu::Queue, buffers: &Self::Buffers, batch: &Self::Batch);
+
+    /// Records every stage of the pipeline into `command_encoder`, including the clearing of the
+    /// output buffers.
+    fn encode(&self, command_encoder: &mut wgpu::CommandEncoder, buffers: &Self::Buffers);
+
+    /// Returns the result buffer and the buffer of the completion counter of each stage.
+    fn output_buffers(buffers: &Self::Buffers) -> [&wgpu::Buffer; 2];
+
+    /// Checks the contents of the completion counter buffer against the dispatches of `buffers`.
+    fn check_canaries(buffers: &Self::Buffers, canaries: &[u8]) -> Result<(), crate::ShaderFailureError>;
+
+    /// Parses the contents of the result buffer.
+    fn decode(num_signatures: usize, results: &[u8]) -> Self::Output;
+
+    /// The result of signature `index` in `output`.
+    fn item(output: &Self::Output, index: usize) -> Self::Item;
+
+    /// Computes the result of signature `index` of `batch` with the CPU implementation.
+    fn reference(batch: &Self::Batch, index: usize) -> Self::Item;
+
+    /// A fixed batch with valid and invalid signatures, which `check_known_answers` runs and checks
+    /// in full against the CPU implementation.
+    fn known_answers() -> Self::Batch;
+}
+
+/// Runs the known-answer batch of `scheme` on `context`, and quarantines the context if any result
+/// differs from that of the CPU. `GpuContext::new_with_integrity` runs this for the default
+/// tables; run it for a scheme with another table before trusting the context with it.
+pub async fn check_known_answers<S: Scheme>(context: &GpuContext, scheme: &S) {
+    if context.quarantined().is_some() {
+        return;
+    }
+    let batch = S::known_answers();
+    let num_signatures = S::num_signatures(&batch);
+    let buffers = scheme.create_buffers(context, num_signatures.next_power_of_two());
+    S::write_batch(&context.queue, &buffers, &batch);
+
+    let mut command_encoder = create_command_encoder(&context.device);
+    scheme.encode(&mut command_encoder, &buffers);
+    let results = context.finish_encoder_and_read_bytes(command_encoder, &S::output_buffers(&buffers)).await;
+
+    // A shader failure is reported by the calls themselves, rather than as a wrong result
+    if S::check_canaries(&buffers, &results[1]).is_ok() {
+        let output = S::decode(num_signatures, &results[0]);
+        let samples = (0..num_signatures).map(|index| (index, S::item(&output, index), S::reference(&batch, index)));
+        check_samples(context, Check::KnownAnswers, samples);
+    }
+    S::release_buffers(buffers, &context.pool);
+}
+
+struct BufferSet<B> {
+    next_pow_2: usize,
+    buffers: B,
+    staging_buffers: [wgpu::Buffer; 2],
+}
+
+/// A batch which has been submitted to the GPU but not yet read back.
+struct InFlight<T> {
+    slot: usize,
+    num_signatures: usize,
+    submission_index: Option<wgpu::SubmissionIndex>,
+    receivers: Vec<OneshotReceiver<Result<(), wgpu::BufferAsyncError>>>,
+    check: Check,
+    /// The sampled signatures and their results on the CPU, which are computed while the GPU runs.
+    samples: Vec<(usize, T)>,
+}
+
+/// Verifies (or recovers) a stream of signature batches on a long-lived device. Each batch is
+/// uploaded into one of `NUM_BUFFER_SETS` rotating buffer sets and submitted before the oldest
+/// batch in flight is read back, so that the GPU stays busy while the host packs and parses. With
+/// the `Integrity` of the context, a sample of every batch is checked against the CPU.
+pub struct Verifier<S: Scheme> {
+    context: GpuContext,
+    scheme: S,
+    buffer_sets: Vec<Option<BufferSet<S::Buffers>>>,
+    /// Whether the staging buffers of each buffer set are mapped, or about to be, by a batch which
+    /// has not been read back. A stream which is dropped with batches in flight leaves them so.
+    is_pending: Vec<bool>,
+}
+
+impl<S: Scheme> Verifier<S> {
+    pub fn new(context: GpuContext, scheme: S) -> Self {
+        Self {
+            context,
+            scheme,
+            buffer_sets: (0..NUM_BUFFER_SETS).map(|_| None).collect(),
+            is_pending: vec![false; NUM_BUFFER_SETS],
+        }
+    }
+
+    pub fn context(&self) -> &GpuContext {
+        &self.context
+    }
+
+    /// Returns a stream which yields the result of each batch of `batches`, in order. The stream
+    /// may be dropped before it ends, and the `Verifier` reused: the batches still in flight are
+    /// waited for and discarded when their buffer sets are next needed.
+    ///
+    /// Reading a batch back blocks the polling thread until the GPU has finished it, as wgpu only
+    /// runs the mapping callbacks of native devices from `Device::poll`. Drive the stream on a
+    /// thread which may block, e.g. inside `tokio::task::spawn_blocking` or on a multi-threaded
+    /// runtime with `tokio::task::block_in_place`, rather than on a single-threaded executor
+    /// shared with other tasks.
+    pub fn verify_stream<'a, B>(
+        &'a mut self,
+        batches: B,
+    ) -> impl Stream<Item = Result<S::Output, crate::ShaderFailureError>> + 'a
+    where
+        B: Stream<Item = S::Batch> + 'a,
+    {
+        let state = (self, Box::pin(batches), VecDeque::<InFlight<S::Item>>::new(), 0usize, false);
+
+        futures::stream::unfold(state, |(verifier, mut batches, mut in_flight, mut next_slot, mut is_exhausted)| async move {
+            // Keep every buffer set busy before waiting on the oldest batch
+            while !is_exhausted && in_flight.len() < NUM_BUFFER_SETS {
+                match batches.next().await {
+                    Some(batch) => {
+                        in_flight.push_back(verifier.submit(next_slot, &batch));
+                        next_slot = (next_slot + 1) % NUM_BUFFER_SETS;
+                    }
+                    None => is_exhausted = true,
+                }
+            }
+
+            let oldest = in_flight.pop_front()?;
+            let output = verifier.read(oldest).await;
+            Some((output, (verifier, batches, in_flight, next_slot, is_exhausted)))
+        })
+    }
+
+    /// Uploads a batch into the buffer set at `slot`, records the pipeline and the copies into
+    /// the staging buffers, submits it, and requests that the staging buffers be mapped. With
+    /// `Integrity`, a sample of the batch is then recomputed on the CPU.
+    fn submit(&mut self, slot: usize, batch: &S::Batch) -> InFlight<S::Item> {
+        if self.is_pending[slot] {
+            self.drain(slot);
+        }
+
+        let num_signatures = S::num_signatures(batch);
+        let check = Check::Sample { batch: self.context.next_batch() };
+        // A quarantined device is not trusted with any batch
+        if num_signatures == 0 || self.context.quarantined().is_some() {
+            return InFlight { slot, num_signatures, submission_index: None, receivers: vec![], check, samples: vec![] };
+        }
+
+        // Swap the buffer set for one from the pool if the padded batch size has changed
+        let next_pow_2 = num_signatures.next_power_of_two();
+        let needs_alloc = match &self.buffer_sets[slot] {
+            Some(set) => set.next_pow_2 != next_pow_2,
+            None => true,
+        };
+        if needs_alloc {
+            if let Some(set) = self.buffer_sets[slot].take() {
+                S::release_buffers(set.buffers, &self.context.pool);
+                for staging_buffer in set.staging_buffers {
+                    self.context.pool.release(staging_buffer);
+                }
+            }
+            let buffers = self.scheme.create_buffers(&self.context, next_pow_2);
+            let staging_buffers = S::output_buffers(&buffers).map(|buf| self.context.pool.acquire_staging(&self.context.device, buf.size()));
+            self.buffer_sets[slot] = Some(BufferSet { next_pow_2, buffers, staging_buffers });
+        }
+        let set = self.buffer_sets[slot].as_ref().unwrap();
+
+        S::write_batch(&self.context.queue, &set.buffers, batch);
+
+        let mut command_encoder = create_command_encoder(&self.context.device);
+        self.scheme.encode(&mut command_encoder, &set.buffers);
+        for (buffer, staging_buffer) in S::output_buffers(&set.buffers).iter().zip(&set.staging_buffers) {
+            command_encoder.copy_buffer_to_buffer(buffer, 0, staging_buffer, 0, buffer.size());
+        }
+        let submission_index = self.context.queue.submit(Some(command_encoder.finish()));
+
+        let receivers = set
+            .staging_buffers
+            .iter()
+            .map(|staging_buffer| {
+                let (sender, receiver) = futures_intrusive::channel::shared::oneshot_channel();
+                staging_buffer.slice(..).map_async(wgpu::MapMode::Read, move |v| {
+                    // The receiver is gone if the stream was dropped with this batch in flight
+                    let _ = sender.send(v);
+                });
+                receiver
+            })
+            .collect();
+
+        let samples = sample_indices(num_signatures, self.context.integrity().num_samples())
+            .into_iter()
+            .map(|index| (index, S::reference(batch, index)))
+            .collect();
+
+        self.is_pending[slot] = true;
+        InFlight { slot, num_signatures, submission_index: Some(submission_index), receivers, check, samples }
+    }
+
+    /// Waits for the batch which a dropped stream left in flight in the buffer set at `slot`, and
+    /// unmaps its staging buffers, so that they can be copied into and mapped again.
+    fn drain(&mut self, slot: usize) {
+        self.context.device.poll(wgpu::Maintain::Wait);
+        if let Some(set) = &self.buffer_sets[slot] {
+            for staging_buffer in &set.staging_buffers {
+                staging_buffer.unmap();
+            }
+        }
+        self.is_pending[slot] = false;
+    }
+
+    /// Waits for a submitted batch to finish, and parses and unmaps its staging buffers. The
+    /// batch fails with the quarantine's failure if the context is quarantined, or if its sample
+    /// differs from the CPU results.
+    /// The wait blocks the calling thread; see `verify_stream`.
+    async fn read(&mut self, in_flight: InFlight<S::Item>) -> Result<S::Output, crate::ShaderFailureError> {
+        let submission_index = match in_flight.submission_index {
+            Some(submission_index) => submission_index,
+            // Nothing was submitted, either because the batch is empty or because of the quarantine
+            None => match self.context.quarantined() {
+                Some(failure) => return Err(failure.into()),
+                None => return Ok(S::decode(0, &[])),
+            },
+        };
+
+        self.context.device.poll(wgpu::Maintain::wait_for(submission_index));
+
+        let set = self.buffer_sets[in_flight.slot].as_ref().unwrap();
+        for receiver in in_flight.receivers {
+            if !matches!(receiver.receive().await, Some(Ok(()))) {
+                panic!("failed to run compute on gpu!")
+            }
+        }
+
+        let [result_staging_buffer, canaries_staging_buffer] = &set.staging_buffers;
+
+        let checked = S::check_canaries(&set.buffers, &canaries_staging_buffer.slice(..).get_mapped_range());
+        canaries_staging_buffer.unmap();
+
+        let output = checked.map(|()| {
+            let data = result_staging_buffer.slice(..).get_mapped_range();
+            S::decode(in_flight.num_signatures, &data)
+        });
+        result_staging_buffer.unmap();
+        self.is_pending[in_flight.slot] = false;
+
+        if let Ok(output) = &output {
+            let samples = in_flight.samples.into_iter().map(|(index, cpu)| (index, S::item(output, index), cpu));
+            check_samples(&self.context, in_flight.check, samples);
+        }
+
+        // The batches which were already in flight when another was caught fail as well
+        if let Some(failure) = self.context.quarantined() {
+            return Err(failure.into());
+        }
+        output
+    }
+}
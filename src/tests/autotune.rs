@@ -0,0 +1,24 @@
+use crate::autotune::{autotune, autotuned_log_limb_size, load_profile, AdapterKey, CANDIDATE_LOG_LIMB_SIZES};
+use crate::gpu::GpuContext;
+
+#[serial_test::serial]
+#[tokio::test]
+pub async fn test_autotune_persists_profile() {
+    let context = GpuContext::new().await;
+    let path = std::env::temp_dir().join("wgpu_sig_ops_test_autotune_profile.json");
+    let _ = std::fs::remove_file(&path);
+
+    let profile = autotune(&context).await.expect("No candidate limb size is correct");
+    assert_eq!(profile.candidates.len(), CANDIDATE_LOG_LIMB_SIZES.len());
+    assert!(profile.candidates.iter().any(|c| c.log_limb_size == profile.log_limb_size && c.is_correct));
+
+    let log_limb_size = autotuned_log_limb_size(&context, &path).await.unwrap();
+    let adapter = AdapterKey::from(&context.adapter_info);
+    let saved = load_profile(&path, &adapter).unwrap().expect("Profile was not saved");
+    assert_eq!(saved.log_limb_size, log_limb_size);
+
+    // A second call reads the saved profile instead of tuning again
+    assert_eq!(autotuned_log_limb_size(&context, &path).await.unwrap(), log_limb_size);
+
+    std::fs::remove_file(&path).unwrap();
+}
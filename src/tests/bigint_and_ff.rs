@@ -3,7 +3,7 @@ use crate::gpu::{
     create_sb_with_data, execute_pipeline, finish_encoder_and_read_from_gpu, get_device_and_queue,
 };
 use crate::moduli;
-use crate::shader::render_bigint_ff_mont_tests;
+use crate::shader::{render_bigint_ff_mont_tests, render_bigint_ff_mont_tests_with_num_bits};
 use crate::tests::get_secp256k1_b;
 use ark_ff::{BigInteger, Field, PrimeField};
 use multiprecision::utils::calc_num_limbs;
@@ -123,89 +123,61 @@ pub async fn bigint_div2() {
     }
 }
 
+fn field_inverse<F: PrimeField>(a: &BigUint) -> BigUint {
+    let a = F::from_be_bytes_mod_order(&a.to_bytes_be());
+    // ff_inverse maps 0 to 0
+    let inverse = a.inverse().unwrap_or(F::zero());
+    BigUint::from_bytes_be(&inverse.into_bigint().to_bytes_be())
+}
+
 #[serial_test::serial]
 #[tokio::test]
 pub async fn ff_inverse() {
     let mut rng = gen_rng();
-    let p0 = moduli::secp256k1_fq_modulus_biguint();
-    let p1 = moduli::secp256k1_fr_modulus_biguint();
-    let p2 = moduli::secp256r1_fq_modulus_biguint();
-    let p3 = moduli::secp256r1_fr_modulus_biguint();
-    let p4 = moduli::ed25519_fq_modulus_biguint();
-    let p5 = moduli::ed25519_fr_modulus_biguint();
 
-    let calc_inverse = |a: &BigUint, p: &BigUint| -> BigUint {
-        if p == &p0 {
-            return BigUint::from_bytes_be(
-                &ark_secp256k1::Fq::from_be_bytes_mod_order(&a.to_bytes_be())
-                    .inverse()
-                    .unwrap()
-                    .into_bigint()
-                    .to_bytes_be(),
-            );
-        } else if p == &p1 {
-            return BigUint::from_bytes_be(
-                &ark_secp256k1::Fr::from_be_bytes_mod_order(&a.to_bytes_be())
-                    .inverse()
-                    .unwrap()
-                    .into_bigint()
-                    .to_bytes_be(),
-            );
-        } else if p == &p2 {
-            return BigUint::from_bytes_be(
-                &ark_secp256r1::Fq::from_be_bytes_mod_order(&a.to_bytes_be())
-                    .inverse()
-                    .unwrap()
-                    .into_bigint()
-                    .to_bytes_be(),
-            );
-        } else if p == &p3 {
-            return BigUint::from_bytes_be(
-                &ark_secp256r1::Fr::from_be_bytes_mod_order(&a.to_bytes_be())
-                    .inverse()
-                    .unwrap()
-                    .into_bigint()
-                    .to_bytes_be(),
-            );
-        } else if p == &p4 {
-            return BigUint::from_bytes_be(
-                &ark_ed25519::Fq::from_be_bytes_mod_order(&a.to_bytes_be())
-                    .inverse()
-                    .unwrap()
-                    .into_bigint()
-                    .to_bytes_be(),
-            );
-        } else if p == &p5 {
-            return BigUint::from_bytes_be(
-                &ark_ed25519::Fr::from_be_bytes_mod_order(&a.to_bytes_be())
-                    .inverse()
-                    .unwrap()
-                    .into_bigint()
-                    .to_bytes_be(),
-            );
-        } else {
-            unimplemented!();
-        }
-    };
-
-    for p in &[&p0, &p1, &p2, &p3, &p4, &p5] {
+    // Every field in moduli.rs, with the width of its big integers
+    let fields: [(BigUint, usize, fn(&BigUint) -> BigUint); 8] = [
+        (moduli::secp256k1_fq_modulus_biguint(), 256, field_inverse::<ark_secp256k1::Fq>),
+        (moduli::secp256k1_fr_modulus_biguint(), 256, field_inverse::<ark_secp256k1::Fr>),
+        (moduli::secp256r1_fq_modulus_biguint(), 256, field_inverse::<ark_secp256r1::Fq>),
+        (moduli::secp256r1_fr_modulus_biguint(), 256, field_inverse::<ark_secp256r1::Fr>),
+        (moduli::ed25519_fq_modulus_biguint(), 256, field_inverse::<ark_ed25519::Fq>),
+        (moduli::ed25519_fr_modulus_biguint(), 256, field_inverse::<ark_ed25519::Fr>),
+        (moduli::secp384r1_fq_modulus_biguint(), 384, field_inverse::<ark_secp384r1::Fq>),
+        (moduli::secp384r1_fr_modulus_biguint(), 384, field_inverse::<ark_secp384r1::Fr>),
+    ];
+
+    for (p, num_bits, calc_inverse) in &fields {
         for log_limb_size in 11..16 {
-            let num_limbs = calc_num_limbs(log_limb_size, 256);
-
+            let num_limbs = calc_num_limbs(log_limb_size, *num_bits);
+
+            // The edge cases, where the GCD takes the fewest and the most iterations, and random
+            // values
+            let mut inputs = vec![
+                BigUint::from(0u32),
+                BigUint::from(1u32),
+                BigUint::from(2u32),
+                p - 1u32,
+                p >> 1,
+            ];
             for _ in 0..NUM_RUNS_PER_TEST {
-                let mut a: BigUint = rng.sample::<BigUint, RandomBits>(RandomBits::new(256)) % *p;
-                if a == BigUint::from(0u32) {
-                    a = BigUint::from(1u32);
-                }
+                let a: BigUint = rng.sample(RandomBits::new(*num_bits as u64));
+                inputs.push(a % p);
+            }
 
-                let expected = calc_inverse(&a, &p);
+            for a in &inputs {
+                let expected = calc_inverse(a);
+                if a.bits() > 0 {
+                    assert_eq!(a * &expected % p, BigUint::from(1u32));
+                }
 
-                do_expected_test(
-                    &a,
-                    &p,
+                do_expected_test_with_num_bits(
+                    a,
+                    p,
                     &expected,
                     log_limb_size,
                     num_limbs,
+                    *num_bits,
                     "bigint_and_ff_tests.wgsl",
                     "test_ff_inverse",
                 )
@@ -284,6 +256,29 @@ pub async fn do_expected_test(
     num_limbs: usize,
     filename: &str,
     entrypoint: &str,
+) {
+    do_expected_test_with_num_bits(
+        a,
+        p,
+        expected,
+        log_limb_size,
+        num_limbs,
+        256,
+        filename,
+        entrypoint,
+    )
+    .await;
+}
+
+pub async fn do_expected_test_with_num_bits(
+    a: &BigUint,
+    p: &BigUint,
+    expected: &BigUint,
+    log_limb_size: u32,
+    num_limbs: usize,
+    num_bits: usize,
+    filename: &str,
+    entrypoint: &str,
 ) {
     let a_limbs = bigint::from_biguint_le(a, num_limbs, log_limb_size);
 
@@ -293,7 +288,13 @@ pub async fn do_expected_test(
     let b_buf = create_empty_sb(&device, a_buf.size());
     let result_buf = create_empty_sb(&device, a_buf.size() + 4);
 
-    let source = render_bigint_ff_mont_tests(filename, &p, &get_secp256k1_b(), log_limb_size);
+    let source = render_bigint_ff_mont_tests_with_num_bits(
+        filename,
+        p,
+        &get_secp256k1_b(),
+        log_limb_size,
+        num_bits,
+    );
     let compute_pipeline = create_compute_pipeline(&device, &source, entrypoint);
 
     let mut command_encoder = create_command_encoder(&device);
@@ -0,0 +1,49 @@
+use crate::curves::{CurveForm, CurveParams, Ed25519, Secp256k1, Secp256r1, Secp384r1};
+use crate::shader::curve_context;
+use crate::tests::{get_ed25519_d2, get_secp256k1_b, get_secp256r1_b, get_secp384r1_b};
+use num_bigint::BigUint;
+
+fn short_weierstrass_b<C: CurveParams>() -> BigUint {
+    match C::form() {
+        CurveForm::ShortWeierstrass { b, .. } => b,
+        CurveForm::TwistedEdwards { .. } => panic!("{} is not short Weierstrass", C::NAME),
+    }
+}
+
+#[test]
+pub fn test_curve_params() {
+    assert_eq!(short_weierstrass_b::<Secp256k1>(), get_secp256k1_b());
+    assert_eq!(short_weierstrass_b::<Secp256r1>(), get_secp256r1_b());
+    assert_eq!(short_weierstrass_b::<Secp384r1>(), get_secp384r1_b());
+
+    let p = Ed25519::base_field_modulus();
+    match Ed25519::form() {
+        CurveForm::TwistedEdwards { d, .. } => {
+            assert_eq!(BigUint::from(2u32) * d % &p, get_ed25519_d2());
+        }
+        CurveForm::ShortWeierstrass { .. } => panic!("ed25519 is twisted Edwards"),
+    }
+}
+
+#[test]
+pub fn test_curve_context_has_only_its_own_constants() {
+    let names = ["secp256k1", "secp256r1", "secp384r1", "ed25519"];
+    let check = |context_name: &str, keys: Vec<String>| {
+        for name in names {
+            let has_generator = keys.contains(&format!("{}_generator_xr_bigint", name));
+            assert_eq!(has_generator, name == context_name);
+        }
+    };
+
+    let keys = |context: std::collections::BTreeMap<String, minijinja::Value>| {
+        context.into_keys().collect::<Vec<_>>()
+    };
+    check(Secp256k1::NAME, keys(curve_context::<Secp256k1>(13)));
+    check(Secp256r1::NAME, keys(curve_context::<Secp256r1>(13)));
+    check(Secp384r1::NAME, keys(curve_context::<Secp384r1>(13)));
+
+    let ed25519_keys = keys(curve_context::<Ed25519>(13));
+    check(Ed25519::NAME, ed25519_keys.clone());
+    assert!(ed25519_keys.contains(&"sqrt_m1r_bigint".to_owned()));
+    assert!(!ed25519_keys.contains(&"br_bigint".to_owned()));
+}
@@ -0,0 +1,64 @@
+use crate::precompute::{ed25519_bases, secp256k1_bases, secp256r1_bases};
+use ed25519_dalek::SigningKey;
+use fuel_crypto::SecretKey;
+use rand_chacha::rand_core::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+
+// Not a multiple of the workgroup size, so that the batch is padded
+const NUM_KEYS: usize = 300;
+
+#[serial_test::serial]
+#[tokio::test]
+pub async fn test_secp256k1_derive_public_keys() {
+    let mut rng = ChaCha8Rng::seed_from_u64(2);
+    let secret_keys: Vec<SecretKey> = (0..NUM_KEYS).map(|_| SecretKey::random(&mut rng)).collect();
+    for log_limb_size in 13..14 {
+        let table_limbs = secp256k1_bases(log_limb_size);
+        let public_keys =
+            crate::secp256k1_ecdsa::derive_public_keys(&secret_keys, &table_limbs, log_limb_size)
+                .await
+                .expect("Shader failed");
+        assert_eq!(public_keys.len(), NUM_KEYS);
+        for (pk, sk) in public_keys.into_iter().zip(&secret_keys) {
+            assert_eq!(pk, sk.public_key());
+        }
+    }
+}
+
+#[serial_test::serial]
+#[tokio::test]
+pub async fn test_secp256r1_derive_public_keys() {
+    let mut rng = ChaCha8Rng::seed_from_u64(2);
+    let secret_keys: Vec<p256::SecretKey> =
+        (0..NUM_KEYS).map(|_| p256::SecretKey::random(&mut rng)).collect();
+    for log_limb_size in 13..14 {
+        let table_limbs = secp256r1_bases(log_limb_size);
+        let public_keys =
+            crate::secp256r1_ecdsa::derive_public_keys(&secret_keys, &table_limbs, log_limb_size)
+                .await
+                .expect("Shader failed");
+        assert_eq!(public_keys.len(), NUM_KEYS);
+        for (pk, sk) in public_keys.into_iter().zip(&secret_keys) {
+            assert_eq!(pk, sk.public_key());
+        }
+    }
+}
+
+#[serial_test::serial]
+#[tokio::test]
+pub async fn test_ed25519_derive_public_keys() {
+    let mut rng = ChaCha8Rng::seed_from_u64(2);
+    let signing_keys: Vec<SigningKey> =
+        (0..NUM_KEYS).map(|_| SigningKey::generate(&mut rng)).collect();
+    for log_limb_size in 13..14 {
+        let table_limbs = ed25519_bases(log_limb_size);
+        let verifying_keys =
+            crate::ed25519_eddsa::derive_public_keys(&signing_keys, &table_limbs, log_limb_size)
+                .await
+                .expect("Shader failed");
+        assert_eq!(verifying_keys.len(), NUM_KEYS);
+        for (vk, sk) in verifying_keys.into_iter().zip(&signing_keys) {
+            assert_eq!(vk, sk.verifying_key());
+        }
+    }
+}
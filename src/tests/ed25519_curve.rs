@@ -5,7 +5,8 @@ use crate::gpu::{
     create_bind_group, create_command_encoder, create_compute_pipeline, create_empty_sb,
     create_sb_with_data, execute_pipeline, finish_encoder_and_read_from_gpu, get_device_and_queue,
 };
-use crate::shader::render_ed25519_curve_tests;
+use crate::curves::Ed25519;
+use crate::shader::{render_test, Reduction};
 use crate::tests::eteprojective_to_mont_limbs;
 use ark_ec::{AffineRepr, CurveGroup};
 use ark_ed25519::{EdwardsAffine as Affine, EdwardsProjective as Projective, Fq, Fr};
@@ -250,8 +251,8 @@ pub async fn do_add_test(
     let res = mont::calc_rinv_and_n0(&p, &r, log_limb_size);
     let rinv = res.0;
 
-    let pt_a_limbs = eteprojective_to_mont_limbs::<Fq>(&a, &p, log_limb_size);
-    let pt_b_limbs = eteprojective_to_mont_limbs::<Fq>(&b, &p, log_limb_size);
+    let pt_a_limbs = eteprojective_to_mont_limbs::<Fq>(&a, &p, log_limb_size, Reduction::Montgomery);
+    let pt_b_limbs = eteprojective_to_mont_limbs::<Fq>(&b, &p, log_limb_size, Reduction::Montgomery);
 
     let a = Projective::new(a.x, a.y, a.t, a.z);
     let b = Projective::new(b.x, b.y, a.t, b.z);
@@ -263,7 +264,7 @@ pub async fn do_add_test(
     let pt_b_buf = create_sb_with_data(&device, &pt_b_limbs);
     let result_buf = create_empty_sb(&device, pt_a_buf.size());
 
-    let source = render_ed25519_curve_tests(filename, log_limb_size);
+    let source = render_test::<Ed25519>(filename, log_limb_size);
     let compute_pipeline = create_compute_pipeline(&device, &source, entrypoint);
 
     let mut command_encoder = create_command_encoder(&device);
@@ -318,7 +319,7 @@ pub async fn do_dbl_test(
     let res = mont::calc_rinv_and_n0(&p, &r, log_limb_size);
     let rinv = res.0;
 
-    let pt_a_limbs = eteprojective_to_mont_limbs::<Fq>(&a, &p, log_limb_size);
+    let pt_a_limbs = eteprojective_to_mont_limbs::<Fq>(&a, &p, log_limb_size, Reduction::Montgomery);
 
     let a = Projective::new(a.x, a.y, a.t, a.z);
     let expected_sum_affine = (a + a).into_affine();
@@ -329,7 +330,7 @@ pub async fn do_dbl_test(
     let pt_b_buf = create_empty_sb(&device, pt_a_buf.size());
     let result_buf = create_empty_sb(&device, pt_a_buf.size());
 
-    let source = render_ed25519_curve_tests(filename, log_limb_size);
+    let source = render_test::<Ed25519>(filename, log_limb_size);
     let compute_pipeline = create_compute_pipeline(&device, &source, entrypoint);
 
     let mut command_encoder = create_command_encoder(&device);
@@ -386,8 +387,8 @@ pub async fn do_strauss_shamir_mul_test(
 
     let x_limbs = bigint::from_biguint_le(&x, num_limbs, log_limb_size);
     let y_limbs = bigint::from_biguint_le(&y, num_limbs, log_limb_size);
-    let pt_a_limbs = eteprojective_to_mont_limbs(&a, &p, log_limb_size);
-    let pt_b_limbs = eteprojective_to_mont_limbs(&b, &p, log_limb_size);
+    let pt_a_limbs = eteprojective_to_mont_limbs(&a, &p, log_limb_size, Reduction::Montgomery);
+    let pt_b_limbs = eteprojective_to_mont_limbs(&b, &p, log_limb_size, Reduction::Montgomery);
 
     let res = mont::calc_rinv_and_n0(&p, &r, log_limb_size);
     let rinv = res.0;
@@ -409,7 +410,7 @@ pub async fn do_strauss_shamir_mul_test(
     let y_buf = create_sb_with_data(&device, &y_limbs);
     let result_buf = create_empty_sb(&device, pt_a_buf.size());
 
-    let source = render_ed25519_curve_tests(filename, log_limb_size);
+    let source = render_test::<Ed25519>(filename, log_limb_size);
     let compute_pipeline = create_compute_pipeline(&device, &source, entrypoint);
 
     let mut command_encoder = create_command_encoder(&device);
@@ -460,7 +461,7 @@ pub async fn do_ete_to_affine_test(
     let p = BigUint::from_bytes_be(&Fq::MODULUS.to_bytes_be());
     let num_limbs = calc_num_limbs(log_limb_size, 256);
 
-    let pt_a_limbs = eteprojective_to_mont_limbs::<Fq>(&a, &p, log_limb_size);
+    let pt_a_limbs = eteprojective_to_mont_limbs::<Fq>(&a, &p, log_limb_size, Reduction::Montgomery);
 
     let a = Projective::new(a.x, a.y, a.t, a.z);
     let expected_affine = a.into_affine();
@@ -471,7 +472,7 @@ pub async fn do_ete_to_affine_test(
     let pt_b_buf = create_empty_sb(&device, pt_a_buf.size());
     let result_buf = create_empty_sb(&device, pt_a_buf.size());
 
-    let source = render_ed25519_curve_tests(filename, log_limb_size);
+    let source = render_test::<Ed25519>(filename, log_limb_size);
     let compute_pipeline = create_compute_pipeline(&device, &source, entrypoint);
 
     let mut command_encoder = create_command_encoder(&device);
@@ -532,7 +533,7 @@ pub async fn do_ete_mul_test(
         t: pt.t,
         z: pt.z,
     };
-    let pt_limbs = eteprojective_to_mont_limbs(&pt_proj, &p, log_limb_size);
+    let pt_limbs = eteprojective_to_mont_limbs(&pt_proj, &p, log_limb_size, Reduction::Montgomery);
 
     let s = Fr::from(-1i32);
     let expected: Affine = pt.mul(s).into_affine();
@@ -543,7 +544,7 @@ pub async fn do_ete_mul_test(
     let pt_b_buf = create_empty_sb(&device, pt_a_buf.size());
     let result_buf = create_empty_sb(&device, pt_a_buf.size());
 
-    let source = render_ed25519_curve_tests(filename, log_limb_size);
+    let source = render_test::<Ed25519>(filename, log_limb_size);
     let compute_pipeline = create_compute_pipeline(&device, &source, entrypoint);
 
     let mut command_encoder = create_command_encoder(&device);
@@ -611,7 +612,7 @@ pub async fn do_ete_fixed_mul_test(
     let s_buf = create_sb_with_data(&device, &s_limbs);
     let result_buf = create_empty_sb(&device, (num_limbs * 4 * std::mem::size_of::<u32>()) as u64);
 
-    let source = render_ed25519_curve_tests(filename, log_limb_size);
+    let source = render_test::<Ed25519>(filename, log_limb_size);
     let compute_pipeline = create_compute_pipeline(&device, &source, entrypoint);
 
     let mut command_encoder = create_command_encoder(&device);
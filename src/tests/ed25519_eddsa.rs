@@ -1,5 +1,15 @@
-use crate::precompute::ed25519_bases;
-use crate::ed25519_eddsa::{ecverify, ecverify_single};
+use crate::all_valid::Bitmap;
+use crate::checkpoint::Checkpointing;
+use crate::compaction::Compaction;
+use crate::gpu::{create_command_encoder, create_sb_with_data, write_sb_with, GpuContext};
+use crate::precompute::{ed25519_bases, ed25519_bases_with_reduction};
+use crate::shader::Reduction;
+use crate::ed25519_eddsa::{
+    bytes_to_is_valid, clear_outputs, create_pipelines, dispatches, ecverify, ecverify_all_valid,
+    ecverify_single, ecverify_with_options, pack_messages, pack_signatures, pack_verifying_keys,
+    Buffers, VerificationOptions,
+};
+use crate::Verdict;
 use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
 use fuel_crypto::Message;
 use rand_chacha::ChaCha8Rng;
@@ -54,20 +64,308 @@ pub async fn test_ed25519_ecverify_multi() {
     }
 }
 
+#[serial_test::serial]
+#[tokio::test]
+pub async fn test_ed25519_ecverify_pseudo_mersenne() {
+    let mut rng = ChaCha8Rng::seed_from_u64(3);
+    let context = GpuContext::new().await;
+
+    let mut signatures = vec![];
+    let mut messages = vec![];
+    let mut verifying_keys = vec![];
+    for _ in 0..10 {
+        let mut message = [0u8; 100];
+        rng.fill_bytes(&mut message);
+        let message = Message::new(&message);
+        let signing_key: SigningKey = SigningKey::generate(&mut rng);
+        signatures.push(signing_key.sign(message.as_slice()));
+        messages.push(message);
+        verifying_keys.push(signing_key.verifying_key());
+    }
+
+    // Another signer's key
+    verifying_keys[0] = verifying_keys[1];
+
+    for log_limb_size in 12..15 {
+        let table_limbs = ed25519_bases_with_reduction(log_limb_size, Reduction::PseudoMersenne);
+        for (checkpointing, compaction) in [
+            (Checkpointing::Off, Compaction::Off),
+            (Checkpointing::BitsPerDispatch(7), Compaction::Indirect),
+        ] {
+            let options = VerificationOptions {
+                reduction: Reduction::PseudoMersenne,
+                checkpointing,
+                compaction,
+                ..Default::default()
+            };
+            let result = ecverify_with_options(
+                &context,
+                &signatures,
+                &messages,
+                &verifying_keys,
+                &table_limbs,
+                log_limb_size,
+                &options,
+            )
+            .await
+            .expect("Shader failed");
+            for (i, is_valid) in bytes_to_is_valid(&result).into_iter().enumerate() {
+                assert_eq!(is_valid, i > 0);
+            }
+        }
+    }
+}
+
+#[serial_test::serial]
+#[tokio::test]
+pub async fn test_ed25519_ecverify_checkpointed() {
+    let mut rng = ChaCha8Rng::seed_from_u64(2);
+    let log_limb_size = 13;
+    let table_limbs = ed25519_bases(log_limb_size);
+    let context = GpuContext::new().await;
+
+    let mut signatures = vec![];
+    let mut messages = vec![];
+    let mut verifying_keys = vec![];
+    for _ in 0..10 {
+        let mut message = [0u8; 100];
+        rng.fill_bytes(&mut message);
+        let message = Message::new(&message);
+        let signing_key: SigningKey = SigningKey::generate(&mut rng);
+        signatures.push(signing_key.sign(message.as_slice()));
+        messages.push(message);
+        verifying_keys.push(signing_key.verifying_key());
+    }
+
+    // Another signer's key
+    verifying_keys[0] = verifying_keys[1];
+
+    let unsplit_result = ecverify_with_options(
+        &context,
+        &signatures,
+        &messages,
+        &verifying_keys,
+        &table_limbs,
+        log_limb_size,
+        &VerificationOptions::default(),
+    )
+    .await
+    .expect("Shader failed");
+
+    for bits in [7, 64, 256] {
+        let options = VerificationOptions {
+            checkpointing: Checkpointing::BitsPerDispatch(bits),
+            ..Default::default()
+        };
+        let result = ecverify_with_options(
+            &context,
+            &signatures,
+            &messages,
+            &verifying_keys,
+            &table_limbs,
+            log_limb_size,
+            &options,
+        )
+        .await
+        .expect("Shader failed");
+        // The sub-dispatches perform the same operations, so the output is bit-identical
+        assert_eq!(result, unsplit_result);
+        for (i, is_valid) in bytes_to_is_valid(&result).into_iter().enumerate() {
+            assert_eq!(is_valid, i > 0);
+        }
+    }
+
+    // Checkpointing combines with compaction
+    let options = VerificationOptions {
+        checkpointing: Checkpointing::BitsPerDispatch(7),
+        compaction: Compaction::Indirect,
+        ..Default::default()
+    };
+    let result = ecverify_with_options(
+        &context,
+        &signatures,
+        &messages,
+        &verifying_keys,
+        &table_limbs,
+        log_limb_size,
+        &options,
+    )
+    .await
+    .expect("Shader failed");
+    for (i, is_valid) in bytes_to_is_valid(&result).into_iter().enumerate() {
+        assert_eq!(is_valid, i > 0);
+    }
+}
+
+#[serial_test::serial]
+#[tokio::test]
+pub async fn test_ed25519_ecverify_compacted() {
+    let mut rng = ChaCha8Rng::seed_from_u64(3);
+    let log_limb_size = 13;
+    let table_limbs = ed25519_bases(log_limb_size);
+    let context = GpuContext::new().await;
+
+    let mut signatures = vec![];
+    let mut messages = vec![];
+    let mut verifying_keys = vec![];
+    for _ in 0..10 {
+        let mut message = [0u8; 100];
+        rng.fill_bytes(&mut message);
+        let message = Message::new(&message);
+        let signing_key: SigningKey = SigningKey::generate(&mut rng);
+        signatures.push(signing_key.sign(message.as_slice()));
+        messages.push(message);
+        verifying_keys.push(signing_key.verifying_key());
+    }
+
+    // A `VerifyingKey` always decompresses, so every signature survives stage 0, and the
+    // verdicts must be scattered back to their original indices
+    verifying_keys[0] = verifying_keys[1];
+    verifying_keys[5] = verifying_keys[6];
+
+    let options = VerificationOptions { compaction: Compaction::Indirect, ..Default::default() };
+    let result = ecverify_with_options(
+        &context,
+        &signatures,
+        &messages,
+        &verifying_keys,
+        &table_limbs,
+        log_limb_size,
+        &options,
+    )
+    .await
+    .expect("Shader failed");
+    for (i, is_valid) in bytes_to_is_valid(&result).into_iter().enumerate() {
+        assert_eq!(is_valid, i != 0 && i != 5);
+    }
+}
+
+#[serial_test::serial]
+#[tokio::test]
+pub async fn test_ed25519_ecverify_all_valid() {
+    let mut rng = ChaCha8Rng::seed_from_u64(4);
+    let log_limb_size = 13;
+    let table_limbs = ed25519_bases(log_limb_size);
+    let context = GpuContext::new().await;
+
+    let mut signatures = vec![];
+    let mut messages = vec![];
+    let mut verifying_keys = vec![];
+    for _ in 0..40 {
+        let mut message = [0u8; 100];
+        rng.fill_bytes(&mut message);
+        let message = Message::new(&message);
+        let signing_key: SigningKey = SigningKey::generate(&mut rng);
+        signatures.push(signing_key.sign(message.as_slice()));
+        messages.push(message);
+        verifying_keys.push(signing_key.verifying_key());
+    }
+
+    let result = ecverify_all_valid(
+        &context,
+        &signatures,
+        &messages,
+        &verifying_keys,
+        &table_limbs,
+        log_limb_size,
+        Bitmap::Packed,
+    )
+    .await
+    .expect("Shader failed");
+    assert!(result.is_all_valid());
+    assert!((0..40).all(|i| result.verdict(i).unwrap().is_valid()));
+
+    // Another signer's key in each word of the bitmap
+    verifying_keys[33] = verifying_keys[0];
+    verifying_keys[12] = verifying_keys[0];
+
+    let result = ecverify_all_valid(
+        &context,
+        &signatures,
+        &messages,
+        &verifying_keys,
+        &table_limbs,
+        log_limb_size,
+        Bitmap::Packed,
+    )
+    .await
+    .expect("Shader failed");
+    assert_eq!(result.first_failure, Some(12));
+    for i in 0..40 {
+        assert_eq!(result.verdict(i).unwrap().is_valid(), i != 12 && i != 33);
+    }
+}
+
 pub async fn do_eddsa_test(
     verifying_key: &VerifyingKey,
     signature: &Signature,
     message: &Message,
-    table_limbs: &Vec<u32>,
+    table_limbs: &[u32],
     log_limb_size: u32,
     invoke_single: bool,
 ) {
     let result = if invoke_single {
-        ecverify_single(&vec![*signature], &vec![*message], &vec![*verifying_key], log_limb_size).await
+        ecverify_single(&[*signature], &[*message], &[*verifying_key], log_limb_size).await
     } else {
-        ecverify(&vec![*signature], &vec![*message], &vec![*verifying_key], table_limbs, log_limb_size).await
+        ecverify(&[*signature], &[*message], &[*verifying_key], table_limbs, log_limb_size).await
     }.expect("Shader failed");
     for r in result {
-        assert!(r);
+        assert_eq!(r, Verdict::Valid);
     }
 }
+
+#[serial_test::serial]
+#[tokio::test]
+pub async fn test_ed25519_ecverify_skipped_sub_dispatch() {
+    let log_limb_size = 13;
+    let table_limbs = ed25519_bases(log_limb_size);
+    let (signatures, messages, verifying_keys) = crate::benchmarks::ed25519_eddsa::gen_test_data(300);
+    let context = GpuContext::new().await;
+    let device = &context.device;
+
+    let next_pow_2 = signatures.len().next_power_of_two();
+    let checkpointing = Checkpointing::BitsPerDispatch(64);
+    let options = VerificationOptions { checkpointing, ..Default::default() };
+    let pipelines = create_pipelines(device, log_limb_size, &options);
+    let table_buf = create_sb_with_data(device, &table_limbs);
+    let sig_buf = context.pool.acquire_sb(device, (64 * next_pow_2) as u64);
+    write_sb_with(&context.queue, &sig_buf, |buf| pack_signatures(buf, &signatures));
+    let pk_buf = context.pool.acquire_sb(device, (32 * next_pow_2) as u64);
+    write_sb_with(&context.queue, &pk_buf, |buf| pack_verifying_keys(buf, &verifying_keys));
+    let msg_buf = context.pool.acquire_sb(device, (32 * next_pow_2) as u64);
+    write_sb_with(&context.queue, &msg_buf, |buf| pack_messages(buf, &messages));
+    let buffers = Buffers::new(
+        &context,
+        &pipelines,
+        &table_buf,
+        sig_buf,
+        pk_buf,
+        msg_buf,
+        next_pow_2,
+        log_limb_size,
+        &options,
+    );
+
+    // Leave out the first of the sub-dispatches of stage 3, so that it runs only in part
+    let mut command_encoder = create_command_encoder(device);
+    clear_outputs(&mut command_encoder, &buffers);
+    let mut skipped = false;
+    for dispatch in dispatches(&pipelines, &buffers) {
+        if dispatch.label == "variable-base mul k * -A" && !skipped {
+            skipped = true;
+            continue;
+        }
+        dispatch.execute(&mut command_encoder);
+    }
+    let results = context
+        .finish_encoder_and_read_bytes(command_encoder, &[&buffers.canaries_buf])
+        .await;
+
+    let num_dispatches = checkpointing.num_dispatches() as u32;
+    let failure = buffers.check_canaries(&results[0], None).unwrap_err().stage.unwrap();
+    assert_eq!(failure.stage, 3);
+    assert_eq!(failure.completed, (num_dispatches - 1) * next_pow_2 as u32);
+    assert_eq!(failure.expected, num_dispatches * next_pow_2 as u32);
+
+    buffers.release(&context.pool);
+}
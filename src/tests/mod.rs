@@ -1,10 +1,16 @@
 #[cfg(test)]
+pub mod autotune;
+#[cfg(test)]
 pub mod bigint_and_ff;
 #[cfg(test)]
 pub mod buffers;
 #[cfg(test)]
 pub mod bytes_to_limbs;
 #[cfg(test)]
+pub mod curves;
+#[cfg(test)]
+pub mod derive;
+#[cfg(test)]
 pub mod ed25519_curve;
 #[cfg(test)]
 pub mod ed25519_eddsa;
@@ -15,6 +21,12 @@ pub mod limbs_to_bytes;
 #[cfg(test)]
 pub mod mont;
 #[cfg(test)]
+pub mod msm;
+#[cfg(test)]
+pub mod ops;
+#[cfg(test)]
+pub mod precompute;
+#[cfg(test)]
 pub mod secp256k1_curve;
 #[cfg(test)]
 pub mod secp256k1_ecdsa;
@@ -23,8 +35,16 @@ pub mod secp256r1_curve;
 #[cfg(test)]
 pub mod secp256r1_ecdsa;
 #[cfg(test)]
+pub mod secp384r1_ecdsa;
+#[cfg(test)]
 pub mod sha512;
 #[cfg(test)]
+pub mod signature_encoding;
+#[cfg(test)]
+pub mod streaming;
+#[cfg(test)]
+pub mod webauthn;
+#[cfg(test)]
 pub mod multi_stage;
 
 use crate::curve_algos::coords;
@@ -34,6 +54,7 @@ use fuel_crypto::Signature;
 use multiprecision::bigint;
 use multiprecision::utils::calc_num_limbs;
 use num_bigint::BigUint;
+use crate::shader::Reduction;
 
 pub fn get_secp256k1_b() -> BigUint {
     BigUint::from(7u32)
@@ -55,6 +76,14 @@ pub fn get_secp256r1_b() -> BigUint {
     .unwrap()
 }
 
+pub fn get_secp384r1_b() -> BigUint {
+    BigUint::parse_bytes(
+        b"b3312fa7e23ee7e4988e056be3f82d19181d9c6efe8141120314088f5013875ac656398d8a2ed19d2a85c8edd3ec2aef",
+        16,
+    )
+    .unwrap()
+}
+
 pub fn get_ed25519_d2() -> BigUint {
     BigUint::parse_bytes(
         b"16295367250680780974490674513165176452449235426866156013048779062215315747161",
@@ -72,9 +101,10 @@ pub fn eteprojective_to_mont_limbs<F: PrimeField>(
     a: &coords::ETEProjective<F>,
     p: &BigUint,
     log_limb_size: u32,
+    reduction: Reduction,
 ) -> Vec<u32> {
     let num_limbs = calc_num_limbs(log_limb_size, 256);
-    let r = multiprecision::mont::calc_mont_radix(num_limbs, log_limb_size);
+    let r = reduction.radix(num_limbs, log_limb_size);
     let a_x_r = fq_to_biguint::<F>(a.x) * &r % p;
     let a_y_r = fq_to_biguint::<F>(a.y) * &r % p;
     let a_t_r = fq_to_biguint::<F>(a.t) * &r % p;
@@ -95,9 +125,10 @@ pub fn eteprojective_to_xyt_mont_limbs<F: PrimeField>(
     a: &coords::ETEProjective<F>,
     p: &BigUint,
     log_limb_size: u32,
+    reduction: Reduction,
 ) -> Vec<u32> {
     let num_limbs = calc_num_limbs(log_limb_size, 256);
-    let r = multiprecision::mont::calc_mont_radix(num_limbs, log_limb_size);
+    let r = reduction.radix(num_limbs, log_limb_size);
     let a_x_r = fq_to_biguint::<F>(a.x) * &r % p;
     let a_y_r = fq_to_biguint::<F>(a.y) * &r % p;
     let a_t_r = fq_to_biguint::<F>(a.t) * &r % p;
@@ -115,9 +146,10 @@ pub fn projectivexyz_to_mont_limbs<F: PrimeField>(
     a: &coords::ProjectiveXYZ<F>,
     p: &BigUint,
     log_limb_size: u32,
+    reduction: Reduction,
 ) -> Vec<u32> {
     let num_limbs = calc_num_limbs(log_limb_size, 256);
-    let r = multiprecision::mont::calc_mont_radix(num_limbs, log_limb_size);
+    let r = reduction.radix(num_limbs, log_limb_size);
     let a_x_r = fq_to_biguint::<F>(a.x) * &r % p;
     let a_y_r = fq_to_biguint::<F>(a.y) * &r % p;
     let a_z_r = fq_to_biguint::<F>(a.z) * &r % p;
@@ -135,9 +167,11 @@ pub fn projectivexy_to_mont_limbs<F: PrimeField>(
     a: &coords::ProjectiveXYZ<F>,
     p: &BigUint,
     log_limb_size: u32,
+    num_bits: usize,
+    reduction: Reduction,
 ) -> Vec<u32> {
-    let num_limbs = calc_num_limbs(log_limb_size, 256);
-    let r = multiprecision::mont::calc_mont_radix(num_limbs, log_limb_size);
+    let num_limbs = calc_num_limbs(log_limb_size, num_bits);
+    let r = reduction.radix(num_limbs, log_limb_size);
     let a_x_r = fq_to_biguint::<F>(a.x) * &r % p;
     let a_y_r = fq_to_biguint::<F>(a.y) * &r % p;
     let a_x_r_limbs = bigint::from_biguint_le(&a_x_r, num_limbs, log_limb_size);
@@ -3,7 +3,10 @@ use crate::gpu::{
     create_sb_with_data, execute_pipeline, finish_encoder_and_read_from_gpu, get_device_and_queue,
 };
 use crate::moduli;
-use crate::shader::{render_bigint_ff_mont_tests, render_mont_sqrt_case3mod4_test};
+use crate::shader::{
+    render_bigint_ff_mont_tests, render_bigint_ff_mont_tests_with_reduction,
+    render_mont_sqrt_case3mod4_test, Reduction, ReductionError,
+};
 use crate::tests::get_secp256k1_b;
 use multiprecision::mont::{calc_nsafe, calc_rinv_and_n0};
 use multiprecision::utils::calc_num_limbs;
@@ -233,3 +236,113 @@ pub async fn do_mont_sqrt_case3mod4_test(
     assert!(result_a == expected_a || result_a == expected_b);
     assert!(result_b == expected_b || result_b == expected_a);
 }
+
+#[test]
+pub fn pseudo_mersenne_support() {
+    assert!(Reduction::PseudoMersenne.supports(&moduli::secp256k1_fq_modulus_biguint()));
+    assert!(Reduction::PseudoMersenne.supports(&moduli::ed25519_fq_modulus_biguint()));
+    assert!(!Reduction::PseudoMersenne.supports(&moduli::secp256r1_fq_modulus_biguint()));
+    assert!(!Reduction::PseudoMersenne.supports(&moduli::secp256k1_fr_modulus_biguint()));
+
+    let (k, c) = Reduction::pseudo_mersenne_form(&moduli::ed25519_fq_modulus_biguint()).unwrap();
+    assert_eq!((k, c), (255, BigUint::from(19u32)));
+
+    // pm_fold would overflow 16-bit limbs, so the options are rejected before rendering
+    let p = moduli::secp256k1_fq_modulus_biguint();
+    for log_limb_size in 11..16 {
+        assert_eq!(Reduction::PseudoMersenne.check(&p, log_limb_size), Ok(()));
+    }
+    assert_eq!(
+        Reduction::PseudoMersenne.check(&p, 16),
+        Err(ReductionError::LimbsTooLarge { log_limb_size: 16 })
+    );
+    assert_eq!(
+        Reduction::PseudoMersenne.check(&moduli::secp256r1_fq_modulus_biguint(), 13),
+        Err(ReductionError::UnsupportedModulus(Reduction::PseudoMersenne))
+    );
+}
+
+#[serial_test::serial]
+#[tokio::test]
+pub async fn pseudo_mersenne_mul() {
+    let mut rng = gen_rng();
+
+    let p0 = moduli::secp256k1_fq_modulus_biguint();
+    let p1 = moduli::ed25519_fq_modulus_biguint();
+
+    for p in &[&p0, &p1] {
+        for log_limb_size in 11..16 {
+            let num_limbs = calc_num_limbs(log_limb_size, 256);
+            let max = (BigUint::from(1u32) << (num_limbs * log_limb_size as usize)) - 1u32;
+
+            // Random field elements, the largest field element, and the largest inputs which fit
+            // in the limbs, which mont_mul need not have reduced
+            let mut inputs = vec![(*p - 1u32, *p - 1u32), (max.clone(), max)];
+            for _ in 0..NUM_RUNS_PER_TEST {
+                let a: BigUint = rng.sample::<BigUint, RandomBits>(RandomBits::new(256)) % *p;
+                let b: BigUint = rng.sample::<BigUint, RandomBits>(RandomBits::new(256)) % *p;
+                inputs.push((a, b));
+            }
+
+            for (a, b) in &inputs {
+                do_pseudo_mersenne_test(a, b, p, log_limb_size, num_limbs).await;
+            }
+        }
+    }
+}
+
+/// Runs `test_mont_mul` rendered with `Reduction::PseudoMersenne`, whose radix is 1, so the
+/// result is a * b % p.
+pub async fn do_pseudo_mersenne_test(
+    a: &BigUint,
+    b: &BigUint,
+    p: &BigUint,
+    log_limb_size: u32,
+    num_limbs: usize,
+) {
+    let expected = a * b % p;
+    let a_limbs = bigint::from_biguint_le(a, num_limbs, log_limb_size);
+    let b_limbs = bigint::from_biguint_le(b, num_limbs, log_limb_size);
+
+    let (device, queue) = get_device_and_queue().await;
+
+    let a_buf = create_sb_with_data(&device, &a_limbs);
+    let b_buf = create_sb_with_data(&device, &b_limbs);
+    let result_buf = create_empty_sb(&device, (num_limbs * 8 * std::mem::size_of::<u8>()) as u64);
+
+    let source = render_bigint_ff_mont_tests_with_reduction(
+        "mont_tests.wgsl",
+        p,
+        &get_secp256k1_b(),
+        log_limb_size,
+        Reduction::PseudoMersenne,
+    );
+    let compute_pipeline = create_compute_pipeline(&device, &source, "test_mont_mul");
+
+    let mut command_encoder = create_command_encoder(&device);
+
+    let bind_group = create_bind_group(
+        &device,
+        &compute_pipeline,
+        0,
+        &[&a_buf, &b_buf, &result_buf],
+    );
+
+    execute_pipeline(
+        &mut command_encoder,
+        &compute_pipeline,
+        &bind_group,
+        1,
+        1,
+        1,
+    );
+
+    let results =
+        finish_encoder_and_read_from_gpu(&device, &queue, Box::new(command_encoder), &[result_buf])
+            .await;
+
+    let result =
+        bigint::to_biguint_le(&results[0][0..num_limbs].to_vec(), num_limbs, log_limb_size);
+
+    assert_eq!(result, expected);
+}
@@ -0,0 +1,93 @@
+use crate::gpu::GpuContext;
+use crate::msm::{ed25519_msm, secp256k1_msm, secp256r1_msm, window_size, MAX_WINDOW_SIZE};
+use ark_ec::{AffineRepr, CurveGroup, VariableBaseMSM};
+use ark_ff::{UniformRand, Zero};
+use rand_chacha::rand_core::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+
+// Not a multiple of the workgroup size, so that the stages need padding threads
+const NUM_POINTS: usize = 300;
+
+/// Random points and scalars, with the cases which the bucket sums must get right: a repeated
+/// point, zero scalars, and a point together with its negation under the same scalar.
+fn gen_inputs<G: CurveGroup>(seed: u64) -> (Vec<G::Affine>, Vec<G::ScalarField>) {
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    let mut points: Vec<G::Affine> = (0..NUM_POINTS - 4)
+        .map(|_| (G::Affine::generator() * G::ScalarField::rand(&mut rng)).into_affine())
+        .collect();
+    let mut scalars: Vec<G::ScalarField> =
+        (0..NUM_POINTS - 4).map(|_| G::ScalarField::rand(&mut rng)).collect();
+
+    points[1] = points[0];
+    scalars[2] = G::ScalarField::zero();
+
+    let pt = points[3];
+    let s = G::ScalarField::rand(&mut rng);
+    points.extend_from_slice(&[pt, -pt, pt, pt]);
+    scalars.extend_from_slice(&[s, s, G::ScalarField::zero(), G::ScalarField::from(1u64)]);
+    (points, scalars)
+}
+
+#[test]
+pub fn test_window_size() {
+    assert_eq!(window_size(1), 3);
+    assert_eq!(window_size(NUM_POINTS), 8);
+    assert_eq!(window_size(1 << 20), MAX_WINDOW_SIZE);
+}
+
+#[serial_test::serial]
+#[tokio::test]
+pub async fn test_secp256k1_msm() {
+    let context = GpuContext::new().await;
+    let (points, scalars) = gen_inputs::<ark_secp256k1::Projective>(2);
+    let expected = ark_secp256k1::Projective::msm(&points, &scalars).unwrap();
+    for log_limb_size in 13..14 {
+        let result = secp256k1_msm(&context, &points, &scalars, log_limb_size)
+            .await
+            .expect("Shader failed");
+        assert_eq!(result.into_affine(), expected.into_affine());
+    }
+}
+
+#[serial_test::serial]
+#[tokio::test]
+pub async fn test_secp256r1_msm() {
+    let context = GpuContext::new().await;
+    let (points, scalars) = gen_inputs::<ark_secp256r1::Projective>(2);
+    let expected = ark_secp256r1::Projective::msm(&points, &scalars).unwrap();
+    for log_limb_size in 13..14 {
+        let result = secp256r1_msm(&context, &points, &scalars, log_limb_size)
+            .await
+            .expect("Shader failed");
+        assert_eq!(result.into_affine(), expected.into_affine());
+    }
+}
+
+#[serial_test::serial]
+#[tokio::test]
+pub async fn test_ed25519_msm() {
+    let context = GpuContext::new().await;
+    let (points, scalars) = gen_inputs::<ark_ed25519::EdwardsProjective>(2);
+    let expected = ark_ed25519::EdwardsProjective::msm(&points, &scalars).unwrap();
+    for log_limb_size in 13..14 {
+        let result = ed25519_msm(&context, &points, &scalars, log_limb_size)
+            .await
+            .expect("Shader failed");
+        assert_eq!(result.into_affine(), expected.into_affine());
+    }
+}
+
+#[serial_test::serial]
+#[tokio::test]
+pub async fn test_secp256k1_msm_of_cancelling_points() {
+    let context = GpuContext::new().await;
+    let mut rng = ChaCha8Rng::seed_from_u64(3);
+    let pt = (ark_secp256k1::Affine::generator() * ark_secp256k1::Fr::rand(&mut rng)).into_affine();
+    let s = ark_secp256k1::Fr::rand(&mut rng);
+    let result = secp256k1_msm(&context, &[pt, -pt], &[s, s], 13).await.expect("Shader failed");
+    assert!(result.is_zero());
+
+    // The second call reuses the pipelines which the first compiled
+    secp256k1_msm(&context, &[pt], &[s], 13).await.expect("Shader failed");
+    assert_eq!(context.num_cached_pipelines(), 1);
+}
@@ -2,6 +2,7 @@ use crate::gpu::{
     create_bind_group, create_command_encoder, create_compute_pipeline, create_empty_sb,
     create_sb_with_data, execute_pipeline, finish_encoder_and_read_from_gpu, get_device_and_queue,
 };
+use crate::canary;
 use crate::shader::render_simple;
 
 #[serial_test::serial]
@@ -64,3 +65,12 @@ pub async fn multi_stage_test() {
 
     assert_eq!(results[0][0], val[0] + 1 + b[0]);
 }
+
+#[test]
+pub fn canary_counts_wrap_around() {
+    // Two dispatches of 2^31 + 2^30 invocations wrap the counter around, as atomicAdd does
+    let num_invocations = canary::num_invocations((49152, 256, 1), 256);
+    assert_eq!(num_invocations, 3 << 30);
+    let canaries = (1u32 << 31).to_le_bytes();
+    assert_eq!(canary::check(&["stage"], [(0, num_invocations), (0, num_invocations)], &canaries), Ok(()));
+}
@@ -0,0 +1,88 @@
+use crate::curves::{Ed25519, Secp256k1, Secp256r1};
+use crate::gpu::GpuContext;
+use crate::ops::{ArkCurve, FieldOps, GroupOps};
+use ark_ec::{CurveGroup, Group};
+use ark_ff::{Field, PrimeField, UniformRand, Zero};
+use num_bigint::BigUint;
+use rand_chacha::rand_core::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+
+// Not a multiple of the workgroup size, so that the batch is padded
+const NUM_ITEMS: usize = 300;
+
+async fn check_field_ops<C: ArkCurve>(context: &GpuContext, log_limb_size: u32) {
+    let mut rng = ChaCha8Rng::seed_from_u64(2);
+    let mut a: Vec<C::Fq> = (0..NUM_ITEMS).map(|_| C::Fq::rand(&mut rng)).collect();
+    let b: Vec<C::Fq> = (0..NUM_ITEMS).map(|_| C::Fq::rand(&mut rng)).collect();
+    a[0] = C::Fq::zero();
+
+    let products = FieldOps::<C>::mul_batch(context, &a, &b, log_limb_size)
+        .await
+        .expect("Shader failed");
+    let expected: Vec<C::Fq> = a.iter().zip(&b).map(|(x, y)| *x * y).collect();
+    assert_eq!(products, expected);
+
+    let inverses = FieldOps::<C>::inverse_batch(context, &a, log_limb_size)
+        .await
+        .expect("Shader failed");
+    let expected: Vec<Option<C::Fq>> = a.iter().map(|x| x.inverse()).collect();
+    assert_eq!(inverses, expected);
+
+    let exponent = BigUint::from_bytes_le(&C::Fq::MODULUS_MINUS_ONE_DIV_TWO.to_bytes_le());
+    let powers = FieldOps::<C>::pow_batch(context, &a, &exponent, log_limb_size)
+        .await
+        .expect("Shader failed");
+    let expected: Vec<C::Fq> =
+        a.iter().map(|x| x.pow(C::Fq::MODULUS_MINUS_ONE_DIV_TWO)).collect();
+    assert_eq!(powers, expected);
+}
+
+async fn check_group_ops<C: ArkCurve>(context: &GpuContext, log_limb_size: u32) {
+    let mut rng = ChaCha8Rng::seed_from_u64(3);
+    let g = C::Group::generator();
+    let mut a: Vec<C::Group> = (0..NUM_ITEMS).map(|_| g * C::Fr::rand(&mut rng)).collect();
+    let mut b: Vec<C::Group> = (0..NUM_ITEMS).map(|_| g * C::Fr::rand(&mut rng)).collect();
+    let mut scalars: Vec<C::Fr> = (0..NUM_ITEMS).map(|_| C::Fr::rand(&mut rng)).collect();
+
+    // The exceptional cases: doubling, cancellation, the identity, and the zero scalar
+    b[0] = a[0];
+    b[1] = -a[1];
+    a[2] = C::Group::zero();
+    scalars[3] = C::Fr::zero();
+
+    let sums = GroupOps::<C>::point_add_batch(context, &a, &b, log_limb_size)
+        .await
+        .expect("Shader failed");
+    let expected: Vec<C::Group> = a.iter().zip(&b).map(|(x, y)| *x + y).collect();
+    assert_eq!(C::Group::normalize_batch(&sums), C::Group::normalize_batch(&expected));
+
+    let products = GroupOps::<C>::scalar_mul_batch(context, &a, &scalars, log_limb_size)
+        .await
+        .expect("Shader failed");
+    let expected: Vec<C::Group> = a.iter().zip(&scalars).map(|(x, s)| *x * s).collect();
+    assert_eq!(C::Group::normalize_batch(&products), C::Group::normalize_batch(&expected));
+}
+
+#[serial_test::serial]
+#[tokio::test]
+pub async fn test_secp256k1_ops() {
+    let context = GpuContext::new().await;
+    check_field_ops::<Secp256k1>(&context, 13).await;
+    check_group_ops::<Secp256k1>(&context, 13).await;
+}
+
+#[serial_test::serial]
+#[tokio::test]
+pub async fn test_secp256r1_ops() {
+    let context = GpuContext::new().await;
+    check_field_ops::<Secp256r1>(&context, 13).await;
+    check_group_ops::<Secp256r1>(&context, 13).await;
+}
+
+#[serial_test::serial]
+#[tokio::test]
+pub async fn test_ed25519_ops() {
+    let context = GpuContext::new().await;
+    check_field_ops::<Ed25519>(&context, 13).await;
+    check_group_ops::<Ed25519>(&context, 13).await;
+}
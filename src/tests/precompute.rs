@@ -0,0 +1,106 @@
+use crate::curves::{Ed25519, Secp256k1, Secp256r1};
+use crate::gpu::GpuContext;
+use crate::precompute::{secp256k1_bases, BaseTable, BaseTableError};
+use crate::secp256k1_ecdsa::ecrecover_with_table;
+use crate::PipelineError;
+use std::path::PathBuf;
+
+fn temp_path(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(name);
+    let _ = std::fs::remove_file(&path);
+    path
+}
+
+#[test]
+pub fn test_base_table_save_and_load() {
+    for log_limb_size in 11..16 {
+        let path = temp_path(&format!("wgpu_sigops_base_table_{}.bin", log_limb_size));
+        let table = BaseTable::generate::<Secp256k1>(log_limb_size);
+        assert_eq!(table.limbs(), secp256k1_bases(log_limb_size).as_slice());
+
+        table.save(&path).unwrap();
+        let loaded = BaseTable::load_for::<Secp256k1>(&path, log_limb_size).unwrap();
+        assert_eq!(loaded, table);
+
+        // The packed limbs take less space than u32s
+        let file_len = std::fs::metadata(&path).unwrap().len() as usize;
+        assert!(file_len < table.limbs().len() * 4);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
+
+#[test]
+pub fn test_base_table_refuses_other_params() {
+    let path = temp_path("wgpu_sigops_base_table_params.bin");
+    BaseTable::generate::<Secp256r1>(13).save(&path).unwrap();
+
+    assert!(matches!(
+        BaseTable::load_for::<Secp256r1>(&path, 14),
+        Err(BaseTableError::ParamsMismatch { .. })
+    ));
+    assert!(matches!(
+        BaseTable::load_for::<Ed25519>(&path, 13),
+        Err(BaseTableError::ParamsMismatch { .. })
+    ));
+    // Existing files for other parameters are not overwritten
+    assert!(BaseTable::load_or_generate::<Secp256r1>(&path, 14).is_err());
+    assert!(BaseTable::load_for::<Secp256r1>(&path, 13).is_ok());
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+pub fn test_base_table_detects_corruption() {
+    let path = temp_path("wgpu_sigops_base_table_corrupt.bin");
+    BaseTable::generate::<Secp256k1>(13).save(&path).unwrap();
+
+    let mut bytes = std::fs::read(&path).unwrap();
+    let mid = bytes.len() / 2;
+    bytes[mid] ^= 1;
+    std::fs::write(&path, &bytes).unwrap();
+    assert!(matches!(
+        BaseTable::load(&path),
+        Err(BaseTableError::ChecksumMismatch)
+    ));
+
+    std::fs::write(&path, &bytes[..mid]).unwrap();
+    assert!(matches!(
+        BaseTable::load(&path),
+        Err(BaseTableError::InvalidFormat)
+    ));
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+pub fn test_base_table_load_or_generate() {
+    let path = temp_path("wgpu_sigops_base_table_generate.bin");
+    let generated = BaseTable::load_or_generate::<Ed25519>(&path, 13).unwrap();
+    let loaded = BaseTable::load_or_generate::<Ed25519>(&path, 13).unwrap();
+    assert_eq!(generated, loaded);
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[serial_test::serial]
+#[tokio::test]
+pub async fn test_ecrecover_with_table() {
+    let context = GpuContext::new().await;
+    let (signatures, messages, expected_pks) = crate::benchmarks::secp256k1_ecdsa::gen_test_data(10);
+    let table = BaseTable::generate::<Secp256k1>(13);
+
+    let recovered = ecrecover_with_table(&context, &signatures, &messages, &table, 13)
+        .await
+        .expect("Shader failed");
+    for (pk, expected) in recovered.iter().zip(&expected_pks) {
+        assert_eq!(pk, &Some(*expected));
+    }
+
+    // A table for another limb size or curve is refused before anything runs
+    let result = ecrecover_with_table(&context, &signatures, &messages, &table, 14).await;
+    assert!(matches!(result, Err(PipelineError::Table(BaseTableError::ParamsMismatch { .. }))));
+    let table = BaseTable::generate::<Ed25519>(13);
+    let result = ecrecover_with_table(&context, &signatures, &messages, &table, 13).await;
+    assert!(matches!(result, Err(PipelineError::Table(BaseTableError::ParamsMismatch { .. }))));
+}
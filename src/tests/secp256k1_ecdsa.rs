@@ -1,10 +1,27 @@
-use crate::secp256k1_ecdsa::{ecrecover, ecrecover_single_shader};
+use crate::all_valid::{AllValidError, Bitmap};
+use crate::checkpoint::Checkpointing;
+use crate::compaction::Compaction;
+use crate::coop::{Cooperation, CooperationError};
+use crate::curves::Ed25519;
+use crate::gpu::{create_command_encoder, create_sb_with_data, write_sb_with, GpuContext};
+use crate::secp256k1_ecdsa::{
+    bytes_to_public_keys, clear_outputs, create_pipelines, dispatches, ecrecover, ecrecover_bytes,
+    ecrecover_compressed, ecrecover_encoded, ecrecover_single_shader, ecrecover_with_context,
+    ecrecover_with_options, ecrecover_with_timings, ecverify_compressed,
+    ecverify_compressed_all_valid, pack_messages, pack_signatures, Buffers, RecoveryOptions,
+};
+use crate::sec1::FinalStage;
+use crate::shader::{Coordinates, Reduction};
+use crate::signature_encoding::{compact_from_parts, SignatureEncoding};
+use ark_ec::{AffineRepr, CurveGroup};
+use ark_ff::{BigInteger, PrimeField};
 use fuel_crypto::{Message, SecretKey, Signature, PublicKey};
 use num_bigint::{BigUint, RandomBits};
 use rand::Rng;
 use rand_chacha::rand_core::SeedableRng;
 use rand_chacha::ChaCha8Rng;
-use crate::precompute::secp256k1_bases;
+use crate::precompute::{secp256k1_bases, secp256k1_bases_with_reduction};
+use crate::PipelineError;
 
 const NUM_RUNS_PER_TEST: usize = 10;
 
@@ -87,15 +104,818 @@ pub async fn do_secp256k1_test(
     signature: &Signature,
     message: &Message,
     verifying_key: &PublicKey,
-    table_limbs: &Vec<u32>,
+    table_limbs: &[u32],
     log_limb_size: u32,
     invoke_single: bool,
 ) {
-    let pk_affine_bytes = verifying_key.as_slice();
     let result = if invoke_single {
-        ecrecover_single_shader(&vec![*signature], &vec![*message], log_limb_size).await
+        ecrecover_single_shader(&[*signature], &[*message], log_limb_size).await
     } else {
-        ecrecover(&vec![*signature], &vec![*message], table_limbs, log_limb_size).await
+        ecrecover(&[*signature], &[*message], table_limbs, log_limb_size).await
     }.expect("Shader failed");
-    assert_eq!(result[0], pk_affine_bytes);
+    assert_eq!(result[0], Some(*verifying_key));
+}
+
+#[serial_test::serial]
+#[tokio::test]
+pub async fn test_secp256k1_ecrecover_bytes() {
+    let mut rng = ChaCha8Rng::seed_from_u64(4);
+    let scalar_p = crate::moduli::secp256k1_fr_modulus_biguint();
+    let log_limb_size = 13;
+    let table_limbs = secp256k1_bases(log_limb_size);
+
+    let mut sig_bytes = Vec::with_capacity(NUM_RUNS_PER_TEST * 64);
+    let mut msg_bytes = Vec::with_capacity(NUM_RUNS_PER_TEST * 32);
+    let mut expected_pk_bytes = Vec::with_capacity(NUM_RUNS_PER_TEST * 64);
+    for _ in 0..NUM_RUNS_PER_TEST {
+        let msg: BigUint = rng.sample::<BigUint, RandomBits>(RandomBits::new(256)) % &scalar_p;
+        let message = Message::new(hex::encode(msg.to_bytes_be()));
+        let secret = SecretKey::random(&mut rng);
+        let signature = Signature::sign(&secret, &message);
+
+        sig_bytes.extend_from_slice(signature.as_slice());
+        msg_bytes.extend_from_slice(message.as_slice());
+        expected_pk_bytes.extend_from_slice(secret.public_key().as_slice());
+    }
+
+    let result = ecrecover_bytes(&sig_bytes, &msg_bytes, &table_limbs, log_limb_size)
+        .await
+        .expect("Shader failed");
+    assert_eq!(result, expected_pk_bytes);
+}
+
+#[serial_test::serial]
+#[tokio::test]
+pub async fn test_secp256k1_ecrecover_reuses_buffers() {
+    let log_limb_size = 13;
+    let table_limbs = secp256k1_bases(log_limb_size);
+    let (signatures, messages, expected_pks) = crate::benchmarks::secp256k1_ecdsa::gen_test_data(300);
+    let context = GpuContext::new().await;
+
+    // Batches of 300 and 260 signatures both pad to 512, so they share every bucket
+    for num_signatures in [300, 260] {
+        let misses = context.pool.misses();
+        let result = ecrecover_with_context(&context, &signatures[..num_signatures], &messages[..num_signatures], &table_limbs, log_limb_size)
+            .await
+            .expect("Shader failed");
+
+        for i in 0..num_signatures {
+            assert_eq!(result[i], Some(expected_pks[i]));
+        }
+
+        if num_signatures == 260 {
+            assert_eq!(context.pool.misses(), misses);
+        }
+    }
+    assert!(context.pool.hits() > 0);
+    // The pipelines are compiled and the table is uploaded only once
+    assert_eq!(context.num_cached_pipelines(), 1);
+    assert_eq!(context.num_cached_tables(), 1);
+
+    // Trimming frees every pooled buffer, so the next batch allocates again
+    assert!(context.pool.free_bytes() > 0);
+    assert!(context.pool.free_bytes() <= context.pool.max_free_bytes());
+    context.pool.trim();
+    assert_eq!(context.pool.free_bytes(), 0);
+    let misses = context.pool.misses();
+    ecrecover_with_context(&context, &signatures[..260], &messages[..260], &table_limbs, log_limb_size)
+        .await
+        .expect("Shader failed");
+    assert!(context.pool.misses() > misses);
+}
+
+#[serial_test::serial]
+#[tokio::test]
+pub async fn test_secp256k1_ecrecover_with_timings() {
+    let log_limb_size = 13;
+    let table_limbs = secp256k1_bases(log_limb_size);
+    let (signatures, messages, expected_pks) = crate::benchmarks::secp256k1_ecdsa::gen_test_data(NUM_RUNS_PER_TEST);
+    let context = GpuContext::new().await;
+
+    let (result, timings) = ecrecover_with_timings(&context, &signatures, &messages, &table_limbs, log_limb_size)
+        .await
+        .expect("Shader failed");
+
+    for i in 0..NUM_RUNS_PER_TEST {
+        assert_eq!(result[i], Some(expected_pks[i]));
+    }
+    assert_eq!(timings.stages.len(), 5);
+    assert_eq!(timings.stages[1].0, "fixed-base mul u1 * G");
+    assert!(timings.total() >= timings.upload + timings.readback);
+}
+
+#[serial_test::serial]
+#[tokio::test]
+pub async fn test_secp256k1_ecrecover_compressed() {
+    let log_limb_size = 13;
+    let table_limbs = secp256k1_bases(log_limb_size);
+    let (signatures, messages, expected_pks) = crate::benchmarks::secp256k1_ecdsa::gen_test_data(NUM_RUNS_PER_TEST);
+
+    let result = ecrecover_compressed(&signatures, &messages, &table_limbs, log_limb_size)
+        .await
+        .expect("Shader failed");
+
+    for i in 0..NUM_RUNS_PER_TEST {
+        let mut sec1_bytes = [0u8; 65];
+        sec1_bytes[0] = 0x04;
+        sec1_bytes[1..].copy_from_slice(expected_pks[i].as_slice());
+        let expected = secp256k1::PublicKey::from_slice(&sec1_bytes).unwrap().serialize();
+        assert_eq!(result[i], Some(expected));
+    }
+}
+
+#[serial_test::serial]
+#[tokio::test]
+pub async fn test_secp256k1_ecverify_compressed() {
+    let log_limb_size = 13;
+    let table_limbs = secp256k1_bases(log_limb_size);
+    let (signatures, messages, _) = crate::benchmarks::secp256k1_ecdsa::gen_test_data(NUM_RUNS_PER_TEST);
+    let mut public_keys: Vec<[u8; 33]> = ecrecover_compressed(&signatures, &messages, &table_limbs, log_limb_size)
+        .await
+        .expect("Shader failed")
+        .into_iter()
+        .map(|pk| pk.unwrap())
+        .collect();
+
+    // Another signer's key, the wrong parity, an invalid prefix, and an uncompressed prefix
+    public_keys[0] = public_keys[1];
+    public_keys[2][0] ^= 1;
+    public_keys[3][0] = 0x05;
+    public_keys[4][0] = 0x04;
+
+    let result = ecverify_compressed(&signatures, &messages, &public_keys, &table_limbs, log_limb_size)
+        .await
+        .expect("Shader failed");
+
+    for (i, verdict) in result.iter().enumerate() {
+        assert_eq!(verdict.is_valid(), i > 4);
+    }
+}
+
+#[serial_test::serial]
+#[tokio::test]
+pub async fn test_secp256k1_ecverify_compressed_all_valid() {
+    let log_limb_size = 13;
+    let table_limbs = secp256k1_bases(log_limb_size);
+    let context = GpuContext::new().await;
+    // More than one word of the bitmap, and not a power of 2
+    let num_signatures = NUM_RUNS_PER_TEST * 4;
+    let (signatures, messages, _) = crate::benchmarks::secp256k1_ecdsa::gen_test_data(num_signatures);
+    let mut public_keys: Vec<[u8; 33]> = ecrecover_compressed(&signatures, &messages, &table_limbs, log_limb_size)
+        .await
+        .expect("Shader failed")
+        .into_iter()
+        .map(|pk| pk.unwrap())
+        .collect();
+
+    let result = ecverify_compressed_all_valid(
+        &context,
+        &signatures,
+        &messages,
+        &public_keys,
+        &table_limbs,
+        log_limb_size,
+        Bitmap::Off,
+    )
+    .await
+    .expect("Shader failed");
+    assert!(result.is_all_valid());
+    assert_eq!(result.bitmap, None);
+
+    // Another signer's key in each word of the bitmap
+    public_keys[35] = public_keys[0];
+    public_keys[7] = public_keys[0];
+
+    for bitmap in [Bitmap::Off, Bitmap::Packed] {
+        let result = ecverify_compressed_all_valid(
+            &context,
+            &signatures,
+            &messages,
+            &public_keys,
+            &table_limbs,
+            log_limb_size,
+            bitmap,
+        )
+        .await
+        .expect("Shader failed");
+        assert!(!result.is_all_valid());
+        assert_eq!(result.first_failure, Some(7));
+        if bitmap.is_on() {
+            for i in 0..num_signatures {
+                assert_eq!(result.verdict(i).unwrap().is_valid(), i != 7 && i != 35);
+            }
+        }
+    }
+
+    // Only verdicts can be summarised, so recovering keys with an all-valid check is rejected
+    let options = RecoveryOptions {
+        final_stage: FinalStage::Compressed,
+        all_valid: Some(Bitmap::Off),
+        ..Default::default()
+    };
+    let result = ecrecover_with_options(
+        &context,
+        &signatures,
+        &messages,
+        &[],
+        &table_limbs,
+        log_limb_size,
+        &options,
+    )
+    .await;
+    assert!(matches!(
+        result,
+        Err(PipelineError::AllValid(AllValidError { final_stage: FinalStage::Compressed }))
+    ));
+    // A set of recovery pipelines per bitmap option, and the reduction stage only once
+    assert_eq!(context.num_cached_pipelines(), 3);
+}
+
+#[serial_test::serial]
+#[tokio::test]
+pub async fn test_secp256k1_ecrecover_encoded() {
+    let mut rng = ChaCha8Rng::seed_from_u64(5);
+    let log_limb_size = 13;
+    let table_limbs = secp256k1_bases(log_limb_size);
+    let secp = secp256k1::Secp256k1::new();
+    let n = crate::moduli::secp256k1_fr_modulus_biguint();
+
+    let mut messages = vec![];
+    let mut rsv_sigs = vec![];
+    let mut eip155_sigs = vec![];
+    let mut high_s_sigs = vec![];
+    let mut der_sigs = vec![];
+    let mut expected_pks = vec![];
+    for _ in 0..NUM_RUNS_PER_TEST {
+        let message = Message::new([rng.gen::<u8>(); 100]);
+        let msg = secp256k1::Message::from_slice(message.as_slice()).unwrap();
+        let secret = secp256k1::SecretKey::new(&mut rng);
+        let signature = secp.sign_ecdsa_recoverable(&msg, &secret);
+        let (recovery_id, rs) = signature.serialize_compact();
+        let recovery_id = recovery_id.to_i32() as u8;
+
+        let mut rsv = rs.to_vec();
+        rsv.push(27 + recovery_id);
+        rsv_sigs.push(rsv);
+
+        // EIP-155 with chain id 0x10000
+        let mut eip155 = rs.to_vec();
+        eip155.extend_from_slice(&(35 + 2 * 0x10000 + recovery_id as u32).to_be_bytes());
+        eip155_sigs.push(eip155);
+
+        // (r, n - s) is a valid signature for -R
+        let high_s = &n - BigUint::from_bytes_be(&rs[32..]);
+        let mut high_s_sig = rs[..32].to_vec();
+        high_s_sig.extend_from_slice(&high_s.to_bytes_be());
+        high_s_sig.push(recovery_id ^ 1);
+        high_s_sigs.push(high_s_sig);
+
+        let mut der = signature.to_standard().serialize_der().to_vec();
+        der.push(recovery_id);
+        der_sigs.push(der);
+
+        let recovered = secp.recover_ecdsa(&msg, &signature).unwrap();
+        expected_pks.push(PublicKey::try_from(&recovered.serialize_uncompressed()[1..]).unwrap());
+        messages.push(message);
+    }
+
+    for (encoding, signatures) in [
+        (SignatureEncoding::Rsv, &rsv_sigs),
+        (SignatureEncoding::Rsv, &eip155_sigs),
+        (SignatureEncoding::Rsv, &high_s_sigs),
+        (SignatureEncoding::DerWithRecoveryId, &der_sigs),
+    ] {
+        let result =
+            ecrecover_encoded(encoding, signatures, &messages, &table_limbs, log_limb_size)
+                .await
+                .expect("Shader failed");
+        for i in 0..NUM_RUNS_PER_TEST {
+            assert_eq!(result[i], Some(expected_pks[i]));
+        }
+    }
+
+    // A malformed DER signature maps to None
+    let mut der_sigs = der_sigs;
+    der_sigs[0][0] = 0x31;
+    let result = ecrecover_encoded(
+        SignatureEncoding::DerWithRecoveryId,
+        &der_sigs,
+        &messages,
+        &table_limbs,
+        log_limb_size,
+    )
+    .await
+    .expect("Shader failed");
+    assert_eq!(result[0], None);
+    assert_eq!(result[1], Some(expected_pks[1]));
+}
+
+/// Builds a compact signature over the 32-byte digest `z` whose nonce is `k`, for a given low s.
+/// Recovery computes u1 * G + u2 * R with u1 = -z / r and u2 = s / r, so the choice of k and z
+/// controls how u1 * G and u2 * R relate.
+fn signature_with_nonce(k: &BigUint, z: &BigUint, s: &BigUint) -> (Signature, Message) {
+    let n = crate::moduli::secp256k1_fr_modulus_biguint();
+    let k = ark_secp256k1::Fr::from_be_bytes_mod_order(&k.to_bytes_be());
+    let big_r = (ark_secp256k1::Affine::generator() * k).into_affine();
+    let r = BigUint::from_bytes_be(&big_r.x.into_bigint().to_bytes_be()) % &n;
+    let is_y_odd = big_r.y.into_bigint().is_odd() as u64;
+
+    let compact = compact_from_parts(&r, s, is_y_odd, &n).unwrap();
+    let mut z_bytes = [0u8; 32];
+    let z_be = z.to_bytes_be();
+    z_bytes[32 - z_be.len()..].copy_from_slice(&z_be);
+    (Signature::from_bytes(compact), Message::from_bytes(z_bytes))
+}
+
+/// Generates signatures for which the sum in recovery is exceptional, in runs of three: u1 * G is
+/// the point at infinity, u1 * G == u2 * R, and u1 * G == -u2 * R.
+fn gen_exceptional_signatures(rng: &mut ChaCha8Rng) -> (Vec<Signature>, Vec<Message>) {
+    let n = crate::moduli::secp256k1_fr_modulus_biguint();
+    let half_n = &n >> 1;
+
+    // A random scalar in [1, bound]
+    let mut random_scalar = |bound: &BigUint| -> BigUint {
+        rng.sample::<BigUint, RandomBits>(RandomBits::new(256)) % bound + 1u32
+    };
+
+    let mut signatures = vec![];
+    let mut messages = vec![];
+    for _ in 0..NUM_RUNS_PER_TEST {
+        let s = random_scalar(&half_n);
+        let z = random_scalar(&(&n - 1u32));
+        let s_inv = s.modpow(&(&n - 2u32), &n);
+
+        // z == 0, so u1 * G is the point at infinity
+        let k = random_scalar(&(&n - 1u32));
+        let (signature, message) = signature_with_nonce(&k, &BigUint::from(0u32), &s);
+        signatures.push(signature);
+        messages.push(message);
+
+        // k == -z / s, so u1 * G == u2 * R and the sum is a doubling
+        let k = (&n - &z) * &s_inv % &n;
+        let (signature, message) = signature_with_nonce(&k, &z, &s);
+        signatures.push(signature);
+        messages.push(message);
+
+        // k == z / s, so u1 * G == -u2 * R and the sum is the point at infinity
+        let k = &z * &s_inv % &n;
+        let (signature, message) = signature_with_nonce(&k, &z, &s);
+        signatures.push(signature);
+        messages.push(message);
+    }
+    (signatures, messages)
+}
+
+#[serial_test::serial]
+#[tokio::test]
+pub async fn test_secp256k1_ecrecover_exceptional_cases() {
+    let mut rng = ChaCha8Rng::seed_from_u64(7);
+    let log_limb_size = 13;
+    let table_limbs = secp256k1_bases(log_limb_size);
+    let (signatures, messages) = gen_exceptional_signatures(&mut rng);
+
+    let expected: Vec<Option<PublicKey>> = signatures
+        .iter()
+        .zip(&messages)
+        .map(|(signature, message)| signature.recover(message).ok())
+        .collect();
+    for (i, pk) in expected.iter().enumerate() {
+        assert_eq!(pk.is_some(), i % 3 != 2);
+    }
+
+    let result = ecrecover(&signatures, &messages, &table_limbs, log_limb_size)
+        .await
+        .expect("Shader failed");
+    assert_eq!(result, expected);
+
+    let result = ecrecover_single_shader(&signatures, &messages, log_limb_size)
+        .await
+        .expect("Shader failed");
+    assert_eq!(result, expected);
+}
+
+#[serial_test::serial]
+#[tokio::test]
+pub async fn test_secp256k1_ecrecover_jacobian() {
+    let mut rng = ChaCha8Rng::seed_from_u64(8);
+    let log_limb_size = 13;
+    let table_limbs = secp256k1_bases(log_limb_size);
+    let context = GpuContext::new().await;
+
+    let (mut signatures, mut messages) = gen_exceptional_signatures(&mut rng);
+    for _ in 0..NUM_RUNS_PER_TEST {
+        let message = Message::new([rng.gen::<u8>(); 100]);
+        let secret = SecretKey::random(&mut rng);
+        signatures.push(Signature::sign(&secret, &message));
+        messages.push(message);
+    }
+
+    let expected: Vec<Option<PublicKey>> = signatures
+        .iter()
+        .zip(&messages)
+        .map(|(signature, message)| signature.recover(message).ok())
+        .collect();
+
+    // Both coordinate systems read the same table
+    for coordinates in [Coordinates::Projective, Coordinates::Jacobian] {
+        let options = RecoveryOptions { coordinates, ..Default::default() };
+        let result = ecrecover_with_options(
+            &context,
+            &signatures,
+            &messages,
+            &[],
+            &table_limbs,
+            log_limb_size,
+            &options,
+        )
+        .await
+        .expect("Shader failed");
+        assert_eq!(bytes_to_public_keys(&result), expected);
+    }
+}
+
+#[serial_test::serial]
+#[tokio::test]
+pub async fn test_secp256k1_ecrecover_pseudo_mersenne() {
+    let mut rng = ChaCha8Rng::seed_from_u64(11);
+    let context = GpuContext::new().await;
+
+    let (mut signatures, mut messages) = gen_exceptional_signatures(&mut rng);
+    for _ in 0..NUM_RUNS_PER_TEST {
+        let message = Message::new([rng.gen::<u8>(); 100]);
+        let secret = SecretKey::random(&mut rng);
+        signatures.push(Signature::sign(&secret, &message));
+        messages.push(message);
+    }
+
+    let expected: Vec<Option<PublicKey>> = signatures
+        .iter()
+        .zip(&messages)
+        .map(|(signature, message)| signature.recover(message).ok())
+        .collect();
+
+    for log_limb_size in 12..15 {
+        let table_limbs = secp256k1_bases_with_reduction(log_limb_size, Reduction::PseudoMersenne);
+        for coordinates in [Coordinates::Projective, Coordinates::Jacobian] {
+            let options = RecoveryOptions {
+                coordinates,
+                reduction: Reduction::PseudoMersenne,
+                ..Default::default()
+            };
+            let result = ecrecover_with_options(
+                &context,
+                &signatures,
+                &messages,
+                &[],
+                &table_limbs,
+                log_limb_size,
+                &options,
+            )
+            .await
+            .expect("Shader failed");
+            assert_eq!(bytes_to_public_keys(&result), expected);
+        }
+    }
+
+    let log_limb_size = 13;
+    let table_limbs = secp256k1_bases_with_reduction(log_limb_size, Reduction::PseudoMersenne);
+
+    // The reduction combines with checkpointing and compaction
+    let options = RecoveryOptions {
+        reduction: Reduction::PseudoMersenne,
+        checkpointing: Checkpointing::BitsPerDispatch(7),
+        compaction: Compaction::Indirect,
+        ..Default::default()
+    };
+    let result = ecrecover_with_options(
+        &context,
+        &signatures,
+        &messages,
+        &[],
+        &table_limbs,
+        log_limb_size,
+        &options,
+    )
+    .await
+    .expect("Shader failed");
+    assert_eq!(bytes_to_public_keys(&result), expected);
+
+    // The cooperative kernel only implements Montgomery multiplication
+    let options = RecoveryOptions {
+        reduction: Reduction::PseudoMersenne,
+        cooperation: Cooperation::Lanes(4),
+        ..Default::default()
+    };
+    let result = ecrecover_with_options(
+        &context,
+        &signatures,
+        &messages,
+        &[],
+        &table_limbs,
+        log_limb_size,
+        &options,
+    )
+    .await;
+    assert!(matches!(
+        result,
+        Err(PipelineError::Cooperation(CooperationError::UnsupportedReduction(Reduction::PseudoMersenne)))
+    ));
+}
+
+#[serial_test::serial]
+#[tokio::test]
+pub async fn test_secp256k1_ecrecover_cooperative() {
+    let mut rng = ChaCha8Rng::seed_from_u64(9);
+    let log_limb_size = 13;
+    let table_limbs = secp256k1_bases(log_limb_size);
+    let context = GpuContext::new().await;
+
+    let (mut signatures, mut messages) = gen_exceptional_signatures(&mut rng);
+    for _ in 0..NUM_RUNS_PER_TEST {
+        let message = Message::new([rng.gen::<u8>(); 100]);
+        let secret = SecretKey::random(&mut rng);
+        signatures.push(Signature::sign(&secret, &message));
+        messages.push(message);
+    }
+
+    let expected: Vec<Option<PublicKey>> = signatures
+        .iter()
+        .zip(&messages)
+        .map(|(signature, message)| signature.recover(message).ok())
+        .collect();
+
+    for coordinates in [Coordinates::Projective, Coordinates::Jacobian] {
+        for lanes in [4, 8] {
+            let options = RecoveryOptions {
+                coordinates,
+                cooperation: Cooperation::Lanes(lanes),
+                ..Default::default()
+            };
+            let result = ecrecover_with_options(
+                &context,
+                &signatures,
+                &messages,
+                &[],
+                &table_limbs,
+                log_limb_size,
+                &options,
+            )
+            .await
+            .expect("Shader failed");
+            assert_eq!(bytes_to_public_keys(&result), expected);
+        }
+    }
+
+    // Options which the kernel cannot run are rejected before anything is compiled
+    for (lanes, log_limb_size, expected_error) in [
+        (4, 14, CooperationError::LimbsTooLarge { log_limb_size: 14 }),
+        (3, 13, CooperationError::InvalidLanes(3)),
+        (128, 13, CooperationError::InvalidLanes(128)),
+    ] {
+        let options = RecoveryOptions {
+            cooperation: Cooperation::Lanes(lanes),
+            ..Default::default()
+        };
+        let result = ecrecover_with_options(
+            &context,
+            &signatures,
+            &messages,
+            &[],
+            &table_limbs,
+            log_limb_size,
+            &options,
+        )
+        .await;
+        match result {
+            Err(PipelineError::Cooperation(e)) => assert_eq!(e, expected_error),
+            _ => panic!("expected {:?}", expected_error),
+        }
+    }
+    assert_eq!(
+        Cooperation::Lanes(4).check::<Ed25519>(13),
+        Err(CooperationError::Unsupported { curve: "ed25519" })
+    );
+}
+
+#[serial_test::serial]
+#[tokio::test]
+pub async fn test_secp256k1_ecrecover_checkpointed() {
+    let mut rng = ChaCha8Rng::seed_from_u64(10);
+    let log_limb_size = 13;
+    let table_limbs = secp256k1_bases(log_limb_size);
+    let context = GpuContext::new().await;
+
+    let (mut signatures, mut messages) = gen_exceptional_signatures(&mut rng);
+    for _ in 0..NUM_RUNS_PER_TEST {
+        let message = Message::new([rng.gen::<u8>(); 100]);
+        let secret = SecretKey::random(&mut rng);
+        signatures.push(Signature::sign(&secret, &message));
+        messages.push(message);
+    }
+
+    let expected: Vec<Option<PublicKey>> = signatures
+        .iter()
+        .zip(&messages)
+        .map(|(signature, message)| signature.recover(message).ok())
+        .collect();
+
+    // 7 does not divide 256 or the table window size, and 256 is a single dispatch
+    for coordinates in [Coordinates::Projective, Coordinates::Jacobian] {
+        let unsplit = RecoveryOptions { coordinates, ..Default::default() };
+        let unsplit_result = ecrecover_with_options(
+            &context,
+            &signatures,
+            &messages,
+            &[],
+            &table_limbs,
+            log_limb_size,
+            &unsplit,
+        )
+        .await
+        .expect("Shader failed");
+
+        for bits in [7, 64, 256] {
+            let options = RecoveryOptions {
+                checkpointing: Checkpointing::BitsPerDispatch(bits),
+                ..unsplit
+            };
+            let result = ecrecover_with_options(
+                &context,
+                &signatures,
+                &messages,
+                &[],
+                &table_limbs,
+                log_limb_size,
+                &options,
+            )
+            .await
+            .expect("Shader failed");
+            // The sub-dispatches perform the same operations, so the output is bit-identical
+            assert_eq!(result, unsplit_result);
+            assert_eq!(bytes_to_public_keys(&result), expected);
+        }
+
+        // Checkpointing combines with the cooperative kernel and with compaction
+        for (cooperation, compaction) in [
+            (Cooperation::Lanes(4), Compaction::Off),
+            (Cooperation::PerInvocation, Compaction::Indirect),
+            (Cooperation::Lanes(4), Compaction::Indirect),
+        ] {
+            let options = RecoveryOptions {
+                cooperation,
+                checkpointing: Checkpointing::BitsPerDispatch(7),
+                compaction,
+                ..unsplit
+            };
+            let result = ecrecover_with_options(
+                &context,
+                &signatures,
+                &messages,
+                &[],
+                &table_limbs,
+                log_limb_size,
+                &options,
+            )
+            .await
+            .expect("Shader failed");
+            assert_eq!(bytes_to_public_keys(&result), expected);
+        }
+    }
+}
+
+/// Generates signatures whose r is not the x-coordinate of any point on the curve, so that
+/// recovery fails in stage 0.
+fn gen_off_curve_signatures(rng: &mut ChaCha8Rng) -> (Vec<Signature>, Vec<Message>) {
+    let n = crate::moduli::secp256k1_fr_modulus_biguint();
+    let half_n = &n >> 1;
+
+    let mut signatures = vec![];
+    let mut messages = vec![];
+    while signatures.len() < NUM_RUNS_PER_TEST {
+        let r = rng.sample::<BigUint, RandomBits>(RandomBits::new(256)) % &n;
+        let x = ark_secp256k1::Fq::from_be_bytes_mod_order(&r.to_bytes_be());
+        if r == BigUint::from(0u32) || ark_secp256k1::Affine::get_point_from_x_unchecked(x, false).is_some() {
+            continue;
+        }
+        let s = rng.sample::<BigUint, RandomBits>(RandomBits::new(256)) % &half_n + 1u32;
+        let compact = compact_from_parts(&r, &s, 0, &n).unwrap();
+        signatures.push(Signature::from_bytes(compact));
+        messages.push(Message::new([rng.gen::<u8>(); 100]));
+    }
+    (signatures, messages)
+}
+
+#[serial_test::serial]
+#[tokio::test]
+pub async fn test_secp256k1_ecrecover_compacted() {
+    let mut rng = ChaCha8Rng::seed_from_u64(11);
+    let log_limb_size = 13;
+    let table_limbs = secp256k1_bases(log_limb_size);
+    let context = GpuContext::new().await;
+
+    let (off_curve_signatures, off_curve_messages) = gen_off_curve_signatures(&mut rng);
+    let (mut signatures, mut messages) = gen_exceptional_signatures(&mut rng);
+    for _ in 0..NUM_RUNS_PER_TEST {
+        let message = Message::new([rng.gen::<u8>(); 100]);
+        let secret = SecretKey::random(&mut rng);
+        signatures.push(Signature::sign(&secret, &message));
+        messages.push(message);
+    }
+
+    // Interleave the signatures which do not survive stage 0 with those which do
+    for (i, (signature, message)) in off_curve_signatures.iter().zip(&off_curve_messages).enumerate() {
+        signatures.insert(i * 3, *signature);
+        messages.insert(i * 3, *message);
+    }
+
+    let expected: Vec<Option<PublicKey>> = signatures
+        .iter()
+        .zip(&messages)
+        .map(|(signature, message)| signature.recover(message).ok())
+        .collect();
+    assert!(expected[..off_curve_signatures.len() * 3].iter().step_by(3).all(Option::is_none));
+
+    for coordinates in [Coordinates::Projective, Coordinates::Jacobian] {
+        let options = RecoveryOptions {
+            coordinates,
+            compaction: Compaction::Indirect,
+            ..Default::default()
+        };
+        let result = ecrecover_with_options(
+            &context,
+            &signatures,
+            &messages,
+            &[],
+            &table_limbs,
+            log_limb_size,
+            &options,
+        )
+        .await
+        .expect("Shader failed");
+        assert_eq!(bytes_to_public_keys(&result), expected);
+
+        // No signature survives, but the last stage still runs
+        let result = ecrecover_with_options(
+            &context,
+            &off_curve_signatures,
+            &off_curve_messages,
+            &[],
+            &table_limbs,
+            log_limb_size,
+            &options,
+        )
+        .await
+        .expect("Shader failed");
+        assert_eq!(bytes_to_public_keys(&result), vec![None; off_curve_signatures.len()]);
+    }
+}
+
+#[serial_test::serial]
+#[tokio::test]
+pub async fn test_secp256k1_ecrecover_skipped_stage() {
+    let log_limb_size = 13;
+    let table_limbs = secp256k1_bases(log_limb_size);
+    let (signatures, messages, _) = crate::benchmarks::secp256k1_ecdsa::gen_test_data(300);
+    let context = GpuContext::new().await;
+    let device = &context.device;
+
+    let next_pow_2 = signatures.len().next_power_of_two();
+    let options = RecoveryOptions::default();
+    let pipelines = create_pipelines(device, log_limb_size, &options);
+    let table_buf = create_sb_with_data(device, &table_limbs);
+    let sig_buf = context.pool.acquire_sb(device, (64 * next_pow_2) as u64);
+    write_sb_with(&context.queue, &sig_buf, |buf| pack_signatures(buf, &signatures));
+    let msg_buf = context.pool.acquire_sb(device, (32 * next_pow_2) as u64);
+    write_sb_with(&context.queue, &msg_buf, |buf| pack_messages(buf, &messages));
+    let buffers = Buffers::new(
+        &context,
+        &pipelines,
+        &table_buf,
+        sig_buf,
+        msg_buf,
+        None,
+        next_pow_2,
+        log_limb_size,
+        &options,
+    );
+
+    // Leave out stage 2, as a driver which silently drops a dispatch would. Its output stays
+    // zeroed, and so do the public keys
+    let mut command_encoder = create_command_encoder(device);
+    clear_outputs(&mut command_encoder, &buffers);
+    for dispatch in dispatches(&pipelines, &buffers) {
+        if dispatch.label != "variable-base mul u2 * R" {
+            dispatch.execute(&mut command_encoder);
+        }
+    }
+    let results = context
+        .finish_encoder_and_read_bytes(command_encoder, &[&buffers.canaries_buf])
+        .await;
+
+    let failure = buffers.check_canaries(&results[0], None).unwrap_err().stage.unwrap();
+    assert_eq!(failure.stage, 2);
+    assert_eq!(failure.completed, 0);
+    assert_eq!(failure.expected, next_pow_2 as u32);
+
+    buffers.release(&context.pool);
 }
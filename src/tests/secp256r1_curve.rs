@@ -5,7 +5,8 @@ use crate::gpu::{
     create_bind_group, create_command_encoder, create_compute_pipeline, create_empty_sb,
     create_sb_with_data, execute_pipeline, finish_encoder_and_read_from_gpu, get_device_and_queue,
 };
-use crate::shader::render_secp256r1_curve_tests;
+use crate::curves::Secp256r1;
+use crate::shader::{render_test, Reduction};
 use crate::tests::{fq_to_biguint, projectivexyz_to_mont_limbs};
 use ark_ec::{AffineRepr, CurveGroup};
 use ark_ff::{BigInteger, One, PrimeField};
@@ -213,8 +214,8 @@ pub async fn do_add_test(
     let res = mont::calc_rinv_and_n0(&p, &r, log_limb_size);
     let rinv = res.0;
 
-    let pt_a_limbs = projectivexyz_to_mont_limbs::<Fq>(&a, &p, log_limb_size);
-    let pt_b_limbs = projectivexyz_to_mont_limbs::<Fq>(&b, &p, log_limb_size);
+    let pt_a_limbs = projectivexyz_to_mont_limbs::<Fq>(&a, &p, log_limb_size, Reduction::Montgomery);
+    let pt_b_limbs = projectivexyz_to_mont_limbs::<Fq>(&b, &p, log_limb_size, Reduction::Montgomery);
 
     let a = Projective::new(a.x, a.y, a.z);
     let b = Projective::new(b.x, b.y, b.z);
@@ -226,7 +227,7 @@ pub async fn do_add_test(
     let pt_b_buf = create_sb_with_data(&device, &pt_b_limbs);
     let result_buf = create_empty_sb(&device, pt_a_buf.size());
 
-    let source = render_secp256r1_curve_tests(filename, log_limb_size);
+    let source = render_test::<Secp256r1>(filename, log_limb_size);
     let compute_pipeline = create_compute_pipeline(&device, &source, entrypoint);
 
     let mut command_encoder = create_command_encoder(&device);
@@ -280,7 +281,7 @@ pub async fn do_dbl_test(
     let res = mont::calc_rinv_and_n0(&p, &r, log_limb_size);
     let rinv = res.0;
 
-    let pt_a_limbs = projectivexyz_to_mont_limbs(&a, &p, log_limb_size);
+    let pt_a_limbs = projectivexyz_to_mont_limbs(&a, &p, log_limb_size, Reduction::Montgomery);
 
     let a = Projective::new(a.x, a.y, a.z);
     let expected_sum_affine = (a + a).into_affine();
@@ -291,7 +292,7 @@ pub async fn do_dbl_test(
     let pt_b_buf = create_empty_sb(&device, pt_a_buf.size());
     let result_buf = create_empty_sb(&device, pt_a_buf.size());
 
-    let source = render_secp256r1_curve_tests(filename, log_limb_size);
+    let source = render_test::<Secp256r1>(filename, log_limb_size);
     let compute_pipeline = create_compute_pipeline(&device, &source, entrypoint);
 
     let mut command_encoder = create_command_encoder(&device);
@@ -353,7 +354,7 @@ pub async fn do_recover_affine_ys_test(
     let result_0_buf = create_empty_sb(&device, xr_buf.size());
     let result_1_buf = create_empty_sb(&device, xr_buf.size());
 
-    let source = render_secp256r1_curve_tests(filename, log_limb_size);
+    let source = render_test::<Secp256r1>(filename, log_limb_size);
     let compute_pipeline = create_compute_pipeline(&device, &source, entrypoint);
 
     let mut command_encoder = create_command_encoder(&device);
@@ -408,7 +409,7 @@ pub async fn do_projective_to_affine_test(
     let p = BigUint::from_bytes_be(&Fq::MODULUS.to_bytes_be());
     let num_limbs = calc_num_limbs(log_limb_size, 256);
 
-    let pt_a_limbs = projectivexyz_to_mont_limbs(&a, &p, log_limb_size);
+    let pt_a_limbs = projectivexyz_to_mont_limbs(&a, &p, log_limb_size, Reduction::Montgomery);
 
     let expected_affine = to_affine_func(a.x, a.y, a.z);
 
@@ -418,7 +419,7 @@ pub async fn do_projective_to_affine_test(
     let pt_b_buf = create_empty_sb(&device, pt_a_buf.size());
     let result_buf = create_empty_sb(&device, pt_a_buf.size());
 
-    let source = render_secp256r1_curve_tests(filename, log_limb_size);
+    let source = render_test::<Secp256r1>(filename, log_limb_size);
     let compute_pipeline = create_compute_pipeline(&device, &source, entrypoint);
 
     let mut command_encoder = create_command_encoder(&device);
@@ -473,7 +474,7 @@ pub async fn do_projective_mul_test(
     let rinv = res.0;
 
     let pt_xyz = curve::affine_to_projectivexyz(pt);
-    let pt_limbs = projectivexyz_to_mont_limbs(&pt_xyz, &p, log_limb_size);
+    let pt_limbs = projectivexyz_to_mont_limbs(&pt_xyz, &p, log_limb_size, Reduction::Montgomery);
 
     let s = Fr::from(-1i32);
     let expected: Affine = pt.mul(s).into_affine();
@@ -484,7 +485,7 @@ pub async fn do_projective_mul_test(
     let pt_b_buf = create_empty_sb(&device, pt_a_buf.size());
     let result_buf = create_empty_sb(&device, pt_a_buf.size());
 
-    let source = render_secp256r1_curve_tests(filename, log_limb_size);
+    let source = render_test::<Secp256r1>(filename, log_limb_size);
     let compute_pipeline = create_compute_pipeline(&device, &source, entrypoint);
 
     let mut command_encoder = create_command_encoder(&device);
@@ -551,7 +552,7 @@ pub async fn do_projective_fixed_mul_test(
     let s_buf = create_sb_with_data(&device, &s_limbs);
     let result_buf = create_empty_sb(&device, (num_limbs * 3 * std::mem::size_of::<u32>()) as u64);
 
-    let source = render_secp256r1_curve_tests(filename, log_limb_size);
+    let source = render_test::<Secp256r1>(filename, log_limb_size);
     let compute_pipeline = create_compute_pipeline(&device, &source, entrypoint);
 
     let mut command_encoder = create_command_encoder(&device);
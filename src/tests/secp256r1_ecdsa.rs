@@ -1,8 +1,20 @@
-use crate::secp256r1_ecdsa::{ecrecover, ecrecover_single_shader};
+use crate::all_valid::Bitmap;
+use crate::checkpoint::Checkpointing;
+use crate::compaction::Compaction;
+use crate::coop::Cooperation;
+use crate::gpu::GpuContext;
+use crate::secp256r1_ecdsa::{
+    bytes_to_public_keys, ecrecover, ecrecover_compressed, ecrecover_single_shader,
+    ecrecover_with_options, ecverify_compressed, ecverify_compressed_all_valid, RecoveryOptions,
+};
 use fuel_crypto::secp256r1::p256::{encode_pubkey, recover, sign_prehashed};
 use fuel_crypto::Message;
 use num_bigint::{BigUint, RandomBits};
 use p256::ecdsa::{SigningKey, VerifyingKey};
+use p256::elliptic_curve::point::DecompressPoint;
+use p256::elliptic_curve::sec1::ToEncodedPoint;
+use p256::elliptic_curve::subtle::Choice;
+use p256::PublicKey;
 use fuel_types::Bytes64;
 use rand::Rng;
 use rand_chacha::rand_core::SeedableRng;
@@ -87,15 +99,227 @@ pub async fn do_secp256r1_test(
     signature: &Bytes64,
     message: &Message,
     verifying_key: &VerifyingKey,
-    table_limbs: &Vec<u32>,
+    table_limbs: &[u32],
     log_limb_size: u32,
     invoke_single: bool,
 ) {
-    let pk_affine_bytes = &verifying_key.to_sec1_bytes()[1..65];
     let result = if invoke_single {
-        ecrecover_single_shader(&vec![*signature], &vec![*message], log_limb_size).await
+        ecrecover_single_shader(&[*signature], &[*message], log_limb_size).await
     } else {
-        ecrecover(&vec![*signature], &vec![*message], table_limbs, log_limb_size).await
+        ecrecover(&[*signature], &[*message], table_limbs, log_limb_size).await
     }.expect("Shader failed");
-    assert_eq!(result[0], pk_affine_bytes);
+    assert_eq!(result[0], Some(PublicKey::from(verifying_key)));
+}
+
+#[serial_test::serial]
+#[tokio::test]
+pub async fn test_secp256r1_ecrecover_compressed() {
+    let log_limb_size = 13;
+    let table_limbs = secp256r1_bases(log_limb_size);
+    let (signatures, messages, expected_pks) = crate::benchmarks::secp256r1_ecdsa::gen_test_data(NUM_RUNS_PER_TEST);
+
+    let result = ecrecover_compressed(&signatures, &messages, &table_limbs, log_limb_size)
+        .await
+        .expect("Shader failed");
+
+    for i in 0..NUM_RUNS_PER_TEST {
+        let expected: [u8; 33] = expected_pks[i].to_encoded_point(true).as_bytes().try_into().unwrap();
+        assert_eq!(result[i], Some(expected));
+    }
+}
+
+#[serial_test::serial]
+#[tokio::test]
+pub async fn test_secp256r1_ecrecover_cooperative() {
+    let log_limb_size = 13;
+    let table_limbs = secp256r1_bases(log_limb_size);
+    let context = GpuContext::new().await;
+    let (signatures, messages, expected_pks) = crate::benchmarks::secp256r1_ecdsa::gen_test_data(NUM_RUNS_PER_TEST);
+
+    for lanes in [4, 8] {
+        let options = RecoveryOptions { cooperation: Cooperation::Lanes(lanes), ..Default::default() };
+        let result = ecrecover_with_options(
+            &context,
+            &signatures,
+            &messages,
+            &[],
+            &table_limbs,
+            log_limb_size,
+            &options,
+        )
+        .await
+        .expect("Shader failed");
+        let result = bytes_to_public_keys(&result);
+
+        for i in 0..NUM_RUNS_PER_TEST {
+            assert_eq!(result[i], Some(expected_pks[i]));
+        }
+    }
+}
+
+#[serial_test::serial]
+#[tokio::test]
+pub async fn test_secp256r1_ecverify_compressed() {
+    let log_limb_size = 13;
+    let table_limbs = secp256r1_bases(log_limb_size);
+    let (signatures, messages, expected_pks) = crate::benchmarks::secp256r1_ecdsa::gen_test_data(NUM_RUNS_PER_TEST);
+    let mut public_keys: Vec<[u8; 33]> = expected_pks
+        .iter()
+        .map(|pk| pk.to_encoded_point(true).as_bytes().try_into().unwrap())
+        .collect();
+
+    // Another signer's key, the wrong parity, an invalid prefix, and an uncompressed prefix
+    public_keys[0] = public_keys[1];
+    public_keys[2][0] ^= 1;
+    public_keys[3][0] = 0x05;
+    public_keys[4][0] = 0x04;
+
+    let result = ecverify_compressed(&signatures, &messages, &public_keys, &table_limbs, log_limb_size)
+        .await
+        .expect("Shader failed");
+
+    for (i, verdict) in result.iter().enumerate() {
+        assert_eq!(verdict.is_valid(), i > 4);
+    }
+}
+
+#[serial_test::serial]
+#[tokio::test]
+pub async fn test_secp256r1_ecverify_compressed_all_valid() {
+    let log_limb_size = 13;
+    let table_limbs = secp256r1_bases(log_limb_size);
+    let context = GpuContext::new().await;
+    let (signatures, messages, expected_pks) = crate::benchmarks::secp256r1_ecdsa::gen_test_data(NUM_RUNS_PER_TEST);
+    let mut public_keys: Vec<[u8; 33]> = expected_pks
+        .iter()
+        .map(|pk| pk.to_encoded_point(true).as_bytes().try_into().unwrap())
+        .collect();
+
+    // The wrong parity
+    public_keys[4][0] ^= 1;
+
+    let result = ecverify_compressed_all_valid(
+        &context,
+        &signatures,
+        &messages,
+        &public_keys,
+        &table_limbs,
+        log_limb_size,
+        Bitmap::Packed,
+    )
+    .await
+    .expect("Shader failed");
+
+    assert_eq!(result.first_failure, Some(4));
+    for i in 0..NUM_RUNS_PER_TEST {
+        assert_eq!(result.verdict(i).unwrap().is_valid(), i != 4);
+    }
+}
+
+#[serial_test::serial]
+#[tokio::test]
+pub async fn test_secp256r1_ecrecover_checkpointed() {
+    let log_limb_size = 13;
+    let table_limbs = secp256r1_bases(log_limb_size);
+    let context = GpuContext::new().await;
+    let (signatures, messages, expected_pks) = crate::benchmarks::secp256r1_ecdsa::gen_test_data(NUM_RUNS_PER_TEST);
+    let expected: Vec<Option<PublicKey>> = expected_pks.into_iter().map(Some).collect();
+
+    let unsplit_result = ecrecover_with_options(
+        &context,
+        &signatures,
+        &messages,
+        &[],
+        &table_limbs,
+        log_limb_size,
+        &RecoveryOptions::default(),
+    )
+    .await
+    .expect("Shader failed");
+
+    for bits in [7, 64, 256] {
+        let options = RecoveryOptions {
+            checkpointing: Checkpointing::BitsPerDispatch(bits),
+            ..Default::default()
+        };
+        let result = ecrecover_with_options(
+            &context,
+            &signatures,
+            &messages,
+            &[],
+            &table_limbs,
+            log_limb_size,
+            &options,
+        )
+        .await
+        .expect("Shader failed");
+        // The sub-dispatches perform the same operations, so the output is bit-identical
+        assert_eq!(result, unsplit_result);
+        assert_eq!(bytes_to_public_keys(&result), expected);
+    }
+
+    // Checkpointing combines with the cooperative kernel and with compaction
+    let options = RecoveryOptions {
+        cooperation: Cooperation::Lanes(4),
+        checkpointing: Checkpointing::BitsPerDispatch(7),
+        compaction: Compaction::Indirect,
+        ..Default::default()
+    };
+    let result = ecrecover_with_options(
+        &context,
+        &signatures,
+        &messages,
+        &[],
+        &table_limbs,
+        log_limb_size,
+        &options,
+    )
+    .await
+    .expect("Shader failed");
+    assert_eq!(bytes_to_public_keys(&result), expected);
+}
+
+#[serial_test::serial]
+#[tokio::test]
+pub async fn test_secp256r1_ecrecover_compacted() {
+    let mut rng = ChaCha8Rng::seed_from_u64(3);
+    let log_limb_size = 13;
+    let table_limbs = secp256r1_bases(log_limb_size);
+    let context = GpuContext::new().await;
+    let (mut signatures, messages, expected_pks) =
+        crate::benchmarks::secp256r1_ecdsa::gen_test_data(NUM_RUNS_PER_TEST);
+
+    // Replace r in every other signature with an x-coordinate of no point on the curve, so that
+    // recovery fails in stage 0
+    for signature in signatures.iter_mut().step_by(2) {
+        let r = loop {
+            let r: [u8; 32] = rng.gen();
+            if bool::from(p256::AffinePoint::decompress(&r.into(), Choice::from(0)).is_none()) {
+                break r;
+            }
+        };
+        let mut bytes = **signature;
+        bytes[..32].copy_from_slice(&r);
+        *signature = Bytes64::from(bytes);
+        assert!(recover(signature, &messages[0]).is_err());
+    }
+
+    let options = RecoveryOptions { compaction: Compaction::Indirect, ..Default::default() };
+    let result = ecrecover_with_options(
+        &context,
+        &signatures,
+        &messages,
+        &[],
+        &table_limbs,
+        log_limb_size,
+        &options,
+    )
+    .await
+    .expect("Shader failed");
+    let result = bytes_to_public_keys(&result);
+
+    for i in 0..NUM_RUNS_PER_TEST {
+        let expected = if i % 2 == 0 { None } else { Some(expected_pks[i]) };
+        assert_eq!(result[i], expected);
+    }
 }
@@ -0,0 +1,74 @@
+use crate::precompute::secp384r1_bases;
+use crate::secp384r1_ecdsa::{ecrecover, ecverify, recover, sign_prehashed, Message, Signature};
+use crate::Verdict;
+use p384::ecdsa::SigningKey;
+use p384::PublicKey;
+use rand::Rng;
+use rand_chacha::rand_core::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+use sha2::{Digest, Sha384};
+
+const NUM_SIGNATURES: usize = 300;
+
+pub fn gen_test_data(num_signatures: usize) -> (Vec<Signature>, Vec<Message>, Vec<PublicKey>) {
+    let mut rng = ChaCha8Rng::seed_from_u64(2);
+
+    let mut signatures = Vec::with_capacity(num_signatures);
+    let mut messages = Vec::with_capacity(num_signatures);
+    let mut expected_pks = Vec::with_capacity(num_signatures);
+
+    for _ in 0..num_signatures {
+        // Hash a random message
+        let mut message: Message = [0u8; 48];
+        message.copy_from_slice(&Sha384::digest(rng.gen::<[u8; 32]>()));
+        let signing_key = SigningKey::random(&mut rng);
+        let signature = sign_prehashed(&signing_key, &message);
+
+        signatures.push(signature);
+        messages.push(message);
+        expected_pks.push(PublicKey::from(signing_key.verifying_key()));
+    }
+    (signatures, messages, expected_pks)
+}
+
+#[test]
+pub fn test_secp384r1_cpu_recover() {
+    let (signatures, messages, expected_pks) = gen_test_data(NUM_SIGNATURES);
+    for i in 0..NUM_SIGNATURES {
+        assert_eq!(recover(&signatures[i], &messages[i]), Some(expected_pks[i]));
+    }
+}
+
+#[serial_test::serial]
+#[tokio::test]
+pub async fn test_secp384r1_ecrecover() {
+    let (signatures, messages, expected_pks) = gen_test_data(NUM_SIGNATURES);
+    for log_limb_size in 11..15 {
+        let table_limbs = secp384r1_bases(log_limb_size);
+        let recovered = ecrecover(&signatures, &messages, &table_limbs, log_limb_size)
+            .await
+            .expect("Shader failed");
+        for (pk, expected) in recovered.into_iter().zip(&expected_pks) {
+            assert_eq!(pk.as_ref(), Some(expected));
+        }
+    }
+}
+
+#[serial_test::serial]
+#[tokio::test]
+pub async fn test_secp384r1_ecverify() {
+    let log_limb_size = 13;
+    let table_limbs = secp384r1_bases(log_limb_size);
+    let (signatures, messages, mut public_keys) = gen_test_data(NUM_SIGNATURES);
+
+    // Swap two keys so that their signatures no longer verify
+    public_keys.swap(0, 1);
+
+    let verdicts = ecverify(&signatures, &messages, &public_keys, &table_limbs, log_limb_size)
+        .await
+        .expect("Shader failed");
+    for (i, verdict) in verdicts.into_iter().enumerate() {
+        let expected = if i < 2 { Verdict::Invalid } else { Verdict::Valid };
+        assert_eq!(verdict, expected);
+    }
+}
@@ -0,0 +1,72 @@
+use crate::signature_encoding::{compact_from_parts, parse_der, SignatureEncoding};
+use num_bigint::BigUint;
+
+fn n() -> BigUint {
+    crate::moduli::secp256k1_fr_modulus_biguint()
+}
+
+fn rsv(r: u8, s: u8, v: &[u8]) -> Vec<u8> {
+    let mut sig = vec![0u8; 64];
+    sig[31] = r;
+    sig[63] = s;
+    sig.extend_from_slice(v);
+    sig
+}
+
+#[test]
+pub fn test_rsv_recovery_ids() {
+    let n = n();
+    let even = compact_from_parts(&BigUint::from(5u32), &BigUint::from(7u32), 0, &n).unwrap();
+    let odd = compact_from_parts(&BigUint::from(5u32), &BigUint::from(7u32), 1, &n).unwrap();
+    assert_eq!(odd[32], 0x80);
+
+    // Raw, pre-EIP-155, EIP-155 with chain id 1, and EIP-155 with a multi-byte chain id
+    for (v, expected) in [
+        (vec![0u8], even),
+        (vec![1], odd),
+        (vec![27], even),
+        (vec![28], odd),
+        (vec![37], even),
+        (vec![38], odd),
+        (vec![0x02, 0x00, 0x23], even),
+        (vec![0x02, 0x00, 0x24], odd),
+    ] {
+        assert_eq!(SignatureEncoding::Rsv.to_compact(&rsv(5, 7, &v), &n), Some(expected));
+    }
+
+    for v in [vec![2u8], vec![29], vec![26], vec![31]] {
+        assert_eq!(SignatureEncoding::Rsv.to_compact(&rsv(5, 7, &v), &n), None);
+    }
+    assert_eq!(SignatureEncoding::Rsv.to_compact(&rsv(0, 7, &[27]), &n), None);
+    assert_eq!(SignatureEncoding::Rsv.to_compact(&[0u8; 64], &n), None);
+}
+
+#[test]
+pub fn test_high_s_is_normalised() {
+    let n = n();
+    let r = BigUint::from(5u32);
+    let s = &n - 7u32;
+    let compact = compact_from_parts(&r, &s, 0, &n).unwrap();
+    assert_eq!(compact, compact_from_parts(&r, &BigUint::from(7u32), 1, &n).unwrap());
+    assert_eq!(compact_from_parts(&r, &n, 0, &n), None);
+}
+
+#[test]
+pub fn test_parse_der() {
+    // r = 0x80 needs a leading zero byte, s = 1 does not
+    let der = [0x30, 0x07, 0x02, 0x02, 0x00, 0x80, 0x02, 0x01, 0x01];
+    assert_eq!(parse_der(&der), Some((BigUint::from(0x80u32), BigUint::from(1u32))));
+
+    let mut sig = der.to_vec();
+    sig.push(1);
+    let compact = SignatureEncoding::DerWithRecoveryId.to_compact(&sig, &n()).unwrap();
+    assert_eq!(compact[31], 0x80);
+    assert_eq!(compact[32], 0x80);
+    assert_eq!(compact[63], 1);
+
+    // A wrong sequence length, a negative integer, a redundant leading zero, and trailing bytes
+    assert_eq!(parse_der(&[0x30, 0x06, 0x02, 0x02, 0x00, 0x80, 0x02, 0x01, 0x01]), None);
+    assert_eq!(parse_der(&[0x30, 0x06, 0x02, 0x01, 0x80, 0x02, 0x01, 0x01]), None);
+    assert_eq!(parse_der(&[0x30, 0x07, 0x02, 0x02, 0x00, 0x01, 0x02, 0x01, 0x01]), None);
+    assert_eq!(parse_der(&[0x30, 0x07, 0x02, 0x01, 0x01, 0x02, 0x01, 0x01, 0x00]), None);
+}
@@ -0,0 +1,236 @@
+use crate::benchmarks::ed25519_eddsa::gen_test_data as gen_ed25519_test_data;
+use crate::benchmarks::secp256k1_ecdsa::gen_test_data as gen_secp256k1_test_data;
+use crate::all_valid::Bitmap;
+use crate::ed25519_eddsa::ecverify_with_context as ed25519_ecverify_with_context;
+use crate::gpu::GpuContext;
+use crate::integrity::{Check, Integrity};
+use crate::precompute::{ed25519_bases, secp256k1_bases};
+use crate::sec1::compress;
+use crate::secp256k1_ecdsa::{derive_public_keys_with_context, ecrecover_with_context, ecverify_compressed_all_valid};
+use crate::streaming::{check_known_answers, Verifier};
+use crate::Verdict;
+use fuel_crypto::SecretKey;
+use fuel_types::Bytes32;
+use futures::StreamExt;
+
+// Includes an empty batch, and batches which do and do not pad to the same size, so that the
+// buffer sets are both reused and reallocated
+const BATCH_SIZES: [usize; 6] = [300, 512, 0, 7, 300, 1];
+
+#[serial_test::serial]
+#[tokio::test]
+pub async fn test_secp256k1_verify_stream() {
+    let log_limb_size = 13;
+    let table_limbs = secp256k1_bases(log_limb_size);
+    let (signatures, messages, expected_pks) = gen_secp256k1_test_data(BATCH_SIZES.iter().sum());
+
+    let mut batches = vec![];
+    let mut start = 0;
+    for size in BATCH_SIZES {
+        batches.push((signatures[start..start + size].to_vec(), messages[start..start + size].to_vec()));
+        start += size;
+    }
+
+    let context = GpuContext::new().await;
+    let scheme = crate::secp256k1_ecdsa::Recovery::new(&context.device, &table_limbs, log_limb_size);
+    let mut verifier = Verifier::new(context, scheme);
+
+    let results: Vec<_> = verifier.verify_stream(futures::stream::iter(batches)).collect().await;
+    assert_eq!(results.len(), BATCH_SIZES.len());
+
+    let mut start = 0;
+    for (result, size) in results.into_iter().zip(BATCH_SIZES) {
+        let recovered = result.expect("Shader failed");
+        assert_eq!(recovered.len(), size);
+        for (i, pk) in recovered.into_iter().enumerate() {
+            assert_eq!(pk, Some(expected_pks[start + i]));
+        }
+        start += size;
+    }
+}
+
+#[serial_test::serial]
+#[tokio::test]
+pub async fn test_secp256k1_verify_stream_reuse_after_drop() {
+    let log_limb_size = 13;
+    let table_limbs = secp256k1_bases(log_limb_size);
+    let (signatures, messages, expected_pks) = gen_secp256k1_test_data(BATCH_SIZES.iter().sum());
+
+    let mut batches = vec![];
+    let mut start = 0;
+    for size in BATCH_SIZES {
+        batches.push((signatures[start..start + size].to_vec(), messages[start..start + size].to_vec()));
+        start += size;
+    }
+
+    let context = GpuContext::new().await;
+    let scheme = crate::secp256k1_ecdsa::Recovery::new(&context.device, &table_limbs, log_limb_size);
+    let mut verifier = Verifier::new(context, scheme);
+
+    // Every buffer set is in flight when the first result is yielded, and the stream is dropped
+    {
+        let mut stream = Box::pin(verifier.verify_stream(futures::stream::iter(batches.clone())));
+        let first = stream.next().await.expect("The stream ended early").expect("Shader failed");
+        assert_eq!(first.len(), BATCH_SIZES[0]);
+    }
+
+    let results: Vec<_> = verifier.verify_stream(futures::stream::iter(batches)).collect().await;
+    assert_eq!(results.len(), BATCH_SIZES.len());
+
+    let mut start = 0;
+    for (result, size) in results.into_iter().zip(BATCH_SIZES) {
+        let recovered = result.expect("Shader failed");
+        for (i, pk) in recovered.into_iter().enumerate() {
+            assert_eq!(pk, Some(expected_pks[start + i]));
+        }
+        start += size;
+    }
+}
+
+#[serial_test::serial]
+#[tokio::test]
+pub async fn test_ed25519_verify_stream() {
+    let log_limb_size = 13;
+    let table_limbs = ed25519_bases(log_limb_size);
+    let (signatures, messages, verifying_keys) = gen_ed25519_test_data(BATCH_SIZES.iter().sum());
+
+    let mut batches = vec![];
+    let mut start = 0;
+    for size in BATCH_SIZES {
+        batches.push((
+            signatures[start..start + size].to_vec(),
+            messages[start..start + size].to_vec(),
+            verifying_keys[start..start + size].to_vec(),
+        ));
+        start += size;
+    }
+
+    let context = GpuContext::new().await;
+    let scheme = crate::ed25519_eddsa::Verification::new(&context.device, &table_limbs, log_limb_size);
+    let mut verifier = Verifier::new(context, scheme);
+
+    let results: Vec<_> = verifier.verify_stream(futures::stream::iter(batches)).collect().await;
+    assert_eq!(results.len(), BATCH_SIZES.len());
+
+    for (result, size) in results.into_iter().zip(BATCH_SIZES) {
+        let verdicts = result.expect("Shader failed");
+        assert_eq!(verdicts, vec![Verdict::Valid; size]);
+    }
+}
+
+#[serial_test::serial]
+#[tokio::test]
+pub async fn test_secp256k1_verify_stream_with_integrity() {
+    let log_limb_size = 13;
+    let table_limbs = secp256k1_bases(log_limb_size);
+    let (signatures, messages, expected_pks) = gen_secp256k1_test_data(BATCH_SIZES.iter().sum());
+
+    let mut batches = vec![];
+    let mut start = 0;
+    for size in BATCH_SIZES {
+        batches.push((signatures[start..start + size].to_vec(), messages[start..start + size].to_vec()));
+        start += size;
+    }
+
+    let context = GpuContext::new_with_integrity(Integrity::SamplesPerBatch(16), log_limb_size).await;
+    assert_eq!(context.quarantined(), None);
+    let scheme = crate::secp256k1_ecdsa::Recovery::new(&context.device, &table_limbs, log_limb_size);
+    let mut verifier = Verifier::new(context, scheme);
+
+    let results: Vec<_> = verifier.verify_stream(futures::stream::iter(batches)).collect().await;
+    assert_eq!(verifier.context().quarantined(), None);
+
+    let mut start = 0;
+    for (result, size) in results.into_iter().zip(BATCH_SIZES) {
+        let recovered = result.expect("Shader failed");
+        for (i, pk) in recovered.into_iter().enumerate() {
+            assert_eq!(pk, Some(expected_pks[start + i]));
+        }
+        start += size;
+    }
+}
+
+#[serial_test::serial]
+#[tokio::test]
+pub async fn test_ed25519_verify_stream_quarantine() {
+    let log_limb_size = 13;
+    // A zeroed table of bases makes every fixed-base multiplication wrong, without failing a shader
+    let table_limbs = vec![0u32; ed25519_bases(log_limb_size).len()];
+    let (signatures, messages, verifying_keys) = gen_ed25519_test_data(BATCH_SIZES[0]);
+
+    let context = GpuContext::new_with_integrity(Integrity::SamplesPerBatch(16), log_limb_size).await;
+    assert_eq!(context.quarantined(), None);
+    let scheme = crate::ed25519_eddsa::Verification::new(&context.device, &table_limbs, log_limb_size);
+    check_known_answers(&context, &scheme).await;
+    let mut verifier = Verifier::new(context, scheme);
+
+    let failure = verifier.context().quarantined().expect("The context was not quarantined");
+    assert_eq!(failure.check, Check::KnownAnswers);
+
+    // Every later batch fails instead of returning wrong verdicts
+    let batches = vec![(signatures, messages, verifying_keys), (vec![], vec![], vec![])];
+    let results: Vec<_> = verifier.verify_stream(futures::stream::iter(batches)).collect().await;
+    for result in results {
+        assert_eq!(result.unwrap_err().integrity, Some(failure.clone()));
+    }
+}
+
+#[serial_test::serial]
+#[tokio::test]
+pub async fn test_secp256k1_ecrecover_with_integrity() {
+    let log_limb_size = 13;
+    let table_limbs = secp256k1_bases(log_limb_size);
+    let (signatures, messages, expected_pks) = gen_secp256k1_test_data(BATCH_SIZES[0]);
+
+    let context = GpuContext::new_with_integrity(Integrity::SamplesPerBatch(16), log_limb_size).await;
+    let recovered = ecrecover_with_context(&context, &signatures, &messages, &table_limbs, log_limb_size)
+        .await
+        .expect("Shader failed");
+    assert_eq!(recovered, expected_pks.iter().copied().map(Some).collect::<Vec<_>>());
+
+    // The summary only determines the verdicts up to the first failure, which are sampled
+    let mut public_keys: Vec<_> =
+        expected_pks.iter().map(|pk| compress(pk.as_slice().try_into().unwrap())).collect();
+    public_keys[100][0] ^= 1;
+    let batch_verdict = ecverify_compressed_all_valid(
+        &context,
+        &signatures,
+        &messages,
+        &public_keys,
+        &table_limbs,
+        log_limb_size,
+        Bitmap::Off,
+    )
+    .await
+    .expect("Shader failed");
+    assert_eq!(batch_verdict.first_failure, Some(100));
+    assert_eq!(context.quarantined(), None);
+}
+
+#[serial_test::serial]
+#[tokio::test]
+pub async fn test_secp256k1_ecrecover_quarantine() {
+    let log_limb_size = 13;
+    // A zeroed table of bases makes every fixed-base multiplication wrong, without failing a shader
+    let table_limbs = vec![0u32; secp256k1_bases(log_limb_size).len()];
+    let (signatures, messages, _) = gen_secp256k1_test_data(BATCH_SIZES[0]);
+
+    let context = GpuContext::new_with_integrity(Integrity::SamplesPerBatch(16), log_limb_size).await;
+    let result = ecrecover_with_context(&context, &signatures, &messages, &table_limbs, log_limb_size).await;
+    let failure = context.quarantined().expect("The context was not quarantined");
+    assert_eq!(failure.check, Check::Sample { batch: 0 });
+    // The error carries the failure
+    assert_eq!(result.unwrap_err().integrity, Some(failure.clone()));
+
+    // Every later call fails, including those which are not sampled
+    let (signatures, messages, verifying_keys) = gen_ed25519_test_data(BATCH_SIZES[0]);
+    let table_limbs = ed25519_bases(log_limb_size);
+    let result =
+        ed25519_ecverify_with_context(&context, &signatures, &messages, &verifying_keys, &table_limbs, log_limb_size)
+            .await;
+    assert_eq!(result.unwrap_err().integrity, Some(failure.clone()));
+    let secret_keys = [SecretKey::try_from(Bytes32::new([1u8; 32])).unwrap()];
+    let table_limbs = secp256k1_bases(log_limb_size);
+    let result = derive_public_keys_with_context(&context, &secret_keys, &table_limbs, log_limb_size).await;
+    assert_eq!(result.unwrap_err().integrity, Some(failure));
+}
@@ -0,0 +1,270 @@
+use crate::precompute::secp256r1_bases;
+use crate::webauthn::{verify_assertions, WebAuthnAssertion};
+use num_bigint::BigUint;
+use p256::ecdsa::signature::{Signer, Verifier};
+use p256::ecdsa::{Signature, SigningKey};
+use p256::PublicKey;
+use rand::Rng;
+use rand_chacha::rand_core::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+use sha2::{Digest, Sha256};
+
+const NUM_ASSERTIONS: usize = 10;
+
+/// A fixed, synthetic assertion in the wire format of an authenticator, with the credential's
+/// public key both as the COSE_Key of its attestation and in SEC1 form. These were not captured
+/// from a real authenticator: the authenticatorData and clientDataJSON were built by hand in the
+/// layout which browsers produce, and signed with OpenSSL rather than the p256 crate, so the
+/// signatures come from an independent implementation and are not normalised to low s.
+struct Fixture {
+    authenticator_data: &'static str,
+    client_data_json: &'static str,
+    signature: &'static str,
+    cose_key: &'static str,
+    sec1_key: &'static str,
+}
+
+const FIXTURES: [Fixture; 4] = [
+    // A platform authenticator with user presence and verification, sign count 17
+    Fixture {
+        authenticator_data: "74a6ea9213c99c2f74b22492b320cf40262a94c1a950a0397f29250b60841ef00500000011",
+        client_data_json: r#"{"type":"webauthn.get","challenge":"9pB7XNWDScV_nME8XNgDJqkQsEHWPA12vgH-eJ-ls68","origin":"https://webauthn.io","crossOrigin":false}"#,
+        signature: concat!(
+            "3045022100a92aaf3bd6b1bad7fbc8951d80544bf88c0a01be92f40cd8c41944",
+            "d7cdd7e08302203ae4f865311804c0e80dfad60215c5f668091413b0d199fc6f",
+            "728f4837754e27",
+        ),
+        cose_key: concat!(
+            "a501020326200121582093b492df36957ea6004ea2b0c033be15cd1ebf977268",
+            "37c50c26c8d7a333a823225820fdbbac7a76f6ae20da872fceeb04b527aeb824",
+            "be57e8efef519d396d333376d2",
+        ),
+        sec1_key: concat!(
+            "0493b492df36957ea6004ea2b0c033be15cd1ebf97726837c50c26c8d7a333a8",
+            "23fdbbac7a76f6ae20da872fceeb04b527aeb824be57e8efef519d396d333376",
+            "d2",
+        ),
+    },
+    // A passkey with backup eligibility and state and a zero sign count, and a high s
+    Fixture {
+        authenticator_data: "74a6ea9213c99c2f74b22492b320cf40262a94c1a950a0397f29250b60841ef01d00000000",
+        client_data_json: r#"{"type":"webauthn.get","challenge":"KNkJA59oUFGn1Fvi55ddRNW8VcdeZ1G3EW-JE0IqOfU","origin":"https://webauthn.io","crossOrigin":false}"#,
+        signature: concat!(
+            "304602210088b0afa991b90d148194fd6a6003dcb2c5c9867142fea18f87abfa",
+            "b1ab1f979e022100cff9e581d3ce5df38ceadf5a265d86a995719c51dfba4727",
+            "1de77ab1152c486a",
+        ),
+        cose_key: concat!(
+            "a50102032620012158208768891dc5886204b77c21e23a480826cd7176595462",
+            "a382b9b732ff5464626d2258208de78d88cb0c9ac3b0db6f499b7ad61ee49645",
+            "fa1815cfa848199d5b9526e35a",
+        ),
+        sec1_key: concat!(
+            "048768891dc5886204b77c21e23a480826cd7176595462a382b9b732ff546462",
+            "6d8de78d88cb0c9ac3b0db6f499b7ad61ee49645fa1815cfa848199d5b9526e3",
+            "5a",
+        ),
+    },
+    // Another assertion by the first key, for a different relying party
+    Fixture {
+        authenticator_data: "a379a6f6eeafb9a55e378c118034e2751e682fab9f2d30ab13d2125586ce1947050000053e",
+        client_data_json: r#"{"type":"webauthn.get","challenge":"InZIhLJxI2-rbbH4ebK3SX3NzaGzBIkakMielTcaODc","origin":"https://example.com","crossOrigin":false}"#,
+        signature: concat!(
+            "304502210093f3e72701ca9d3ee7979b5231295dac7d81d1e4c985ad1a55eacd",
+            "d94d290e1b0220528b20f1effcfc92dcf3c9f0f2f7daf7ef6f92266fbc4c9060",
+            "60b2a93a5eeb94",
+        ),
+        cose_key: concat!(
+            "a501020326200121582093b492df36957ea6004ea2b0c033be15cd1ebf977268",
+            "37c50c26c8d7a333a823225820fdbbac7a76f6ae20da872fceeb04b527aeb824",
+            "be57e8efef519d396d333376d2",
+        ),
+        sec1_key: concat!(
+            "0493b492df36957ea6004ea2b0c033be15cd1ebf97726837c50c26c8d7a333a8",
+            "23fdbbac7a76f6ae20da872fceeb04b527aeb824be57e8efef519d396d333376",
+            "d2",
+        ),
+    },
+    // A security key with user presence only, and a high s
+    Fixture {
+        authenticator_data: "a379a6f6eeafb9a55e378c118034e2751e682fab9f2d30ab13d2125586ce19470100000007",
+        client_data_json: r#"{"type":"webauthn.get","challenge":"GKfugl_RXNsTFOhpSK0PFUHzR0wySzUvlBxi-nsMhE4","origin":"https://example.com","crossOrigin":false}"#,
+        signature: concat!(
+            "3046022100bed52f81133116b2a53253796f7f4897c4b52a85249ef882a140c1",
+            "8a494f0c4c022100fabce6dd177e26a18c4861f939b594f93bbacc7fbac23165",
+            "46690b8e031e67bb",
+        ),
+        cose_key: concat!(
+            "a50102032620012158208768891dc5886204b77c21e23a480826cd7176595462",
+            "a382b9b732ff5464626d2258208de78d88cb0c9ac3b0db6f499b7ad61ee49645",
+            "fa1815cfa848199d5b9526e35a",
+        ),
+        sec1_key: concat!(
+            "048768891dc5886204b77c21e23a480826cd7176595462a382b9b732ff546462",
+            "6d8de78d88cb0c9ac3b0db6f499b7ad61ee49645fa1815cfa848199d5b9526e3",
+            "5a",
+        ),
+    },
+];
+
+/// Extracts the coordinates of an EC2 P-256 COSE_Key with the canonical CBOR layout which
+/// authenticators emit: {1: 2, 3: -7, -1: 1, -2: x, -3: y}.
+fn cose_key_to_sec1(cose_key: &[u8]) -> Vec<u8> {
+    assert_eq!(cose_key.len(), 77);
+    assert_eq!(cose_key[..10], hex::decode("a5010203262001215820").unwrap());
+    assert_eq!(cose_key[42..45], hex::decode("225820").unwrap());
+    [&[0x04], &cose_key[10..42], &cose_key[45..]].concat()
+}
+
+/// DER-encodes (r, s), so that the s of a signature can be replaced with n - s.
+fn der_encode(r: &BigUint, s: &BigUint) -> Vec<u8> {
+    let encode_integer = |x: &BigUint| {
+        let mut bytes = x.to_bytes_be();
+        if bytes[0] & 0x80 != 0 {
+            bytes.insert(0, 0);
+        }
+        let mut out = vec![0x02, bytes.len() as u8];
+        out.extend_from_slice(&bytes);
+        out
+    };
+    let body = [encode_integer(r), encode_integer(s)].concat();
+    let mut der = vec![0x30, body.len() as u8];
+    der.extend_from_slice(&body);
+    der
+}
+
+/// Builds an assertion in the layout an authenticator returns: authenticatorData is the
+/// rpIdHash, the flags (user present and verified), and a big-endian sign count, and
+/// clientDataJSON is the serialised CollectedClientData.
+fn gen_assertion(
+    rng: &mut ChaCha8Rng,
+    signing_key: &SigningKey,
+) -> (WebAuthnAssertion, Signature) {
+    let mut authenticator_data = Sha256::digest(b"example.com").to_vec();
+    authenticator_data.push(0x05);
+    authenticator_data.extend_from_slice(&rng.gen::<u32>().to_be_bytes());
+
+    let challenge = hex::encode(rng.gen::<[u8; 32]>());
+    let client_data_json = format!(
+        r#"{{"type":"webauthn.get","challenge":"{}","origin":"https://example.com","crossOrigin":false}}"#,
+        challenge
+    )
+    .into_bytes();
+
+    let mut signed_data = authenticator_data.clone();
+    signed_data.extend_from_slice(&Sha256::digest(&client_data_json));
+    let signature: Signature = signing_key.sign(&signed_data);
+    signing_key.verifying_key().verify(&signed_data, &signature).unwrap();
+
+    let assertion = WebAuthnAssertion {
+        authenticator_data,
+        client_data_json,
+        signature: signature.to_der().as_bytes().to_vec(),
+    };
+    (assertion, signature)
+}
+
+#[test]
+pub fn test_digest() {
+    let assertion = WebAuthnAssertion {
+        authenticator_data: vec![1, 2, 3],
+        client_data_json: b"{}".to_vec(),
+        signature: vec![],
+    };
+    let mut signed_data = vec![1, 2, 3];
+    signed_data.extend_from_slice(&Sha256::digest(b"{}"));
+    assert_eq!(assertion.digest().as_slice(), Sha256::digest(&signed_data).as_slice());
+}
+
+#[serial_test::serial]
+#[tokio::test]
+pub async fn test_verify_assertions() {
+    let mut rng = ChaCha8Rng::seed_from_u64(6);
+    let log_limb_size = 13;
+    let table_limbs = secp256r1_bases(log_limb_size);
+    let n = crate::moduli::secp256r1_fr_modulus_biguint();
+
+    let mut assertions = vec![];
+    let mut public_keys = vec![];
+    for i in 0..NUM_ASSERTIONS {
+        let signing_key = SigningKey::random(&mut rng);
+        let (mut assertion, signature) = gen_assertion(&mut rng, &signing_key);
+
+        // P-256 signers do not normalise s, but make sure that both halves are covered
+        let bytes = signature.to_bytes();
+        let r = BigUint::from_bytes_be(&bytes[..32]);
+        let s = BigUint::from_bytes_be(&bytes[32..]);
+        let is_high_s = s > (&n >> 1);
+        if (i % 2 == 0) != is_high_s {
+            assertion.signature = der_encode(&r, &(&n - &s));
+        }
+
+        assertions.push(assertion);
+        public_keys.push(PublicKey::from(signing_key.verifying_key()));
+    }
+
+    let result = verify_assertions(&assertions, &public_keys, &table_limbs, log_limb_size)
+        .await
+        .expect("Shader failed");
+    assert!(result.iter().all(|verdict| verdict.is_valid()));
+
+    // Assertion 0: a tampered clientDataJSON
+    assertions[0].client_data_json[2] ^= 1;
+    // Assertion 1: another credential's key
+    public_keys[1] = public_keys[2];
+    // Assertion 2: a malformed DER signature, whose SEQUENCE tag is replaced by that of a SET
+    assertions[2].signature[0] = 0x31;
+    // Assertion 3: a tampered authenticatorData, whose flags byte gains the AT bit
+    assertions[3].authenticator_data[32] ^= 0x40;
+
+    let result = verify_assertions(&assertions, &public_keys, &table_limbs, log_limb_size)
+        .await
+        .expect("Shader failed");
+    for (i, verdict) in result.iter().enumerate() {
+        assert_eq!(verdict.is_valid(), i > 3);
+    }
+}
+
+#[serial_test::serial]
+#[tokio::test]
+pub async fn test_verify_assertion_fixtures() {
+    let log_limb_size = 13;
+    let table_limbs = secp256r1_bases(log_limb_size);
+    let n = crate::moduli::secp256r1_fr_modulus_biguint();
+
+    let mut assertions = vec![];
+    let mut public_keys = vec![];
+    let mut num_high_s = 0;
+    for fixture in &FIXTURES {
+        let sec1_key = hex::decode(fixture.sec1_key).unwrap();
+        assert_eq!(cose_key_to_sec1(&hex::decode(fixture.cose_key).unwrap()), sec1_key);
+
+        let signature = hex::decode(fixture.signature).unwrap();
+        let bytes = Signature::from_der(&signature).unwrap().to_bytes();
+        if BigUint::from_bytes_be(&bytes[32..]) > (&n >> 1) {
+            num_high_s += 1;
+        }
+
+        assertions.push(WebAuthnAssertion {
+            authenticator_data: hex::decode(fixture.authenticator_data).unwrap(),
+            client_data_json: fixture.client_data_json.as_bytes().to_vec(),
+            signature,
+        });
+        public_keys.push(PublicKey::from_sec1_bytes(&sec1_key).unwrap());
+    }
+    assert!(num_high_s > 0);
+
+    let result = verify_assertions(&assertions, &public_keys, &table_limbs, log_limb_size)
+        .await
+        .expect("Shader failed");
+    assert!(result.iter().all(|verdict| verdict.is_valid()));
+
+    // The fixtures share two keys, so swapping the keys of the first two invalidates both
+    public_keys.swap(0, 1);
+    let result = verify_assertions(&assertions, &public_keys, &table_limbs, log_limb_size)
+        .await
+        .expect("Shader failed");
+    for (i, verdict) in result.iter().enumerate() {
+        assert_eq!(verdict.is_valid(), i > 1);
+    }
+}
@@ -0,0 +1,119 @@
+use crate::gpu::{create_command_encoder, Dispatch, GpuContext};
+use std::fmt;
+use std::time::{Duration, Instant};
+
+/// How the stage durations of a `StageTimings` report were measured.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TimingSource {
+    /// Each stage's compute pass wrote timestamps on the GPU.
+    GpuTimestamps,
+    /// The adapter does not support timestamp queries, so each stage was submitted on its own and
+    /// timed on the host from submission until the device was idle.
+    #[default]
+    CpuSubmitPoll,
+}
+
+/// A breakdown of where the time of a single call was spent.
+#[derive(Debug, Clone, Default)]
+pub struct StageTimings {
+    pub source: TimingSource,
+    /// Host-side time spent packing the inputs into upload memory.
+    pub upload: Duration,
+    /// The duration of each dispatch, labelled by what it computes.
+    pub stages: Vec<(&'static str, Duration)>,
+    /// Host-side time spent copying the outputs into staging buffers and reading them back.
+    pub readback: Duration,
+}
+
+impl StageTimings {
+    pub fn total(&self) -> Duration {
+        self.upload + self.stages.iter().map(|(_, d)| *d).sum::<Duration>() + self.readback
+    }
+}
+
+impl fmt::Display for StageTimings {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "| Stage                                    | Time (ms)          |")?;
+        writeln!(f, "| ---------------------------------------- | ------------------ |")?;
+        writeln!(f, "| {: <40} | {: <18.3} |", "upload", self.upload.as_secs_f64() * 1000.0)?;
+        for (label, duration) in &self.stages {
+            writeln!(f, "| {: <40} | {: <18.3} |", label, duration.as_secs_f64() * 1000.0)?;
+        }
+        writeln!(f, "| {: <40} | {: <18.3} |", "readback", self.readback.as_secs_f64() * 1000.0)?;
+        match self.source {
+            TimingSource::GpuTimestamps => write!(f, "\nStage timings are from GPU timestamp queries."),
+            TimingSource::CpuSubmitPoll => write!(f, "\nStage timings are from CPU-side submit/poll timing."),
+        }
+    }
+}
+
+/// Records `dispatches` after whatever `command_encoder` already holds (e.g. clears of the output
+/// buffers), submits them, waits for them to finish, and returns the duration of each one.
+pub async fn run_timed(
+    context: &GpuContext,
+    mut command_encoder: wgpu::CommandEncoder,
+    dispatches: &[Dispatch<'_>],
+) -> (TimingSource, Vec<(&'static str, Duration)>) {
+    let device = &context.device;
+    let queue = &context.queue;
+
+    if !context.supports_timestamp_queries() {
+        queue.submit(Some(command_encoder.finish()));
+        device.poll(wgpu::Maintain::Wait);
+
+        let mut stages = Vec::with_capacity(dispatches.len());
+        for dispatch in dispatches {
+            let sw = Instant::now();
+            let mut command_encoder = create_command_encoder(device);
+            dispatch.execute(&mut command_encoder);
+            queue.submit(Some(command_encoder.finish()));
+            device.poll(wgpu::Maintain::Wait);
+            stages.push((dispatch.label, sw.elapsed()));
+        }
+        return (TimingSource::CpuSubmitPoll, stages);
+    }
+
+    // Each pass writes a timestamp at its beginning and its end
+    let num_queries = 2 * dispatches.len() as u32;
+    let query_set = device.create_query_set(&wgpu::QuerySetDescriptor {
+        label: None,
+        ty: wgpu::QueryType::Timestamp,
+        count: num_queries,
+    });
+
+    for (i, dispatch) in dispatches.iter().enumerate() {
+        dispatch.execute_with_timestamps(
+            &mut command_encoder,
+            Some(wgpu::ComputePassTimestampWrites {
+                query_set: &query_set,
+                beginning_of_pass_write_index: Some(2 * i as u32),
+                end_of_pass_write_index: Some(2 * i as u32 + 1),
+            }),
+        );
+    }
+
+    let size = (num_queries as usize * std::mem::size_of::<u64>()) as u64;
+    let resolve_buf = context.pool.acquire(
+        device,
+        size,
+        wgpu::BufferUsages::QUERY_RESOLVE | wgpu::BufferUsages::COPY_SRC,
+    );
+    command_encoder.resolve_query_set(&query_set, 0..num_queries, &resolve_buf, 0);
+
+    let timestamps = context.finish_encoder_and_read_bytes(command_encoder, &[&resolve_buf]).await;
+    context.pool.release(resolve_buf);
+
+    let timestamps: &[u64] = bytemuck::cast_slice(&timestamps[0][..size as usize]);
+    let period = queue.get_timestamp_period() as f64;
+
+    let stages = dispatches
+        .iter()
+        .enumerate()
+        .map(|(i, dispatch)| {
+            let ticks = timestamps[2 * i + 1].saturating_sub(timestamps[2 * i]);
+            (dispatch.label, Duration::from_nanos((ticks as f64 * period) as u64))
+        })
+        .collect();
+
+    (TimingSource::GpuTimestamps, stages)
+}
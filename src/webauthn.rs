@@ -0,0 +1,105 @@
+use crate::gpu::GpuContext;
+use crate::sec1::CompressedPublicKey;
+use crate::secp256r1_ecdsa::ecverify_compressed_with_context;
+use crate::signature_encoding::{compact_from_parts, parse_der};
+use crate::Verdict;
+use fuel_crypto::Message;
+use fuel_types::Bytes64;
+use p256::elliptic_curve::sec1::ToEncodedPoint;
+use p256::PublicKey;
+use sha2::{Digest, Sha256};
+
+/// The parts of a WebAuthn assertion which the signature covers, and the signature itself.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WebAuthnAssertion {
+    pub authenticator_data: Vec<u8>,
+    pub client_data_json: Vec<u8>,
+    /// The DER-encoded ECDSA signature, as returned by the authenticator.
+    pub signature: Vec<u8>,
+}
+
+impl WebAuthnAssertion {
+    /// The digest which the authenticator signs: sha256(authenticatorData ||
+    /// sha256(clientDataJSON)). There is no SHA-256 shader, so it is computed on the host.
+    pub fn digest(&self) -> Message {
+        let client_data_hash = Sha256::digest(&self.client_data_json);
+        let mut signed_data = self.authenticator_data.clone();
+        signed_data.extend_from_slice(&client_data_hash);
+        // Message::new hashes its input with SHA-256
+        Message::new(signed_data)
+    }
+}
+
+/// Verifies each assertion's signature against the public key of its credential on the secp256r1
+/// pipeline. Only the signature is checked: the relying party must still check the challenge,
+/// origin, and flags in the assertion.
+pub async fn verify_assertions(
+    assertions: &[WebAuthnAssertion],
+    public_keys: &[PublicKey],
+    table_limbs: &[u32],
+    log_limb_size: u32,
+) -> Result<Vec<Verdict>, crate::ShaderFailureError> {
+    let context = GpuContext::new().await;
+    verify_assertions_with_context(&context, assertions, public_keys, table_limbs, log_limb_size)
+        .await
+}
+
+/// Like `verify_assertions`, but runs on an existing context so that buffers are reused across
+/// calls.
+///
+/// A DER signature carries no recovery id, so each assertion is verified twice, once for each
+/// parity of the y-coordinate of R, in a single batch of `2 * assertions.len()` signatures. An
+/// assertion is valid if either recovers its public key. High s values are normalised by
+/// `compact_from_parts`, and assertions whose signatures cannot be parsed are invalid.
+///
+/// Recovery takes the x-coordinate of R to be r, but the signer reduced it modulo n, so a
+/// signature whose R has an x-coordinate of n or more, i.e. r + n, is rejected although it is
+/// valid. As p - n < 2 ^ 127 for P-256, this happens to about one signature in 2 ^ 130. Callers
+/// which cannot tolerate such false negatives should verify rejected assertions on the CPU.
+pub async fn verify_assertions_with_context(
+    context: &GpuContext,
+    assertions: &[WebAuthnAssertion],
+    public_keys: &[PublicKey],
+    table_limbs: &[u32],
+    log_limb_size: u32,
+) -> Result<Vec<Verdict>, crate::ShaderFailureError> {
+    assert_eq!(assertions.len(), public_keys.len());
+    let n = crate::moduli::secp256r1_fr_modulus_biguint();
+
+    let mut is_decodable = Vec::with_capacity(assertions.len());
+    let mut signatures = Vec::with_capacity(2 * assertions.len());
+    let mut messages = Vec::with_capacity(2 * assertions.len());
+    let mut compressed_pks = Vec::with_capacity(2 * assertions.len());
+    for (assertion, pk) in assertions.iter().zip(public_keys) {
+        let rs = parse_der(&assertion.signature);
+        let digest = assertion.digest();
+        let compressed_pk: CompressedPublicKey =
+            pk.to_encoded_point(true).as_bytes().try_into().unwrap();
+
+        let mut decodable = true;
+        for is_y_odd in [0, 1] {
+            let compact = rs.as_ref().and_then(|(r, s)| compact_from_parts(r, s, is_y_odd, &n));
+            decodable &= compact.is_some();
+            signatures.push(Bytes64::from(compact.unwrap_or([0u8; 64])));
+            messages.push(digest);
+            compressed_pks.push(compressed_pk);
+        }
+        is_decodable.push(decodable);
+    }
+
+    let verdicts = ecverify_compressed_with_context(
+        context,
+        &signatures,
+        &messages,
+        &compressed_pks,
+        table_limbs,
+        log_limb_size,
+    )
+    .await?;
+
+    Ok(verdicts
+        .chunks_exact(2)
+        .zip(is_decodable)
+        .map(|(v, decodable)| Verdict::from(decodable && (v[0].is_valid() || v[1].is_valid())))
+        .collect())
+}
@@ -79,6 +79,41 @@ fn bigint_sub(
     return result;
 }
 
+/*
+ * Writes lhs - rhs, wrapped modulo 2 ^ ({{ num_limbs }} * {{ log_limb_size }}), to result and
+ * returns the borrow, i.e. 1 if lhs < rhs. Unlike bigint_sub, it does not branch on the limbs.
+ */
+fn bigint_sub_with_borrow(
+    lhs: ptr<function, BigInt>,
+    rhs: ptr<function, BigInt>,
+    result: ptr<function, BigInt>,
+) -> u32 {
+    var borrow: u32 = 0u;
+
+    for (var i: u32 = 0u; i < {{ num_limbs }}u; i ++) {
+        // Limbs are much narrower than 32 bits, so a negative difference sets the top bit
+        let d = (*lhs).limbs[i] - (*rhs).limbs[i] - borrow;
+        borrow = d >> 31u;
+        (*result).limbs[i] = (d + (borrow << {{ log_limb_size }}u)) & {{ mask }}u;
+    }
+    return borrow;
+}
+
+/*
+ * Returns t if cond is true and f otherwise, like the builtin select(), without branching
+ */
+fn bigint_select(
+    f: ptr<function, BigInt>,
+    t: ptr<function, BigInt>,
+    cond: bool,
+) -> BigInt {
+    var result: BigInt;
+    for (var i: u32 = 0u; i < {{ num_limbs }}u; i ++) {
+        result.limbs[i] = select((*f).limbs[i], (*t).limbs[i], cond);
+    }
+    return result;
+}
+
 fn bigint_wide_sub(
     lhs: ptr<function, BigIntMediumWide>,
     rhs: ptr<function, BigIntMediumWide>,
@@ -195,18 +230,18 @@ fn bigint_div2(
 }
 
 struct BitsResult {
-    bits: array<bool, 256>,
+    bits: array<bool, {{ num_bits }}>,
     num_bits: u32
 }
 
 /*
  * Calculate the binary expansion of x, which must not be in Montgomery form.
- * Supports up to 256 bits.
+ * Supports up to {{ num_bits }} bits.
  */
 fn bigint_to_bits_le(
     x: ptr<function, BigInt>
 ) -> BitsResult {
-    var bits: array<bool, 256>;
+    var bits: array<bool, {{ num_bits }}>;
     var num_bits = 0u;
 
     var s = *x;
@@ -222,6 +257,16 @@ fn bigint_to_bits_le(
     return BitsResult(bits, num_bits);
 }
 
+/*
+ * Returns bit i of x, which must not be in Montgomery form.
+ */
+fn bigint_get_bit(
+    x: ptr<function, BigInt>,
+    i: u32
+) -> bool {
+    return (((*x).limbs[i / {{ log_limb_size }}u] >> (i % {{ log_limb_size }}u)) & 1u) == 1u;
+}
+
 fn bigint_shr_384(
     v: ptr<function, BigIntWide>
 ) -> BigInt {
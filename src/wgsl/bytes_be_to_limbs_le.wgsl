@@ -21,12 +21,12 @@ fn u32_be_to_le(val: u32) -> u32 {
 }
 
 fn bytes_be_to_limbs_le(
-    bytes: ptr<function, array<u32, 32>>
+    bytes: ptr<function, array<u32, {{ num_bytes }}>>
 ) -> BigInt {
     var result: BigInt;
     var log_limb_size = {{ log_limb_size }}u;
 
-    for (var i = 0u; i < (256u / log_limb_size); i ++) {
+    for (var i = 0u; i < ({{ num_bits }}u / log_limb_size); i ++) {
         var x = (log_limb_size * (i + 1u)) % 8u;
         var y_p_z = log_limb_size - x;
         var y = 0u;
@@ -41,7 +41,7 @@ fn bytes_be_to_limbs_le(
 
         let bx = (log_limb_size * (i + 1u)) / 8u;
 
-        if (bx > 31u) {
+        if (bx > {{ num_bytes - 1 }}u) {
             break;
         }
 
@@ -57,9 +57,9 @@ fn bytes_be_to_limbs_le(
         var y_mask = ((1u << y) - 1u);
         var z_mask = ((1u << z) - 1u);
 
-        var byte_x: u32 = (*bytes)[31u - bx];
-        var byte_y: u32 = (*bytes)[31u - by];
-        var byte_z: u32 = (*bytes)[31u - bz];
+        var byte_x: u32 = (*bytes)[{{ num_bytes - 1 }}u - bx];
+        var byte_y: u32 = (*bytes)[{{ num_bytes - 1 }}u - by];
+        var byte_z: u32 = (*bytes)[{{ num_bytes - 1 }}u - bz];
 
         var oz: u32;
         if (z >= 8u) {
@@ -76,11 +76,11 @@ fn bytes_be_to_limbs_le(
         result.limbs[i] = limb;
     }
 
-    if (log_limb_size == 15u) {
+    if ({{ num_bits }}u == 256u && log_limb_size == 15u) {
         var limb = (*bytes)[0] >> 7u;
         result.limbs[{{ num_limbs - 2 }}] = limb;
     } else {
-        var a: u32 = {{ num_limbs }}u * log_limb_size - 256u;
+        var a: u32 = {{ num_limbs }}u * log_limb_size - {{ num_bits }}u;
         var limb: u32;
         if ((log_limb_size - a) > 8) {
             var b: u32 = log_limb_size - a - 8u;
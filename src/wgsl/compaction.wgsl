@@ -0,0 +1,23 @@
+// The survivors of the decode stage with compaction::Compaction::Indirect: the signatures which it
+// could decode, by their index in the batch, in the order in which they were appended.
+struct Survivors {
+    count: u32,
+    ids: array<u32>
+}
+
+// The same buffer as Survivors, for the decode stage, which appends to it
+struct AtomicSurvivors {
+    count: atomic<u32>,
+    ids: array<u32>
+}
+
+// The most workgroups in a row of the indirect dispatches over the survivors, which is the default
+// max_compute_workgroups_per_dimension
+const MAX_SURVIVOR_ROW_WORKGROUPS: u32 = 65535u;
+
+// The index in the list of survivors of an invocation of an indirect dispatch over them, which
+// the compaction stage lays out in rows of MAX_SURVIVOR_ROW_WORKGROUPS workgroups of 256
+// invocations each, so that batches with more survivors than fit in one row use several
+fn survivor_index(global_id: vec3<u32>) -> u32 {
+    return global_id.y * MAX_SURVIVOR_ROW_WORKGROUPS * 256u + global_id.x;
+}
@@ -0,0 +1,349 @@
+// Cooperative big-integer arithmetic, in which {{ coop_lanes }} invocations (the lanes of a group)
+// share every field element. Lane j holds limbs [j * {{ coop_slice_len }}, (j + 1) *
+// {{ coop_slice_len }}) of each element in a Slice, so that each invocation keeps only a fraction
+// of a point in registers, and the lanes exchange limbs through workgroup memory.
+//
+// Every function here contains barriers, so it must be called in uniform control flow: all the
+// invocations of a workgroup must make the same calls in the same order. Data-dependent choices
+// are made with coop_select instead of branches.
+//
+// Requires bigint.wgsl and constants.wgsl. The column sums below fit in a u32 only for limbs of at
+// most 13 bits, which shader::render_main_cooperative checks.
+
+struct Slice {
+    limbs: array<u32, {{ coop_slice_len }}>
+}
+
+struct CoopPoint {
+    x: Slice,
+    y: Slice,
+    z: Slice
+}
+
+// The lane of this invocation within its group, and the index of the group in the workgroup
+var<private> coop_lane: u32;
+var<private> coop_group: u32;
+
+// Constants which the point formulas multiply by, as slices
+var<private> coop_ar: Slice;
+var<private> coop_br3: Slice;
+var<private> coop_one: Slice;
+
+// The operands and the double-width accumulator of each group
+var<workgroup> coop_x: array<array<u32, {{ num_limbs }}>, {{ coop_groups }}>;
+var<workgroup> coop_y: array<array<u32, {{ num_limbs }}>, {{ coop_groups }}>;
+var<workgroup> coop_w: array<array<u32, {{ num_limbs * 2 }}>, {{ coop_groups }}>;
+
+// p and -p^-1 mod R, which every group reads
+var<workgroup> coop_p: array<u32, {{ num_limbs }}>;
+var<workgroup> coop_np: array<u32, {{ num_limbs }}>;
+
+fn get_coop_ar() -> BigInt {
+    {{ ar_bigint }}
+    return ar;
+}
+
+fn get_coop_br3() -> BigInt {
+    {{ br3_bigint }}
+    return br3;
+}
+
+fn get_coop_np() -> BigInt {
+    {{ coop_np_bigint }}
+    return coop_np;
+}
+
+/*
+ * Returns this lane's slice of x.
+ */
+fn coop_slice_of(x: ptr<function, BigInt>) -> Slice {
+    var s: Slice;
+    for (var k = 0u; k < {{ coop_slice_len }}u; k ++) {
+        let i = coop_lane * {{ coop_slice_len }}u + k;
+        if (i < {{ num_limbs }}u) {
+            s.limbs[k] = (*x).limbs[i];
+        }
+    }
+    return s;
+}
+
+/*
+ * Sets the lane and group of this invocation, and loads the constants. Must be called before any
+ * other function here.
+ */
+fn coop_init(local_index: u32) {
+    coop_lane = local_index % {{ coop_lanes }}u;
+    coop_group = local_index / {{ coop_lanes }}u;
+
+    if (local_index == 0u) {
+        var p = get_p();
+        var np = get_coop_np();
+        for (var i = 0u; i < {{ num_limbs }}u; i ++) {
+            coop_p[i] = p.limbs[i];
+            coop_np[i] = np.limbs[i];
+        }
+    }
+
+    var ar = get_coop_ar();
+    var br3 = get_coop_br3();
+    var r = get_r();
+    coop_ar = coop_slice_of(&ar);
+    coop_br3 = coop_slice_of(&br3);
+    coop_one = coop_slice_of(&r);
+
+    workgroupBarrier();
+}
+
+/*
+ * Writes this lane's slices of a and b to the group's operands.
+ */
+fn coop_store(a: ptr<function, Slice>, b: ptr<function, Slice>) {
+    let g = coop_group;
+    for (var k = 0u; k < {{ coop_slice_len }}u; k ++) {
+        let i = coop_lane * {{ coop_slice_len }}u + k;
+        if (i < {{ num_limbs }}u) {
+            coop_x[g][i] = (*a).limbs[k];
+            coop_y[g][i] = (*b).limbs[k];
+        }
+    }
+    workgroupBarrier();
+}
+
+/*
+ * Reads this lane's slice of the group's result, which the operations leave in coop_y.
+ */
+fn coop_load() -> Slice {
+    workgroupBarrier();
+    let g = coop_group;
+    var s: Slice;
+    for (var k = 0u; k < {{ coop_slice_len }}u; k ++) {
+        let i = coop_lane * {{ coop_slice_len }}u + k;
+        if (i < {{ num_limbs }}u) {
+            s.limbs[k] = coop_y[g][i];
+        }
+    }
+    // The next operation must not overwrite coop_y before every lane has read it
+    workgroupBarrier();
+    return s;
+}
+
+/*
+ * Propagates the carries of the first `len` limbs of the group's accumulator. Only lane 0 calls
+ * this.
+ */
+fn coop_carry_w(len: u32) {
+    let g = coop_group;
+    var carry = 0u;
+    for (var i = 0u; i < len; i ++) {
+        let v = coop_w[g][i] + carry;
+        coop_w[g][i] = v & {{ mask }}u;
+        carry = v >> {{ log_limb_size }}u;
+    }
+}
+
+/*
+ * Writes s - p to coop_y if s >= p, or s otherwise, where s is the group's accumulator from limb
+ * `offset`. Only lane 0 calls this.
+ */
+fn coop_conditional_reduce_w(offset: u32) {
+    let g = coop_group;
+    var borrow = 0u;
+    for (var i = 0u; i < {{ num_limbs }}u; i ++) {
+        let d = coop_w[g][offset + i] - coop_p[i] - borrow;
+        coop_y[g][i] = d & {{ mask }}u;
+        borrow = d >> 31u;
+    }
+    if (borrow == 1u) {
+        for (var i = 0u; i < {{ num_limbs }}u; i ++) {
+            coop_y[g][i] = coop_w[g][offset + i];
+        }
+    }
+}
+
+/*
+ * The Montgomery product of a and b. The lanes split the columns of each of the three products
+ * (a * b, the low half of its product with -p^-1, and that with p), and lane 0 propagates the
+ * carries in between.
+ */
+fn coop_mont_mul(a: ptr<function, Slice>, b: ptr<function, Slice>) -> Slice {
+    let g = coop_group;
+    coop_store(a, b);
+
+    // w = a * b
+    for (var c = coop_lane; c < {{ num_limbs * 2 }}u; c += {{ coop_lanes }}u) {
+        var sum = 0u;
+        let lo = select(0u, c - {{ num_limbs - 1 }}u, c > {{ num_limbs - 1 }}u);
+        let hi = min(c, {{ num_limbs - 1 }}u);
+        for (var i = lo; i <= hi; i ++) {
+            sum += coop_x[g][i] * coop_y[g][c - i];
+        }
+        coop_w[g][c] = sum;
+    }
+    workgroupBarrier();
+    if (coop_lane == 0u) {
+        coop_carry_w({{ num_limbs * 2 }}u);
+    }
+    workgroupBarrier();
+
+    // m = w * -p^-1 mod R, into coop_x
+    for (var c = coop_lane; c < {{ num_limbs }}u; c += {{ coop_lanes }}u) {
+        var sum = 0u;
+        for (var i = 0u; i <= c; i ++) {
+            sum += coop_w[g][i] * coop_np[c - i];
+        }
+        coop_x[g][c] = sum;
+    }
+    workgroupBarrier();
+    if (coop_lane == 0u) {
+        var carry = 0u;
+        for (var i = 0u; i < {{ num_limbs }}u; i ++) {
+            let v = coop_x[g][i] + carry;
+            coop_x[g][i] = v & {{ mask }}u;
+            carry = v >> {{ log_limb_size }}u;
+        }
+    }
+    workgroupBarrier();
+
+    // w += m * p, whose low half is then zero
+    for (var c = coop_lane; c < {{ num_limbs * 2 }}u; c += {{ coop_lanes }}u) {
+        var sum = coop_w[g][c];
+        let lo = select(0u, c - {{ num_limbs - 1 }}u, c > {{ num_limbs - 1 }}u);
+        let hi = min(c, {{ num_limbs - 1 }}u);
+        for (var i = lo; i <= hi; i ++) {
+            sum += coop_x[g][i] * coop_p[c - i];
+        }
+        coop_w[g][c] = sum;
+    }
+    workgroupBarrier();
+    if (coop_lane == 0u) {
+        coop_carry_w({{ num_limbs * 2 }}u);
+        // w / R < 2p
+        coop_conditional_reduce_w({{ num_limbs }}u);
+    }
+    return coop_load();
+}
+
+/*
+ * (a + b) mod p, which lane 0 computes.
+ */
+fn coop_add(a: ptr<function, Slice>, b: ptr<function, Slice>) -> Slice {
+    let g = coop_group;
+    coop_store(a, b);
+    if (coop_lane == 0u) {
+        for (var i = 0u; i < {{ num_limbs }}u; i ++) {
+            coop_w[g][i] = coop_x[g][i] + coop_y[g][i];
+        }
+        // p is at least a bit narrower than the limbs, so there is no carry out
+        coop_carry_w({{ num_limbs }}u);
+        coop_conditional_reduce_w(0u);
+    }
+    return coop_load();
+}
+
+/*
+ * (a - b) mod p, which lane 0 computes.
+ */
+fn coop_sub(a: ptr<function, Slice>, b: ptr<function, Slice>) -> Slice {
+    let g = coop_group;
+    coop_store(a, b);
+    if (coop_lane == 0u) {
+        var borrow = 0u;
+        for (var i = 0u; i < {{ num_limbs }}u; i ++) {
+            let d = coop_x[g][i] - coop_y[g][i] - borrow;
+            coop_y[g][i] = d & {{ mask }}u;
+            borrow = d >> 31u;
+        }
+        if (borrow == 1u) {
+            var carry = 0u;
+            for (var i = 0u; i < {{ num_limbs }}u; i ++) {
+                let v = coop_y[g][i] + coop_p[i] + carry;
+                coop_y[g][i] = v & {{ mask }}u;
+                carry = v >> {{ log_limb_size }}u;
+            }
+        }
+    }
+    return coop_load();
+}
+
+fn coop_select(f: ptr<function, Slice>, t: ptr<function, Slice>, cond: bool) -> Slice {
+    var s: Slice;
+    for (var k = 0u; k < {{ coop_slice_len }}u; k ++) {
+        s.limbs[k] = select((*f).limbs[k], (*t).limbs[k], cond);
+    }
+    return s;
+}
+
+fn coop_point_select(f: ptr<function, CoopPoint>, t: ptr<function, CoopPoint>, cond: bool) -> CoopPoint {
+    var x = coop_select(&(*f).x, &(*t).x, cond);
+    var y = coop_select(&(*f).y, &(*t).y, cond);
+    var z = coop_select(&(*f).z, &(*t).z, cond);
+    return CoopPoint(x, y, z);
+}
+
+/*
+ * The point at infinity, (0 : 1 : 0) in Montgomery form.
+ */
+fn coop_identity() -> CoopPoint {
+    var zero: Slice;
+    return CoopPoint(zero, coop_one, zero);
+}
+
+/*
+ * Algorithm 1 of "Complete addition formulas for prime order elliptic curves" by Renes, Costello,
+ * and Batina (https://eprint.iacr.org/2015/1060), for any a. It is complete, so it also doubles,
+ * and has no branches, which the barriers need.
+ * Cost: 12M + 3m_a + 2m_3b
+ */
+fn coop_point_add(a: ptr<function, CoopPoint>, b: ptr<function, CoopPoint>) -> CoopPoint {
+    var x1 = (*a).x;
+    var y1 = (*a).y;
+    var z1 = (*a).z;
+    var x2 = (*b).x;
+    var y2 = (*b).y;
+    var z2 = (*b).z;
+    var ar = coop_ar;
+    var br3 = coop_br3;
+
+    var t0 = coop_mont_mul(&x1, &x2);
+    var t1 = coop_mont_mul(&y1, &y2);
+    var t2 = coop_mont_mul(&z1, &z2);
+    var t3 = coop_add(&x1, &y1);
+    var t4 = coop_add(&x2, &y2);
+    t3 = coop_mont_mul(&t3, &t4);
+    t4 = coop_add(&t0, &t1);
+    t3 = coop_sub(&t3, &t4);
+    t4 = coop_add(&x1, &z1);
+    var t5 = coop_add(&x2, &z2);
+    t4 = coop_mont_mul(&t4, &t5);
+    t5 = coop_add(&t0, &t2);
+    t4 = coop_sub(&t4, &t5);
+    t5 = coop_add(&y1, &z1);
+    var x3 = coop_add(&y2, &z2);
+    t5 = coop_mont_mul(&t5, &x3);
+    x3 = coop_add(&t1, &t2);
+    t5 = coop_sub(&t5, &x3);
+    var z3 = coop_mont_mul(&ar, &t4);
+    x3 = coop_mont_mul(&br3, &t2);
+    z3 = coop_add(&x3, &z3);
+    x3 = coop_sub(&t1, &z3);
+    z3 = coop_add(&t1, &z3);
+    var y3 = coop_mont_mul(&x3, &z3);
+    t1 = coop_add(&t0, &t0);
+    t1 = coop_add(&t1, &t0);
+    t2 = coop_mont_mul(&ar, &t2);
+    t4 = coop_mont_mul(&br3, &t4);
+    t1 = coop_add(&t1, &t2);
+    t2 = coop_sub(&t0, &t2);
+    t2 = coop_mont_mul(&ar, &t2);
+    t4 = coop_add(&t4, &t2);
+    t0 = coop_mont_mul(&t1, &t4);
+    y3 = coop_add(&y3, &t0);
+    t0 = coop_mont_mul(&t5, &t4);
+    x3 = coop_mont_mul(&t3, &x3);
+    x3 = coop_sub(&x3, &t0);
+    t0 = coop_mont_mul(&t3, &t1);
+    z3 = coop_mont_mul(&t5, &z3);
+    z3 = coop_add(&z3, &t0);
+
+    return CoopPoint(x3, y3, z3);
+}
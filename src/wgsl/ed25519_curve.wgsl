@@ -290,3 +290,114 @@ fn ete_fixed_mul(
 
     return result;
 }
+{% if checkpoint_bits %}
+
+// The state of a scalar multiplication which checkpoint::Checkpointing splits across dispatches:
+// the accumulator, the doubled base of double-and-add, whether the accumulator is still the point
+// at infinity, and how many bits of the scalar have been processed. The host zeroes the
+// checkpoints before the first sub-dispatch.
+struct MulCheckpoint {
+    acc: ETEPoint,
+    temp: ETEPoint,
+    is_inf: u32,
+    num_bits_done: u32
+}
+
+/*
+ * Resumes ete_mul from ckpt for the next {{ checkpoint_bits }} bits of x. The bits above the
+ * highest set bit only double temp, so after all 256 bits, acc is what ete_mul returns. pt is only
+ * read by the first sub-dispatch.
+ */
+fn ete_mul_resume(
+    ckpt: ptr<function, MulCheckpoint>,
+    pt: ptr<function, ETEPoint>,
+    x: ptr<function, BigInt>,
+    p: ptr<function, BigInt>
+) {
+    let start = (*ckpt).num_bits_done;
+    if (start == 0u) {
+        var zero: BigInt;
+        var one: BigInt;
+        one.limbs[0] = 1u;
+        (*ckpt).acc = ETEPoint(zero, one, zero, one);
+        (*ckpt).temp = *pt;
+        (*ckpt).is_inf = 1u;
+    }
+
+    var result = (*ckpt).acc;
+    var temp = (*ckpt).temp;
+    var result_is_inf = (*ckpt).is_inf == 1u;
+    let end = min(start + {{ checkpoint_bits }}u, 256u);
+    for (var i = start; i < end; i ++) {
+        if (bigint_get_bit(x, i)) {
+            if (result_is_inf) {
+                result = temp;
+                result_is_inf = false;
+            } else {
+                result = ete_add_2008_hwcd_3(&result, &temp, p);
+            }
+        }
+        temp = ete_dbl_2008_hwcd(&temp, p);
+    }
+
+    (*ckpt).acc = result;
+    (*ckpt).temp = temp;
+    (*ckpt).is_inf = select(0u, 1u, result_is_inf);
+    (*ckpt).num_bits_done = end;
+}
+
+/*
+ * Resumes ete_fixed_mul from ckpt for the next {{ checkpoint_windows }} windows of s, from the
+ * most significant.
+ */
+fn ete_fixed_mul_resume(
+    ckpt: ptr<function, MulCheckpoint>,
+    table: ptr<function, array<ETEXYT, {{ table_size }}>>,
+    s: ptr<function, BigInt>,
+    p: ptr<function, BigInt>,
+    r: ptr<function, BigInt>,
+) {
+    if ((*ckpt).num_bits_done == 0u) {
+        var inf: ETEPoint;
+        (*ckpt).acc = inf;
+        (*ckpt).is_inf = 1u;
+    }
+
+    var result = (*ckpt).acc;
+    var result_is_inf = (*ckpt).is_inf == 1u;
+    var i = 256u - (*ckpt).num_bits_done;
+    for (var w = 0u; w < {{ checkpoint_windows }}u && i > 0u; w ++) {
+        var bits = 0u;
+        for (var j = 0u; j < {{ log_table_size }}u; j ++){
+            if (i > 0u) {
+                i -= 1u;
+                bits <<= 1u;
+                if (bigint_get_bit(s, i)) {
+                    bits |= 1u;
+                }
+            }
+        }
+
+        if (!result_is_inf) {
+            for (var j = 0u; j < {{ log_table_size }}u; j ++){
+                result = ete_dbl_2008_hwcd(&result, p);
+            }
+        }
+
+        if (bits != 0u) {
+            var table_pt = (*table)[bits - 1u];
+            var t = ETEPoint(table_pt.x, table_pt.y, table_pt.t, *r);
+            if (result_is_inf) {
+                result = t;
+            } else {
+                result = ete_add_2008_hwcd_3(&result, &t, p);
+            }
+            result_is_inf = false;
+        }
+    }
+
+    (*ckpt).acc = result;
+    (*ckpt).is_inf = select(0u, 1u, result_is_inf);
+    (*ckpt).num_bits_done = 256u - i;
+}
+{% endif %}
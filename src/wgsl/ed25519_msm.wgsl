@@ -0,0 +1,30 @@
+{% include "ed25519_curve.wgsl" %}
+{% include "ed25519_constants.wgsl" %}
+{% include "ed25519_utils.wgsl" %}
+{% include "limbs_le_to_u32s_be.wgsl" %}
+
+// The point type and group operations which main/msm_main_*.wgsl and main/ops_*_main.wgsl are
+// written against. The addition and doubling must handle the point at infinity, and the addition
+// must handle equal points.
+alias MsmPoint = ETEPoint;
+
+fn msm_identity() -> MsmPoint {
+    var zero: BigInt;
+    var r = get_r();
+    return ETEPoint(zero, r, zero, r);
+}
+
+fn msm_add(
+    a: ptr<function, MsmPoint>,
+    b: ptr<function, MsmPoint>,
+    p: ptr<function, BigInt>
+) -> MsmPoint {
+    return ete_add_2008_hwcd_3(a, b, p);
+}
+
+fn msm_dbl(
+    a: ptr<function, MsmPoint>,
+    p: ptr<function, BigInt>
+) -> MsmPoint {
+    return ete_dbl_2008_hwcd(a, p);
+}
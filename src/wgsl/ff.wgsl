@@ -48,75 +48,87 @@ fn ff_sub(
 }
 
 /*
-  From "Efficient Software-Implementation of Finite Fields with Applications
-  to Cryptography" by Guajardo, et al, Algorithm 16
-  https://www.sandeep.de/my/papers/2006_ActaApplMath_EfficientSoftFiniteF.pdf
+ * Returns lhs - rhs % p for lhs and rhs in [0, p), like ff_sub, without branching
+ */
+fn ff_sub_branchless(
+    lhs: ptr<function, BigInt>,
+    rhs: ptr<function, BigInt>,
+    p: ptr<function, BigInt>,
+) -> BigInt {
+    var diff: BigInt;
+    let borrow = bigint_sub_with_borrow(lhs, rhs, &diff);
 
-  Note that x must not be in Montgomery form.
+    // If lhs < rhs, diff wrapped around, and adding p with the carry dropped gives p - (rhs - lhs)
+    var diff_p = bigint_add_unsafe(&diff, p);
+    return bigint_select(&diff, &diff_p, borrow == 1u);
+}
 
-  TODO: find out why this, when used, may cause the whole shader to silently fail
-  TODO: find out why this fails if x == 0
-*/
+/*
+ * Returns x / 2 % p for x in [0, p), without branching
+ */
+fn ff_div2(
+    x: ptr<function, BigInt>,
+    p: ptr<function, BigInt>,
+) -> BigInt {
+    var xp = bigint_add_unsafe(x, p);
+    var even = bigint_select(&xp, x, bigint_is_even(x));
+    return bigint_div2(&even);
+}
+
+/*
+ * Returns x ^ -1 % p for x in [0, p) and an odd prime p, or 0 if x == 0. Works for any such p
+ * (e.g. the scalar field moduli in the ECDSA shaders), as it does not use Montgomery
+ * multiplication, whose constants are specific to the base field.
+ *
+ * This is a binary extended GCD which runs a fixed number of iterations and selects the result
+ * of each step instead of branching, so that every thread in a workgroup runs the same
+ * instructions. It keeps b * x == u and c * x == v (mod p), starting with u = x and v = p. Each
+ * iteration subtracts the smaller of u and v from the larger if both are odd, which keeps the
+ * other odd, and then halves whichever is even, so u * v at least halves until v == 1. As
+ * x * p < 2 ^ (2 * {{ num_bits }}), 2 * {{ num_bits }} iterations leave v == gcd(x, p) == 1 and
+ * c == x ^ -1, after which further iterations only change u and b. If x == 0, u stays 0 and c
+ * stays 0.
+ */
 fn ff_inverse(
     x: ptr<function, BigInt>,
     p: ptr<function, BigInt>,
 ) -> BigInt {
-    var c: BigInt;
+    var u = *x;
+    var v = *p;
     var b: BigInt;
     b.limbs[0] = 1u;
+    var c: BigInt;
 
-    // u = x; v = p
-    var u: BigInt;
-    var v: BigInt;
-    for (var i = 0u; i < {{ num_limbs }}u; i ++) {
-        u.limbs[i] = (*x).limbs[i];
-        v.limbs[i] = (*p).limbs[i];
-    }
-
-    while (!bigint_is_one(&u) && !bigint_is_one(&v)) {
-        while (bigint_is_even(&u)) {
-            u = bigint_div2(&u);
-
-            if (bigint_is_even(&b)) {
-                b = bigint_div2(&b);
-            } else {
-                var bp = bigint_add_unsafe(&b, p);
-                b = bigint_div2(&bp);
-            }
-        }
-
-        while (bigint_is_even(&v)) {
-            v = bigint_div2(&v);
-
-            if (bigint_is_even(&c)) {
-                c = bigint_div2(&c);
-            } else {
-                var cp = bigint_add_unsafe(&c, p);
-                c = bigint_div2(&cp);
-            }
-        }
-
-        if (bigint_gte(&u, &v)) {
-            u = ff_sub(&u, &v, p);
-            b = ff_sub(&b, &c, p);
-        } else {
-            v = ff_sub(&v, &u, p);
-            c = ff_sub(&c, &b, p);
-        }
-    }
-
-    var result: BigInt;
-    if (bigint_is_one(&u)) {
-        result = b;
-    } else {
-        result = c;
-    }
-
-    if bigint_gte(&result, p) {
-        result = bigint_sub(&result, p);
+    for (var i = 0u; i < 2u * {{ num_bits }}u; i ++) {
+        let both_odd = !bigint_is_even(&u) && !bigint_is_even(&v);
+
+        var u_minus_v: BigInt;
+        let u_lt_v = bigint_sub_with_borrow(&u, &v, &u_minus_v) == 1u;
+        var v_minus_u: BigInt;
+        bigint_sub_with_borrow(&v, &u, &v_minus_u);
+        var b_minus_c = ff_sub_branchless(&b, &c, p);
+        var c_minus_b = ff_sub_branchless(&c, &b, p);
+
+        let sub_from_u = both_odd && !u_lt_v;
+        let sub_from_v = both_odd && u_lt_v;
+        u = bigint_select(&u, &u_minus_v, sub_from_u);
+        b = bigint_select(&b, &b_minus_c, sub_from_u);
+        v = bigint_select(&v, &v_minus_u, sub_from_v);
+        c = bigint_select(&c, &c_minus_b, sub_from_v);
+
+        // Exactly one of u and v is even
+        let u_is_even = bigint_is_even(&u);
+        var u_half = bigint_div2(&u);
+        var b_half = ff_div2(&b, p);
+        var v_half = bigint_div2(&v);
+        var c_half = ff_div2(&c, p);
+        u = bigint_select(&u, &u_half, u_is_even);
+        b = bigint_select(&b, &b_half, u_is_even);
+        v = bigint_select(&v_half, &v, u_is_even);
+        c = bigint_select(&c_half, &c, u_is_even);
     }
 
-    return result;
+    return c;
 }
 
 fn bigint_mul(a: ptr<function, BigInt>, b: ptr<function, BigInt>) -> BigIntWide {
@@ -3,7 +3,7 @@ fn byte_from_limbs_le(
     idx: u32,
     log_limb_size: u32,
 ) -> u32 {
-    var i = 31u - idx;
+    var i = {{ num_bytes - 1 }}u - idx;
     // Calculate the bit position of the i-th byte
     var bit_pos = i * 8u;
 
@@ -25,9 +25,9 @@ fn byte_from_limbs_le(
     };
 }
 
-fn bytes_be_to_u32s(bytes: ptr<function, array<u32, 32>>) -> array<u32, 8> {
-    var result_arr: array<u32, 8>;
-    for (var i = 0u; i < 8u; i ++) {
+fn bytes_be_to_u32s(bytes: ptr<function, array<u32, {{ num_bytes }}>>) -> array<u32, {{ num_words }}> {
+    var result_arr: array<u32, {{ num_words }}>;
+    for (var i = 0u; i < {{ num_words }}u; i ++) {
         var r = 0u;
         r += (*bytes)[i * 4u];
         r += (*bytes)[i * 4u + 1u] << 8u;
@@ -41,9 +41,9 @@ fn bytes_be_to_u32s(bytes: ptr<function, array<u32, 32>>) -> array<u32, 8> {
 fn limbs_le_to_bytes_be(
     limbs: ptr<function, array<u32, {{ num_limbs }}>>,
     log_limb_size: u32,
-) -> array<u32, 32> {
-    var bytes: array<u32, 32>;
-    for (var i = 0u; i < 32u; i ++) {
+) -> array<u32, {{ num_bytes }}> {
+    var bytes: array<u32, {{ num_bytes }}>;
+    for (var i = 0u; i < {{ num_bytes }}u; i ++) {
         bytes[i] = byte_from_limbs_le(limbs, i, log_limb_size);
     }
 
@@ -53,7 +53,7 @@ fn limbs_le_to_bytes_be(
 fn limbs_le_to_u32s_be(
     limbs: ptr<function, array<u32, {{ num_limbs }}>>,
     log_limb_size: u32,
-) -> array<u32, 8> {
+) -> array<u32, {{ num_words }}> {
     // Convert limbs to bytes
     var bytes = limbs_le_to_bytes_be(limbs, log_limb_size);
 
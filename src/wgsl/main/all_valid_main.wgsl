@@ -0,0 +1,57 @@
+// The summary of a batch. first_failure is the complement of the lowest index of an invalid
+// signature, so that its zeroed value means that there is none, and bit i % 32 of bitmap[i / 32]
+// is set if signature i is valid.
+struct Summary {
+    first_failure: atomic<u32>,
+    bitmap: array<atomic<u32>>
+}
+
+@group(0) @binding(0) var<storage, read_write> verdicts: array<u32>;
+@group(0) @binding(1) var<storage, read_write> summary: Summary;
+// (number of signatures, whether to write the bitmap, unused, unused)
+@group(0) @binding(2) var<uniform> all_valid_params: vec4<u32>;
+@group(0) @binding(3) var<uniform> params: vec3<u32>;
+
+var<workgroup> workgroup_first_failure: atomic<u32>;
+
+/*
+ * Reduces the verdict of each signature, 1 if it is valid, to the summary. Each workgroup reduces
+ * its own signatures first, so that the summary is updated once per workgroup.
+ */
+@compute
+@workgroup_size(256)
+fn all_valid(
+    @builtin(global_invocation_id) global_id: vec3<u32>,
+    @builtin(local_invocation_index) local_index: u32
+) {
+    let gidx = global_id.x;
+    let gidy = global_id.y;
+    let gidz = global_id.z;
+    let num_x_workgroups = params[0];
+    let num_y_workgroups = params[1];
+    let num_z_workgroups = params[2];
+    let id = (gidx * num_y_workgroups + gidy) * num_z_workgroups + gidz;
+
+    let num_signatures = all_valid_params[0];
+    let has_bitmap = all_valid_params[1] == 1u;
+
+    // The padding of the batch is not part of the summary
+    if (id < num_signatures) {
+        if (verdicts[id] == 1u) {
+            if (has_bitmap) {
+                atomicOr(&summary.bitmap[id / 32u], 1u << (id % 32u));
+            }
+        } else {
+            atomicMax(&workgroup_first_failure, ~id);
+        }
+    }
+
+    workgroupBarrier();
+
+    if (local_index == 0u) {
+        let first_failure = atomicLoad(&workgroup_first_failure);
+        if (first_failure != 0u) {
+            atomicMax(&summary.first_failure, first_failure);
+        }
+    }
+}
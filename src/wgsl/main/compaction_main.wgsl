@@ -0,0 +1,22 @@
+{% include "compaction.wgsl" %}
+
+@group(0) @binding(0) var<storage, read_write> survivors: Survivors;
+@group(0) @binding(1) var<storage, read_write> num_workgroups: array<u32, 3>;
+@group(0) @binding(2) var<storage, read_write> canaries: array<atomic<u32>>;
+
+/*
+ * Writes the number of workgroups of 256 invocations over the survivors, for the indirect
+ * dispatches of the later stages. There is at least one, so that every later stage always runs
+ * and adds to its completion counter. Workgroups beyond the first row of
+ * MAX_SURVIVOR_ROW_WORKGROUPS go in further rows along y, which survivor_index accounts for.
+ */
+@compute
+@workgroup_size(1)
+fn compact() {
+    let total = max((survivors.count + 255u) / 256u, 1u);
+    num_workgroups[0] = min(total, MAX_SURVIVOR_ROW_WORKGROUPS);
+    num_workgroups[1] = (total + MAX_SURVIVOR_ROW_WORKGROUPS - 1u) / MAX_SURVIVOR_ROW_WORKGROUPS;
+    num_workgroups[2] = 1u;
+
+    atomicAdd(&canaries[{{ stage }}u], 1u);
+}
@@ -0,0 +1,81 @@
+{% include "bigint.wgsl" %}
+{% include "constants.wgsl" %}
+{% include "coop.wgsl" %}
+
+struct Point {
+    x: BigInt,
+    y: BigInt,
+    z: BigInt
+}
+
+// The same bindings as stage 2 of the secp256k1 and secp256r1 recovery pipelines, which this
+// replaces with Cooperation::Lanes
+@group(0) @binding(0) var<storage, read_write> scalars: array<BigInt>;
+@group(0) @binding(1) var<storage, read_write> points: array<Point>;
+@group(0) @binding(2) var<storage, read_write> result: array<Point>;
+@group(0) @binding(3) var<storage, read_write> canaries: array<atomic<u32>>;
+@group(0) @binding(4) var<uniform> params: vec3<u32>;
+
+// The scalar of each group, from which every lane reads the bits
+var<workgroup> coop_scalars: array<array<u32, {{ num_limbs }}>, {{ coop_groups }}>;
+
+/*
+ * result[i] = scalars[i] * points[i] in projective coordinates{% if coordinates == "jacobian" %},
+ * converted to Jacobian coordinates{% endif %}, with {{ coop_lanes }} invocations per point. The
+ * multiplication runs a fixed {{ num_bits }} rounds of double-and-add, and selects whether to
+ * keep each sum, so that every invocation reaches the barriers together.
+ */
+@compute
+@workgroup_size({{ coop_workgroup_size }})
+fn coop_scalar_mul(
+    @builtin(workgroup_id) workgroup_id: vec3<u32>,
+    @builtin(local_invocation_index) local_index: u32,
+) {
+    let num_y_workgroups = params[1];
+    let num_z_workgroups = params[2];
+    let workgroup_index = (workgroup_id.x * num_y_workgroups + workgroup_id.y) * num_z_workgroups + workgroup_id.z;
+
+    coop_init(local_index);
+    let g = coop_group;
+    let id = workgroup_index * {{ coop_groups }}u + g;
+
+    var pt: CoopPoint;
+    for (var k = 0u; k < {{ coop_slice_len }}u; k ++) {
+        let i = coop_lane * {{ coop_slice_len }}u + k;
+        if (i < {{ num_limbs }}u) {
+            pt.x.limbs[k] = points[id].x.limbs[i];
+            pt.y.limbs[k] = points[id].y.limbs[i];
+            pt.z.limbs[k] = points[id].z.limbs[i];
+            coop_scalars[g][i] = scalars[id].limbs[i];
+        }
+    }
+    workgroupBarrier();
+
+    var acc = coop_identity();
+    for (var idx = 0u; idx < {{ num_bits }}u; idx ++) {
+        let i = {{ num_bits - 1 }}u - idx;
+        acc = coop_point_add(&acc, &acc);
+        var sum = coop_point_add(&acc, &pt);
+        let bit = (coop_scalars[g][i / {{ log_limb_size }}u] >> (i % {{ log_limb_size }}u)) & 1u;
+        acc = coop_point_select(&acc, &sum, bit == 1u);
+    }
+
+    {% if coordinates == "jacobian" -%}
+    // (X : Y : Z) in projective coordinates is (XZ : YZ^2 : Z) in Jacobian coordinates
+    var z = acc.z;
+    acc.x = coop_mont_mul(&acc.x, &z);
+    var yz = coop_mont_mul(&acc.y, &z);
+    acc.y = coop_mont_mul(&yz, &z);
+    {% endif -%}
+
+    for (var k = 0u; k < {{ coop_slice_len }}u; k ++) {
+        let i = coop_lane * {{ coop_slice_len }}u + k;
+        if (i < {{ num_limbs }}u) {
+            result[id].x.limbs[i] = acc.x.limbs[k];
+            result[id].y.limbs[i] = acc.y.limbs[k];
+            result[id].z.limbs[i] = acc.z.limbs[k];
+        }
+    }
+
+    atomicAdd(&canaries[2], 1u);
+}
@@ -0,0 +1,78 @@
+{% include "bigint.wgsl" %}
+{% include "ff.wgsl" %}
+{% include "mont.wgsl" %}
+{% include "ed25519_curve.wgsl" %}
+{% include "constants.wgsl" %}
+{% include "ed25519_constants.wgsl" %}
+{% include "ed25519_utils.wgsl" %}
+{% include "bytes_be_to_limbs_le.wgsl" %}
+{% include "limbs_le_to_u32s_be.wgsl" %}
+{% include "sha512.wgsl" %}
+
+@group(0) @binding(0) var<storage, read_write> table: array<ETEXYT>;
+@group(0) @binding(1) var<storage, read_write> sk: array<u32>;
+@group(0) @binding(2) var<storage, read_write> result: array<u32>;
+@group(0) @binding(3) var<storage, read_write> canaries: array<atomic<u32>>;
+@group(0) @binding(4) var<uniform> params: vec3<u32>;
+
+@compute
+@workgroup_size(256)
+fn ed25519_derive_public_keys(@builtin(global_invocation_id) global_id: vec3<u32>) {
+    let gidx = global_id.x; 
+    let gidy = global_id.y; 
+    let gidz = global_id.z; 
+    let num_x_workgroups = params[0];
+    let num_y_workgroups = params[1];
+    let num_z_workgroups = params[2];
+    let id = (gidx * num_y_workgroups + gidy) * num_z_workgroups + gidz;
+
+    var p = get_p();
+    var r = get_r();
+    var p_wide = get_p_wide();
+    var rinv = get_rinv();
+    var mu_fp = get_mu_fp();
+
+    var table_size = {{ table_size }}u;
+    var table_pts: array<ETEXYT, {{ table_size }}>;
+    for (var i = 0u; i < table_size; i ++) {
+        table_pts[i] = table[i];
+    }
+
+    // Hash the 32-byte secret key
+    var sk_u32s: array<u32, 8>;
+    for (var i = 0u; i < 8u; i ++) {
+        sk_u32s[i] = u32_be_to_le(sk[id * 8u + i]);
+    }
+    var hash_u32s: array<u32, 16> = sha512_32(&sk_u32s);
+
+    // The scalar is the first half of the hash, read as a little-endian integer
+    var h: array<u32, 32>;
+    for (var i = 0u; i < 8u; i ++) {
+        for (var j = 0u; j < 4u; j ++) {
+            h[i * 4u + j] = (hash_u32s[i] >> (24u - j * 8u)) & 255u;
+        }
+    }
+
+    // Clamp the scalar
+    h[0] &= 248u;
+    h[31] &= 127u;
+    h[31] |= 64u;
+
+    var a_bytes_be: array<u32, 32>;
+    for (var i = 0u; i < 32u; i ++) {
+        a_bytes_be[i] = h[31u - i];
+    }
+    var a_val = bytes_be_to_limbs_le(&a_bytes_be);
+
+    // Multiply the base point by the clamped scalar. As the base point has prime order, the
+    // scalar does not need to be reduced.
+    var pk = ete_fixed_mul(&table_pts, &a_val, &p, &r);
+    var pk_affine = ete_to_affine_non_mont(&pk, &p, &p_wide, &r, &rinv, &mu_fp);
+
+    var compressed_y_u32s = compress_eteaffine(&pk_affine, {{ log_limb_size }}u);
+    for (var i = 0u; i < 8u; i ++) {
+        result[id * 8u + i] = u32_be_to_le(compressed_y_u32s[7u - i]);
+    }
+
+    atomicAdd(&canaries[0], 1u);
+}
@@ -10,6 +10,7 @@
 {% include "limbs_le_to_u32s_be.wgsl" %}
 {% include "sha512.wgsl" %}
 {% include "ed25519_reduce_fr.wgsl" %}
+{% include "compaction.wgsl" %}
 
 @group(0) @binding(0) var<storage, read_write> signature: array<u32>;
 @group(0) @binding(1) var<storage, read_write> pk: array<u32>;
@@ -17,8 +18,14 @@
 @group(0) @binding(3) var<storage, read_write> s: array<BigInt>;
 @group(0) @binding(4) var<storage, read_write> ayr: array<BigInt>;
 @group(0) @binding(5) var<storage, read_write> preimage: array<u32>;
-@group(0) @binding(6) var<storage, read_write> compressed_sign_bit: array<u32>;
+// The completion counter of each stage, to which every invocation adds one as it finishes
+@group(0) @binding(6) var<storage, read_write> canaries: array<atomic<u32>>;
+{% if compaction %}
+@group(0) @binding(7) var<storage, read_write> survivors: AtomicSurvivors;
+@group(0) @binding(8) var<uniform> params: vec3<u32>;
+{% else %}
 @group(0) @binding(7) var<uniform> params: vec3<u32>;
+{% endif %}
 
 @compute
 @workgroup_size(256)
@@ -54,7 +61,8 @@ fn ed25519_verify_main_0(@builtin(global_invocation_id) global_id: vec3<u32>) {
     var s_val = bytes_be_to_limbs_le(&s_bytes_be);
 
     var ay_bytes_be = u32s_to_bytes_be(&pk_u32s);
-    compressed_sign_bit[id] = ay_bytes_be[31] >> 7u;
+    // The later stages read the sign bit of x from pk rather than from another buffer
+    let compressed_sign_bit = ay_bytes_be[31] >> 7u;
 
     ay_bytes_be[31] &= 0x7fu;
     var ay_bytes_le: array<u32, 32>;
@@ -83,4 +91,16 @@ fn ed25519_verify_main_0(@builtin(global_invocation_id) global_id: vec3<u32>) {
     for (var i = 0u; i < 24u; i ++) {
         preimage[id * 24 + i] = preimage_u32s[i];
     }
+{% if compaction %}
+
+    // Only the signatures whose A decompresses survive to the later stages. Stage 3 decompresses
+    // A again rather than keep the point in another buffer, as this stage has no binding to spare
+    var decoded_a = reconstruct_ete_from_y(&ayr_val, compressed_sign_bit == 1u, &p);
+    if (decoded_a.is_valid_y_coord) {
+        let slot = atomicAdd(&survivors.count, 1u);
+        survivors.ids[slot] = id;
+    }
+{% endif %}
+
+    atomicAdd(&canaries[0], 1u);
 }
@@ -10,14 +10,25 @@
 {% include "limbs_le_to_u32s_be.wgsl" %}
 {% include "sha512.wgsl" %}
 {% include "ed25519_reduce_fr.wgsl" %}
+{% include "compaction.wgsl" %}
 
 @group(0) @binding(0) var<storage, read_write> table: array<ETEXYT>;
 @group(0) @binding(1) var<storage, read_write> s: array<BigInt>;
-@group(0) @binding(2) var<storage, read_write> ayr: array<BigInt>;
-@group(0) @binding(3) var<storage, read_write> k: array<BigInt>;
-@group(0) @binding(4) var<storage, read_write> compressed_sign_bit: array<u32>;
-@group(0) @binding(5) var<storage, read_write> gs: array<ETEPoint>;
+@group(0) @binding(2) var<storage, read_write> gs: array<ETEPoint>;
+@group(0) @binding(3) var<storage, read_write> canaries: array<atomic<u32>>;
+{% if checkpoint_bits and compaction %}
+@group(0) @binding(4) var<storage, read_write> checkpoints: array<MulCheckpoint>;
+@group(0) @binding(5) var<storage, read_write> survivors: Survivors;
 @group(0) @binding(6) var<uniform> params: vec3<u32>;
+{% elif checkpoint_bits %}
+@group(0) @binding(4) var<storage, read_write> checkpoints: array<MulCheckpoint>;
+@group(0) @binding(5) var<uniform> params: vec3<u32>;
+{% elif compaction %}
+@group(0) @binding(4) var<storage, read_write> survivors: Survivors;
+@group(0) @binding(5) var<uniform> params: vec3<u32>;
+{% else %}
+@group(0) @binding(4) var<uniform> params: vec3<u32>;
+{% endif %}
 
 @compute
 @workgroup_size(256)
@@ -28,7 +39,17 @@ fn ed25519_verify_main_2(@builtin(global_invocation_id) global_id: vec3<u32>) {
     let num_x_workgroups = params[0];
     let num_y_workgroups = params[1];
     let num_z_workgroups = params[2];
+{% if compaction %}
+    // Only the survivors of stage 0 run this stage
+    let survivor = survivor_index(global_id);
+    if (survivor >= survivors.count) {
+        atomicAdd(&canaries[2], 1u);
+        return;
+    }
+    let id = survivors.ids[survivor];
+{% else %}
     let id = (gidx * num_y_workgroups + gidy) * num_z_workgroups + gidz;
+{% endif %}
 
     var p = get_p();
     var r = get_r();
@@ -43,9 +64,16 @@ fn ed25519_verify_main_2(@builtin(global_invocation_id) global_id: vec3<u32>) {
     }
 
     var s_val = s[id];
-    var ayr_val = ayr[id];
-    var k_val = k[id];
-    var x_sign = compressed_sign_bit[id] == 1u;
 
+{% if checkpoint_bits %}
+    // Resume from the previous sub-dispatch. The last one leaves the product in gs
+    var ckpt = checkpoints[id];
+    ete_fixed_mul_resume(&ckpt, &table_pts, &s_val, &p, &r);
+    checkpoints[id] = ckpt;
+    gs[id] = ckpt.acc;
+{% else %}
     gs[id] = ete_fixed_mul(&table_pts, &s_val, &p, &r);
+{% endif %}
+
+    atomicAdd(&canaries[2], 1u);
 }
@@ -10,13 +10,27 @@
 {% include "limbs_le_to_u32s_be.wgsl" %}
 {% include "sha512.wgsl" %}
 {% include "ed25519_reduce_fr.wgsl" %}
+{% include "compaction.wgsl" %}
 
 @group(0) @binding(0) var<storage, read_write> s: array<BigInt>;
 @group(0) @binding(1) var<storage, read_write> ayr: array<BigInt>;
 @group(0) @binding(2) var<storage, read_write> k: array<BigInt>;
-@group(0) @binding(3) var<storage, read_write> compressed_sign_bit: array<u32>;
+@group(0) @binding(3) var<storage, read_write> pk: array<u32>;
 @group(0) @binding(4) var<storage, read_write> neg_ak: array<ETEPoint>;
-@group(0) @binding(5) var<uniform> params: vec3<u32>;
+@group(0) @binding(5) var<storage, read_write> canaries: array<atomic<u32>>;
+{% if checkpoint_bits and compaction %}
+@group(0) @binding(6) var<storage, read_write> checkpoints: array<MulCheckpoint>;
+@group(0) @binding(7) var<storage, read_write> survivors: Survivors;
+@group(0) @binding(8) var<uniform> params: vec3<u32>;
+{% elif checkpoint_bits %}
+@group(0) @binding(6) var<storage, read_write> checkpoints: array<MulCheckpoint>;
+@group(0) @binding(7) var<uniform> params: vec3<u32>;
+{% elif compaction %}
+@group(0) @binding(6) var<storage, read_write> survivors: Survivors;
+@group(0) @binding(7) var<uniform> params: vec3<u32>;
+{% else %}
+@group(0) @binding(6) var<uniform> params: vec3<u32>;
+{% endif %}
 
 @compute
 @workgroup_size(256)
@@ -27,7 +41,17 @@ fn ed25519_verify_main_3(@builtin(global_invocation_id) global_id: vec3<u32>) {
     let num_x_workgroups = params[0];
     let num_y_workgroups = params[1];
     let num_z_workgroups = params[2];
+{% if compaction %}
+    // Only the survivors of stage 0 run this stage
+    let survivor = survivor_index(global_id);
+    if (survivor >= survivors.count) {
+        atomicAdd(&canaries[3], 1u);
+        return;
+    }
+    let id = survivors.ids[survivor];
+{% else %}
     let id = (gidx * num_y_workgroups + gidy) * num_z_workgroups + gidz;
+{% endif %}
 
     var p = get_p();
     var r = get_r();
@@ -38,8 +62,28 @@ fn ed25519_verify_main_3(@builtin(global_invocation_id) global_id: vec3<u32>) {
     var s_val = s[id];
     var ayr_val = ayr[id];
     var k_val = k[id];
-    var x_sign = compressed_sign_bit[id] == 1u;
+    // The sign bit of x is the top bit of the last byte of the encoding of A
+    var x_sign = (pk[id * 8u + 7u] >> 31u) == 1u;
+
+{% if checkpoint_bits %}
+    // Resume from the previous sub-dispatch. Only the first one decompresses -A, which the
+    // checkpoint then holds, and the last one leaves the product in neg_ak
+    var ckpt = checkpoints[id];
+    var neg_a_pt: ETEPoint;
+    if (ckpt.num_bits_done == 0u) {
+        var res = compute_neg_a_pt(&s_val, &k_val, &ayr_val, x_sign, &p, &p_wide, &rinv, &mu_fp);
+        neg_a_pt = res.neg_a_pt;
 
+        if (!res.is_valid_y_coord) {
+            var empty: ETEPoint;
+            neg_a_pt = empty;
+        }
+    }
+
+    ete_mul_resume(&ckpt, &neg_a_pt, &k_val, &p);
+    checkpoints[id] = ckpt;
+    neg_ak[id] = ckpt.acc;
+{% else %}
     var res = compute_neg_a_pt(&s_val, &k_val, &ayr_val, x_sign, &p, &p_wide, &rinv, &mu_fp);
     var neg_a_pt = res.neg_a_pt;
 
@@ -49,4 +93,7 @@ fn ed25519_verify_main_3(@builtin(global_invocation_id) global_id: vec3<u32>) {
     }
 
     neg_ak[id] = ete_mul(&neg_a_pt, &k_val, &p);
+{% endif %}
+
+    atomicAdd(&canaries[3], 1u);
 }
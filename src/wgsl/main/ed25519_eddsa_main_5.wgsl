@@ -10,12 +10,18 @@
 {% include "limbs_le_to_u32s_be.wgsl" %}
 {% include "sha512.wgsl" %}
 {% include "ed25519_reduce_fr.wgsl" %}
+{% include "compaction.wgsl" %}
 
 @group(0) @binding(0) var<storage, read_write> pt: array<ETEAffinePoint>;
 @group(0) @binding(1) var<storage, read_write> is_valid: array<u32>;
 @group(0) @binding(2) var<storage, read_write> sig: array<u32>;
-@group(0) @binding(3) var<storage, read_write> success: u32;
+@group(0) @binding(3) var<storage, read_write> canaries: array<atomic<u32>>;
+{% if compaction %}
+@group(0) @binding(4) var<storage, read_write> survivors: Survivors;
+@group(0) @binding(5) var<uniform> params: vec3<u32>;
+{% else %}
 @group(0) @binding(4) var<uniform> params: vec3<u32>;
+{% endif %}
 
 @compute
 @workgroup_size(256)
@@ -26,7 +32,17 @@ fn ed25519_verify_main_5(@builtin(global_invocation_id) global_id: vec3<u32>) {
     let num_x_workgroups = params[0];
     let num_y_workgroups = params[1];
     let num_z_workgroups = params[2];
+{% if compaction %}
+    // Only the survivors of stage 0 run this stage. The verdicts of the others stay zeroed
+    let survivor = survivor_index(global_id);
+    if (survivor >= survivors.count) {
+        atomicAdd(&canaries[5], 1u);
+        return;
+    }
+    let id = survivors.ids[survivor];
+{% else %}
     let id = (gidx * num_y_workgroups + gidy) * num_z_workgroups + gidz;
+{% endif %}
 
     var result_affine = pt[id];
 
@@ -42,5 +58,5 @@ fn ed25519_verify_main_5(@builtin(global_invocation_id) global_id: vec3<u32>) {
 
     is_valid[id] = v;
 
-    success = 1u;
+    atomicAdd(&canaries[5], 1u);
 }
@@ -0,0 +1,51 @@
+{% include "bigint.wgsl" %}
+{% include "ff.wgsl" %}
+{% include "mont.wgsl" %}
+{% include "constants.wgsl" %}
+{% include curve_name ~ "_msm.wgsl" %}
+
+@group(0) @binding(0) var<storage, read_write> scalars: array<u32>;
+@group(0) @binding(1) var<storage, read_write> digits: array<u32>;
+@group(0) @binding(2) var<storage, read_write> canaries: array<atomic<u32>>;
+// (number of points, window size in bits, number of windows, unused)
+@group(0) @binding(3) var<uniform> msm_params: vec4<u32>;
+@group(0) @binding(4) var<uniform> params: vec3<u32>;
+
+/*
+ * Split each scalar into its window digits. The digits of window w are stored contiguously so
+ * that the bucket accumulation stage reads them in order.
+ */
+@compute
+@workgroup_size(256)
+fn msm_main_0(@builtin(global_invocation_id) global_id: vec3<u32>) {
+    let gidx = global_id.x; 
+    let gidy = global_id.y; 
+    let gidz = global_id.z; 
+    let num_x_workgroups = params[0];
+    let num_y_workgroups = params[1];
+    let num_z_workgroups = params[2];
+    let id = (gidx * num_y_workgroups + gidy) * num_z_workgroups + gidz;
+
+    let num_points = msm_params[0];
+    let window_size = msm_params[1];
+    let num_windows = msm_params[2];
+    if (id >= num_points) {
+        atomicAdd(&canaries[0], 1u);
+        return;
+    }
+
+    let mask = (1u << window_size) - 1u;
+    for (var w = 0u; w < num_windows; w ++) {
+        let offset = w * window_size;
+        let word_idx = offset / 32u;
+        let shift = offset % 32u;
+
+        var digit = scalars[id * {{ num_words }}u + word_idx] >> shift;
+        if (shift + window_size > 32u && word_idx + 1u < {{ num_words }}u) {
+            digit |= scalars[id * {{ num_words }}u + word_idx + 1u] << (32u - shift);
+        }
+        digits[w * num_points + id] = digit & mask;
+    }
+
+    atomicAdd(&canaries[0], 1u);
+}
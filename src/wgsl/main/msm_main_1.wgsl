@@ -0,0 +1,55 @@
+{% include "bigint.wgsl" %}
+{% include "ff.wgsl" %}
+{% include "mont.wgsl" %}
+{% include "constants.wgsl" %}
+{% include curve_name ~ "_msm.wgsl" %}
+
+@group(0) @binding(0) var<storage, read_write> points: array<MsmPoint>;
+@group(0) @binding(1) var<storage, read_write> digits: array<u32>;
+@group(0) @binding(2) var<storage, read_write> buckets: array<MsmPoint>;
+@group(0) @binding(3) var<storage, read_write> canaries: array<atomic<u32>>;
+// (number of points, window size in bits, number of windows, unused)
+@group(0) @binding(4) var<uniform> msm_params: vec4<u32>;
+@group(0) @binding(5) var<uniform> params: vec3<u32>;
+
+/*
+ * Each thread accumulates the points whose digit in its window equals its bucket index. Bucket 0
+ * of every window stays at infinity.
+ */
+@compute
+@workgroup_size(256)
+fn msm_main_1(@builtin(global_invocation_id) global_id: vec3<u32>) {
+    let gidx = global_id.x; 
+    let gidy = global_id.y; 
+    let gidz = global_id.z; 
+    let num_x_workgroups = params[0];
+    let num_y_workgroups = params[1];
+    let num_z_workgroups = params[2];
+    let id = (gidx * num_y_workgroups + gidy) * num_z_workgroups + gidz;
+
+    let num_points = msm_params[0];
+    let window_size = msm_params[1];
+    let num_windows = msm_params[2];
+    if (id >= (num_windows << window_size)) {
+        atomicAdd(&canaries[1], 1u);
+        return;
+    }
+
+    var p = get_p();
+
+    let w = id >> window_size;
+    let b = id & ((1u << window_size) - 1u);
+
+    var acc = msm_identity();
+    if (b != 0u) {
+        for (var i = 0u; i < num_points; i ++) {
+            if (digits[w * num_points + i] == b) {
+                var pt = points[i];
+                acc = msm_add(&acc, &pt, &p);
+            }
+        }
+    }
+    buckets[id] = acc;
+
+    atomicAdd(&canaries[1], 1u);
+}
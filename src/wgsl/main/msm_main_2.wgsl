@@ -0,0 +1,49 @@
+{% include "bigint.wgsl" %}
+{% include "ff.wgsl" %}
+{% include "mont.wgsl" %}
+{% include "constants.wgsl" %}
+{% include curve_name ~ "_msm.wgsl" %}
+
+@group(0) @binding(0) var<storage, read_write> buckets: array<MsmPoint>;
+@group(0) @binding(1) var<storage, read_write> window_sums: array<MsmPoint>;
+@group(0) @binding(2) var<storage, read_write> canaries: array<atomic<u32>>;
+// (number of points, window size in bits, number of windows, unused)
+@group(0) @binding(3) var<uniform> msm_params: vec4<u32>;
+@group(0) @binding(4) var<uniform> params: vec3<u32>;
+
+/*
+ * Each thread reduces the buckets of its window to sum(b * bucket_b) with a running sum, which
+ * needs 2 additions per bucket instead of a scalar multiplication.
+ */
+@compute
+@workgroup_size(256)
+fn msm_main_2(@builtin(global_invocation_id) global_id: vec3<u32>) {
+    let gidx = global_id.x; 
+    let gidy = global_id.y; 
+    let gidz = global_id.z; 
+    let num_x_workgroups = params[0];
+    let num_y_workgroups = params[1];
+    let num_z_workgroups = params[2];
+    let id = (gidx * num_y_workgroups + gidy) * num_z_workgroups + gidz;
+
+    let window_size = msm_params[1];
+    let num_windows = msm_params[2];
+    if (id >= num_windows) {
+        atomicAdd(&canaries[2], 1u);
+        return;
+    }
+
+    var p = get_p();
+
+    let num_buckets = 1u << window_size;
+    var running = msm_identity();
+    var total = msm_identity();
+    for (var b = num_buckets - 1u; b > 0u; b --) {
+        var bucket = buckets[id * num_buckets + b];
+        running = msm_add(&running, &bucket, &p);
+        total = msm_add(&total, &running, &p);
+    }
+    window_sums[id] = total;
+
+    atomicAdd(&canaries[2], 1u);
+}
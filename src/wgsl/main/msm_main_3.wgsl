@@ -0,0 +1,49 @@
+{% include "bigint.wgsl" %}
+{% include "ff.wgsl" %}
+{% include "mont.wgsl" %}
+{% include "constants.wgsl" %}
+{% include curve_name ~ "_msm.wgsl" %}
+
+@group(0) @binding(0) var<storage, read_write> window_sums: array<MsmPoint>;
+@group(0) @binding(1) var<storage, read_write> result: array<MsmPoint>;
+@group(0) @binding(2) var<storage, read_write> canaries: array<atomic<u32>>;
+// (number of points, window size in bits, number of windows, unused)
+@group(0) @binding(3) var<uniform> msm_params: vec4<u32>;
+@group(0) @binding(4) var<uniform> params: vec3<u32>;
+
+/*
+ * Combine the window sums from the most significant window down, doubling window_size times in
+ * between.
+ */
+@compute
+@workgroup_size(256)
+fn msm_main_3(@builtin(global_invocation_id) global_id: vec3<u32>) {
+    let gidx = global_id.x; 
+    let gidy = global_id.y; 
+    let gidz = global_id.z; 
+    let num_x_workgroups = params[0];
+    let num_y_workgroups = params[1];
+    let num_z_workgroups = params[2];
+    let id = (gidx * num_y_workgroups + gidy) * num_z_workgroups + gidz;
+
+    let window_size = msm_params[1];
+    let num_windows = msm_params[2];
+    if (id != 0u) {
+        atomicAdd(&canaries[3], 1u);
+        return;
+    }
+
+    var p = get_p();
+
+    var acc = window_sums[num_windows - 1u];
+    for (var w = num_windows - 1u; w > 0u; w --) {
+        for (var j = 0u; j < window_size; j ++) {
+            acc = msm_dbl(&acc, &p);
+        }
+        var window_sum = window_sums[w - 1u];
+        acc = msm_add(&acc, &window_sum, &p);
+    }
+    result[0] = acc;
+
+    atomicAdd(&canaries[3], 1u);
+}
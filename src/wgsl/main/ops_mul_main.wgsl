@@ -0,0 +1,31 @@
+{% include "bigint.wgsl" %}
+{% include "mont.wgsl" %}
+{% include "constants.wgsl" %}
+
+@group(0) @binding(0) var<storage, read_write> a: array<BigInt>;
+@group(0) @binding(1) var<storage, read_write> b: array<BigInt>;
+@group(0) @binding(2) var<storage, read_write> result: array<BigInt>;
+@group(0) @binding(3) var<storage, read_write> canaries: array<atomic<u32>>;
+@group(0) @binding(4) var<uniform> params: vec3<u32>;
+
+/*
+ * result[i] = a[i] * b[i], with all values in Montgomery form.
+ */
+@compute
+@workgroup_size(256)
+fn ops_mul(@builtin(global_invocation_id) global_id: vec3<u32>) {
+    let gidx = global_id.x; 
+    let gidy = global_id.y; 
+    let gidz = global_id.z; 
+    let num_x_workgroups = params[0];
+    let num_y_workgroups = params[1];
+    let num_z_workgroups = params[2];
+    let id = (gidx * num_y_workgroups + gidy) * num_z_workgroups + gidz;
+
+    var p = get_p();
+    var x = a[id];
+    var y = b[id];
+    result[id] = mont_mul(&x, &y, &p);
+
+    atomicAdd(&canaries[0], 1u);
+}
@@ -0,0 +1,33 @@
+{% include "bigint.wgsl" %}
+{% include "ff.wgsl" %}
+{% include "mont.wgsl" %}
+{% include "constants.wgsl" %}
+{% include curve_name ~ "_msm.wgsl" %}
+
+@group(0) @binding(0) var<storage, read_write> a: array<MsmPoint>;
+@group(0) @binding(1) var<storage, read_write> b: array<MsmPoint>;
+@group(0) @binding(2) var<storage, read_write> result: array<MsmPoint>;
+@group(0) @binding(3) var<storage, read_write> canaries: array<atomic<u32>>;
+@group(0) @binding(4) var<uniform> params: vec3<u32>;
+
+/*
+ * result[i] = a[i] + b[i], with the group law of the curve's `{curve_name}_msm.wgsl`.
+ */
+@compute
+@workgroup_size(256)
+fn ops_point_add(@builtin(global_invocation_id) global_id: vec3<u32>) {
+    let gidx = global_id.x; 
+    let gidy = global_id.y; 
+    let gidz = global_id.z; 
+    let num_x_workgroups = params[0];
+    let num_y_workgroups = params[1];
+    let num_z_workgroups = params[2];
+    let id = (gidx * num_y_workgroups + gidy) * num_z_workgroups + gidz;
+
+    var p = get_p();
+    var x = a[id];
+    var y = b[id];
+    result[id] = msm_add(&x, &y, &p);
+
+    atomicAdd(&canaries[0], 1u);
+}
@@ -0,0 +1,43 @@
+{% include "bigint.wgsl" %}
+{% include "ff.wgsl" %}
+{% include "mont.wgsl" %}
+{% include "constants.wgsl" %}
+{% include curve_name ~ "_msm.wgsl" %}
+
+@group(0) @binding(0) var<storage, read_write> points: array<MsmPoint>;
+// {{ num_words }} little-endian u32 words per scalar
+@group(0) @binding(1) var<storage, read_write> scalars: array<u32>;
+@group(0) @binding(2) var<storage, read_write> result: array<MsmPoint>;
+@group(0) @binding(3) var<storage, read_write> canaries: array<atomic<u32>>;
+@group(0) @binding(4) var<uniform> params: vec3<u32>;
+
+/*
+ * result[i] = scalars[i] * points[i], by double-and-add from the most significant bit.
+ */
+@compute
+@workgroup_size(256)
+fn ops_scalar_mul(@builtin(global_invocation_id) global_id: vec3<u32>) {
+    let gidx = global_id.x; 
+    let gidy = global_id.y; 
+    let gidz = global_id.z; 
+    let num_x_workgroups = params[0];
+    let num_y_workgroups = params[1];
+    let num_z_workgroups = params[2];
+    let id = (gidx * num_y_workgroups + gidy) * num_z_workgroups + gidz;
+
+    var p = get_p();
+    var pt = points[id];
+    var acc = msm_identity();
+    for (var i = {{ num_words }}u; i > 0u; i --) {
+        let word = scalars[id * {{ num_words }}u + i - 1u];
+        for (var j = 32u; j > 0u; j --) {
+            acc = msm_dbl(&acc, &p);
+            if (((word >> (j - 1u)) & 1u) == 1u) {
+                acc = msm_add(&acc, &pt, &p);
+            }
+        }
+    }
+    result[id] = acc;
+
+    atomicAdd(&canaries[0], 1u);
+}
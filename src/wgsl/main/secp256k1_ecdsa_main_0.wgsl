@@ -10,13 +10,21 @@
 {% include "secp256k1_curve_generators.wgsl" %}
 {% include "bytes_be_to_limbs_le.wgsl" %}
 {% include "limbs_le_to_u32s_be.wgsl" %}
+{% include "sec1.wgsl" %}
+{% include "compaction.wgsl" %}
 
 @group(0) @binding(0) var<storage, read_write> sig: array<u32>;
 @group(0) @binding(1) var<storage, read_write> msg: array<u32>;
 @group(0) @binding(2) var<storage, read_write> u1: array<BigInt>;
 @group(0) @binding(3) var<storage, read_write> u2: array<BigInt>;
 @group(0) @binding(4) var<storage, read_write> recovered_r: array<Point>;
-@group(0) @binding(5) var<uniform> params: vec3<u32>;
+@group(0) @binding(5) var<storage, read_write> canaries: array<atomic<u32>>;
+{% if compaction %}
+@group(0) @binding(6) var<storage, read_write> survivors: AtomicSurvivors;
+@group(0) @binding(7) var<uniform> params: vec3<u32>;
+{% else %}
+@group(0) @binding(6) var<uniform> params: vec3<u32>;
+{% endif %}
 
 @compute
 @workgroup_size(256)
@@ -73,4 +81,15 @@ fn secp256k1_recover_0(@builtin(global_invocation_id) global_id: vec3<u32>) {
     u1[id] = intermediate.u1;
     u2[id] = intermediate.u2;
     recovered_r[id] = intermediate.recovered_r;
+{% if compaction %}
+
+    // Only the signatures whose R is on the curve survive to the later stages
+    var recovered_r_pt = intermediate.recovered_r;
+    if (sec1_is_on_curve(&recovered_r_pt.x, &recovered_r_pt.y, &p_bigint)) {
+        let slot = atomicAdd(&survivors.count, 1u);
+        survivors.ids[slot] = id;
+    }
+{% endif %}
+
+    atomicAdd(&canaries[0], 1u);
 }
@@ -10,11 +10,25 @@
 {% include "secp256k1_curve_generators.wgsl" %}
 {% include "bytes_be_to_limbs_le.wgsl" %}
 {% include "limbs_le_to_u32s_be.wgsl" %}
+{% include "compaction.wgsl" %}
 
 @group(0) @binding(0) var<storage, read_write> u2: array<BigInt>;
 @group(0) @binding(1) var<storage, read_write> recovered_r: array<Point>;
 @group(0) @binding(2) var<storage, read_write> u2r: array<Point>;
-@group(0) @binding(3) var<uniform> params: vec3<u32>;
+@group(0) @binding(3) var<storage, read_write> canaries: array<atomic<u32>>;
+{% if checkpoint_bits and compaction %}
+@group(0) @binding(4) var<storage, read_write> checkpoints: array<MulCheckpoint>;
+@group(0) @binding(5) var<storage, read_write> survivors: Survivors;
+@group(0) @binding(6) var<uniform> params: vec3<u32>;
+{% elif checkpoint_bits %}
+@group(0) @binding(4) var<storage, read_write> checkpoints: array<MulCheckpoint>;
+@group(0) @binding(5) var<uniform> params: vec3<u32>;
+{% elif compaction %}
+@group(0) @binding(4) var<storage, read_write> survivors: Survivors;
+@group(0) @binding(5) var<uniform> params: vec3<u32>;
+{% else %}
+@group(0) @binding(4) var<uniform> params: vec3<u32>;
+{% endif %}
 
 @compute
 @workgroup_size(256)
@@ -25,12 +39,32 @@ fn secp256k1_recover_2(@builtin(global_invocation_id) global_id: vec3<u32>) {
     let num_x_workgroups = params[0];
     let num_y_workgroups = params[1];
     let num_z_workgroups = params[2];
+{% if compaction %}
+    // Only the survivors of stage 0 run this stage
+    let survivor = survivor_index(global_id);
+    if (survivor >= survivors.count) {
+        atomicAdd(&canaries[2], 1u);
+        return;
+    }
+    let id = survivors.ids[survivor];
+{% else %}
     let id = (gidx * num_y_workgroups + gidy) * num_z_workgroups + gidz;
+{% endif %}
 
     var p = get_p();
     var recovered_r_pt = recovered_r[id];
 
     // Multiply recovered_r by u2
     var u2_val = u2[id];
-    u2r[id] = projective_mul(&recovered_r_pt, &u2_val, &p);
+{% if checkpoint_bits %}
+    // Resume from the previous sub-dispatch. The last one leaves the product in u2r
+    var ckpt = checkpoints[id];
+    ecdsa_mul_resume(&ckpt, &recovered_r_pt, &u2_val, &p);
+    checkpoints[id] = ckpt;
+    u2r[id] = ckpt.acc;
+{% else %}
+    u2r[id] = ecdsa_mul(&recovered_r_pt, &u2_val, &p);
+{% endif %}
+
+    atomicAdd(&canaries[2], 1u);
 }
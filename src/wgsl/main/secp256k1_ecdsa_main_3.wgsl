@@ -10,11 +10,18 @@
 {% include "secp256k1_curve_generators.wgsl" %}
 {% include "bytes_be_to_limbs_le.wgsl" %}
 {% include "limbs_le_to_u32s_be.wgsl" %}
+{% include "compaction.wgsl" %}
 
 @group(0) @binding(0) var<storage, read_write> u1g: array<Point>;
 @group(0) @binding(1) var<storage, read_write> u2r: array<Point>;
 @group(0) @binding(2) var<storage, read_write> sum: array<Point>;
-@group(0) @binding(3) var<uniform> params: vec3<u32>;
+@group(0) @binding(3) var<storage, read_write> canaries: array<atomic<u32>>;
+{% if compaction %}
+@group(0) @binding(4) var<storage, read_write> survivors: Survivors;
+@group(0) @binding(5) var<uniform> params: vec3<u32>;
+{% else %}
+@group(0) @binding(4) var<uniform> params: vec3<u32>;
+{% endif %}
 
 @compute
 @workgroup_size(256)
@@ -25,7 +32,17 @@ fn secp256k1_recover_3(@builtin(global_invocation_id) global_id: vec3<u32>) {
     let num_x_workgroups = params[0];
     let num_y_workgroups = params[1];
     let num_z_workgroups = params[2];
+{% if compaction %}
+    // Only the survivors of stage 0 run this stage
+    let survivor = survivor_index(global_id);
+    if (survivor >= survivors.count) {
+        atomicAdd(&canaries[3], 1u);
+        return;
+    }
+    let id = survivors.ids[survivor];
+{% else %}
     let id = (gidx * num_y_workgroups + gidy) * num_z_workgroups + gidz;
+{% endif %}
 
     var p = get_p();
     var p_wide = get_p_wide();
@@ -39,6 +56,9 @@ fn secp256k1_recover_3(@builtin(global_invocation_id) global_id: vec3<u32>) {
     var u1g_pt = u1g[id];
     var u2r_pt = u2r[id];
 
-    // Add u1g and u2r
-    sum[id] = projective_add_2007_bl_unsafe(&u1g_pt, &u2r_pt, &p);
+    // Add u1g and u2r. u1g == u2r, u1g == -u2r, and either being the point at infinity need no
+    // special cases
+    sum[id] = ecdsa_add(&u1g_pt, &u2r_pt, &p);
+
+    atomicAdd(&canaries[3], 1u);
 }
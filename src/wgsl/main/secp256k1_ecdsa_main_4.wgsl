@@ -10,11 +10,18 @@
 {% include "secp256k1_curve_generators.wgsl" %}
 {% include "bytes_be_to_limbs_le.wgsl" %}
 {% include "limbs_le_to_u32s_be.wgsl" %}
+{% include "sec1.wgsl" %}
+{% include "compaction.wgsl" %}
 
 @group(0) @binding(0) var<storage, read_write> sum: array<Point>;
 @group(0) @binding(1) var<storage, read_write> result: array<u32>;
-@group(0) @binding(2) var<storage, read_write> success: u32;
+@group(0) @binding(2) var<storage, read_write> canaries: array<atomic<u32>>;
+{% if compaction %}
+@group(0) @binding(3) var<storage, read_write> survivors: Survivors;
+@group(0) @binding(4) var<uniform> params: vec3<u32>;
+{% else %}
 @group(0) @binding(3) var<uniform> params: vec3<u32>;
+{% endif %}
 
 @compute
 @workgroup_size(256)
@@ -25,7 +32,17 @@ fn secp256k1_recover_4(@builtin(global_invocation_id) global_id: vec3<u32>) {
     let num_x_workgroups = params[0];
     let num_y_workgroups = params[1];
     let num_z_workgroups = params[2];
+{% if compaction %}
+    // Only the survivors of stage 0 run this stage. The output of the others stays zeroed
+    let survivor = survivor_index(global_id);
+    if (survivor >= survivors.count) {
+        atomicAdd(&canaries[4], 1u);
+        return;
+    }
+    let id = survivors.ids[survivor];
+{% else %}
     let id = (gidx * num_y_workgroups + gidy) * num_z_workgroups + gidz;
+{% endif %}
 
     var p = get_p();
     var p_wide = get_p_wide();
@@ -39,7 +56,7 @@ fn secp256k1_recover_4(@builtin(global_invocation_id) global_id: vec3<u32>) {
     var sum_pt = sum[id];
 
     // Convert the point in affine form
-    var recovered = projective_to_affine_non_mont(&sum_pt, &p, &p_wide, &r, &rinv, &mu_fp);
+    var recovered = ecdsa_to_affine_non_mont(&sum_pt, &p, &p_wide, &r, &rinv, &mu_fp);
 
     var x_limbs = recovered.x.limbs;
     var y_limbs = recovered.y.limbs;
@@ -50,5 +67,51 @@ fn secp256k1_recover_4(@builtin(global_invocation_id) global_id: vec3<u32>) {
         result[id * 16u + i + 8u] = y_bytes[i];
     }
 
-    success = 1u;
+    atomicAdd(&canaries[4], 1u);
+}
+
+/*
+ * Like secp256k1_recover_4, but writes each public key as a 33-byte SEC1 compressed key padded to 36
+ * bytes. Points which are not on the curve, e.g. those recovered from malformed signatures, are
+ * written with a zero prefix.
+ */
+@compute
+@workgroup_size(256)
+fn secp256k1_recover_4_compressed(@builtin(global_invocation_id) global_id: vec3<u32>) {
+    let gidx = global_id.x; 
+    let gidy = global_id.y; 
+    let gidz = global_id.z; 
+    let num_x_workgroups = params[0];
+    let num_y_workgroups = params[1];
+    let num_z_workgroups = params[2];
+{% if compaction %}
+    // Only the survivors of stage 0 run this stage. The output of the others stays zeroed
+    let survivor = survivor_index(global_id);
+    if (survivor >= survivors.count) {
+        atomicAdd(&canaries[4], 1u);
+        return;
+    }
+    let id = survivors.ids[survivor];
+{% else %}
+    let id = (gidx * num_y_workgroups + gidy) * num_z_workgroups + gidz;
+{% endif %}
+
+    var p = get_p();
+    var p_wide = get_p_wide();
+    var mu_fp = get_mu_fp();
+    var r = get_r();
+    var rinv = get_rinv();
+
+    var sum_pt = sum[id];
+
+    // Convert the point in affine form
+    var recovered = ecdsa_to_affine_non_mont(&sum_pt, &p, &p_wide, &r, &rinv, &mu_fp);
+    var is_valid = sec1_is_on_curve_non_mont(&recovered, &p, &p_wide, &r, &mu_fp);
+
+    var compressed = sec1_compress(&recovered, is_valid);
+    for (var i = 0u; i < 9u; i ++) {
+        result[id * 9u + i] = compressed[i];
+    }
+
+    atomicAdd(&canaries[4], 1u);
 }
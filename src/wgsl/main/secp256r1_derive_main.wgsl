@@ -0,0 +1,66 @@
+{% include "bigint.wgsl" %}
+{% include "ff.wgsl" %}
+{% include "mont.wgsl" %}
+{% include "secp256r1_curve.wgsl" %}
+{% include "constants.wgsl" %}
+{% include "secp_constants.wgsl" %}
+{% include "secp_curve_utils.wgsl" %}
+{% include "bytes_be_to_limbs_le.wgsl" %}
+{% include "limbs_le_to_u32s_be.wgsl" %}
+
+@group(0) @binding(0) var<storage, read_write> table: array<PointAffine>;
+@group(0) @binding(1) var<storage, read_write> sk: array<u32>;
+@group(0) @binding(2) var<storage, read_write> result: array<u32>;
+@group(0) @binding(3) var<storage, read_write> canaries: array<atomic<u32>>;
+@group(0) @binding(4) var<uniform> params: vec3<u32>;
+
+@compute
+@workgroup_size(256)
+fn secp256r1_derive_public_keys(@builtin(global_invocation_id) global_id: vec3<u32>) {
+    let gidx = global_id.x; 
+    let gidy = global_id.y; 
+    let gidz = global_id.z; 
+    let num_x_workgroups = params[0];
+    let num_y_workgroups = params[1];
+    let num_z_workgroups = params[2];
+    let id = (gidx * num_y_workgroups + gidy) * num_z_workgroups + gidz;
+
+    var p = get_p();
+    var p_wide = get_p_wide();
+    var mu_fp = get_mu_fp();
+    var r = get_r();
+    var rinv = get_rinv();
+
+    var table_size = {{ table_size }}u;
+    var table_pts: array<PointAffine, {{ table_size }}>;
+    for (var i = 0u; i < table_size; i ++) {
+        table_pts[i] = table[i];
+    }
+
+    // Convert the big-endian secret key to limbs
+    var sk_bytes_be: array<u32, 32>;
+    for (var i = 0u; i < 8u; i ++) {
+        let s = sk[id * 8u + i];
+        for (var j = 0u; j < 4u; j ++) {
+            sk_bytes_be[i * 4u + j] = (s >> (j * 8u)) & 255u;
+        }
+    }
+    var sk_val = bytes_be_to_limbs_le(&sk_bytes_be);
+
+    // Multiply g by the secret key
+    var pk = projective_fixed_mul(&table_pts, &sk_val, &p, &r);
+
+    // Convert the point in affine form
+    var pk_affine = projective_to_affine_non_mont(&pk, &p, &p_wide, &r, &rinv, &mu_fp);
+
+    var x_limbs = pk_affine.x.limbs;
+    var y_limbs = pk_affine.y.limbs;
+    var x_bytes = limbs_le_to_u32s_be(&x_limbs, {{ log_limb_size }}u);
+    var y_bytes = limbs_le_to_u32s_be(&y_limbs, {{ log_limb_size }}u);
+    for (var i = 0u; i < 8u; i ++) {
+        result[id * 16u + i] = x_bytes[i];
+        result[id * 16u + i + 8u] = y_bytes[i];
+    }
+
+    atomicAdd(&canaries[0], 1u);
+}
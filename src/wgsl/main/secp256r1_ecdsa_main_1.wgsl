@@ -10,11 +10,25 @@
 {% include "secp256r1_curve_generators.wgsl" %}
 {% include "bytes_be_to_limbs_le.wgsl" %}
 {% include "limbs_le_to_u32s_be.wgsl" %}
+{% include "compaction.wgsl" %}
 
 @group(0) @binding(0) var<storage, read_write> table: array<PointAffine>;
 @group(0) @binding(1) var<storage, read_write> u1: array<BigInt>;
 @group(0) @binding(2) var<storage, read_write> u1g: array<Point>;
-@group(0) @binding(3) var<uniform> params: vec3<u32>;
+@group(0) @binding(3) var<storage, read_write> canaries: array<atomic<u32>>;
+{% if checkpoint_bits and compaction %}
+@group(0) @binding(4) var<storage, read_write> checkpoints: array<MulCheckpoint>;
+@group(0) @binding(5) var<storage, read_write> survivors: Survivors;
+@group(0) @binding(6) var<uniform> params: vec3<u32>;
+{% elif checkpoint_bits %}
+@group(0) @binding(4) var<storage, read_write> checkpoints: array<MulCheckpoint>;
+@group(0) @binding(5) var<uniform> params: vec3<u32>;
+{% elif compaction %}
+@group(0) @binding(4) var<storage, read_write> survivors: Survivors;
+@group(0) @binding(5) var<uniform> params: vec3<u32>;
+{% else %}
+@group(0) @binding(4) var<uniform> params: vec3<u32>;
+{% endif %}
 
 @compute
 @workgroup_size(256)
@@ -25,7 +39,17 @@ fn secp256r1_recover_1(@builtin(global_invocation_id) global_id: vec3<u32>) {
     let num_x_workgroups = params[0];
     let num_y_workgroups = params[1];
     let num_z_workgroups = params[2];
+{% if compaction %}
+    // Only the survivors of stage 0 run this stage
+    let survivor = survivor_index(global_id);
+    if (survivor >= survivors.count) {
+        atomicAdd(&canaries[1], 1u);
+        return;
+    }
+    let id = survivors.ids[survivor];
+{% else %}
     let id = (gidx * num_y_workgroups + gidy) * num_z_workgroups + gidz;
+{% endif %}
 
     var p = get_p();
     var r = get_r();
@@ -38,11 +62,21 @@ fn secp256r1_recover_1(@builtin(global_invocation_id) global_id: vec3<u32>) {
 
     // Multiply g by u1
     var u1_val = u1[id];
+{% if checkpoint_bits %}
+    // Resume from the previous sub-dispatch. The last one leaves the product in u1g
+    var ckpt = checkpoints[id];
+    projective_fixed_mul_resume(&ckpt, &table_pts, &u1_val, &p, &r);
+    checkpoints[id] = ckpt;
+    var result = ckpt.acc;
+{% else %}
     var result = projective_fixed_mul(&table_pts, &u1_val, &p, &r);
+{% endif %}
 
     /*
     var g = get_secp256k1_generator();
     var result = projective_mul(&g, &u1_val, &p);
     */
     u1g[id] = result;
+
+    atomicAdd(&canaries[1], 1u);
 }
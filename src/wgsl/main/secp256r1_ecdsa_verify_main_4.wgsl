@@ -0,0 +1,79 @@
+{% include "bigint.wgsl" %}
+{% include "ff.wgsl" %}
+{% include "mont.wgsl" %}
+{% include "secp256r1_curve.wgsl" %}
+{% include "signature.wgsl" %}
+{% include "secp256r1_ecdsa.wgsl" %}
+{% include "constants.wgsl" %}
+{% include "secp_constants.wgsl" %}
+{% include "secp_curve_utils.wgsl" %}
+{% include "secp256r1_curve_generators.wgsl" %}
+{% include "bytes_be_to_limbs_le.wgsl" %}
+{% include "limbs_le_to_u32s_be.wgsl" %}
+{% include "sec1.wgsl" %}
+{% include "compaction.wgsl" %}
+
+@group(0) @binding(0) var<storage, read_write> sum: array<Point>;
+// 33-byte SEC1 compressed public keys, each padded to 36 bytes
+@group(0) @binding(1) var<storage, read_write> public_keys: array<u32>;
+@group(0) @binding(2) var<storage, read_write> result: array<u32>;
+@group(0) @binding(3) var<storage, read_write> canaries: array<atomic<u32>>;
+{% if compaction %}
+@group(0) @binding(4) var<storage, read_write> survivors: Survivors;
+@group(0) @binding(5) var<uniform> params: vec3<u32>;
+{% else %}
+@group(0) @binding(4) var<uniform> params: vec3<u32>;
+{% endif %}
+
+/*
+ * The last stage of verification against compressed public keys. Decompresses each public key
+ * and writes 1 if it equals the recovered key, or 0 otherwise.
+ */
+@compute
+@workgroup_size(256)
+fn secp256r1_verify_4(@builtin(global_invocation_id) global_id: vec3<u32>) {
+    let gidx = global_id.x; 
+    let gidy = global_id.y; 
+    let gidz = global_id.z; 
+    let num_x_workgroups = params[0];
+    let num_y_workgroups = params[1];
+    let num_z_workgroups = params[2];
+{% if compaction %}
+    // Only the survivors of stage 0 run this stage. The output of the others stays zeroed
+    let survivor = survivor_index(global_id);
+    if (survivor >= survivors.count) {
+        atomicAdd(&canaries[4], 1u);
+        return;
+    }
+    let id = survivors.ids[survivor];
+{% else %}
+    let id = (gidx * num_y_workgroups + gidy) * num_z_workgroups + gidz;
+{% endif %}
+
+    var p = get_p();
+    var p_wide = get_p_wide();
+    var mu_fp = get_mu_fp();
+    var r = get_r();
+    var rinv = get_rinv();
+
+    var sum_pt = sum[id];
+
+    // Convert the point in affine form
+    var recovered = projective_to_affine_non_mont(&sum_pt, &p, &p_wide, &r, &rinv, &mu_fp);
+    var is_valid = sec1_is_on_curve_non_mont(&recovered, &p, &p_wide, &r, &mu_fp);
+
+    var pk_words: array<u32, 9>;
+    for (var i = 0u; i < 9u; i ++) {
+        pk_words[i] = public_keys[id * 9u + i];
+    }
+    var pk: Point;
+    if (sec1_decompress(&pk_words, &p, &p_wide, &r, &rinv, &mu_fp, &pk)) {
+        is_valid = is_valid && bigint_eq(&pk.x, &recovered.x) && bigint_eq(&pk.y, &recovered.y);
+    } else {
+        is_valid = false;
+    }
+
+    result[id] = select(0u, 1u, is_valid);
+
+    atomicAdd(&canaries[4], 1u);
+}
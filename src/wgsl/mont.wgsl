@@ -1,3 +1,18 @@
+{% if reduction == "pseudo_mersenne" %}
+{% include "pseudo_mersenne.wgsl" %}
+
+/*
+ * With Reduction::PseudoMersenne, the Montgomery radix is 1, so that field elements are in
+ * canonical form and the product is just pm_mul.
+ */
+fn mont_mul(
+    x: ptr<function, BigInt>,
+    y: ptr<function, BigInt>,
+    p: ptr<function, BigInt>
+) -> BigInt {
+    return pm_mul(x, y, p);
+}
+{% else %}
 /*
  * An optimised variant of the Montgomery product algorithm from
  * https://github.com/mitschabaude/montgomery#13-x-30-bit-multiplication.
@@ -68,6 +83,7 @@ fn mont_mul(
     {% endif %}
     return conditional_reduce(&s, p);
 }
+{% endif %}
 
 fn conditional_reduce(x: ptr<function, BigInt>, y: ptr<function, BigInt>) -> BigInt {
     if (bigint_gte(x, y)) {
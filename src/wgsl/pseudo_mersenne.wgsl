@@ -0,0 +1,71 @@
+// Requires bigint.wgsl and ff.wgsl. Only rendered with Reduction::PseudoMersenne, for a base field
+// modulus p = 2 ^ {{ pm_k }} - c with a c of at most {{ pm_c_num_limbs }} limbs.
+
+fn get_pm_c() -> BigInt {
+    {{ pm_c_bigint }}
+    return pm_c;
+}
+
+/*
+ * Returns lo + hi * c, where lo is the lowest {{ pm_k }} bits of w and hi is the rest. As
+ * 2 ^ {{ pm_k }} == c (mod p), this is congruent to w, and about {{ pm_k }} bits narrower.
+ */
+fn pm_fold(w: ptr<function, BigIntWide>) -> BigIntWide {
+    var c = get_pm_c();
+
+    var lo: BigIntWide;
+    for (var i = 0u; i < {{ pm_k_limb }}u; i ++) {
+        lo.limbs[i] = (*w).limbs[i];
+    }
+    lo.limbs[{{ pm_k_limb }}u] = (*w).limbs[{{ pm_k_limb }}u] & ((1u << {{ pm_k_shift }}u) - 1u);
+
+    var hi: BigIntWide;
+    for (var i = 0u; i < {{ num_limbs * 2 - pm_k_limb }}u; i ++) {
+        let j = i + {{ pm_k_limb }}u;
+        var next = 0u;
+        if (j + 1u < {{ num_limbs * 2 }}u) {
+            next = (*w).limbs[j + 1u];
+        }
+        hi.limbs[i] = (((*w).limbs[j] >> {{ pm_k_shift }}u) | (next << ({{ log_limb_size }}u - {{ pm_k_shift }}u))) & {{ mask }}u;
+    }
+
+    // At most {{ pm_c_num_limbs }} products of two limbs are added to each limb, which
+    // Reduction::PseudoMersenne checks cannot overflow, so the carries are propagated once at the
+    // end
+    for (var i = 0u; i < {{ num_limbs * 2 - pm_k_limb }}u; i ++) {
+        for (var j = 0u; j < {{ pm_c_num_limbs }}u; j ++) {
+            if (i + j < {{ num_limbs * 2 }}u) {
+                lo.limbs[i + j] += hi.limbs[i] * c.limbs[j];
+            }
+        }
+    }
+
+    var carry = 0u;
+    for (var i = 0u; i < {{ num_limbs * 2 }}u; i ++) {
+        let v = lo.limbs[i] + carry;
+        lo.limbs[i] = v & {{ mask }}u;
+        carry = v >> {{ log_limb_size }}u;
+    }
+    return lo;
+}
+
+/*
+ * Returns x * y % p for x and y of up to {{ num_limbs }} limbs. The product is folded twice: the
+ * first fold leaves about {{ pm_k }} bits plus the width of c and the excess of the inputs, and
+ * the second leaves less than 2p, so a single conditional subtraction reduces it.
+ */
+fn pm_mul(
+    x: ptr<function, BigInt>,
+    y: ptr<function, BigInt>,
+    p: ptr<function, BigInt>
+) -> BigInt {
+    var w = bigint_mul(x, y);
+    var w1 = pm_fold(&w);
+    var w2 = pm_fold(&w1);
+
+    var s: BigInt;
+    for (var i = 0u; i < {{ num_limbs }}u; i ++) {
+        s.limbs[i] = w2.limbs[i];
+    }
+    return conditional_reduce(&s, p);
+}
@@ -0,0 +1,106 @@
+// Requires bigint.wgsl, ff.wgsl, mont.wgsl, the curve's {{ curve_name }}_curve.wgsl,
+// bytes_be_to_limbs_le.wgsl, and limbs_le_to_u32s_be.wgsl
+
+/*
+ * Returns true if the affine point (xr, yr), in Montgomery form, satisfies the curve equation
+ */
+fn sec1_is_on_curve(
+    xr: ptr<function, BigInt>,
+    yr: ptr<function, BigInt>,
+    p: ptr<function, BigInt>
+) -> bool {
+    var lhs = mont_mul(yr, yr, p);
+    var rhs = curve_equation_rhs(xr, p);
+    return bigint_eq(&lhs, &rhs);
+}
+
+/*
+ * Returns true if the affine point, not in Montgomery form, satisfies the curve equation
+ */
+fn sec1_is_on_curve_non_mont(
+    pt: ptr<function, Point>,
+    p: ptr<function, BigInt>,
+    p_wide: ptr<function, BigIntWide>,
+    r: ptr<function, BigInt>,
+    mu_fp: ptr<function, BigInt>
+) -> bool {
+    var x = (*pt).x;
+    var y = (*pt).y;
+    var xr = ff_mul(&x, r, p, p_wide, mu_fp);
+    var yr = ff_mul(&y, r, p, p_wide, mu_fp);
+    return sec1_is_on_curve(&xr, &yr, p);
+}
+
+/*
+ * Encodes an affine point, not in Montgomery form, as a 33-byte SEC1 compressed public key
+ * followed by 3 zero bytes. The bytes are in memory order, i.e. the prefix is the lowest byte of
+ * the first word. The prefix is 0x02 or 0x03 for an even or odd y, or 0x00 if is_valid is false.
+ */
+fn sec1_compress(
+    pt: ptr<function, Point>,
+    is_valid: bool
+) -> array<u32, 9> {
+    var x_limbs = (*pt).x.limbs;
+    var x_words = limbs_le_to_u32s_be(&x_limbs, {{ log_limb_size }}u);
+
+    var prefix = 0u;
+    if (is_valid) {
+        prefix = 2u + ((*pt).y.limbs[0] & 1u);
+    }
+
+    var out: array<u32, 9>;
+    out[0] = prefix | (x_words[0] << 8u);
+    for (var i = 1u; i < 8u; i ++) {
+        out[i] = (x_words[i - 1u] >> 24u) | (x_words[i] << 8u);
+    }
+    out[8] = x_words[7] >> 24u;
+    return out;
+}
+
+/*
+ * Decodes a public key in the layout of sec1_compress into an affine point, not in Montgomery
+ * form, by recovering y with {{ curve_name }}_recover_affine_ys. Returns false if the prefix is
+ * neither 0x02 nor 0x03, or if x is not the X-coordinate of a point on the curve.
+ */
+fn sec1_decompress(
+    words: ptr<function, array<u32, 9>>,
+    p: ptr<function, BigInt>,
+    p_wide: ptr<function, BigIntWide>,
+    r: ptr<function, BigInt>,
+    rinv: ptr<function, BigInt>,
+    mu_fp: ptr<function, BigInt>,
+    out: ptr<function, Point>
+) -> bool {
+    let prefix = (*words)[0] & 255u;
+
+    var x_bytes_be: array<u32, 32>;
+    for (var i = 0u; i < 32u; i ++) {
+        let k = i + 1u;
+        x_bytes_be[i] = ((*words)[k / 4u] >> ((k % 4u) * 8u)) & 255u;
+    }
+    var x = bytes_be_to_limbs_le(&x_bytes_be);
+
+    if ((prefix != 2u && prefix != 3u) || bigint_gte(&x, p)) {
+        return false;
+    }
+
+    var xr = ff_mul(&x, r, p, p_wide, mu_fp);
+    var ys = {{ curve_name }}_recover_affine_ys(&xr, p);
+
+    // The square root is only correct if x^3 + ax + b is a quadratic residue
+    var yr = ys[0];
+    if (!sec1_is_on_curve(&xr, &yr, p)) {
+        return false;
+    }
+
+    var y = ff_mul(&yr, rinv, p, p_wide, mu_fp);
+    if ((y.limbs[0] & 1u) != (prefix & 1u)) {
+        yr = ys[1];
+        y = ff_mul(&yr, rinv, p, p_wide, mu_fp);
+    }
+
+    var one: BigInt;
+    one.limbs[0] = 1u;
+    *out = Point(x, y, one);
+    return true;
+}
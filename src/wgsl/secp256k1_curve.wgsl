@@ -110,6 +110,105 @@ fn projective_dbl_2007_bl_unsafe(
     return Point(x3, y3, z3);
 }
 
+/// Algorithm 7 of "Complete addition formulas for prime order elliptic curves" by Renes,
+/// Costello, and Batina (https://eprint.iacr.org/2015/1060), for a = 0.
+/// Complete: correct for every pair of inputs, including a == b, a == -b, and the point at
+/// infinity, which is (0 : y : 0) for any nonzero y, e.g. (0, r, 0).
+/// Cost: 12M + 2m3b
+fn projective_add_2015_rcb(
+    a: ptr<function, Point>,
+    b: ptr<function, Point>,
+    p: ptr<function, BigInt>
+) -> Point {
+    var x1 = (*a).x;
+    var y1 = (*a).y;
+    var z1 = (*a).z;
+    var x2 = (*b).x;
+    var y2 = (*b).y;
+    var z2 = (*b).z;
+
+    var b3 = get_br3();
+
+    var t0 = mont_mul(&x1, &x2, p);
+    var t1 = mont_mul(&y1, &y2, p);
+    var t2 = mont_mul(&z1, &z2, p);
+    var t3 = ff_add(&x1, &y1, p);
+    var t4 = ff_add(&x2, &y2, p);
+    t3 = mont_mul(&t3, &t4, p);
+    t4 = ff_add(&t0, &t1, p);
+    t3 = ff_sub(&t3, &t4, p);
+    t4 = ff_add(&y1, &z1, p);
+    var x3 = ff_add(&y2, &z2, p);
+    t4 = mont_mul(&t4, &x3, p);
+    x3 = ff_add(&t1, &t2, p);
+    t4 = ff_sub(&t4, &x3, p);
+    x3 = ff_add(&x1, &z1, p);
+    var y3 = ff_add(&x2, &z2, p);
+    x3 = mont_mul(&x3, &y3, p);
+    y3 = ff_add(&t0, &t2, p);
+    y3 = ff_sub(&x3, &y3, p);
+    x3 = ff_add(&t0, &t0, p);
+    t0 = ff_add(&x3, &t0, p);
+    t2 = mont_mul(&b3, &t2, p);
+    var z3 = ff_add(&t1, &t2, p);
+    t1 = ff_sub(&t1, &t2, p);
+    y3 = mont_mul(&b3, &y3, p);
+    x3 = mont_mul(&t4, &y3, p);
+    t2 = mont_mul(&t3, &t1, p);
+    x3 = ff_sub(&t2, &x3, p);
+    y3 = mont_mul(&y3, &t0, p);
+    t1 = mont_mul(&t1, &z3, p);
+    y3 = ff_add(&t1, &y3, p);
+    t0 = mont_mul(&t0, &t3, p);
+    z3 = mont_mul(&z3, &t4, p);
+    z3 = ff_add(&z3, &t0, p);
+
+    return Point(x3, y3, z3);
+}
+
+/// Algorithm 9 of "Complete addition formulas for prime order elliptic curves" by Renes,
+/// Costello, and Batina, for a = 0. Complete, like projective_add_2015_rcb.
+/// Cost: 6M + 2S + 1m3b
+fn projective_dbl_2015_rcb(
+    pt: ptr<function, Point>,
+    p: ptr<function, BigInt>
+) -> Point {
+    var x = (*pt).x;
+    var y = (*pt).y;
+    var z = (*pt).z;
+
+    var b3 = get_br3();
+
+    var t0 = mont_mul(&y, &y, p);
+    var z3 = ff_add(&t0, &t0, p);
+    z3 = ff_add(&z3, &z3, p);
+    z3 = ff_add(&z3, &z3, p);
+    var t1 = mont_mul(&y, &z, p);
+    var t2 = mont_mul(&z, &z, p);
+    t2 = mont_mul(&b3, &t2, p);
+    var x3 = mont_mul(&t2, &z3, p);
+    var y3 = ff_add(&t0, &t2, p);
+    z3 = mont_mul(&t1, &z3, p);
+    t1 = ff_add(&t2, &t2, p);
+    t2 = ff_add(&t1, &t2, p);
+    t0 = ff_sub(&t0, &t2, p);
+    y3 = mont_mul(&t0, &y3, p);
+    y3 = ff_add(&x3, &y3, p);
+    t1 = mont_mul(&x, &y, p);
+    x3 = mont_mul(&t0, &t1, p);
+    x3 = ff_add(&x3, &x3, p);
+
+    return Point(x3, y3, z3);
+}
+
+/// The point at infinity in the form which projective_add_2015_rcb and projective_dbl_2015_rcb
+/// expect. The all-zero Point is not a projective point, and they map it to itself.
+fn projective_identity() -> Point {
+    var zero: BigInt;
+    var r = get_r();
+    return Point(zero, r, zero);
+}
+
 /// https://www.hyperelliptic.org/EFD/g1p/auto-shortw-projective.html#addition-add-2007-bl
 /// Assumes that Z2 = 1, a != b, and a is not the point at infinity
 fn projective_madd_1998_cmo_unsafe(
@@ -161,7 +260,7 @@ fn jacobian_negate(
 
     y = ff_sub(p, &y, p);
 
-    Point(x, y, z);
+    return Point(x, y, z);
 }
 
 /// https://www.hyperelliptic.org/EFD/g1p/auto-shortw-jacobian-0.html#addition-add-2007-bl
@@ -252,19 +351,52 @@ fn jacobian_dbl_2009_l(
     return Point(x3, y3, z3);
 }
 
+/// The point at infinity in Jacobian coordinates, (1 : 1 : 0) in Montgomery form.
+fn jacobian_identity() -> Point {
+    var zero: BigInt;
+    var r = get_r();
+    return Point(r, r, zero);
+}
+
+/// Jacobian addition which also handles the point at infinity. jacobian_add_2007_bl_unsafe already
+/// doubles equal points, and returns a point with Z = 0 if a == -b.
+fn jacobian_add(
+    a: ptr<function, Point>,
+    b: ptr<function, Point>,
+    p: ptr<function, BigInt>
+) -> Point {
+    if (bigint_is_zero(&(*a).z)) {
+        return *b;
+    }
+    if (bigint_is_zero(&(*b).z)) {
+        return *a;
+    }
+    return jacobian_add_2007_bl_unsafe(a, b, p);
+}
+
 /*
- * Return the two possible Y-coordinates of an affine point, given its X-coordinate
+ * Return x^3 + 7, the right-hand side of the curve equation, in Montgomery form
  */
-fn secp256k1_recover_affine_ys(
+fn curve_equation_rhs(
     xr: ptr<function, BigInt>,
     p: ptr<function, BigInt>
-) -> array<BigInt, 2> {
+) -> BigInt {
     // Assumes that a = 0
     var xr_squared = mont_mul(xr, xr, p);
     var xr_cubed = mont_mul(&xr_squared, xr, p);
 
     var br = get_br();
-    var xr_cubed_plus_b = ff_add(&xr_cubed, &br, p);
+    return ff_add(&xr_cubed, &br, p);
+}
+
+/*
+ * Return the two possible Y-coordinates of an affine point, given its X-coordinate
+ */
+fn secp256k1_recover_affine_ys(
+    xr: ptr<function, BigInt>,
+    p: ptr<function, BigInt>
+) -> array<BigInt, 2> {
+    var xr_cubed_plus_b = curve_equation_rhs(xr, p);
 
     var ys = mont_sqrt_case3mod4(&xr_cubed_plus_b, p);
 
@@ -279,15 +411,15 @@ fn projective_mul(
     x: ptr<function, BigInt>,
     p: ptr<function, BigInt>
 ) -> Point {
-    var result: Point;
+    var result = projective_identity();
 
     var temp = *pt;
     var b = bigint_to_bits_le(x);
     for (var i = 0u; i < b.num_bits; i ++) {
         if (b.bits[i]) {
-            result = projective_add_2007_bl_unsafe(&result, &temp, p);
+            result = projective_add_2015_rcb(&result, &temp, p);
         }
-        temp = projective_dbl_2007_bl_unsafe(&temp, p);
+        temp = projective_dbl_2015_rcb(&temp, p);
     }
 
     return result;
@@ -341,7 +473,7 @@ fn projective_strauss_shamir_mul(
     p: ptr<function, BigInt>
 ) -> Point {
     // From https://github.com/mratsim/constantine/issues/36
-    var result: Point;
+    var result = projective_identity();
 
     var s0 = *x;
     var s1 = *y;
@@ -351,7 +483,7 @@ fn projective_strauss_shamir_mul(
     var s1_bitsresult = bigint_to_bits_le(&s1);
 
     // Precompute a + b
-    var ab = projective_add_2007_bl_unsafe(a, b, p);
+    var ab = projective_add_2015_rcb(a, b, p);
 
     // Determine the length of the longest bitstring to avoid doing more loop
     // iterations than necessary
@@ -364,7 +496,7 @@ fn projective_strauss_shamir_mul(
         let a_bit = s0_bitsresult.bits[i];
         let b_bit = s1_bitsresult.bits[i];
 
-        result = projective_dbl_2007_bl_unsafe(&result, p);
+        result = projective_dbl_2015_rcb(&result, p);
 
         if (a_bit && !b_bit) {
             point_to_add = *a;
@@ -376,7 +508,7 @@ fn projective_strauss_shamir_mul(
             continue;
         }
 
-        result = projective_add_2007_bl_unsafe(&result, &point_to_add, p);
+        result = projective_add_2015_rcb(&result, &point_to_add, p);
     }
 
     return result;
@@ -405,7 +537,7 @@ fn projective_fixed_mul(
         temp = bigint_div2(&temp);
     }
 
-    var result: Point;
+    var result = projective_identity();
     var result_is_inf = true;
 
     var i = 256u;
@@ -423,7 +555,7 @@ fn projective_fixed_mul(
 
         if (!result_is_inf) {
             for (var j = 0u; j < {{ log_table_size }}u; j ++){
-                result = projective_dbl_2007_bl_unsafe(&result, p);
+                result = projective_dbl_2015_rcb(&result, p);
             }
         }
 
@@ -433,11 +565,7 @@ fn projective_fixed_mul(
             if (result_is_inf) {
                 result = t;
             } else {
-                result = projective_add_2007_bl_unsafe(&result, &t, p);
-
-                // mixed addition has fewer multiplications but the benchmarks
-                // show a slowdown for unknown reasons 
-                // result = projective_madd_1998_cmo_unsafe(&result, &t, p);
+                result = projective_add_2015_rcb(&result, &t, p);
             }
             result_is_inf = false;
         }
@@ -446,6 +574,243 @@ fn projective_fixed_mul(
     return result;
 }
 
+/*
+ * Like projective_fixed_mul, but in Jacobian coordinates. The table holds affine points, which
+ * are the same in both systems.
+ */
+fn jacobian_fixed_mul(
+    table: ptr<function, array<PointAffine, {{ table_size }}>>,
+    s: ptr<function, BigInt>,
+    p: ptr<function, BigInt>,
+    r: ptr<function, BigInt>
+) -> Point {
+    // Convert s to bits
+    var temp = *s;
+    var scalar_bits: array<bool, 256>;
+
+    for (var i = 0u; i < 256u; i ++) {
+        if bigint_is_zero(&temp) {
+            break;
+        }
+
+        scalar_bits[i] = !bigint_is_even(&temp);
+
+        temp = bigint_div2(&temp);
+    }
+
+    var result = jacobian_identity();
+
+    var i = 256u;
+    while (i > 0u) {
+        var bits = 0u;
+        for (var j = 0u; j < {{ log_table_size }}u; j ++){
+            if (i > 0u) {
+                i -= 1u;
+                bits <<= 1u;
+                if (scalar_bits[i]) {
+                    bits |= 1u;
+                }
+            }
+        }
+
+        // Doubling the point at infinity leaves Z = 0
+        for (var j = 0u; j < {{ log_table_size }}u; j ++){
+            result = jacobian_dbl_2009_l(&result, p);
+        }
+
+        if (bits != 0u) {
+            var t_affine = (*table)[bits - 1u];
+            var t = Point(t_affine.x, t_affine.y, *r);
+            result = jacobian_add(&result, &t, p);
+        }
+    }
+
+    return result;
+}
+
+{% if checkpoint_bits %}
+// The state of a scalar multiplication which checkpoint::Checkpointing splits across dispatches:
+// the accumulator, the doubled base of double-and-add, whether the accumulator is still the point
+// at infinity, and how many bits of the scalar have been processed. The host zeroes the
+// checkpoints before the first sub-dispatch.
+struct MulCheckpoint {
+    acc: Point,
+    temp: Point,
+    is_inf: u32,
+    num_bits_done: u32
+}
+
+/*
+ * Resumes projective_mul from ckpt for the next {{ checkpoint_bits }} bits of x. The bits above
+ * the highest set bit only double temp, so after all 256 bits, acc is what projective_mul returns.
+ */
+fn projective_mul_resume(
+    ckpt: ptr<function, MulCheckpoint>,
+    pt: ptr<function, Point>,
+    x: ptr<function, BigInt>,
+    p: ptr<function, BigInt>
+) {
+    let start = (*ckpt).num_bits_done;
+    if (start == 0u) {
+        (*ckpt).acc = projective_identity();
+        (*ckpt).temp = *pt;
+    }
+
+    var result = (*ckpt).acc;
+    var temp = (*ckpt).temp;
+    let end = min(start + {{ checkpoint_bits }}u, 256u);
+    for (var i = start; i < end; i ++) {
+        if (bigint_get_bit(x, i)) {
+            result = projective_add_2015_rcb(&result, &temp, p);
+        }
+        temp = projective_dbl_2015_rcb(&temp, p);
+    }
+
+    (*ckpt).acc = result;
+    (*ckpt).temp = temp;
+    (*ckpt).num_bits_done = end;
+}
+
+/*
+ * Resumes jacobian_mul from ckpt for the next {{ checkpoint_bits }} bits of x.
+ */
+fn jacobian_mul_resume(
+    ckpt: ptr<function, MulCheckpoint>,
+    pt: ptr<function, Point>,
+    x: ptr<function, BigInt>,
+    p: ptr<function, BigInt>
+) {
+    let start = (*ckpt).num_bits_done;
+    if (start == 0u) {
+        var zero: BigInt;
+        var one: BigInt;
+        one.limbs[0] = 1u;
+        (*ckpt).acc = Point(one, one, zero);
+        (*ckpt).temp = *pt;
+        (*ckpt).is_inf = 1u;
+    }
+
+    var result = (*ckpt).acc;
+    var temp = (*ckpt).temp;
+    var result_is_inf = (*ckpt).is_inf == 1u;
+    let end = min(start + {{ checkpoint_bits }}u, 256u);
+    for (var i = start; i < end; i ++) {
+        if (bigint_get_bit(x, i)) {
+            if (result_is_inf) {
+                result = temp;
+                result_is_inf = false;
+            } else {
+                result = jacobian_add_2007_bl_unsafe(&result, &temp, p);
+            }
+        }
+        temp = jacobian_dbl_2009_l(&temp, p);
+    }
+
+    (*ckpt).acc = result;
+    (*ckpt).temp = temp;
+    (*ckpt).is_inf = select(0u, 1u, result_is_inf);
+    (*ckpt).num_bits_done = end;
+}
+
+/*
+ * Resumes projective_fixed_mul from ckpt for the next {{ checkpoint_windows }} windows of s,
+ * from the most significant.
+ */
+fn projective_fixed_mul_resume(
+    ckpt: ptr<function, MulCheckpoint>,
+    table: ptr<function, array<PointAffine, {{ table_size }}>>,
+    s: ptr<function, BigInt>,
+    p: ptr<function, BigInt>,
+    r: ptr<function, BigInt>
+) {
+    if ((*ckpt).num_bits_done == 0u) {
+        (*ckpt).acc = projective_identity();
+        (*ckpt).is_inf = 1u;
+    }
+
+    var result = (*ckpt).acc;
+    var result_is_inf = (*ckpt).is_inf == 1u;
+    var i = 256u - (*ckpt).num_bits_done;
+    for (var w = 0u; w < {{ checkpoint_windows }}u && i > 0u; w ++) {
+        var bits = 0u;
+        for (var j = 0u; j < {{ log_table_size }}u; j ++){
+            if (i > 0u) {
+                i -= 1u;
+                bits <<= 1u;
+                if (bigint_get_bit(s, i)) {
+                    bits |= 1u;
+                }
+            }
+        }
+
+        if (!result_is_inf) {
+            for (var j = 0u; j < {{ log_table_size }}u; j ++){
+                result = projective_dbl_2015_rcb(&result, p);
+            }
+        }
+
+        if (bits != 0u) {
+            var t_affine = (*table)[bits - 1u];
+            var t = Point(t_affine.x, t_affine.y, *r);
+            if (result_is_inf) {
+                result = t;
+            } else {
+                result = projective_add_2015_rcb(&result, &t, p);
+            }
+            result_is_inf = false;
+        }
+    }
+
+    (*ckpt).acc = result;
+    (*ckpt).is_inf = select(0u, 1u, result_is_inf);
+    (*ckpt).num_bits_done = 256u - i;
+}
+
+/*
+ * Resumes jacobian_fixed_mul from ckpt for the next {{ checkpoint_windows }} windows of s, from
+ * the most significant.
+ */
+fn jacobian_fixed_mul_resume(
+    ckpt: ptr<function, MulCheckpoint>,
+    table: ptr<function, array<PointAffine, {{ table_size }}>>,
+    s: ptr<function, BigInt>,
+    p: ptr<function, BigInt>,
+    r: ptr<function, BigInt>
+) {
+    if ((*ckpt).num_bits_done == 0u) {
+        (*ckpt).acc = jacobian_identity();
+    }
+
+    var result = (*ckpt).acc;
+    var i = 256u - (*ckpt).num_bits_done;
+    for (var w = 0u; w < {{ checkpoint_windows }}u && i > 0u; w ++) {
+        var bits = 0u;
+        for (var j = 0u; j < {{ log_table_size }}u; j ++){
+            if (i > 0u) {
+                i -= 1u;
+                bits <<= 1u;
+                if (bigint_get_bit(s, i)) {
+                    bits |= 1u;
+                }
+            }
+        }
+
+        for (var j = 0u; j < {{ log_table_size }}u; j ++){
+            result = jacobian_dbl_2009_l(&result, p);
+        }
+
+        if (bits != 0u) {
+            var t_affine = (*table)[bits - 1u];
+            var t = Point(t_affine.x, t_affine.y, *r);
+            result = jacobian_add(&result, &t, p);
+        }
+    }
+
+    (*ckpt).acc = result;
+    (*ckpt).num_bits_done = 256u - i;
+}
+{% endif %}
+
 /*
  * Scalar multiplication using the GLV method
 fn projective_glv_mul(
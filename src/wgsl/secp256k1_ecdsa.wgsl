@@ -4,6 +4,112 @@ struct IntermediateResult {
     recovered_r: Point
 }
 
+// The point operations of the recovery pipeline, in the coordinate system which
+// shader::Coordinates selects when the shaders are rendered. Every stage must be rendered with
+// the same one, as the points in the intermediate buffers are in that system.
+{% if coordinates == "jacobian" %}
+fn ecdsa_fixed_mul(
+    table: ptr<function, array<PointAffine, {{ table_size }}>>,
+    s: ptr<function, BigInt>,
+    p: ptr<function, BigInt>,
+    r: ptr<function, BigInt>
+) -> Point {
+    return jacobian_fixed_mul(table, s, p, r);
+}
+
+fn ecdsa_mul(
+    pt: ptr<function, Point>,
+    x: ptr<function, BigInt>,
+    p: ptr<function, BigInt>
+) -> Point {
+    return jacobian_mul(pt, x, p);
+}
+
+fn ecdsa_add(
+    a: ptr<function, Point>,
+    b: ptr<function, Point>,
+    p: ptr<function, BigInt>
+) -> Point {
+    return jacobian_add(a, b, p);
+}
+
+fn ecdsa_to_affine_non_mont(
+    a: ptr<function, Point>,
+    p: ptr<function, BigInt>,
+    p_wide: ptr<function, BigIntWide>,
+    r: ptr<function, BigInt>,
+    rinv: ptr<function, BigInt>,
+    mu_fp: ptr<function, BigInt>,
+) -> Point {
+    return jacobian_to_affine_non_mont(a, p, p_wide, r, rinv, mu_fp);
+}
+{% else %}
+fn ecdsa_fixed_mul(
+    table: ptr<function, array<PointAffine, {{ table_size }}>>,
+    s: ptr<function, BigInt>,
+    p: ptr<function, BigInt>,
+    r: ptr<function, BigInt>
+) -> Point {
+    return projective_fixed_mul(table, s, p, r);
+}
+
+fn ecdsa_mul(
+    pt: ptr<function, Point>,
+    x: ptr<function, BigInt>,
+    p: ptr<function, BigInt>
+) -> Point {
+    return projective_mul(pt, x, p);
+}
+
+fn ecdsa_add(
+    a: ptr<function, Point>,
+    b: ptr<function, Point>,
+    p: ptr<function, BigInt>
+) -> Point {
+    return projective_add_2015_rcb(a, b, p);
+}
+
+fn ecdsa_to_affine_non_mont(
+    a: ptr<function, Point>,
+    p: ptr<function, BigInt>,
+    p_wide: ptr<function, BigIntWide>,
+    r: ptr<function, BigInt>,
+    rinv: ptr<function, BigInt>,
+    mu_fp: ptr<function, BigInt>,
+) -> Point {
+    return projective_to_affine_non_mont(a, p, p_wide, r, rinv, mu_fp);
+}
+{% endif %}
+{% if checkpoint_bits %}
+// The resumable multiplications of stages 1 and 2, for checkpoint::Checkpointing
+fn ecdsa_fixed_mul_resume(
+    ckpt: ptr<function, MulCheckpoint>,
+    table: ptr<function, array<PointAffine, {{ table_size }}>>,
+    s: ptr<function, BigInt>,
+    p: ptr<function, BigInt>,
+    r: ptr<function, BigInt>
+) {
+    {% if coordinates == "jacobian" -%}
+    jacobian_fixed_mul_resume(ckpt, table, s, p, r);
+    {%- else -%}
+    projective_fixed_mul_resume(ckpt, table, s, p, r);
+    {%- endif %}
+}
+
+fn ecdsa_mul_resume(
+    ckpt: ptr<function, MulCheckpoint>,
+    pt: ptr<function, Point>,
+    x: ptr<function, BigInt>,
+    p: ptr<function, BigInt>
+) {
+    {% if coordinates == "jacobian" -%}
+    jacobian_mul_resume(ckpt, pt, x, p);
+    {%- else -%}
+    projective_mul_resume(ckpt, pt, x, p);
+    {%- endif %}
+}
+{% endif %}
+
 fn secp256k1_ecrecover_0(
     sig_r_bytes: ptr<function, array<u32, 32>>,
     sig_s_bytes: ptr<function, array<u32, 32>>,
@@ -116,12 +222,12 @@ fn secp256k1_ecrecover(
     var recovered_r = ir.recovered_r;
 
     var g = get_secp256k1_generator();
-    var u1g = projective_mul(&g, &u1, p);
-    var u2r = projective_mul(&recovered_r, &u2, p);
-    var result_proj = projective_add_2007_bl_unsafe(&u1g, &u2r, p);
+    var u1g = ecdsa_mul(&g, &u1, p);
+    var u2r = ecdsa_mul(&recovered_r, &u2, p);
+    var result_proj = ecdsa_add(&u1g, &u2r, p);
 
     // Return the point in affine form
-    return projective_to_affine_non_mont(&result_proj, p, p_wide, r, rinv, mu_fp);
+    return ecdsa_to_affine_non_mont(&result_proj, p, p_wide, r, rinv, mu_fp);
 
     // At a high enough thread count, using the projective_strauss_shamir_mul()
     // function will make the shader silently fail and the result buffer will
@@ -0,0 +1,26 @@
+{% include "secp256k1_curve.wgsl" %}
+{% include "secp_constants.wgsl" %}
+
+// The point type and group operations which main/msm_main_*.wgsl and main/ops_*_main.wgsl are
+// written against. The addition and doubling must handle the point at infinity, and the addition
+// must handle equal points.
+alias MsmPoint = Point;
+
+fn msm_identity() -> MsmPoint {
+    return projective_identity();
+}
+
+fn msm_add(
+    a: ptr<function, MsmPoint>,
+    b: ptr<function, MsmPoint>,
+    p: ptr<function, BigInt>
+) -> MsmPoint {
+    return projective_add_2015_rcb(a, b, p);
+}
+
+fn msm_dbl(
+    a: ptr<function, MsmPoint>,
+    p: ptr<function, BigInt>
+) -> MsmPoint {
+    return projective_dbl_2015_rcb(a, p);
+}
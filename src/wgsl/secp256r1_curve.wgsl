@@ -146,13 +146,12 @@ fn projective_dbl_2015_rcb(
 }
 
 /*
- * Return the two possible Y-coordinates of an affine point, given its X-coordinate
- * a = 3
+ * Return x^3 - 3x + b, the right-hand side of the curve equation, in Montgomery form
  */
-fn secp256r1_recover_affine_ys(
+fn curve_equation_rhs(
     xr: ptr<function, BigInt>,
     p: ptr<function, BigInt>
-) -> array<BigInt, 2> {
+) -> BigInt {
     var xr_squared = mont_mul(xr, xr, p);
     var xr_cubed = mont_mul(&xr_squared, xr, p);
 
@@ -162,7 +161,18 @@ fn secp256r1_recover_affine_ys(
     var xr_cubed_plus_ar = ff_add(&xr_cubed, &axr, p);
 
     var br = get_br();
-    var xr_cubed_plus_ar_plus_br = ff_add(&xr_cubed_plus_ar, &br, p);
+    return ff_add(&xr_cubed_plus_ar, &br, p);
+}
+
+/*
+ * Return the two possible Y-coordinates of an affine point, given its X-coordinate
+ * a = 3
+ */
+fn secp256r1_recover_affine_ys(
+    xr: ptr<function, BigInt>,
+    p: ptr<function, BigInt>
+) -> array<BigInt, 2> {
+    var xr_cubed_plus_ar_plus_br = curve_equation_rhs(xr, p);
 
     var ys = mont_sqrt_case3mod4(&xr_cubed_plus_ar_plus_br, p);
 
@@ -325,3 +335,111 @@ fn projective_fixed_mul(
 
     return result;
 }
+{% if checkpoint_bits %}
+
+// The state of a scalar multiplication which checkpoint::Checkpointing splits across dispatches:
+// the accumulator, the doubled base of double-and-add, whether the accumulator is still the point
+// at infinity, and how many bits of the scalar have been processed. The host zeroes the
+// checkpoints before the first sub-dispatch.
+struct MulCheckpoint {
+    acc: Point,
+    temp: Point,
+    is_inf: u32,
+    num_bits_done: u32
+}
+
+/*
+ * Resumes projective_mul from ckpt for the next {{ checkpoint_bits }} bits of x. The bits above
+ * the highest set bit only double temp, so after all 256 bits, acc is what projective_mul returns.
+ */
+fn projective_mul_resume(
+    ckpt: ptr<function, MulCheckpoint>,
+    pt: ptr<function, Point>,
+    x: ptr<function, BigInt>,
+    p: ptr<function, BigInt>
+) {
+    let start = (*ckpt).num_bits_done;
+    if (start == 0u) {
+        var inf: Point;
+        (*ckpt).acc = inf;
+        (*ckpt).temp = *pt;
+        (*ckpt).is_inf = 1u;
+    }
+
+    var result = (*ckpt).acc;
+    var temp = (*ckpt).temp;
+    var result_is_inf = (*ckpt).is_inf == 1u;
+    let end = min(start + {{ checkpoint_bits }}u, 256u);
+    for (var i = start; i < end; i ++) {
+        if (bigint_get_bit(x, i)) {
+            if (result_is_inf) {
+                result = temp;
+                result_is_inf = false;
+            } else {
+                result = projective_add_2015_rcb_unsafe(&result, &temp, p);
+            }
+        }
+        temp = projective_dbl_2015_rcb(&temp, p);
+    }
+
+    (*ckpt).acc = result;
+    (*ckpt).temp = temp;
+    (*ckpt).is_inf = select(0u, 1u, result_is_inf);
+    (*ckpt).num_bits_done = end;
+}
+
+/*
+ * Resumes projective_fixed_mul from ckpt for the next {{ checkpoint_windows }} windows of s,
+ * from the most significant.
+ */
+fn projective_fixed_mul_resume(
+    ckpt: ptr<function, MulCheckpoint>,
+    table: ptr<function, array<PointAffine, {{ table_size }}>>,
+    s: ptr<function, BigInt>,
+    p: ptr<function, BigInt>,
+    r: ptr<function, BigInt>
+) {
+    if ((*ckpt).num_bits_done == 0u) {
+        var inf: Point;
+        (*ckpt).acc = inf;
+        (*ckpt).is_inf = 1u;
+    }
+
+    var result = (*ckpt).acc;
+    var result_is_inf = (*ckpt).is_inf == 1u;
+    var i = 256u - (*ckpt).num_bits_done;
+    for (var w = 0u; w < {{ checkpoint_windows }}u && i > 0u; w ++) {
+        var bits = 0u;
+        for (var j = 0u; j < {{ log_table_size }}u; j ++){
+            if (i > 0u) {
+                i -= 1u;
+                bits <<= 1u;
+                if (bigint_get_bit(s, i)) {
+                    bits |= 1u;
+                }
+            }
+        }
+
+        if (!result_is_inf) {
+            for (var j = 0u; j < {{ log_table_size }}u; j ++){
+                result = projective_dbl_2015_rcb(&result, p);
+            }
+        }
+
+        if (bits != 0u) {
+            var t_affine = (*table)[bits - 1u];
+            var t = Point(t_affine.x, t_affine.y, *r);
+            if (result_is_inf) {
+                result = t;
+            } else {
+                result = projective_add_2015_rcb_unsafe(&result, &t, p);
+            }
+            result_is_inf = false;
+        }
+    }
+
+    (*ckpt).acc = result;
+    (*ckpt).is_inf = select(0u, 1u, result_is_inf);
+    (*ckpt).num_bits_done = 256u - i;
+}
+{% endif %}
@@ -0,0 +1,28 @@
+{% include "secp256r1_curve.wgsl" %}
+{% include "secp_constants.wgsl" %}
+
+// The point type and group operations which main/msm_main_*.wgsl and main/ops_*_main.wgsl are
+// written against. The addition and doubling must handle the point at infinity, and the addition
+// must handle equal points.
+alias MsmPoint = Point;
+
+fn msm_identity() -> MsmPoint {
+    var zero: BigInt;
+    var r = get_r();
+    return Point(zero, r, zero);
+}
+
+fn msm_add(
+    a: ptr<function, MsmPoint>,
+    b: ptr<function, MsmPoint>,
+    p: ptr<function, BigInt>
+) -> MsmPoint {
+    return projective_add_2015_rcb_unsafe(a, b, p);
+}
+
+fn msm_dbl(
+    a: ptr<function, MsmPoint>,
+    p: ptr<function, BigInt>
+) -> MsmPoint {
+    return projective_dbl_2015_rcb(a, p);
+}
@@ -0,0 +1,7 @@
+fn get_secp384r1_generator() -> Point {
+    {{ secp384r1_generator_xr_bigint }}
+    {{ secp384r1_generator_yr_bigint }}
+    var r = get_r();
+
+    return Point(secp384r1_generator_xr, secp384r1_generator_yr, r);
+}
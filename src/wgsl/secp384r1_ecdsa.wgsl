@@ -0,0 +1,74 @@
+struct IntermediateResult {
+    u1: BigInt,
+    u2: BigInt,
+    recovered_r: Point
+}
+
+fn secp384r1_ecrecover_0(
+    sig_r_bytes: ptr<function, array<u32, {{ num_bytes }}>>,
+    sig_s_bytes: ptr<function, array<u32, {{ num_bytes }}>>,
+    msg_bytes: ptr<function, array<u32, {{ num_bytes }}>>,
+    p: ptr<function, BigInt>,
+    p_wide: ptr<function, BigIntWide>,
+    scalar_p: ptr<function, BigInt>,
+    scalar_p_wide: ptr<function, BigIntWide>,
+    r: ptr<function, BigInt>,
+    rinv: ptr<function, BigInt>,
+    mu_fp: ptr<function, BigInt>,
+    mu_fr: ptr<function, BigInt>,
+) -> IntermediateResult {
+    var decoded = decode_signature(sig_s_bytes);
+    var ds = decoded.sig;
+    var is_y_odd = decoded.is_y_odd;
+
+    var sig_r = bytes_be_to_limbs_le(sig_r_bytes);
+    var sig_s = bytes_be_to_limbs_le(&ds);
+   
+    var z = bytes_be_to_limbs_le(msg_bytes);
+
+    // The order of P-384 is close to 2^384, so the message hash may exceed it
+    if (bigint_gte(&z, scalar_p)) {
+        z = bigint_sub(&z, scalar_p);
+    }
+
+    if (bigint_is_zero(&sig_r)) {
+        var z: BigInt;
+        return IntermediateResult(z, z, Point(z, z, z));
+    }
+
+    var r_x = sig_r;
+
+    var r_xr = ff_mul(&r_x, r, p, p_wide, mu_fp);
+    var yrs = secp384r1_recover_affine_ys(&r_xr, p);
+    var yr0 = yrs[0];
+    var yr1 = yrs[1];
+
+    var y0 = ff_mul(&yr0, rinv, p, p_wide, mu_fp);
+
+    var y0_is_odd = !bigint_is_even(&y0);
+
+    var yr: BigInt;
+    if (is_y_odd == y0_is_odd) {
+        yr = yr0;
+    } else {
+        yr = yr1;
+    }
+
+    var recovered_r = Point(r_xr, yr, *r);
+
+    if (bigint_gte(&r_x, scalar_p)) {
+        r_x = bigint_sub(&r_x, scalar_p);
+    }
+
+    // compute inverse(r_x) in the scalar field
+    var r_x_inv = ff_inverse(&r_x, scalar_p);
+
+    // compute u1 = -(r_inv * z);
+    var r_x_inv_z = ff_mul(&r_x_inv, &z, scalar_p, scalar_p_wide, mu_fr);
+    var u1 = ff_negate(&r_x_inv_z, scalar_p);
+
+    // compute u2 = r_inv * s;
+    var u2 = ff_mul(&r_x_inv, &sig_s, scalar_p, scalar_p_wide, mu_fr);
+
+    return IntermediateResult(u1, u2, recovered_r);
+}
@@ -122,6 +122,28 @@ fn sha512_96(
     message_array[24] = 2147483648u;
     message_array[31] = 768u;
 
+    return sha512_single_block(&message_array);
+}
+
+fn sha512_32(
+    input_bytes: ptr<function, array<u32, 8>>
+) -> array<u32, 16> {
+    var message_array = array<u32, 32>();
+
+    for (var i = 0u; i < 8u; i ++) {
+        message_array[i] = (*input_bytes)[i];
+    }
+    message_array[8] = 2147483648u;
+    message_array[31] = 256u;
+
+    return sha512_single_block(&message_array);
+}
+
+/// Hashes a message which fits in a single 1024-bit block once padded. `message_array` holds the
+/// padded block as big-endian 32-bit words.
+fn sha512_single_block(
+    message_array: ptr<function, array<u32, 32>>
+) -> array<u32, 16> {
     var rc = round_constants();
 
     var sha512_hash = initial_hash();
@@ -129,7 +151,7 @@ fn sha512_96(
     var w = array<u32, 160>();
 
     for (var i = 0u; i < 32u; i ++) {
-        w[i] = message_array[i];
+        w[i] = (*message_array)[i];
     }
 
     for (var i = 16u; i < 80u; i ++) {
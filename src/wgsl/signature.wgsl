@@ -1,13 +1,13 @@
 struct DecodedSig {
-    sig: array<u32, 32>,
+    sig: array<u32, {{ num_bytes }}>,
     is_y_odd: bool
 }
 
 fn decode_signature(
-    sig_s: ptr<function, array<u32, 32>>
+    sig_s: ptr<function, array<u32, {{ num_bytes }}>>
 ) -> DecodedSig {
-    var s: array<u32, 32>;
-    for (var i = 0u; i < 32; i ++) {
+    var s: array<u32, {{ num_bytes }}>;
+    for (var i = 0u; i < {{ num_bytes }}u; i ++) {
         s[i] = (*sig_s)[i];
     }
     